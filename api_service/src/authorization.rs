@@ -0,0 +1,158 @@
+use super::mongo::user_collection::UserCollection;
+use shared::auth::get_caller_user_name;
+use shared::resource_name::UserName;
+use std::sync::Arc;
+use tonic::{Request, Status};
+
+// Roles are ordered by how much they're allowed to do, and each
+// role is allowed to do everything that the roles below it can.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UserRole {
+    User,
+    Moderator,
+    Admin,
+}
+
+impl UserRole {
+    // Users without a role stored on their document are regular users,
+    // so `User` is never stored and existing documents don't need updating.
+    pub fn to_document_value(self) -> Option<&'static str> {
+        match self {
+            Self::User => None,
+            Self::Moderator => Some("moderator"),
+            Self::Admin => Some("admin"),
+        }
+    }
+
+    pub fn from_document_value(value_or: Option<&str>) -> Self {
+        match value_or {
+            Some("moderator") => Self::Moderator,
+            Some("admin") => Self::Admin,
+            // Unrecognized roles fall back to the least privileged role.
+            _ => Self::User,
+        }
+    }
+}
+
+// Gates RPCs that only privileged users, such as admins and moderators,
+// are allowed to call. Roles are looked up on every call rather than
+// being cached, so that revoking a role takes effect immediately.
+pub struct RoleAuthorizer {
+    user_collection: Arc<dyn UserCollection>,
+}
+
+impl RoleAuthorizer {
+    pub fn new(user_collection: Arc<dyn UserCollection>) -> Self {
+        Self { user_collection }
+    }
+
+    pub async fn check_caller_has_role<T>(
+        &self,
+        request: &Request<T>,
+        required_role: UserRole,
+    ) -> Result<(), Status> {
+        let caller_user_name = match get_caller_user_name(request) {
+            Some(caller_user_name) => caller_user_name,
+            None => {
+                return Err(Status::unauthenticated(
+                    "Request must include a session token.",
+                ))
+            }
+        };
+        let user_name = match UserName::new_from_str(caller_user_name) {
+            Ok(user_name) => user_name,
+            Err(_) => return Err(Self::missing_role_error(required_role)),
+        };
+        // Callers whose user has been deleted are treated the same as callers without the role.
+        let role = match self.user_collection.get_user_role(user_name).await {
+            Ok(role) => role,
+            Err(err) if err.code() == tonic::Code::NotFound => UserRole::User,
+            Err(err) => return Err(err),
+        };
+        if role >= required_role {
+            Ok(())
+        } else {
+            Err(Self::missing_role_error(required_role))
+        }
+    }
+
+    fn missing_role_error(required_role: UserRole) -> Status {
+        Status::permission_denied(format!(
+            "Caller must have the {:?} role to call this method.",
+            required_role
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::mongo::user_collection::MockUserCollection;
+    use super::*;
+    use shared::auth::create_request_authenticated_as;
+
+    #[test]
+    fn convert_roles_to_and_from_document_values() {
+        for role in [UserRole::User, UserRole::Moderator, UserRole::Admin] {
+            assert_eq!(
+                UserRole::from_document_value(role.to_document_value()),
+                role
+            );
+        }
+        assert_eq!(
+            UserRole::from_document_value(Some("superuser")),
+            UserRole::User
+        );
+        assert!(UserRole::Admin > UserRole::Moderator);
+        assert!(UserRole::Moderator > UserRole::User);
+    }
+
+    #[tokio::test]
+    async fn check_caller_has_role() {
+        let mut mock_user_collection = MockUserCollection::new();
+        mock_user_collection
+            .expect_get_user_role()
+            .returning(|user_name| {
+                if user_name.clone_str() == "users/5d8c5ea3e3b0ab3ac6b8fac2" {
+                    Ok(UserRole::Moderator)
+                } else {
+                    Err(Status::not_found("User does not exist."))
+                }
+            });
+        let role_authorizer = RoleAuthorizer::new(Arc::from(mock_user_collection));
+
+        assert_eq!(
+            role_authorizer
+                .check_caller_has_role(&Request::new(()), UserRole::Moderator)
+                .await
+                .unwrap_err()
+                .code(),
+            tonic::Code::Unauthenticated
+        );
+
+        let moderator_request =
+            create_request_authenticated_as((), "users/5d8c5ea3e3b0ab3ac6b8fac2");
+        assert!(role_authorizer
+            .check_caller_has_role(&moderator_request, UserRole::Moderator)
+            .await
+            .is_ok());
+        assert_eq!(
+            role_authorizer
+                .check_caller_has_role(&moderator_request, UserRole::Admin)
+                .await
+                .unwrap_err()
+                .message(),
+            "Caller must have the Admin role to call this method."
+        );
+
+        let deleted_user_request =
+            create_request_authenticated_as((), "users/5d8c5ea3e3b0ab3ac6b8fac3");
+        assert_eq!(
+            role_authorizer
+                .check_caller_has_role(&deleted_user_request, UserRole::Moderator)
+                .await
+                .unwrap_err()
+                .code(),
+            tonic::Code::PermissionDenied
+        );
+    }
+}
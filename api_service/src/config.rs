@@ -0,0 +1,215 @@
+use shared::config::{AuthConfig, ConfigError, ConfigLoader};
+use shared::rate_limit::RateLimit;
+use shared::resource_name::UserName;
+use std::collections::HashMap;
+
+// Where api service keeps its data. In-memory storage is lost on
+// restart, so it's only meant for local development and tests.
+// SQLite keeps everything in a single local file, for small
+// deployments that don't want to run a Mongo server.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StorageBackend {
+    Mongo,
+    Memory,
+    Sqlite,
+}
+
+// See `ConfigLoader` for how values are loaded. Every key below
+// can also be set with its uppercase environment variable.
+pub struct Config {
+    port: u16,
+    metrics_port: u16,
+    storage: StorageBackend,
+    mongo_uri: String,
+    mongo_database: String,
+    sqlite_path: String,
+    sonic_uri: String,
+    sonic_password: String,
+    auth_config: AuthConfig,
+    admin_user_names: Vec<String>,
+    rate_limits: HashMap<String, RateLimit>,
+    default_page_size: i64,
+    max_page_size: i64,
+}
+
+impl Config {
+    pub fn load() -> Result<Self, ConfigError> {
+        Self::from_loader(ConfigLoader::from_env()?)
+    }
+
+    fn from_loader(mut loader: ConfigLoader) -> Result<Self, ConfigError> {
+        let (default_page_size, max_page_size) = loader.get_page_size_limits();
+        let storage = match loader.get_string("storage", "mongo").as_str() {
+            "mongo" => StorageBackend::Mongo,
+            "memory" => StorageBackend::Memory,
+            "sqlite" => StorageBackend::Sqlite,
+            _ => {
+                loader.add_error(String::from(
+                    "`storage` must be one of `mongo`, `memory` or `sqlite`.",
+                ));
+                StorageBackend::Mongo
+            }
+        };
+        let config = Self {
+            port: loader.get_port("port", 50052),
+            metrics_port: loader.get_port("metrics_port", 9090),
+            storage,
+            mongo_uri: loader.get_uri(
+                "mongo_uri",
+                "mongodb://localhost:27017/",
+                &["mongodb://", "mongodb+srv://"],
+            ),
+            mongo_database: loader.get_string("mongo_database", "crustyCards"),
+            sqlite_path: loader.get_string("sqlite_path", "crustyCards.sqlite3"),
+            sonic_uri: loader.get_string("sonic_uri", "127.0.0.1:1491"),
+            // There's deliberately no default here, so that a
+            // deployment can't accidentally use a well-known password.
+            sonic_password: loader.get_required_string("sonic_password"),
+            auth_config: loader.get_auth_config(),
+            admin_user_names: loader.get_string_list("admin_user_names"),
+            rate_limits: loader.get_rate_limits(),
+            default_page_size,
+            max_page_size,
+        };
+        if config.port == config.metrics_port {
+            loader.add_error(String::from("`port` and `metrics_port` must be different."));
+        }
+        for admin_user_name in &config.admin_user_names {
+            if UserName::new_from_str(admin_user_name).is_err() {
+                loader.add_error(format!(
+                    "`admin_user_names` contains `{}`, which is not a valid user name.",
+                    admin_user_name
+                ));
+            }
+        }
+        loader.finish()?;
+        Ok(config)
+    }
+
+    pub fn get_port(&self) -> u16 {
+        self.port
+    }
+
+    // Port for the HTTP server that exposes Prometheus metrics.
+    pub fn get_metrics_port(&self) -> u16 {
+        self.metrics_port
+    }
+
+    pub fn get_storage(&self) -> StorageBackend {
+        self.storage
+    }
+
+    pub fn get_mongo_uri(&self) -> &str {
+        &self.mongo_uri
+    }
+
+    pub fn get_mongo_database(&self) -> &str {
+        &self.mongo_database
+    }
+
+    pub fn get_sqlite_path(&self) -> &str {
+        &self.sqlite_path
+    }
+
+    pub fn get_sonic_uri(&self) -> &str {
+        &self.sonic_uri
+    }
+
+    pub fn get_sonic_password(&self) -> &str {
+        &self.sonic_password
+    }
+
+    pub fn get_auth_config(&self) -> &AuthConfig {
+        &self.auth_config
+    }
+
+    // Users that are given the admin role at startup, such as `users/1234`.
+    pub fn get_admin_user_names(&self) -> &[String] {
+        &self.admin_user_names
+    }
+
+    // Keyed by method, such as `UserService/UserSearch`.
+    pub fn get_rate_limits(&self) -> &HashMap<String, RateLimit> {
+        &self.rate_limits
+    }
+
+    // Returns the default and maximum page sizes for paginated list requests.
+    pub fn get_page_size_limits(&self) -> (i64, i64) {
+        (self.default_page_size, self.max_page_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn sonic_password_is_required() {
+        let mut env_values = HashMap::new();
+        env_values.insert(String::from("AUTH_TEST_MODE"), String::from("true"));
+        let err = Config::from_loader(ConfigLoader::new(None, env_values.clone()).unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.get_messages(),
+            &["`sonic_password` must be set, either in the config file or with the `SONIC_PASSWORD` environment variable."]
+        );
+
+        env_values.insert(String::from("SONIC_PASSWORD"), String::from("password"));
+        let config = Config::from_loader(ConfigLoader::new(None, env_values).unwrap()).unwrap();
+        assert_eq!(config.get_sonic_password(), "password");
+        assert_eq!(config.get_mongo_uri(), "mongodb://localhost:27017/");
+        assert_eq!(config.get_storage(), StorageBackend::Mongo);
+        assert!(config.get_auth_config().is_test_mode());
+        assert!(config.get_admin_user_names().is_empty());
+    }
+
+    #[test]
+    fn admin_user_names_must_be_valid() {
+        let mut env_values = HashMap::new();
+        env_values.insert(String::from("AUTH_TEST_MODE"), String::from("true"));
+        env_values.insert(String::from("SONIC_PASSWORD"), String::from("password"));
+        env_values.insert(
+            String::from("ADMIN_USER_NAMES"),
+            String::from("users/5d8c5ea3e3b0ab3ac6b8fac2, admin"),
+        );
+        let err = Config::from_loader(ConfigLoader::new(None, env_values).unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.get_messages(),
+            &["`admin_user_names` contains `admin`, which is not a valid user name."]
+        );
+    }
+
+    #[test]
+    fn storage_must_be_known() {
+        let mut env_values = HashMap::new();
+        env_values.insert(String::from("AUTH_TEST_MODE"), String::from("true"));
+        env_values.insert(String::from("SONIC_PASSWORD"), String::from("password"));
+        env_values.insert(String::from("STORAGE"), String::from("memory"));
+        let config =
+            Config::from_loader(ConfigLoader::new(None, env_values.clone()).unwrap()).unwrap();
+        assert_eq!(config.get_storage(), StorageBackend::Memory);
+
+        env_values.insert(String::from("STORAGE"), String::from("sqlite"));
+        env_values.insert(
+            String::from("SQLITE_PATH"),
+            String::from("/data/api.sqlite3"),
+        );
+        let config =
+            Config::from_loader(ConfigLoader::new(None, env_values.clone()).unwrap()).unwrap();
+        assert_eq!(config.get_storage(), StorageBackend::Sqlite);
+        assert_eq!(config.get_sqlite_path(), "/data/api.sqlite3");
+
+        env_values.insert(String::from("STORAGE"), String::from("postgres"));
+        let err = Config::from_loader(ConfigLoader::new(None, env_values).unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.get_messages(),
+            &["`storage` must be one of `mongo`, `memory` or `sqlite`."]
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use super::search_client::SearchClient;
+use bson::doc;
+use mongodb::Database;
+use shared::health::ReadinessCheck;
+use std::sync::Arc;
+
+pub struct MongoReadinessCheck {
+    database: Database,
+}
+
+impl MongoReadinessCheck {
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+}
+
+#[tonic::async_trait]
+impl ReadinessCheck for MongoReadinessCheck {
+    fn get_dependency_name(&self) -> &'static str {
+        "mongo"
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        match self.database.run_command(doc! {"ping": 1}, None).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("{}", err)),
+        }
+    }
+}
+
+pub struct SonicReadinessCheck {
+    search_client: Arc<dyn SearchClient>,
+}
+
+impl SonicReadinessCheck {
+    pub fn new(search_client: Arc<dyn SearchClient>) -> Self {
+        Self { search_client }
+    }
+}
+
+#[tonic::async_trait]
+impl ReadinessCheck for SonicReadinessCheck {
+    fn get_dependency_name(&self) -> &'static str {
+        "sonic"
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        // The Sonic client is synchronous, so it's pinged off of
+        // the async runtime to avoid blocking other requests.
+        let search_client = self.search_client.clone();
+        match tokio::task::spawn_blocking(move || search_client.ping()).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(err)) => Err(format!("{}", err)),
+            Err(err) => Err(format!("{}", err)),
+        }
+    }
+}
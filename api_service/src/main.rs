@@ -1,59 +1,152 @@
-mod environment;
+mod authorization;
+mod config;
+mod health;
+mod memory;
+mod metrics;
 mod mongo;
-mod proto_helper;
 mod search_client;
 mod service;
+mod sqlite;
+mod storage;
 
-use mongo::custom_black_card_collection::MongoCustomBlackCardCollection;
-use mongo::custom_cardpack_collection::MongoCustomCardpackCollection;
-use mongo::custom_white_card_collection::MongoCustomWhiteCardCollection;
-use mongo::helper::get_mongo_database_or_panic;
-use mongo::user_collection::MongoUserCollection;
+use authorization::UserRole;
+use config::{Config, StorageBackend};
+use health::SonicReadinessCheck;
 use search_client::SonicSearchClient;
 use service::admin_service_impl::AdminServiceImpl;
 use service::cardpack_service_impl::CardpackServiceImpl;
 use service::default_cardpacks::DefaultCardpackHandler;
 use service::user_service_impl::UserServiceImpl;
+use shared::auth::{SessionAuthInterceptor, SessionTokenKey};
+use shared::health::{spawn_readiness_monitor, ReadinessCheck};
+use shared::metrics::{serve_metrics, GrpcMetricsLayer};
 use shared::proto::crusty_cards_api::{
     admin_service_server::AdminServiceServer, cardpack_service_server::CardpackServiceServer,
     user_service_server::UserServiceServer,
 };
+use shared::proto_validation::BoundedPageSize;
+use shared::rate_limit::{RateLimitLayer, RateLimiter};
+use shared::request_tracing::{init_tracing, RequestTracingLayer};
+use shared::resource_name::UserName;
 use std::sync::Arc;
-use tonic::transport::Server;
+use storage::Collections;
+use tonic::transport::{NamedService, Server};
+use tracing::{error, info, warn};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let env_vars = environment::EnvironmentVariables::new();
-    let port: u16 = 50052;
+    init_tracing();
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            error!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    let (default_page_size, max_page_size) = config.get_page_size_limits();
+    BoundedPageSize::set_limits(default_page_size, max_page_size);
+    let port = config.get_port();
     let address = format!("0.0.0.0:{}", port).parse().unwrap();
+    let metrics_port = config.get_metrics_port();
+    let metrics_address = format!("0.0.0.0:{}", metrics_port).parse().unwrap();
 
-    let mongo_database = get_mongo_database_or_panic(&env_vars).await;
-    let user_collection = Arc::from(MongoUserCollection::new(mongo_database.collection("users")));
-    let sonic_client = Arc::from(SonicSearchClient::new(&env_vars)?);
+    let auth_config = config.get_auth_config();
+    if auth_config.is_test_mode() {
+        warn!("Auth test mode is enabled, so anyone can sign session tokens or act as an internal service. Never enable it in production.");
+    }
+    let session_token_key = Arc::new(SessionTokenKey::new(auth_config.get_session_token_secret()));
+    // Game service calls api service as an internal caller.
+    let session_auth_interceptor = SessionAuthInterceptor::new_with_internal_service_secret(
+        session_token_key.clone(),
+        auth_config.get_internal_service_secret(),
+    );
+    let rate_limit_layer = RateLimitLayer::new(
+        Arc::new(RateLimiter::new(config.get_rate_limits().clone())),
+        session_token_key,
+        auth_config.get_internal_service_secret(),
+    );
 
-    println!("Starting server on port {}", port);
+    if config.get_storage() == StorageBackend::Memory {
+        warn!("Using in-memory storage, so all data will be lost when the server stops.");
+    }
+    let collections = Collections::new(&config).await?;
+    let user_collection = collections.user_collection;
+    let sonic_client = Arc::from(SonicSearchClient::new(&config)?);
+
+    // Admins can't be created through the API, so the first admins are set up
+    // through config. Removing a user from the config doesn't revoke their role.
+    // This runs in the background so that startup isn't blocked on the database.
+    let admin_user_names = config.get_admin_user_names().to_vec();
+    let admin_user_collection = user_collection.clone();
+    tokio::spawn(async move {
+        for admin_user_name in admin_user_names {
+            // Unwrap is safe here because config validates every admin user name.
+            let user_name = UserName::new_from_str(&admin_user_name).unwrap();
+            match admin_user_collection
+                .set_user_role(user_name, UserRole::Admin)
+                .await
+            {
+                Ok(_) => info!(user_name = admin_user_name.as_str(), "Granted admin role."),
+                Err(err) => warn!(
+                    user_name = admin_user_name.as_str(),
+                    error = err.message(),
+                    "Failed to grant admin role."
+                ),
+            };
+        }
+    });
+
+    let mut readiness_checks: Vec<Box<dyn ReadinessCheck>> =
+        collections.readiness_check_or.into_iter().collect();
+    readiness_checks.push(Box::from(SonicReadinessCheck::new(sonic_client.clone())));
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+    spawn_readiness_monitor(
+        health_reporter,
+        vec![
+            <AdminServiceServer<AdminServiceImpl> as NamedService>::NAME,
+            <UserServiceServer<UserServiceImpl> as NamedService>::NAME,
+            <CardpackServiceServer<CardpackServiceImpl> as NamedService>::NAME,
+        ],
+        readiness_checks,
+    );
+
+    info!(port = metrics_port, "Serving metrics.");
+    tokio::spawn(async move {
+        if let Err(err) = serve_metrics(metrics_address).await {
+            error!(error = %err, "Metrics server failed.");
+        }
+    });
+
+    info!(port, "Starting server.");
     Server::builder()
-        .add_service(AdminServiceServer::new(AdminServiceImpl::new(
-            user_collection.clone(),
-            sonic_client.clone(),
-        )))
-        .add_service(UserServiceServer::new(UserServiceImpl::new(
-            user_collection.clone(),
-            sonic_client,
-        )))
-        .add_service(CardpackServiceServer::new(CardpackServiceImpl::new(
-            Box::from(MongoCustomCardpackCollection::new(
-                mongo_database.collection("cardpacks"),
-            )),
-            Box::from(MongoCustomBlackCardCollection::new(
-                mongo_database.collection("blackCards"),
-            )),
-            Box::from(MongoCustomWhiteCardCollection::new(
-                mongo_database.collection("whiteCards"),
-            )),
-            DefaultCardpackHandler::new_with_hardcoded_packs(),
-            user_collection,
-        )))
+        .layer(RequestTracingLayer)
+        .layer(GrpcMetricsLayer)
+        .layer(rate_limit_layer)
+        .add_service(health_service)
+        .add_service(AdminServiceServer::with_interceptor(
+            AdminServiceImpl::new(user_collection.clone(), sonic_client.clone()),
+            session_auth_interceptor.clone(),
+        ))
+        .add_service(UserServiceServer::with_interceptor(
+            UserServiceImpl::new(
+                user_collection.clone(),
+                collections.user_stats_collection,
+                collections.user_achievement_collection,
+                sonic_client,
+            ),
+            session_auth_interceptor.clone(),
+        ))
+        .add_service(CardpackServiceServer::with_interceptor(
+            CardpackServiceImpl::new(
+                collections.custom_cardpack_collection,
+                collections.custom_black_card_collection,
+                collections.custom_white_card_collection,
+                DefaultCardpackHandler::new_with_hardcoded_packs(),
+                user_collection,
+                collections.white_card_stats_collection,
+            ),
+            session_auth_interceptor,
+        ))
         .serve(address)
         .await?;
     Ok(())
@@ -0,0 +1,275 @@
+use super::super::mongo::custom_black_card_collection::CustomBlackCardCollection;
+use super::super::mongo::helper::{resource_not_found_error, ItemStream};
+use super::helper::*;
+use bson::oid::ObjectId;
+use shared::basic_validation::{AnswerFieldCount, ValidatedStringField};
+use shared::proto::crusty_cards_api::*;
+use shared::proto_validation::BoundedPageSize;
+use shared::resource_name::{CustomBlackCardName, CustomCardpackName};
+use shared::time::object_id_to_timestamp_proto;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tonic::Status;
+
+struct CustomBlackCardRecord {
+    parent_user_id: ObjectId,
+    parent_custom_cardpack_id: ObjectId,
+    text: String,
+    answer_fields: i32,
+    update_time: Option<bson::DateTime>,
+    delete_time: Option<bson::DateTime>,
+}
+
+impl CustomBlackCardRecord {
+    fn to_custom_black_card(&self, object_id: &ObjectId) -> CustomBlackCard {
+        CustomBlackCard {
+            name: format!(
+                "users/{}/cardpacks/{}/blackCards/{}",
+                self.parent_user_id.to_hex(),
+                self.parent_custom_cardpack_id.to_hex(),
+                object_id.to_hex()
+            ),
+            text: self.text.clone(),
+            answer_fields: self.answer_fields,
+            create_time: Some(object_id_to_timestamp_proto(object_id)),
+            update_time: self.update_time.as_ref().map(date_time_to_timestamp_proto),
+            delete_time: self.delete_time.as_ref().map(date_time_to_timestamp_proto),
+        }
+    }
+}
+
+// Keeps black cards in memory, with the same behavior as `MongoCustomBlackCardCollection`.
+// Everything is lost when the process exits.
+#[derive(Default)]
+pub struct InMemoryCustomBlackCardCollection {
+    cards: Mutex<BTreeMap<ObjectId, CustomBlackCardRecord>>,
+}
+
+impl InMemoryCustomBlackCardCollection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Applies `update` to the card if it exists and its deletion state
+    // matches `is_deleted`, in the same way as a Mongo `findOneAndUpdate`.
+    fn find_one_and_update(
+        &self,
+        name: CustomBlackCardName,
+        is_deleted: bool,
+        update: &dyn Fn(&mut CustomBlackCardRecord),
+    ) -> Result<CustomBlackCard, Status> {
+        let name_string = name.clone_str();
+        let (user_object_id, custom_cardpack_object_id, custom_black_card_object_id) =
+            name.take_object_ids();
+        let mut cards = self.cards.lock().unwrap();
+        match cards.get_mut(&custom_black_card_object_id) {
+            Some(record)
+                if record.parent_user_id == user_object_id
+                    && record.parent_custom_cardpack_id == custom_cardpack_object_id
+                    && record.delete_time.is_some() == is_deleted =>
+            {
+                update(record);
+                Ok(record.to_custom_black_card(&custom_black_card_object_id))
+            }
+            _ => Err(resource_not_found_error(&name_string)),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl CustomBlackCardCollection for InMemoryCustomBlackCardCollection {
+    async fn create_custom_black_card(
+        &self,
+        parent: CustomCardpackName,
+        card_text: ValidatedStringField,
+        answer_fields: AnswerFieldCount,
+    ) -> Result<CustomBlackCard, Status> {
+        let object_id = ObjectId::new();
+        let (parent_user_id, parent_custom_cardpack_id) = parent.take_object_ids();
+        let record = CustomBlackCardRecord {
+            parent_user_id,
+            parent_custom_cardpack_id,
+            text: card_text.take_string(),
+            answer_fields: answer_fields.take_value(),
+            update_time: None,
+            delete_time: None,
+        };
+        let custom_black_card = record.to_custom_black_card(&object_id);
+        self.cards.lock().unwrap().insert(object_id, record);
+        Ok(custom_black_card)
+    }
+
+    async fn batch_create_custom_black_cards(
+        &self,
+        parent: CustomCardpackName,
+        data: Vec<(ValidatedStringField, AnswerFieldCount)>,
+    ) -> Result<Vec<Option<CustomBlackCard>>, Status> {
+        let mut created_black_cards = Vec::new();
+        for (card_text, answer_fields) in data {
+            created_black_cards.push(Some(
+                self.create_custom_black_card(parent.clone(), card_text, answer_fields)
+                    .await?,
+            ));
+        }
+        Ok(created_black_cards)
+    }
+
+    async fn get_custom_black_card(
+        &self,
+        name: CustomBlackCardName,
+    ) -> Result<CustomBlackCard, Status> {
+        self.find_one_and_update(name, false, &|_| {})
+    }
+
+    async fn soft_delete_custom_black_card(
+        &self,
+        name: CustomBlackCardName,
+    ) -> Result<CustomBlackCard, Status> {
+        self.find_one_and_update(name, false, &|record| {
+            record.delete_time = Some(get_current_time())
+        })
+    }
+
+    async fn undelete_custom_black_card(
+        &self,
+        name: CustomBlackCardName,
+    ) -> Result<CustomBlackCard, Status> {
+        self.find_one_and_update(name, true, &|record| record.delete_time = None)
+    }
+
+    async fn list_custom_black_cards(
+        &self,
+        parent: CustomCardpackName,
+        page_size: BoundedPageSize,
+        last_object_id_or: Option<ObjectId>,
+        show_deleted: bool,
+    ) -> Result<(Vec<CustomBlackCard>, Option<ObjectId>, i64), Status> {
+        let (parent_user_object_id, parent_custom_cardpack_object_id) = parent.take_object_ids();
+        Ok(list_items(
+            &self.cards.lock().unwrap(),
+            &|record: &CustomBlackCardRecord| {
+                record.parent_user_id == parent_user_object_id
+                    && record.parent_custom_cardpack_id == parent_custom_cardpack_object_id
+                    && record.delete_time.is_some() == show_deleted
+            },
+            page_size,
+            last_object_id_or,
+            &|object_id, record| record.to_custom_black_card(object_id),
+        ))
+    }
+
+    async fn stream_custom_black_cards(
+        &self,
+        parent: CustomCardpackName,
+    ) -> Result<ItemStream<CustomBlackCard>, Status> {
+        let (parent_user_object_id, parent_custom_cardpack_object_id) = parent.take_object_ids();
+        Ok(stream_items(
+            &self.cards.lock().unwrap(),
+            &|record: &CustomBlackCardRecord| {
+                record.parent_user_id == parent_user_object_id
+                    && record.parent_custom_cardpack_id == parent_custom_cardpack_object_id
+                    && record.delete_time.is_none()
+            },
+            &|object_id, record| record.to_custom_black_card(object_id),
+        ))
+    }
+
+    async fn update_custom_black_card(
+        &self,
+        name: CustomBlackCardName,
+        updated_card_text_or: Option<ValidatedStringField>,
+        updated_answer_fields_or: Option<AnswerFieldCount>,
+    ) -> Result<CustomBlackCard, Status> {
+        if updated_card_text_or.is_none() && updated_answer_fields_or.is_none() {
+            return self.get_custom_black_card(name).await;
+        }
+
+        let updated_card_text_or = updated_card_text_or.map(|text| text.take_string());
+        let updated_answer_fields_or =
+            updated_answer_fields_or.map(|answer_fields| answer_fields.take_value());
+        self.find_one_and_update(name, false, &|record| {
+            if let Some(updated_card_text) = &updated_card_text_or {
+                record.text = updated_card_text.clone();
+            }
+            if let Some(updated_answer_fields) = updated_answer_fields_or {
+                record.answer_fields = updated_answer_fields;
+            }
+            record.update_time = Some(get_current_time());
+        })
+    }
+
+    async fn unsafe_wipe_data_and_reset(&self) -> Result<(), mongodb::error::Error> {
+        self.cards.lock().unwrap().clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn update_and_list_black_cards() {
+        let collection = InMemoryCustomBlackCardCollection::new();
+        let cardpack_name = CustomCardpackName::new_from_str(
+            "users/5d8c5ea3e3b0ab3ac6b8fac2/cardpacks/5d8c5ea3e3b0ab3ac6b8fac3",
+        )
+        .unwrap();
+        let other_cardpack_name = CustomCardpackName::new_from_str(
+            "users/5d8c5ea3e3b0ab3ac6b8fac2/cardpacks/5d8c5ea3e3b0ab3ac6b8fac4",
+        )
+        .unwrap();
+        let mut card_names = Vec::new();
+        for parent in [&cardpack_name, &cardpack_name, &other_cardpack_name] {
+            let card = collection
+                .create_custom_black_card(
+                    parent.clone(),
+                    ValidatedStringField::new("Card _", "text").unwrap(),
+                    AnswerFieldCount::new(1, "answer_fields").unwrap(),
+                )
+                .await
+                .unwrap();
+            card_names.push(card.name);
+        }
+
+        let updated_card = collection
+            .update_custom_black_card(
+                CustomBlackCardName::new(
+                    &ValidatedStringField::new(&card_names[0], "name").unwrap(),
+                )
+                .unwrap(),
+                None,
+                Some(AnswerFieldCount::new(2, "answer_fields").unwrap()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated_card.text, "Card _");
+        assert_eq!(updated_card.answer_fields, 2);
+        assert!(updated_card.update_time.is_some());
+
+        let (cards, next_object_id_or, total_size) = collection
+            .list_custom_black_cards(
+                cardpack_name.clone(),
+                BoundedPageSize::new(1).unwrap(),
+                None,
+                false,
+            )
+            .await
+            .unwrap();
+        assert_eq!(cards, vec![updated_card]);
+        assert_eq!(total_size, 2);
+
+        let (cards, next_object_id_or, _) = collection
+            .list_custom_black_cards(
+                cardpack_name,
+                BoundedPageSize::new(1).unwrap(),
+                next_object_id_or,
+                false,
+            )
+            .await
+            .unwrap();
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].name, card_names[1]);
+        assert_eq!(next_object_id_or, None);
+    }
+}
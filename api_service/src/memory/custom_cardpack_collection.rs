@@ -0,0 +1,285 @@
+use super::super::mongo::custom_cardpack_collection::CustomCardpackCollection;
+use super::super::mongo::helper::resource_not_found_error;
+use super::helper::*;
+use bson::oid::ObjectId;
+use shared::basic_validation::ValidatedStringField;
+use shared::proto::crusty_cards_api::*;
+use shared::proto_validation::BoundedPageSize;
+use shared::resource_name::{CustomCardpackName, UserName};
+use shared::time::object_id_to_timestamp_proto;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tonic::Status;
+
+struct CustomCardpackRecord {
+    parent_user_id: ObjectId,
+    display_name: String,
+    update_time: Option<bson::DateTime>,
+    delete_time: Option<bson::DateTime>,
+}
+
+impl CustomCardpackRecord {
+    fn to_custom_cardpack(&self, object_id: &ObjectId) -> CustomCardpack {
+        CustomCardpack {
+            name: format!(
+                "users/{}/cardpacks/{}",
+                self.parent_user_id.to_hex(),
+                object_id.to_hex()
+            ),
+            display_name: self.display_name.clone(),
+            create_time: Some(object_id_to_timestamp_proto(object_id)),
+            update_time: self.update_time.as_ref().map(date_time_to_timestamp_proto),
+            delete_time: self.delete_time.as_ref().map(date_time_to_timestamp_proto),
+        }
+    }
+}
+
+// Keeps cardpacks in memory, with the same behavior as `MongoCustomCardpackCollection`.
+// Everything is lost when the process exits.
+#[derive(Default)]
+pub struct InMemoryCustomCardpackCollection {
+    cardpacks: Mutex<BTreeMap<ObjectId, CustomCardpackRecord>>,
+}
+
+impl InMemoryCustomCardpackCollection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Applies `update` to the cardpack if it exists and its deletion state
+    // matches `is_deleted`, in the same way as a Mongo `findOneAndUpdate`.
+    fn find_one_and_update(
+        &self,
+        name: CustomCardpackName,
+        is_deleted: bool,
+        update: &dyn Fn(&mut CustomCardpackRecord),
+    ) -> Result<CustomCardpack, Status> {
+        let name_string = name.clone_str();
+        let (user_object_id, custom_cardpack_object_id) = name.take_object_ids();
+        let mut cardpacks = self.cardpacks.lock().unwrap();
+        match cardpacks.get_mut(&custom_cardpack_object_id) {
+            Some(record)
+                if record.parent_user_id == user_object_id
+                    && record.delete_time.is_some() == is_deleted =>
+            {
+                update(record);
+                Ok(record.to_custom_cardpack(&custom_cardpack_object_id))
+            }
+            _ => Err(resource_not_found_error(&name_string)),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl CustomCardpackCollection for InMemoryCustomCardpackCollection {
+    async fn create_custom_cardpack(
+        &self,
+        parent: UserName,
+        display_name: ValidatedStringField,
+    ) -> Result<CustomCardpack, Status> {
+        let object_id = ObjectId::new();
+        let record = CustomCardpackRecord {
+            parent_user_id: parent.take_object_id(),
+            display_name: display_name.take_string(),
+            update_time: None,
+            delete_time: None,
+        };
+        let custom_cardpack = record.to_custom_cardpack(&object_id);
+        self.cardpacks.lock().unwrap().insert(object_id, record);
+        Ok(custom_cardpack)
+    }
+
+    async fn batch_create_custom_cardpacks(
+        &self,
+        parent: UserName,
+        display_names: Vec<ValidatedStringField>,
+    ) -> Result<Vec<Option<CustomCardpack>>, Status> {
+        let mut created_cardpacks = Vec::new();
+        for display_name in display_names {
+            created_cardpacks.push(Some(
+                self.create_custom_cardpack(parent.clone(), display_name)
+                    .await?,
+            ));
+        }
+        Ok(created_cardpacks)
+    }
+
+    async fn get_custom_cardpack(
+        &self,
+        name: CustomCardpackName,
+    ) -> Result<CustomCardpack, Status> {
+        self.find_one_and_update(name, false, &|_| {})
+    }
+
+    async fn soft_delete_custom_cardpack(
+        &self,
+        name: CustomCardpackName,
+    ) -> Result<CustomCardpack, Status> {
+        self.find_one_and_update(name, false, &|record| {
+            record.delete_time = Some(get_current_time())
+        })
+    }
+
+    async fn undelete_custom_cardpack(
+        &self,
+        name: CustomCardpackName,
+    ) -> Result<CustomCardpack, Status> {
+        self.find_one_and_update(name, true, &|record| record.delete_time = None)
+    }
+
+    async fn list_custom_cardpacks(
+        &self,
+        parent: UserName,
+        page_size: BoundedPageSize,
+        last_object_id_or: Option<ObjectId>,
+        show_deleted: bool,
+    ) -> Result<(Vec<CustomCardpack>, Option<ObjectId>, i64), Status> {
+        let parent_user_object_id = parent.take_object_id();
+        Ok(list_items(
+            &self.cardpacks.lock().unwrap(),
+            &|record: &CustomCardpackRecord| {
+                record.parent_user_id == parent_user_object_id
+                    && record.delete_time.is_some() == show_deleted
+            },
+            page_size,
+            last_object_id_or,
+            &|object_id, record| record.to_custom_cardpack(object_id),
+        ))
+    }
+
+    async fn update_custom_cardpack(
+        &self,
+        name: CustomCardpackName,
+        updated_display_name: ValidatedStringField,
+    ) -> Result<CustomCardpack, Status> {
+        let updated_display_name = updated_display_name.take_string();
+        self.find_one_and_update(name, false, &|record| {
+            record.display_name = updated_display_name.clone();
+            record.update_time = Some(get_current_time());
+        })
+    }
+
+    // Like the Mongo implementation, this also returns deleted cardpacks.
+    async fn get_cardpacks_from_names(
+        &self,
+        names: Vec<CustomCardpackName>,
+    ) -> Result<Vec<Option<CustomCardpack>>, mongodb::error::Error> {
+        let cardpacks = self.cardpacks.lock().unwrap();
+        Ok(names
+            .into_iter()
+            .map(|name| {
+                let (user_object_id, custom_cardpack_object_id) = name.take_object_ids();
+                match cardpacks.get(&custom_cardpack_object_id) {
+                    Some(record) if record.parent_user_id == user_object_id => {
+                        Some(record.to_custom_cardpack(&custom_cardpack_object_id))
+                    }
+                    _ => None,
+                }
+            })
+            .collect())
+    }
+
+    async fn unsafe_wipe_data_and_reset(&self) -> Result<(), mongodb::error::Error> {
+        self.cardpacks.lock().unwrap().clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_display_name(display_name: &str) -> ValidatedStringField {
+        ValidatedStringField::new(display_name, "display_name").unwrap()
+    }
+
+    #[tokio::test]
+    async fn soft_delete_and_undelete() {
+        let collection = InMemoryCustomCardpackCollection::new();
+        let user_name = UserName::new_from_str("users/5d8c5ea3e3b0ab3ac6b8fac2").unwrap();
+        let cardpack = collection
+            .create_custom_cardpack(user_name.clone(), create_display_name("Cardpack"))
+            .await
+            .unwrap();
+        let cardpack_name = CustomCardpackName::new_from_str(&cardpack.name).unwrap();
+
+        let deleted_cardpack = collection
+            .soft_delete_custom_cardpack(cardpack_name.clone())
+            .await
+            .unwrap();
+        assert!(deleted_cardpack.delete_time.is_some());
+        assert_eq!(
+            collection
+                .get_custom_cardpack(cardpack_name.clone())
+                .await
+                .unwrap_err()
+                .code(),
+            tonic::Code::NotFound
+        );
+        assert!(collection
+            .soft_delete_custom_cardpack(cardpack_name.clone())
+            .await
+            .is_err());
+
+        let (cardpacks, next_object_id_or, total_size) = collection
+            .list_custom_cardpacks(
+                user_name.clone(),
+                BoundedPageSize::new(10).unwrap(),
+                None,
+                true,
+            )
+            .await
+            .unwrap();
+        assert_eq!(cardpacks, vec![deleted_cardpack]);
+        assert_eq!(next_object_id_or, None);
+        assert_eq!(total_size, 1);
+
+        let undeleted_cardpack = collection
+            .undelete_custom_cardpack(cardpack_name.clone())
+            .await
+            .unwrap();
+        assert_eq!(undeleted_cardpack, cardpack);
+        assert_eq!(
+            collection.get_custom_cardpack(cardpack_name).await.unwrap(),
+            cardpack
+        );
+    }
+
+    #[tokio::test]
+    async fn cardpacks_are_only_found_under_their_owner() {
+        let collection = InMemoryCustomCardpackCollection::new();
+        let cardpack = collection
+            .create_custom_cardpack(
+                UserName::new_from_str("users/5d8c5ea3e3b0ab3ac6b8fac2").unwrap(),
+                create_display_name("Cardpack"),
+            )
+            .await
+            .unwrap();
+        let wrong_owner_name = CustomCardpackName::new_from_parent(
+            UserName::new_from_str("users/5d8c5ea3e3b0ab3ac6b8fac3").unwrap(),
+            CustomCardpackName::new_from_str(&cardpack.name)
+                .unwrap()
+                .take_object_ids()
+                .1,
+        );
+
+        assert!(collection
+            .get_custom_cardpack(wrong_owner_name.clone())
+            .await
+            .is_err());
+        assert!(collection
+            .update_custom_cardpack(wrong_owner_name.clone(), create_display_name("Renamed"))
+            .await
+            .is_err());
+        assert_eq!(
+            collection
+                .get_cardpacks_from_names(vec![
+                    wrong_owner_name,
+                    CustomCardpackName::new_from_str(&cardpack.name).unwrap()
+                ])
+                .await
+                .unwrap(),
+            vec![None, Some(cardpack)]
+        );
+    }
+}
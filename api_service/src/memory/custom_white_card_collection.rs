@@ -0,0 +1,189 @@
+use super::super::mongo::custom_white_card_collection::CustomWhiteCardCollection;
+use super::super::mongo::helper::{resource_not_found_error, ItemStream};
+use super::helper::*;
+use bson::oid::ObjectId;
+use shared::basic_validation::ValidatedStringField;
+use shared::proto::crusty_cards_api::*;
+use shared::proto_validation::BoundedPageSize;
+use shared::resource_name::{CustomCardpackName, CustomWhiteCardName};
+use shared::time::object_id_to_timestamp_proto;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tonic::Status;
+
+struct CustomWhiteCardRecord {
+    parent_user_id: ObjectId,
+    parent_custom_cardpack_id: ObjectId,
+    text: String,
+    update_time: Option<bson::DateTime>,
+    delete_time: Option<bson::DateTime>,
+}
+
+impl CustomWhiteCardRecord {
+    fn to_custom_white_card(&self, object_id: &ObjectId) -> CustomWhiteCard {
+        CustomWhiteCard {
+            name: format!(
+                "users/{}/cardpacks/{}/whiteCards/{}",
+                self.parent_user_id.to_hex(),
+                self.parent_custom_cardpack_id.to_hex(),
+                object_id.to_hex()
+            ),
+            text: self.text.clone(),
+            create_time: Some(object_id_to_timestamp_proto(object_id)),
+            update_time: self.update_time.as_ref().map(date_time_to_timestamp_proto),
+            delete_time: self.delete_time.as_ref().map(date_time_to_timestamp_proto),
+        }
+    }
+}
+
+// Keeps white cards in memory, with the same behavior as `MongoCustomWhiteCardCollection`.
+// Everything is lost when the process exits.
+#[derive(Default)]
+pub struct InMemoryCustomWhiteCardCollection {
+    cards: Mutex<BTreeMap<ObjectId, CustomWhiteCardRecord>>,
+}
+
+impl InMemoryCustomWhiteCardCollection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Applies `update` to the card if it exists and its deletion state
+    // matches `is_deleted`, in the same way as a Mongo `findOneAndUpdate`.
+    fn find_one_and_update(
+        &self,
+        name: CustomWhiteCardName,
+        is_deleted: bool,
+        update: &dyn Fn(&mut CustomWhiteCardRecord),
+    ) -> Result<CustomWhiteCard, Status> {
+        let name_string = name.clone_str();
+        let (user_object_id, custom_cardpack_object_id, custom_white_card_object_id) =
+            name.take_object_ids();
+        let mut cards = self.cards.lock().unwrap();
+        match cards.get_mut(&custom_white_card_object_id) {
+            Some(record)
+                if record.parent_user_id == user_object_id
+                    && record.parent_custom_cardpack_id == custom_cardpack_object_id
+                    && record.delete_time.is_some() == is_deleted =>
+            {
+                update(record);
+                Ok(record.to_custom_white_card(&custom_white_card_object_id))
+            }
+            _ => Err(resource_not_found_error(&name_string)),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl CustomWhiteCardCollection for InMemoryCustomWhiteCardCollection {
+    async fn create_custom_white_card(
+        &self,
+        parent: CustomCardpackName,
+        card_text: ValidatedStringField,
+    ) -> Result<CustomWhiteCard, Status> {
+        let object_id = ObjectId::new();
+        let (parent_user_id, parent_custom_cardpack_id) = parent.take_object_ids();
+        let record = CustomWhiteCardRecord {
+            parent_user_id,
+            parent_custom_cardpack_id,
+            text: card_text.take_string(),
+            update_time: None,
+            delete_time: None,
+        };
+        let custom_white_card = record.to_custom_white_card(&object_id);
+        self.cards.lock().unwrap().insert(object_id, record);
+        Ok(custom_white_card)
+    }
+
+    async fn batch_create_custom_white_cards(
+        &self,
+        parent: CustomCardpackName,
+        card_texts: Vec<ValidatedStringField>,
+    ) -> Result<Vec<Option<CustomWhiteCard>>, Status> {
+        let mut created_white_cards = Vec::new();
+        for card_text in card_texts {
+            created_white_cards.push(Some(
+                self.create_custom_white_card(parent.clone(), card_text)
+                    .await?,
+            ));
+        }
+        Ok(created_white_cards)
+    }
+
+    async fn get_custom_white_card(
+        &self,
+        name: CustomWhiteCardName,
+    ) -> Result<CustomWhiteCard, Status> {
+        self.find_one_and_update(name, false, &|_| {})
+    }
+
+    async fn soft_delete_custom_white_card(
+        &self,
+        name: CustomWhiteCardName,
+    ) -> Result<CustomWhiteCard, Status> {
+        self.find_one_and_update(name, false, &|record| {
+            record.delete_time = Some(get_current_time())
+        })
+    }
+
+    async fn undelete_custom_white_card(
+        &self,
+        name: CustomWhiteCardName,
+    ) -> Result<CustomWhiteCard, Status> {
+        self.find_one_and_update(name, true, &|record| record.delete_time = None)
+    }
+
+    async fn list_custom_white_cards(
+        &self,
+        parent: CustomCardpackName,
+        page_size: BoundedPageSize,
+        last_object_id_or: Option<ObjectId>,
+        show_deleted: bool,
+    ) -> Result<(Vec<CustomWhiteCard>, Option<ObjectId>, i64), Status> {
+        let (parent_user_object_id, parent_custom_cardpack_object_id) = parent.take_object_ids();
+        Ok(list_items(
+            &self.cards.lock().unwrap(),
+            &|record: &CustomWhiteCardRecord| {
+                record.parent_user_id == parent_user_object_id
+                    && record.parent_custom_cardpack_id == parent_custom_cardpack_object_id
+                    && record.delete_time.is_some() == show_deleted
+            },
+            page_size,
+            last_object_id_or,
+            &|object_id, record| record.to_custom_white_card(object_id),
+        ))
+    }
+
+    async fn stream_custom_white_cards(
+        &self,
+        parent: CustomCardpackName,
+    ) -> Result<ItemStream<CustomWhiteCard>, Status> {
+        let (parent_user_object_id, parent_custom_cardpack_object_id) = parent.take_object_ids();
+        Ok(stream_items(
+            &self.cards.lock().unwrap(),
+            &|record: &CustomWhiteCardRecord| {
+                record.parent_user_id == parent_user_object_id
+                    && record.parent_custom_cardpack_id == parent_custom_cardpack_object_id
+                    && record.delete_time.is_none()
+            },
+            &|object_id, record| record.to_custom_white_card(object_id),
+        ))
+    }
+
+    async fn update_custom_white_card(
+        &self,
+        name: CustomWhiteCardName,
+        updated_card_text: ValidatedStringField,
+    ) -> Result<CustomWhiteCard, Status> {
+        let updated_card_text = updated_card_text.take_string();
+        self.find_one_and_update(name, false, &|record| {
+            record.text = updated_card_text.clone();
+            record.update_time = Some(get_current_time());
+        })
+    }
+
+    async fn unsafe_wipe_data_and_reset(&self) -> Result<(), mongodb::error::Error> {
+        self.cards.lock().unwrap().clear();
+        Ok(())
+    }
+}
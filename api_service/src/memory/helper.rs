@@ -0,0 +1,142 @@
+use super::super::mongo::helper::ItemStream;
+use bson::oid::ObjectId;
+use shared::proto::google::protobuf::Timestamp;
+use shared::proto_validation::BoundedPageSize;
+use shared::time::chrono_timestamp_to_timestamp_proto;
+use std::collections::BTreeMap;
+use tonic::Status;
+
+// Mongo stores `$currentDate` with millisecond precision,
+// so in-memory timestamps are truncated the same way.
+pub fn get_current_time() -> bson::DateTime {
+    bson::DateTime::now()
+}
+
+pub fn date_time_to_timestamp_proto(date_time: &bson::DateTime) -> Timestamp {
+    chrono_timestamp_to_timestamp_proto(&date_time.to_chrono())
+}
+
+// In-memory equivalent of `mongo::helper::list_items`. Items are kept in a
+// `BTreeMap` so that they're listed in the same ObjectId order as in Mongo.
+pub fn list_items<R, T>(
+    items: &BTreeMap<ObjectId, R>,
+    is_match: &dyn Fn(&R) -> bool,
+    page_size: BoundedPageSize,
+    previous_item_id_or: Option<ObjectId>,
+    convert_item: &dyn Fn(&ObjectId, &R) -> T,
+) -> (Vec<T>, Option<ObjectId>, i64) {
+    let page_size_usize = page_size.take_i64() as usize;
+
+    let matching_item_count = items.values().filter(|item| is_match(item)).count() as i64;
+
+    let mut page: Vec<(&ObjectId, &R)> = items
+        .iter()
+        .filter(|(object_id, _)| match &previous_item_id_or {
+            Some(previous_item_id) => *object_id > previous_item_id,
+            None => true,
+        })
+        .filter(|(_, item)| is_match(item))
+        .take(page_size_usize + 1)
+        .collect();
+
+    let next_item_id_or = if page.len() > page_size_usize {
+        page.pop();
+        page.last().map(|(object_id, _)| **object_id)
+    } else {
+        None
+    };
+
+    let items = page
+        .into_iter()
+        .map(|(object_id, item)| convert_item(object_id, item))
+        .collect();
+
+    (items, next_item_id_or, matching_item_count)
+}
+
+// In-memory equivalent of `mongo::helper::stream_items`. Matching items are
+// copied when the stream is created, so later writes don't show up in it.
+pub fn stream_items<R, T: Send + 'static>(
+    items: &BTreeMap<ObjectId, R>,
+    is_match: &dyn Fn(&R) -> bool,
+    convert_item: &dyn Fn(&ObjectId, &R) -> T,
+) -> ItemStream<T> {
+    let items: Vec<Result<T, Status>> = items
+        .iter()
+        .filter(|(_, item)| is_match(item))
+        .map(|(object_id, item)| Ok(convert_item(object_id, item)))
+        .collect();
+    Box::from(futures_lite::stream::iter(items))
+}
+
+// Used by lists that are paginated by index rather than by ObjectId.
+pub fn take_page<T>(
+    items: Vec<T>,
+    page_size: BoundedPageSize,
+    start_index: usize,
+) -> (Vec<T>, Option<usize>) {
+    let page_size_usize = page_size.take_i64() as usize;
+    let mut page: Vec<T> = items
+        .into_iter()
+        .skip(start_index)
+        .take(page_size_usize + 1)
+        .collect();
+    let next_index_or = if page.len() > page_size_usize {
+        page.pop();
+        Some(start_index + page_size_usize)
+    } else {
+        None
+    };
+    (page, next_index_or)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_items_pages_in_object_id_order() {
+        let mut items = BTreeMap::new();
+        for value in 0..5 {
+            items.insert(
+                ObjectId::from_bytes([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, value]),
+                value,
+            );
+        }
+        let is_even = |value: &u8| value % 2 == 0;
+        let get_value = |_: &ObjectId, value: &u8| *value;
+
+        let (page, next_item_id_or, total_size) = list_items(
+            &items,
+            &is_even,
+            BoundedPageSize::new(2).unwrap(),
+            None,
+            &get_value,
+        );
+        assert_eq!(page, vec![0, 2]);
+        assert_eq!(total_size, 3);
+
+        let (page, next_item_id_or, total_size) = list_items(
+            &items,
+            &is_even,
+            BoundedPageSize::new(2).unwrap(),
+            next_item_id_or,
+            &get_value,
+        );
+        assert_eq!(page, vec![4]);
+        assert_eq!(next_item_id_or, None);
+        assert_eq!(total_size, 3);
+    }
+
+    #[test]
+    fn take_page() {
+        assert_eq!(
+            super::take_page(vec![1, 2, 3], BoundedPageSize::new(2).unwrap(), 0),
+            (vec![1, 2], Some(2))
+        );
+        assert_eq!(
+            super::take_page(vec![1, 2, 3], BoundedPageSize::new(2).unwrap(), 2),
+            (vec![3], None)
+        );
+    }
+}
@@ -0,0 +1,8 @@
+pub mod custom_black_card_collection;
+pub mod custom_cardpack_collection;
+pub mod custom_white_card_collection;
+pub mod helper;
+pub mod user_achievement_collection;
+pub mod user_collection;
+pub mod user_stats_collection;
+pub mod white_card_stats_collection;
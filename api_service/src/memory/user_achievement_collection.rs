@@ -0,0 +1,104 @@
+use super::super::mongo::user_achievement_collection::UserAchievementCollection;
+use super::helper::{date_time_to_timestamp_proto, get_current_time, take_page};
+use bson::oid::ObjectId;
+use shared::achievements::get_achievement_definition;
+use shared::proto::crusty_cards_api::UserAchievement;
+use shared::proto_validation::BoundedPageSize;
+use shared::resource_name::UserName;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tonic::Status;
+
+struct AchievementProgressRecord {
+    progress: i64,
+    unlock_time: Option<bson::DateTime>,
+}
+
+// Keeps achievement progress in memory, with the same behavior as
+// `MongoUserAchievementCollection`. Everything is lost when the process exits.
+#[derive(Default)]
+pub struct InMemoryUserAchievementCollection {
+    // Keyed by user id and achievement id.
+    progress: Mutex<HashMap<(ObjectId, String), AchievementProgressRecord>>,
+}
+
+impl InMemoryUserAchievementCollection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[tonic::async_trait]
+impl UserAchievementCollection for InMemoryUserAchievementCollection {
+    async fn add_achievement_progress(
+        &self,
+        user_name: UserName,
+        achievement_id: String,
+        progress: i64,
+        required_progress: i64,
+    ) -> Result<(), Status> {
+        let mut all_progress = self.progress.lock().unwrap();
+        let record = all_progress
+            .entry((user_name.take_object_id(), achievement_id))
+            .or_insert(AchievementProgressRecord {
+                progress: 0,
+                unlock_time: None,
+            });
+        record.progress += progress;
+        if record.progress >= required_progress && record.unlock_time.is_none() {
+            record.unlock_time = Some(get_current_time());
+        }
+        Ok(())
+    }
+
+    async fn list_unlocked_achievements(
+        &self,
+        user_name: UserName,
+        page_size: BoundedPageSize,
+        start_index: usize,
+    ) -> Result<(Vec<UserAchievement>, Option<usize>, i64), Status> {
+        let user_object_id = user_name.clone().take_object_id();
+        let mut unlocked_achievements: Vec<(bson::DateTime, String)> = self
+            .progress
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|((record_user_object_id, _), _)| record_user_object_id == &user_object_id)
+            .filter_map(|((_, achievement_id), record)| {
+                record
+                    .unlock_time
+                    .map(|unlock_time| (unlock_time, achievement_id.clone()))
+            })
+            .collect();
+        let total_size = unlocked_achievements.len() as i64;
+        // Same order as `unlocked_achievements_sort_doc`.
+        unlocked_achievements.sort();
+        let (page, next_index_or) = take_page(unlocked_achievements, page_size, start_index);
+
+        let user_name_string = user_name.clone_str();
+        Ok((
+            page.into_iter()
+                .map(|(unlock_time, achievement_id)| {
+                    let (display_name, description) =
+                        match get_achievement_definition(&achievement_id) {
+                            Some(definition) => (definition.display_name, definition.description),
+                            None => ("", ""),
+                        };
+                    UserAchievement {
+                        name: format!("{}/achievements/{}", user_name_string, achievement_id),
+                        display_name: String::from(display_name),
+                        description: String::from(description),
+                        unlock_time: Some(date_time_to_timestamp_proto(&unlock_time)),
+                    }
+                })
+                .collect(),
+            next_index_or,
+            total_size,
+        ))
+    }
+
+    async fn unsafe_wipe_data_and_reset(&self) -> Result<(), mongodb::error::Error> {
+        self.progress.lock().unwrap().clear();
+        Ok(())
+    }
+}
@@ -0,0 +1,433 @@
+use super::super::authorization::UserRole;
+use super::super::mongo::helper::resource_not_found_error;
+use super::super::mongo::user_collection::{get_stored_game_config, UserCollection, UserStream};
+use super::helper::*;
+use bson::oid::ObjectId;
+use shared::basic_validation::ValidatedStringField;
+use shared::proto::crusty_cards_api::*;
+use shared::proto_validation::{
+    BoundedPageSize, OptionalField, ValidatedColorScheme, ValidatedGameConfig,
+    ValidatedOAuthCredentials,
+};
+use shared::resource_name::{CustomCardpackName, UserName, UserSettingsName};
+use shared::time::object_id_to_timestamp_proto;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tonic::Status;
+
+struct UserRecord {
+    oauth_id: String,
+    oauth_provider: String,
+    display_name: String,
+    update_time: Option<bson::DateTime>,
+    color_scheme: i32,
+    quick_start_game_config_or: Option<GameConfig>,
+    role: UserRole,
+    favorited_cardpack_ids: Vec<ObjectId>,
+}
+
+impl UserRecord {
+    fn to_user(&self, object_id: &ObjectId) -> User {
+        User {
+            name: format!("users/{}", object_id.to_hex()),
+            display_name: self.display_name.clone(),
+            create_time: Some(object_id_to_timestamp_proto(object_id)),
+            update_time: self.update_time.as_ref().map(date_time_to_timestamp_proto),
+        }
+    }
+
+    fn to_user_settings(&self, object_id: &ObjectId) -> UserSettings {
+        UserSettings {
+            name: format!("users/{}/settings", object_id.to_hex()),
+            color_scheme: self.color_scheme,
+            quick_start_game_config: self.quick_start_game_config_or.clone(),
+        }
+    }
+}
+
+// Keeps users in memory, with the same behavior as `MongoUserCollection`.
+// Everything is lost when the process exits.
+#[derive(Default)]
+pub struct InMemoryUserCollection {
+    users: Mutex<BTreeMap<ObjectId, UserRecord>>,
+}
+
+impl InMemoryUserCollection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Applies `update` to the user and returns whatever `read` takes from
+    // it, or a not found error for `name_string` if the user doesn't exist.
+    // Reads pass an `update` that does nothing.
+    fn with_user_record<T>(
+        &self,
+        user_object_id: &ObjectId,
+        name_string: &str,
+        update: &mut dyn FnMut(&mut UserRecord),
+        read: &dyn Fn(&ObjectId, &UserRecord) -> T,
+    ) -> Result<T, Status> {
+        let mut users = self.users.lock().unwrap();
+        match users.get_mut(user_object_id) {
+            Some(record) => {
+                update(record);
+                Ok(read(user_object_id, record))
+            }
+            None => Err(resource_not_found_error(name_string)),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl UserCollection for InMemoryUserCollection {
+    async fn get_user(&self, name: UserName) -> Result<User, Status> {
+        let name_string = name.clone_str();
+        self.with_user_record(
+            &name.take_object_id(),
+            &name_string,
+            &mut |_| {},
+            &|object_id, record| record.to_user(object_id),
+        )
+    }
+
+    async fn update_user(
+        &self,
+        name: UserName,
+        updated_display_name: ValidatedStringField,
+    ) -> Result<User, Status> {
+        let name_string = name.clone_str();
+        let mut updated_display_name_or = Some(updated_display_name.take_string());
+        self.with_user_record(
+            &name.take_object_id(),
+            &name_string,
+            &mut |record| {
+                if let Some(updated_display_name) = updated_display_name_or.take() {
+                    record.display_name = updated_display_name;
+                }
+                record.update_time = Some(get_current_time());
+            },
+            &|object_id, record| record.to_user(object_id),
+        )
+    }
+
+    async fn get_user_settings(&self, name: UserSettingsName) -> Result<UserSettings, Status> {
+        let name_string = name.clone_str();
+        self.with_user_record(
+            &name.take_object_id(),
+            &name_string,
+            &mut |_| {},
+            &|object_id, record| record.to_user_settings(object_id),
+        )
+    }
+
+    async fn update_user_settings(
+        &self,
+        name: UserSettingsName,
+        color_scheme_or: Option<ValidatedColorScheme>,
+        quick_start_game_config_or: Option<OptionalField<ValidatedGameConfig>>,
+    ) -> Result<UserSettings, Status> {
+        let name_string = name.clone_str();
+        let color_scheme_or = color_scheme_or.map(|color_scheme| color_scheme as i32);
+        let quick_start_game_config_or =
+            quick_start_game_config_or.map(|optional_quick_start_game_config| {
+                match optional_quick_start_game_config {
+                    OptionalField::Set(quick_start_game_config) => Some(get_stored_game_config(
+                        &quick_start_game_config.raw_config(),
+                    )),
+                    OptionalField::Unset => None,
+                }
+            });
+        self.with_user_record(
+            &name.take_object_id(),
+            &name_string,
+            &mut |record| {
+                if let Some(color_scheme) = color_scheme_or {
+                    record.color_scheme = color_scheme;
+                }
+                if let Some(quick_start_game_config) = &quick_start_game_config_or {
+                    record.quick_start_game_config_or = quick_start_game_config.clone();
+                }
+            },
+            &|object_id, record| record.to_user_settings(object_id),
+        )
+    }
+
+    async fn get_or_create_user(
+        &self,
+        validated_oauth_credentials: ValidatedOAuthCredentials,
+        display_name: ValidatedStringField,
+    ) -> Result<User, Status> {
+        let oauth_credentials = validated_oauth_credentials.take_oauth_credentials();
+        let mut users = self.users.lock().unwrap();
+        if let Some((object_id, record)) = users.iter().find(|(_, record)| {
+            record.oauth_id == oauth_credentials.oauth_id
+                && record.oauth_provider == oauth_credentials.oauth_provider
+        }) {
+            return Ok(record.to_user(object_id));
+        }
+        let object_id = ObjectId::new();
+        let record = UserRecord {
+            oauth_id: oauth_credentials.oauth_id,
+            oauth_provider: oauth_credentials.oauth_provider,
+            display_name: display_name.take_string(),
+            update_time: None,
+            color_scheme: ValidatedColorScheme::DefaultLight as i32,
+            quick_start_game_config_or: None,
+            role: UserRole::User,
+            favorited_cardpack_ids: Vec::new(),
+        };
+        let user = record.to_user(&object_id);
+        users.insert(object_id, record);
+        Ok(user)
+    }
+
+    async fn assert_user_exists(&self, name: UserName) -> Result<(), Status> {
+        if self
+            .users
+            .lock()
+            .unwrap()
+            .contains_key(name.get_object_id())
+        {
+            Ok(())
+        } else {
+            Err(Status::not_found("User does not exist."))
+        }
+    }
+
+    async fn get_user_role(&self, name: UserName) -> Result<UserRole, Status> {
+        let name_string = name.clone_str();
+        self.with_user_record(
+            &name.take_object_id(),
+            &name_string,
+            &mut |_| {},
+            &|_, record| record.role,
+        )
+    }
+
+    async fn set_user_role(&self, name: UserName, role: UserRole) -> Result<(), Status> {
+        let name_string = name.clone_str();
+        self.with_user_record(
+            &name.take_object_id(),
+            &name_string,
+            &mut |record| record.role = role,
+            &|_, _| (),
+        )
+    }
+
+    async fn get_users_from_names(
+        &self,
+        names: Vec<UserName>,
+    ) -> Result<Vec<Option<User>>, mongodb::error::Error> {
+        let users = self.users.lock().unwrap();
+        Ok(names
+            .iter()
+            .map(|name| {
+                users
+                    .get(name.get_object_id())
+                    .map(|record| record.to_user(name.get_object_id()))
+            })
+            .collect())
+    }
+
+    async fn add_custom_cardpack_to_favorites(
+        &self,
+        user_name: UserName,
+        custom_cardpack_name: CustomCardpackName,
+    ) -> Result<(), Status> {
+        let custom_cardpack_object_id = custom_cardpack_name.take_object_ids().1;
+        let mut users = self.users.lock().unwrap();
+        // Like `$addToSet`, favoriting a cardpack twice doesn't modify anything.
+        match users.get_mut(user_name.get_object_id()) {
+            Some(record)
+                if !record
+                    .favorited_cardpack_ids
+                    .contains(&custom_cardpack_object_id) =>
+            {
+                record
+                    .favorited_cardpack_ids
+                    .push(custom_cardpack_object_id);
+                Ok(())
+            }
+            _ => Err(Status::unknown(
+                "Failed to add custom cardpack to favorites.",
+            )),
+        }
+    }
+
+    async fn remove_custom_cardpack_from_favorites(
+        &self,
+        user_name: UserName,
+        custom_cardpack_name: CustomCardpackName,
+    ) -> Result<(), Status> {
+        let custom_cardpack_object_id = custom_cardpack_name.take_object_ids().1;
+        let mut users = self.users.lock().unwrap();
+        match users.get_mut(user_name.get_object_id()) {
+            Some(record)
+                if record
+                    .favorited_cardpack_ids
+                    .contains(&custom_cardpack_object_id) =>
+            {
+                record
+                    .favorited_cardpack_ids
+                    .retain(|object_id| object_id != &custom_cardpack_object_id);
+                Ok(())
+            }
+            _ => Err(Status::unknown(
+                "Failed to remove custom cardpack from favorites.",
+            )),
+        }
+    }
+
+    async fn check_has_user_favorited_custom_cardpack(
+        &self,
+        user_name: UserName,
+        custom_cardpack_name: CustomCardpackName,
+    ) -> Result<bool, Status> {
+        let custom_cardpack_object_id = custom_cardpack_name.take_object_ids().1;
+        Ok(
+            match self.users.lock().unwrap().get(user_name.get_object_id()) {
+                Some(record) => record
+                    .favorited_cardpack_ids
+                    .contains(&custom_cardpack_object_id),
+                None => false,
+            },
+        )
+    }
+
+    async fn list_favorited_custom_cardpack_names(
+        &self,
+        user_name: UserName,
+        page_size: BoundedPageSize,
+        start_index: usize,
+    ) -> Result<(Vec<CustomCardpackName>, Option<usize>), Status> {
+        let favorited_cardpack_ids = match self.users.lock().unwrap().get(user_name.get_object_id())
+        {
+            Some(record) => record.favorited_cardpack_ids.clone(),
+            None => return Err(Status::not_found("User does not exist.")),
+        };
+        let (page, next_index_or) = take_page(favorited_cardpack_ids, page_size, start_index);
+        Ok((
+            page.into_iter()
+                .map(|object_id| CustomCardpackName::new_from_parent(user_name.clone(), object_id))
+                .collect(),
+            next_index_or,
+        ))
+    }
+
+    async fn user_stream(&self) -> Result<UserStream, mongodb::error::Error> {
+        let users: Vec<Result<User, mongodb::error::Error>> = self
+            .users
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(object_id, record)| Ok(record.to_user(object_id)))
+            .collect();
+        Ok(Box::from(futures_lite::stream::iter(users)))
+    }
+
+    async fn unsafe_wipe_data_and_reset(&self) -> Result<(), mongodb::error::Error> {
+        self.users.lock().unwrap().clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn create_user(collection: &InMemoryUserCollection, oauth_id: &str) -> UserName {
+        let user = collection
+            .get_or_create_user(
+                ValidatedOAuthCredentials::new(
+                    &OAuthCredentials {
+                        oauth_provider: String::from("google"),
+                        oauth_id: String::from(oauth_id),
+                    },
+                    "oauth_credentials",
+                )
+                .unwrap(),
+                ValidatedStringField::new("Tommy", "display_name").unwrap(),
+            )
+            .await
+            .unwrap();
+        UserName::new_from_str(&user.name).unwrap()
+    }
+
+    #[tokio::test]
+    async fn get_or_create_user_only_creates_once() {
+        let collection = InMemoryUserCollection::new();
+        let user_name = create_user(&collection, "1234").await;
+        assert!(create_user(&collection, "1234").await == user_name);
+        assert!(create_user(&collection, "5678").await != user_name);
+
+        let user_settings = collection
+            .get_user_settings(
+                UserSettingsName::new_from_str(&format!("{}/settings", user_name.clone_str()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            user_settings.color_scheme,
+            ValidatedColorScheme::DefaultLight as i32
+        );
+        assert_eq!(
+            collection.get_user_role(user_name).await.unwrap(),
+            UserRole::User
+        );
+    }
+
+    #[tokio::test]
+    async fn favorite_custom_cardpacks() {
+        let collection = InMemoryUserCollection::new();
+        let user_name = create_user(&collection, "1234").await;
+        let mut cardpack_names = Vec::new();
+        for _ in 0..3 {
+            let cardpack_name = CustomCardpackName::new_from_parent(
+                UserName::new_from_str("users/5d8c5ea3e3b0ab3ac6b8fac2").unwrap(),
+                ObjectId::new(),
+            );
+            collection
+                .add_custom_cardpack_to_favorites(user_name.clone(), cardpack_name.clone())
+                .await
+                .unwrap();
+            cardpack_names.push(cardpack_name);
+        }
+
+        // Favoriting a cardpack twice fails, the same as with Mongo.
+        assert!(collection
+            .add_custom_cardpack_to_favorites(user_name.clone(), cardpack_names[0].clone())
+            .await
+            .is_err());
+        assert!(collection
+            .check_has_user_favorited_custom_cardpack(user_name.clone(), cardpack_names[1].clone())
+            .await
+            .unwrap());
+
+        let (page, next_index_or) = collection
+            .list_favorited_custom_cardpack_names(
+                user_name.clone(),
+                BoundedPageSize::new(2).unwrap(),
+                0,
+            )
+            .await
+            .unwrap();
+        assert_eq!(page.len(), 2);
+        assert_eq!(next_index_or, Some(2));
+
+        collection
+            .remove_custom_cardpack_from_favorites(user_name.clone(), cardpack_names[1].clone())
+            .await
+            .unwrap();
+        assert!(!collection
+            .check_has_user_favorited_custom_cardpack(user_name.clone(), cardpack_names[1].clone())
+            .await
+            .unwrap());
+        let (page, next_index_or) = collection
+            .list_favorited_custom_cardpack_names(user_name, BoundedPageSize::new(2).unwrap(), 0)
+            .await
+            .unwrap();
+        assert_eq!(page.len(), 2);
+        assert_eq!(next_index_or, None);
+    }
+}
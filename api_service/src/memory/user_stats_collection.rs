@@ -0,0 +1,88 @@
+use super::super::mongo::user_stats_collection::UserStatsCollection;
+use super::helper::take_page;
+use bson::oid::ObjectId;
+use shared::proto::crusty_cards_api::{UserStats, UserStatsIncrement};
+use shared::proto_validation::BoundedPageSize;
+use shared::resource_name::{UserName, UserStatsName};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tonic::Status;
+
+fn create_user_stats(user_object_id: &ObjectId, user_stats_or: Option<&UserStats>) -> UserStats {
+    let mut user_stats = user_stats_or.cloned().unwrap_or_default();
+    user_stats.name = format!("users/{}/stats", user_object_id.to_hex());
+    user_stats
+}
+
+// Keeps user stats in memory, with the same behavior as `MongoUserStatsCollection`.
+// Everything is lost when the process exits.
+#[derive(Default)]
+pub struct InMemoryUserStatsCollection {
+    user_stats: Mutex<HashMap<ObjectId, UserStats>>,
+}
+
+impl InMemoryUserStatsCollection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[tonic::async_trait]
+impl UserStatsCollection for InMemoryUserStatsCollection {
+    async fn get_user_stats(&self, name: UserStatsName) -> Result<UserStats, Status> {
+        let user_object_id = name.take_object_id();
+        Ok(create_user_stats(
+            &user_object_id,
+            self.user_stats.lock().unwrap().get(&user_object_id),
+        ))
+    }
+
+    async fn increment_user_stats(
+        &self,
+        increments: Vec<(UserName, UserStatsIncrement)>,
+    ) -> Result<(), Status> {
+        let mut user_stats = self.user_stats.lock().unwrap();
+        for (user_name, increment) in increments {
+            let user_stats = user_stats
+                .entry(user_name.take_object_id())
+                .or_insert_with(UserStats::default);
+            user_stats.games_played += increment.games_played;
+            user_stats.games_won += increment.games_won;
+            user_stats.rounds_won += increment.rounds_won;
+            user_stats.rounds_judged += increment.rounds_judged;
+        }
+        Ok(())
+    }
+
+    async fn list_leaderboard(
+        &self,
+        page_size: BoundedPageSize,
+        start_index: usize,
+    ) -> Result<(Vec<UserStats>, Option<usize>, i64), Status> {
+        let mut leaderboard: Vec<UserStats> = self
+            .user_stats
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(user_object_id, user_stats)| create_user_stats(user_object_id, Some(user_stats)))
+            .collect();
+        let total_size = leaderboard.len() as i64;
+        // Same order as `leaderboard_sort_doc`. Names only differ by
+        // user id, so they break ties the same way that ids do.
+        leaderboard.sort_by(|a, b| {
+            (Reverse(a.games_won), Reverse(a.rounds_won), &a.name).cmp(&(
+                Reverse(b.games_won),
+                Reverse(b.rounds_won),
+                &b.name,
+            ))
+        });
+        let (page, next_index_or) = take_page(leaderboard, page_size, start_index);
+        Ok((page, next_index_or, total_size))
+    }
+
+    async fn unsafe_wipe_data_and_reset(&self) -> Result<(), mongodb::error::Error> {
+        self.user_stats.lock().unwrap().clear();
+        Ok(())
+    }
+}
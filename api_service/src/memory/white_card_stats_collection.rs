@@ -0,0 +1,154 @@
+use super::super::mongo::white_card_stats_collection::{
+    take_never_picked_page, WhiteCardStatsCollection,
+};
+use super::helper::take_page;
+use shared::proto::crusty_cards_api::WhiteCardStats;
+use shared::proto_validation::BoundedPageSize;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Mutex;
+use tonic::Status;
+
+struct WhiteCardStatsRecord {
+    parent: String,
+    play_count: i64,
+    win_count: i64,
+}
+
+impl WhiteCardStatsRecord {
+    fn to_white_card_stats(&self, card_name: &str) -> WhiteCardStats {
+        WhiteCardStats {
+            card_name: String::from(card_name),
+            play_count: self.play_count,
+            win_count: self.win_count,
+        }
+    }
+}
+
+// Keeps white card stats in memory, with the same behavior as
+// `MongoWhiteCardStatsCollection`. Everything is lost when the process exits.
+#[derive(Default)]
+pub struct InMemoryWhiteCardStatsCollection {
+    // Keyed by card name.
+    white_card_stats: Mutex<BTreeMap<String, WhiteCardStatsRecord>>,
+}
+
+impl InMemoryWhiteCardStatsCollection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn get_white_card_stats(
+        &self,
+        is_match: &dyn Fn(&WhiteCardStatsRecord) -> bool,
+    ) -> Vec<WhiteCardStats> {
+        self.white_card_stats
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, record)| is_match(record))
+            .map(|(card_name, record)| record.to_white_card_stats(card_name))
+            .collect()
+    }
+}
+
+#[tonic::async_trait]
+impl WhiteCardStatsCollection for InMemoryWhiteCardStatsCollection {
+    async fn increment_white_card_stats(
+        &self,
+        parent: String,
+        white_card_stats: WhiteCardStats,
+    ) -> Result<(), Status> {
+        let mut all_white_card_stats = self.white_card_stats.lock().unwrap();
+        let record = all_white_card_stats
+            .entry(white_card_stats.card_name)
+            .or_insert(WhiteCardStatsRecord {
+                parent: String::new(),
+                play_count: 0,
+                win_count: 0,
+            });
+        record.parent = parent;
+        record.play_count += white_card_stats.play_count;
+        record.win_count += white_card_stats.win_count;
+        Ok(())
+    }
+
+    async fn list_top_white_cards(
+        &self,
+        parent: String,
+        page_size: BoundedPageSize,
+        start_index: usize,
+    ) -> Result<(Vec<WhiteCardStats>, Option<usize>, i64), Status> {
+        let mut top_white_cards =
+            self.get_white_card_stats(&|record| record.parent == parent && record.win_count > 0);
+        let total_size = top_white_cards.len() as i64;
+        // Same order as `top_white_cards_sort_doc`.
+        top_white_cards.sort_by(|a, b| {
+            (Reverse(a.win_count), a.play_count, &a.card_name).cmp(&(
+                Reverse(b.win_count),
+                b.play_count,
+                &b.card_name,
+            ))
+        });
+        let (page, next_index_or) = take_page(top_white_cards, page_size, start_index);
+        Ok((page, next_index_or, total_size))
+    }
+
+    async fn list_never_picked_white_cards(
+        &self,
+        parent: String,
+        card_names: Vec<String>,
+        page_size: BoundedPageSize,
+        start_index: usize,
+    ) -> Result<(Vec<WhiteCardStats>, Option<usize>, i64), Status> {
+        let page_size_i64 = page_size.take_i64();
+        let card_name_set: HashSet<&String> = card_names.iter().collect();
+        let played_white_cards: Vec<WhiteCardStats> = self
+            .white_card_stats
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(card_name, record)| {
+                record.parent == parent
+                    && record.play_count > 0
+                    && card_name_set.contains(card_name)
+            })
+            .map(|(card_name, record)| record.to_white_card_stats(card_name))
+            .collect();
+        let played_card_names: HashSet<String> = played_white_cards
+            .iter()
+            .map(|white_card_stats| white_card_stats.card_name.clone())
+            .collect();
+
+        let mut never_picked_white_cards: Vec<WhiteCardStats> = played_white_cards
+            .into_iter()
+            .filter(|white_card_stats| white_card_stats.win_count == 0)
+            .collect();
+        // Same order as `never_picked_white_cards_sort_doc`.
+        never_picked_white_cards.sort_by(|a, b| {
+            (Reverse(a.play_count), &a.card_name).cmp(&(Reverse(b.play_count), &b.card_name))
+        });
+        let played_count = never_picked_white_cards.len();
+        let played_page = never_picked_white_cards
+            .into_iter()
+            .skip(start_index)
+            .take(page_size_i64 as usize + 1)
+            .collect();
+
+        Ok(take_never_picked_page(
+            played_page,
+            played_count,
+            card_names
+                .into_iter()
+                .filter(|card_name| !played_card_names.contains(card_name))
+                .collect(),
+            page_size_i64,
+            start_index,
+        ))
+    }
+
+    async fn unsafe_wipe_data_and_reset(&self) -> Result<(), mongodb::error::Error> {
+        self.white_card_stats.lock().unwrap().clear();
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+use lazy_static::lazy_static;
+use prometheus::{register_histogram_vec, register_int_counter_vec};
+use prometheus::{HistogramTimer, HistogramVec, IntCounterVec};
+
+lazy_static! {
+    static ref MONGO_OPERATION_DURATION_SECONDS: HistogramVec = register_histogram_vec!(
+        "crusty_cards_mongo_operation_duration_seconds",
+        "Time taken by each collection method, including every MongoDB round trip it makes.",
+        &["collection", "operation"]
+    )
+    .unwrap();
+    static ref SONIC_ERRORS: IntCounterVec = register_int_counter_vec!(
+        "crusty_cards_sonic_errors_total",
+        "Number of requests to Sonic that failed, by operation.",
+        &["operation"]
+    )
+    .unwrap();
+}
+
+// The returned timer records the elapsed time when it's dropped,
+// so bind it to a variable that lives until the operation is done.
+pub fn start_mongo_operation_timer(collection: &str, operation: &str) -> HistogramTimer {
+    MONGO_OPERATION_DURATION_SECONDS
+        .with_label_values(&[collection, operation])
+        .start_timer()
+}
+
+pub fn record_sonic_error(operation: &str) {
+    SONIC_ERRORS.with_label_values(&[operation]).inc();
+}
@@ -1,3 +1,4 @@
+use super::super::metrics::start_mongo_operation_timer;
 use super::helper::*;
 use bson::oid::ObjectId;
 use bson::{doc, Document};
@@ -63,6 +64,12 @@ pub trait CustomBlackCardCollection: Send + Sync {
         show_deleted: bool,
     ) -> Result<(Vec<CustomBlackCard>, Option<ObjectId>, i64), Status>;
 
+    // Streams every card in the cardpack that hasn't been deleted, ordered by creation time.
+    async fn stream_custom_black_cards(
+        &self,
+        parent: CustomCardpackName,
+    ) -> Result<ItemStream<CustomBlackCard>, Status>;
+
     async fn update_custom_black_card(
         &self,
         name: CustomBlackCardName,
@@ -110,6 +117,8 @@ impl CustomBlackCardCollection for MongoCustomBlackCardCollection {
         card_text: ValidatedStringField,
         answer_fields: AnswerFieldCount,
     ) -> Result<CustomBlackCard, Status> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "create_custom_black_card");
         // TODO - Check that parent cardpack exists before creating card.
         // Right now it's possible to create a card that's owned by nobody.
 
@@ -143,6 +152,8 @@ impl CustomBlackCardCollection for MongoCustomBlackCardCollection {
         parent: CustomCardpackName,
         data: Vec<(ValidatedStringField, AnswerFieldCount)>,
     ) -> Result<Vec<Option<CustomBlackCard>>, Status> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "batch_create_custom_black_cards");
         // TODO - Check that parent cardpack exists before creating card.
         // Right now it's possible to create a card that's owned by nobody.
 
@@ -206,6 +217,7 @@ impl CustomBlackCardCollection for MongoCustomBlackCardCollection {
         &self,
         name: CustomBlackCardName,
     ) -> Result<CustomBlackCard, Status> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "get_custom_black_card");
         let name_string = name.clone_str();
         let (user_object_id, custom_cardpack_object_id, custom_black_card_object_id) =
             name.take_object_ids();
@@ -216,7 +228,7 @@ impl CustomBlackCardCollection for MongoCustomBlackCardCollection {
 
         let res = match self.collection.find_one(doc!{"_id": custom_black_card_object_id, "parentCustomCardpackId": custom_cardpack_object_id, "parentUserId": user_object_id, "deleteTime": doc!{"$exists": false}}, options).await {
             Ok(res) => res,
-            _ => return Err(Status::unknown("Failed to fetch card."))
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to fetch card.")),
         };
 
         let res_doc = match res {
@@ -231,6 +243,8 @@ impl CustomBlackCardCollection for MongoCustomBlackCardCollection {
         &self,
         name: CustomBlackCardName,
     ) -> Result<CustomBlackCard, Status> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "soft_delete_custom_black_card");
         let name_string = name.clone_str();
         let (user_object_id, custom_cardpack_object_id, custom_black_card_object_id) =
             name.take_object_ids();
@@ -242,7 +256,7 @@ impl CustomBlackCardCollection for MongoCustomBlackCardCollection {
 
         let res = match self.collection.find_one_and_update(doc!{"_id": custom_black_card_object_id, "parentCustomCardpackId": custom_cardpack_object_id, "parentUserId": user_object_id, "deleteTime": doc!{"$exists": false}}, doc!{"$currentDate": doc!{"deleteTime": true}}, options).await {
             Ok(res) => res,
-            _ => return Err(Status::unknown("Failed to delete card."))
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to delete card.")),
         };
 
         let res_doc = match res {
@@ -257,6 +271,8 @@ impl CustomBlackCardCollection for MongoCustomBlackCardCollection {
         &self,
         name: CustomBlackCardName,
     ) -> Result<CustomBlackCard, Status> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "undelete_custom_black_card");
         let name_string = name.clone_str();
         let (user_object_id, custom_cardpack_object_id, custom_black_card_object_id) =
             name.take_object_ids();
@@ -268,7 +284,7 @@ impl CustomBlackCardCollection for MongoCustomBlackCardCollection {
 
         let res = match self.collection.find_one_and_update(doc!{"_id": custom_black_card_object_id, "parentCustomCardpackId": custom_cardpack_object_id, "parentUserId": user_object_id, "deleteTime": doc!{"$exists": true}}, doc!{"$unset": doc!{"deleteTime": ""}}, options).await {
             Ok(res) => res,
-            _ => return Err(Status::unknown("Failed to undelete card."))
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to undelete card.")),
         };
 
         let res_doc = match res {
@@ -286,6 +302,7 @@ impl CustomBlackCardCollection for MongoCustomBlackCardCollection {
         last_object_id_or: Option<ObjectId>,
         show_deleted: bool,
     ) -> Result<(Vec<CustomBlackCard>, Option<ObjectId>, i64), Status> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "list_custom_black_cards");
         let (parent_user_object_id, parent_custom_cardpack_object_id) = parent.take_object_ids();
         let find_doc = doc! {"parentUserId": parent_user_object_id, "parentCustomCardpackId": parent_custom_cardpack_object_id, "deleteTime": doc!{"$exists": show_deleted}};
         list_items(
@@ -299,12 +316,31 @@ impl CustomBlackCardCollection for MongoCustomBlackCardCollection {
         .await
     }
 
+    async fn stream_custom_black_cards(
+        &self,
+        parent: CustomCardpackName,
+    ) -> Result<ItemStream<CustomBlackCard>, Status> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "stream_custom_black_cards");
+        let (parent_user_object_id, parent_custom_cardpack_object_id) = parent.take_object_ids();
+        let find_doc = doc! {"parentUserId": parent_user_object_id, "parentCustomCardpackId": parent_custom_cardpack_object_id, "deleteTime": doc!{"$exists": false}};
+        stream_items(
+            &self.collection,
+            find_doc,
+            custom_black_card_projection_doc(),
+            document_to_custom_black_card,
+        )
+        .await
+    }
+
     async fn update_custom_black_card(
         &self,
         name: CustomBlackCardName,
         updated_card_text_or: Option<ValidatedStringField>,
         updated_answer_fields_or: Option<AnswerFieldCount>,
     ) -> Result<CustomBlackCard, Status> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "update_custom_black_card");
         if updated_card_text_or.is_none() && updated_answer_fields_or.is_none() {
             return self.get_custom_black_card(name).await;
         }
@@ -346,7 +382,7 @@ impl CustomBlackCardCollection for MongoCustomBlackCardCollection {
             .await
         {
             Ok(res) => res,
-            _ => return Err(Status::unknown("Failed to update card.")),
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to update card.")),
         };
 
         let res_doc = match res {
@@ -1,3 +1,4 @@
+use super::super::metrics::start_mongo_operation_timer;
 use super::helper::*;
 use bson::oid::ObjectId;
 use bson::{doc, Document};
@@ -109,6 +110,7 @@ impl CustomCardpackCollection for MongoCustomCardpackCollection {
         parent: UserName,
         display_name: ValidatedStringField,
     ) -> Result<CustomCardpack, Status> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "create_custom_cardpack");
         // TODO - Check that parent user exists before creating cardpack.
         // Right now it's possible to create a cardpack that's owned by nobody.
 
@@ -145,6 +147,8 @@ impl CustomCardpackCollection for MongoCustomCardpackCollection {
         parent: UserName,
         display_names: Vec<ValidatedStringField>,
     ) -> Result<Vec<Option<CustomCardpack>>, Status> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "batch_create_custom_cardpacks");
         // TODO - Check that parent user exists before creating cardpack.
         // Right now it's possible to create a cardpack that's owned by nobody.
 
@@ -205,6 +209,7 @@ impl CustomCardpackCollection for MongoCustomCardpackCollection {
         &self,
         name: CustomCardpackName,
     ) -> Result<CustomCardpack, Status> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "get_custom_cardpack");
         let name_string = name.clone_str();
         let (user_object_id, custom_cardpack_object_id) = name.take_object_ids();
 
@@ -214,7 +219,7 @@ impl CustomCardpackCollection for MongoCustomCardpackCollection {
 
         let res = match self.collection.find_one(doc!{"_id": custom_cardpack_object_id, "parentUserId": user_object_id, "deleteTime": doc!{"$exists": false}}, options).await {
             Ok(res) => res,
-            _ => return Err(Status::unknown("Failed to fetch cardpack."))
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to fetch cardpack.")),
         };
 
         let res_doc = match res {
@@ -229,6 +234,8 @@ impl CustomCardpackCollection for MongoCustomCardpackCollection {
         &self,
         name: CustomCardpackName,
     ) -> Result<CustomCardpack, Status> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "soft_delete_custom_cardpack");
         let name_string = name.clone_str();
         let (user_object_id, custom_cardpack_object_id) = name.take_object_ids();
 
@@ -239,7 +246,7 @@ impl CustomCardpackCollection for MongoCustomCardpackCollection {
 
         let res = match self.collection.find_one_and_update(doc!{"_id": custom_cardpack_object_id, "parentUserId": user_object_id, "deleteTime": doc!{"$exists": false}}, doc!{"$currentDate": doc!{"deleteTime": true}}, options).await {
             Ok(res) => res,
-            _ => return Err(Status::unknown("Failed to delete cardpack."))
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to delete cardpack.")),
         };
 
         let res_doc = match res {
@@ -254,6 +261,8 @@ impl CustomCardpackCollection for MongoCustomCardpackCollection {
         &self,
         name: CustomCardpackName,
     ) -> Result<CustomCardpack, Status> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "undelete_custom_cardpack");
         let name_string = name.clone_str();
         let (user_object_id, custom_cardpack_object_id) = name.take_object_ids();
 
@@ -264,7 +273,7 @@ impl CustomCardpackCollection for MongoCustomCardpackCollection {
 
         let res = match self.collection.find_one_and_update(doc!{"_id": custom_cardpack_object_id, "parentUserId": user_object_id, "deleteTime": doc!{"$exists": true}}, doc!{"$unset": doc!{"deleteTime": ""}}, options).await {
             Ok(res) => res,
-            _ => return Err(Status::unknown("Failed to undelete cardpack."))
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to undelete cardpack.")),
         };
 
         let res_doc = match res {
@@ -282,6 +291,7 @@ impl CustomCardpackCollection for MongoCustomCardpackCollection {
         last_object_id_or: Option<ObjectId>,
         show_deleted: bool,
     ) -> Result<(Vec<CustomCardpack>, Option<ObjectId>, i64), Status> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "list_custom_cardpacks");
         let parent_user_object_id = parent.take_object_id();
         let find_doc = doc! {"parentUserId": parent_user_object_id, "deleteTime": doc!{"$exists": show_deleted}};
         list_items(
@@ -300,6 +310,7 @@ impl CustomCardpackCollection for MongoCustomCardpackCollection {
         name: CustomCardpackName,
         updated_display_name: ValidatedStringField,
     ) -> Result<CustomCardpack, Status> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "update_custom_cardpack");
         let name_string = name.clone_str();
         let (user_object_id, custom_cardpack_object_id) = name.take_object_ids();
 
@@ -329,7 +340,7 @@ impl CustomCardpackCollection for MongoCustomCardpackCollection {
             .await
         {
             Ok(res) => res,
-            _ => return Err(Status::unknown("Failed to update cardpack.")),
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to update cardpack.")),
         };
 
         let res_doc = match res {
@@ -344,6 +355,8 @@ impl CustomCardpackCollection for MongoCustomCardpackCollection {
         &self,
         names: Vec<CustomCardpackName>,
     ) -> Result<Vec<Option<CustomCardpack>>, mongodb::error::Error> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "get_cardpacks_from_names");
         if names.is_empty() {
             return Ok(Vec::new());
         }
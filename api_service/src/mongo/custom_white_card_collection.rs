@@ -1,3 +1,4 @@
+use super::super::metrics::start_mongo_operation_timer;
 use super::helper::*;
 use bson::oid::ObjectId;
 use bson::{doc, Document};
@@ -61,6 +62,12 @@ pub trait CustomWhiteCardCollection: Send + Sync {
         show_deleted: bool,
     ) -> Result<(Vec<CustomWhiteCard>, Option<ObjectId>, i64), Status>;
 
+    // Streams every card in the cardpack that hasn't been deleted, ordered by creation time.
+    async fn stream_custom_white_cards(
+        &self,
+        parent: CustomCardpackName,
+    ) -> Result<ItemStream<CustomWhiteCard>, Status>;
+
     async fn update_custom_white_card(
         &self,
         name: CustomWhiteCardName,
@@ -104,6 +111,8 @@ impl CustomWhiteCardCollection for MongoCustomWhiteCardCollection {
         parent: CustomCardpackName,
         card_text: ValidatedStringField,
     ) -> Result<CustomWhiteCard, Status> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "create_custom_white_card");
         // TODO - Check that parent cardpack exists before creating card.
         // Right now it's possible to create a card that's owned by nobody.
 
@@ -135,6 +144,8 @@ impl CustomWhiteCardCollection for MongoCustomWhiteCardCollection {
         parent: CustomCardpackName,
         card_texts: Vec<ValidatedStringField>,
     ) -> Result<Vec<Option<CustomWhiteCard>>, Status> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "batch_create_custom_white_cards");
         // TODO - Check that parent cardpack exists before creating card.
         // Right now it's possible to create a card that's owned by nobody.
 
@@ -195,6 +206,7 @@ impl CustomWhiteCardCollection for MongoCustomWhiteCardCollection {
         &self,
         name: CustomWhiteCardName,
     ) -> Result<CustomWhiteCard, Status> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "get_custom_white_card");
         let name_string = name.clone_str();
         let (user_object_id, custom_cardpack_object_id, custom_white_card_object_id) =
             name.take_object_ids();
@@ -205,7 +217,7 @@ impl CustomWhiteCardCollection for MongoCustomWhiteCardCollection {
 
         let res = match self.collection.find_one(doc!{"_id": custom_white_card_object_id, "parentCustomCardpackId": custom_cardpack_object_id, "parentUserId": user_object_id, "deleteTime": doc!{"$exists": false}}, options).await {
             Ok(res) => res,
-            _ => return Err(Status::unknown("Failed to fetch card."))
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to fetch card.")),
         };
 
         let res_doc = match res {
@@ -220,6 +232,8 @@ impl CustomWhiteCardCollection for MongoCustomWhiteCardCollection {
         &self,
         name: CustomWhiteCardName,
     ) -> Result<CustomWhiteCard, Status> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "soft_delete_custom_white_card");
         let name_string = name.clone_str();
         let (user_object_id, custom_cardpack_object_id, custom_white_card_object_id) =
             name.take_object_ids();
@@ -231,7 +245,7 @@ impl CustomWhiteCardCollection for MongoCustomWhiteCardCollection {
 
         let res = match self.collection.find_one_and_update(doc!{"_id": custom_white_card_object_id, "parentCustomCardpackId": custom_cardpack_object_id, "parentUserId": user_object_id, "deleteTime": doc!{"$exists": false}}, doc!{"$currentDate": doc!{"deleteTime": true}}, options).await {
             Ok(res) => res,
-            _ => return Err(Status::unknown("Failed to delete card."))
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to delete card.")),
         };
 
         let res_doc = match res {
@@ -246,6 +260,8 @@ impl CustomWhiteCardCollection for MongoCustomWhiteCardCollection {
         &self,
         name: CustomWhiteCardName,
     ) -> Result<CustomWhiteCard, Status> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "undelete_custom_white_card");
         let name_string = name.clone_str();
         let (user_object_id, custom_cardpack_object_id, custom_white_card_object_id) =
             name.take_object_ids();
@@ -257,7 +273,7 @@ impl CustomWhiteCardCollection for MongoCustomWhiteCardCollection {
 
         let res = match self.collection.find_one_and_update(doc!{"_id": custom_white_card_object_id, "parentCustomCardpackId": custom_cardpack_object_id, "parentUserId": user_object_id, "deleteTime": doc!{"$exists": true}}, doc!{"$unset": doc!{"deleteTime": ""}}, options).await {
             Ok(res) => res,
-            _ => return Err(Status::unknown("Failed to undelete card."))
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to undelete card.")),
         };
 
         let res_doc = match res {
@@ -275,6 +291,7 @@ impl CustomWhiteCardCollection for MongoCustomWhiteCardCollection {
         last_object_id_or: Option<ObjectId>,
         show_deleted: bool,
     ) -> Result<(Vec<CustomWhiteCard>, Option<ObjectId>, i64), Status> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "list_custom_white_cards");
         let (parent_user_object_id, parent_custom_cardpack_object_id) = parent.take_object_ids();
         let find_doc = doc! {"parentUserId": parent_user_object_id, "parentCustomCardpackId": parent_custom_cardpack_object_id, "deleteTime": doc!{"$exists": show_deleted}};
         list_items(
@@ -288,11 +305,30 @@ impl CustomWhiteCardCollection for MongoCustomWhiteCardCollection {
         .await
     }
 
+    async fn stream_custom_white_cards(
+        &self,
+        parent: CustomCardpackName,
+    ) -> Result<ItemStream<CustomWhiteCard>, Status> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "stream_custom_white_cards");
+        let (parent_user_object_id, parent_custom_cardpack_object_id) = parent.take_object_ids();
+        let find_doc = doc! {"parentUserId": parent_user_object_id, "parentCustomCardpackId": parent_custom_cardpack_object_id, "deleteTime": doc!{"$exists": false}};
+        stream_items(
+            &self.collection,
+            find_doc,
+            custom_white_card_projection_doc(),
+            document_to_custom_white_card,
+        )
+        .await
+    }
+
     async fn update_custom_white_card(
         &self,
         name: CustomWhiteCardName,
         updated_card_text: ValidatedStringField,
     ) -> Result<CustomWhiteCard, Status> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "update_custom_white_card");
         let name_string = name.clone_str();
         let (user_object_id, custom_cardpack_object_id, custom_white_card_object_id) =
             name.take_object_ids();
@@ -324,7 +360,7 @@ impl CustomWhiteCardCollection for MongoCustomWhiteCardCollection {
             .await
         {
             Ok(res) => res,
-            _ => return Err(Status::unknown("Failed to update card.")),
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to update card.")),
         };
 
         let res_doc = match res {
@@ -1,20 +1,23 @@
-use super::super::environment::EnvironmentVariables;
+use super::super::config::Config;
 use bson::doc;
 use bson::oid::ObjectId;
 use bson::Document;
-use mongodb::{options::FindOptions, Client, Collection, Database};
+use mongodb::{options::FindOptions, Client, Collection, Database, IndexModel};
 use shared::proto_validation::BoundedPageSize;
 use std::marker::{Send, Sync};
-use tokio_stream::StreamExt;
+use tokio_stream::{Stream, StreamExt};
 use tonic::Status;
+use tracing::error;
 
-pub async fn get_mongo_database_or_panic(env_vars: &EnvironmentVariables) -> Database {
-    let mongo_client = match Client::with_uri_str(env_vars.get_mongo_uri()).await {
+pub async fn get_mongo_database_or_panic(config: &Config) -> Database {
+    // The client connects lazily, so this only fails if the URI is invalid.
+    // Whether MongoDB is reachable is reported by the readiness check instead.
+    let mongo_client = match Client::with_uri_str(config.get_mongo_uri()).await {
         Ok(client) => client,
-        _ => panic!("Failed to connect to MongoDB."),
+        _ => panic!("Failed to parse MongoDB URI."),
     };
 
-    mongo_client.database(env_vars.get_mongo_database())
+    mongo_client.database(config.get_mongo_database())
 }
 
 pub fn resource_not_found_error(resource_name: &str) -> Status {
@@ -24,6 +27,24 @@ pub fn resource_not_found_error(resource_name: &str) -> Status {
     ))
 }
 
+// The underlying error is logged rather than returned
+// so that database details aren't leaked to clients.
+pub fn mongo_error_to_status(err: mongodb::error::Error, message: &str) -> Status {
+    error!(error = %err, "{}", message);
+    Status::unknown(message)
+}
+
+// Mongo connects lazily, so indexes are created in the background rather than
+// making startup wait for the database. Creating an index that already exists does nothing.
+pub fn create_indexes_in_background(collection: &Collection<Document>, indexes: Vec<IndexModel>) {
+    let collection = collection.clone();
+    tokio::spawn(async move {
+        if let Err(err) = collection.create_indexes(indexes, None).await {
+            error!(collection = collection.name(), error = %err, "Failed to create indexes.");
+        }
+    });
+}
+
 pub async fn list_items<T>(
     collection: &Collection<Document>,
     mut find_doc: Document,
@@ -36,7 +57,7 @@ pub async fn list_items<T>(
 
     let matching_doc_count = match collection.count_documents(find_doc.clone(), None).await {
         Ok(count) => count as i64,
-        _ => return Err(Status::unknown("Failed to fetch cardpacks.")),
+        Err(err) => return Err(mongo_error_to_status(err, "Failed to fetch cardpacks.")),
     };
 
     if let Some(previous_item_id) = previous_item_id_or {
@@ -51,7 +72,7 @@ pub async fn list_items<T>(
 
     let res = match collection.find(find_doc, find_options).await {
         Ok(res) => res,
-        _ => return Err(Status::unknown("Failed to fetch items.")),
+        Err(err) => return Err(mongo_error_to_status(err, "Failed to fetch items.")),
     };
 
     let mut docs: Vec<Document> = match res
@@ -59,7 +80,7 @@ pub async fn list_items<T>(
         .await
     {
         Ok(docs) => docs,
-        _ => return Err(Status::unknown("Failed to fetch items.")),
+        Err(err) => return Err(mongo_error_to_status(err, "Failed to fetch items.")),
     };
 
     let next_item_id_or = if docs.len() > page_size_i64 as usize {
@@ -80,3 +101,29 @@ pub async fn list_items<T>(
 
     Ok((items, next_item_id_or, matching_doc_count))
 }
+
+pub type ItemStream<T> = Box<dyn Stream<Item = Result<T, Status>> + Send + Unpin>;
+
+// Streams every matching item, in the same order as `list_items`. Items are read
+// from a cursor in batches, so they're never all held in memory at once.
+pub async fn stream_items<T: 'static>(
+    collection: &Collection<Document>,
+    find_doc: Document,
+    projection_doc: Document,
+    convert_doc_to_item: fn(&Document) -> T,
+) -> Result<ItemStream<T>, Status> {
+    let find_options = FindOptions::builder()
+        .sort(doc! {"_id": 1})
+        .projection(projection_doc)
+        .build();
+
+    let cursor = match collection.find(find_doc, find_options).await {
+        Ok(cursor) => cursor,
+        Err(err) => return Err(mongo_error_to_status(err, "Failed to fetch items.")),
+    };
+
+    Ok(Box::from(cursor.map(move |doc_or| match doc_or {
+        Ok(doc) => Ok(convert_doc_to_item(&doc)),
+        Err(err) => Err(mongo_error_to_status(err, "Failed to fetch items.")),
+    })))
+}
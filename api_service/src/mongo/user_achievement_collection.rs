@@ -0,0 +1,201 @@
+use super::super::metrics::start_mongo_operation_timer;
+use super::helper::mongo_error_to_status;
+use bson::{doc, Document};
+use mockall::automock;
+use mongodb::options::{FindOptions, UpdateOptions};
+use mongodb::Collection;
+use shared::achievements::get_achievement_definition;
+use shared::proto::crusty_cards_api::UserAchievement;
+use shared::proto_validation::BoundedPageSize;
+use shared::resource_name::UserName;
+use shared::time::chrono_timestamp_to_timestamp_proto;
+use tokio_stream::StreamExt;
+use tonic::Status;
+
+// Achievements are listed in the order that they were unlocked.
+fn unlocked_achievements_sort_doc() -> Document {
+    doc! {
+      "unlockTime": 1,
+      "achievementId": 1
+    }
+}
+
+#[automock]
+#[tonic::async_trait]
+pub trait UserAchievementCollection: Send + Sync {
+    // Adds to the user's progress towards the achievement, and unlocks
+    // the achievement once the progress reaches `required_progress`.
+    async fn add_achievement_progress(
+        &self,
+        user_name: UserName,
+        achievement_id: String,
+        progress: i64,
+        required_progress: i64,
+    ) -> Result<(), Status>;
+
+    async fn list_unlocked_achievements(
+        &self,
+        user_name: UserName,
+        page_size: BoundedPageSize,
+        start_index: usize,
+    ) -> Result<(Vec<UserAchievement>, Option<usize>, i64), Status>;
+
+    // WARNING - DO NOT USE IN PROD!!!
+    // Calling this method irreversable erases
+    // all mongo collections related to this service.
+    // This is meant to clear data between test runs.
+    async fn unsafe_wipe_data_and_reset(&self) -> Result<(), mongodb::error::Error>;
+}
+
+// Each document holds one user's progress towards one achievement. Documents
+// are created the first time a user makes progress towards an achievement,
+// and are only listed once the achievement has been unlocked.
+pub struct MongoUserAchievementCollection {
+    collection: Collection<Document>,
+}
+
+impl MongoUserAchievementCollection {
+    pub fn new(collection: Collection<Document>) -> Self {
+        // TODO - Setup indexes here.
+        Self { collection }
+    }
+}
+
+#[tonic::async_trait]
+impl UserAchievementCollection for MongoUserAchievementCollection {
+    async fn add_achievement_progress(
+        &self,
+        user_name: UserName,
+        achievement_id: String,
+        progress: i64,
+        required_progress: i64,
+    ) -> Result<(), Status> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "add_achievement_progress");
+        let find_doc = doc! {
+            "userId": user_name.take_object_id(),
+            "achievementId": achievement_id
+        };
+
+        let options = UpdateOptions::builder().upsert(true).build();
+        let update_doc = doc! {
+            "$inc": doc! {
+                "progress": progress
+            }
+        };
+        if let Err(err) = self
+            .collection
+            .update_one(find_doc.clone(), update_doc, options)
+            .await
+        {
+            return Err(mongo_error_to_status(
+                err,
+                "Failed to update achievement progress.",
+            ));
+        }
+
+        // Unlocking is a separate update so that the unlock
+        // time is only ever set once, by whichever update
+        // first pushes the progress past the requirement.
+        let mut unlock_find_doc = find_doc;
+        unlock_find_doc.insert("progress", doc! {"$gte": required_progress});
+        unlock_find_doc.insert("unlockTime", doc! {"$exists": false});
+        let unlock_update_doc = doc! {
+            "$currentDate": doc! {
+                "unlockTime": true
+            }
+        };
+        match self
+            .collection
+            .update_one(unlock_find_doc, unlock_update_doc, None)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => Err(mongo_error_to_status(err, "Failed to unlock achievement.")),
+        }
+    }
+
+    async fn list_unlocked_achievements(
+        &self,
+        user_name: UserName,
+        page_size: BoundedPageSize,
+        start_index: usize,
+    ) -> Result<(Vec<UserAchievement>, Option<usize>, i64), Status> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "list_unlocked_achievements");
+        let page_size_i64 = page_size.take_i64();
+        let user_name_string = user_name.clone_str();
+        let find_doc = doc! {
+            "userId": user_name.take_object_id(),
+            "unlockTime": doc! {"$exists": true}
+        };
+
+        let total_size = match self
+            .collection
+            .count_documents(find_doc.clone(), None)
+            .await
+        {
+            Ok(count) => count as i64,
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to fetch achievements.")),
+        };
+
+        let find_options = FindOptions::builder()
+            .sort(unlocked_achievements_sort_doc())
+            .skip(start_index as u64)
+            .limit(page_size_i64 + 1)
+            .build();
+
+        let res = match self.collection.find(find_doc, find_options).await {
+            Ok(res) => res,
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to fetch achievements.")),
+        };
+
+        let mut docs: Vec<Document> = match res
+            .collect::<Result<Vec<Document>, mongodb::error::Error>>()
+            .await
+        {
+            Ok(docs) => docs,
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to fetch achievements.")),
+        };
+
+        let next_index_or = if docs.len() > page_size_i64 as usize {
+            docs.pop();
+            Some(start_index + page_size_i64 as usize)
+        } else {
+            None
+        };
+
+        Ok((
+            docs.iter()
+                .map(|doc| document_to_user_achievement(doc, &user_name_string))
+                .collect(),
+            next_index_or,
+            total_size,
+        ))
+    }
+
+    async fn unsafe_wipe_data_and_reset(&self) -> Result<(), mongodb::error::Error> {
+        self.collection.drop(None).await
+    }
+}
+
+// Display fields come from the achievement definitions rather than
+// the database, so that they can be reworded without a migration.
+fn document_to_user_achievement(doc: &Document, user_name: &str) -> UserAchievement {
+    let achievement_id = doc.get_str("achievementId").unwrap_or("");
+    let (display_name, description) = match get_achievement_definition(achievement_id) {
+        Some(definition) => (definition.display_name, definition.description),
+        None => ("", ""),
+    };
+    UserAchievement {
+        name: format!("{}/achievements/{}", user_name, achievement_id),
+        display_name: String::from(display_name),
+        description: String::from(description),
+        unlock_time: match doc.get_datetime("unlockTime") {
+            Ok(unlock_time) => Some(chrono_timestamp_to_timestamp_proto(
+                &unlock_time.to_chrono(),
+            )),
+            _ => None,
+        },
+    }
+}
@@ -1,3 +1,5 @@
+use super::super::authorization::UserRole;
+use super::super::metrics::start_mongo_operation_timer;
 use super::helper::*;
 use bson::{doc, document::ValueAccessError, Bson, Document};
 use futures_lite::{Stream, StreamExt};
@@ -35,7 +37,7 @@ fn user_settings_projection_doc() -> Document {
     }
 }
 
-type UserStream = Box<dyn Stream<Item = Result<User, mongodb::error::Error>> + Send + Unpin>;
+pub type UserStream = Box<dyn Stream<Item = Result<User, mongodb::error::Error>> + Send + Unpin>;
 
 // TODO - More thoroughly test this collection.
 #[automock]
@@ -66,6 +68,10 @@ pub trait UserCollection: Send + Sync {
 
     async fn assert_user_exists(&self, name: UserName) -> Result<(), Status>;
 
+    async fn get_user_role(&self, name: UserName) -> Result<UserRole, Status>;
+
+    async fn set_user_role(&self, name: UserName, role: UserRole) -> Result<(), Status>;
+
     // Converts a list of user names into a list of user proto structs.
     // The return vec is guaranteed to be the same length as the input,
     // and have its items in the same order. Any users that weren't found
@@ -124,6 +130,7 @@ impl MongoUserCollection {
 #[tonic::async_trait]
 impl UserCollection for MongoUserCollection {
     async fn get_user(&self, name: UserName) -> Result<User, Status> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "get_user");
         let name_string = name.clone_str();
         let user_object_id = name.take_object_id();
 
@@ -137,7 +144,7 @@ impl UserCollection for MongoUserCollection {
             .await
         {
             Ok(res) => res,
-            _ => return Err(Status::unknown("Failed to fetch user.")),
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to fetch user.")),
         };
 
         let res_doc = match res {
@@ -153,6 +160,7 @@ impl UserCollection for MongoUserCollection {
         name: UserName,
         updated_display_name: ValidatedStringField,
     ) -> Result<User, Status> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "update_user");
         let name_string = name.clone_str();
         let user_object_id = name.take_object_id();
 
@@ -176,7 +184,7 @@ impl UserCollection for MongoUserCollection {
             .await
         {
             Ok(res) => res,
-            _ => return Err(Status::unknown("Failed to update user.")),
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to update user.")),
         };
 
         let res_doc = match res {
@@ -188,6 +196,7 @@ impl UserCollection for MongoUserCollection {
     }
 
     async fn get_user_settings(&self, name: UserSettingsName) -> Result<UserSettings, Status> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "get_user_settings");
         let name_string = name.clone_str();
         let user_object_id = name.take_object_id();
 
@@ -201,7 +210,7 @@ impl UserCollection for MongoUserCollection {
             .await
         {
             Ok(res) => res,
-            _ => return Err(Status::unknown("Failed to fetch user settings.")),
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to fetch user settings.")),
         };
 
         let res_doc = match res {
@@ -218,6 +227,7 @@ impl UserCollection for MongoUserCollection {
         color_scheme_or: Option<ValidatedColorScheme>,
         quick_start_game_config_or: Option<OptionalField<ValidatedGameConfig>>,
     ) -> Result<UserSettings, Status> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "update_user_settings");
         if color_scheme_or.is_none() && quick_start_game_config_or.is_none() {
             return self.get_user_settings(name).await;
         }
@@ -267,7 +277,12 @@ impl UserCollection for MongoUserCollection {
             .await
         {
             Ok(res) => res,
-            _ => return Err(Status::unknown("Failed to update user settings.")),
+            Err(err) => {
+                return Err(mongo_error_to_status(
+                    err,
+                    "Failed to update user settings.",
+                ))
+            }
         };
 
         let res_doc = match res {
@@ -283,6 +298,7 @@ impl UserCollection for MongoUserCollection {
         validated_oauth_credentials: ValidatedOAuthCredentials,
         display_name: ValidatedStringField,
     ) -> Result<User, Status> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "get_or_create_user");
         let oauth_credentials = validated_oauth_credentials.take_oauth_credentials();
 
         let filter_doc = doc! {
@@ -333,6 +349,7 @@ impl UserCollection for MongoUserCollection {
     }
 
     async fn assert_user_exists(&self, name: UserName) -> Result<(), Status> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "assert_user_exists");
         let user_object_id = name.take_object_id();
 
         let options = mongodb::options::FindOneOptions::builder()
@@ -345,7 +362,12 @@ impl UserCollection for MongoUserCollection {
             .await
         {
             Ok(res) => res,
-            _ => return Err(Status::unknown("Failed to check if user exists.")),
+            Err(err) => {
+                return Err(mongo_error_to_status(
+                    err,
+                    "Failed to check if user exists.",
+                ))
+            }
         };
 
         if res.is_none() {
@@ -355,10 +377,60 @@ impl UserCollection for MongoUserCollection {
         }
     }
 
+    async fn get_user_role(&self, name: UserName) -> Result<UserRole, Status> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "get_user_role");
+        let name_string = name.clone_str();
+        let user_object_id = name.take_object_id();
+
+        let options = mongodb::options::FindOneOptions::builder()
+            .projection(doc! { "_id": 1, "role": 1 })
+            .build();
+
+        let res = match self
+            .collection
+            .find_one(doc! {"_id": user_object_id}, options)
+            .await
+        {
+            Ok(res) => res,
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to fetch user role.")),
+        };
+
+        match res {
+            Some(doc) => Ok(UserRole::from_document_value(doc.get_str("role").ok())),
+            None => Err(resource_not_found_error(&name_string)),
+        }
+    }
+
+    async fn set_user_role(&self, name: UserName, role: UserRole) -> Result<(), Status> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "set_user_role");
+        let name_string = name.clone_str();
+        let user_object_id = name.take_object_id();
+
+        let update_doc = match role.to_document_value() {
+            Some(role_value) => doc! {"$set": {"role": role_value}},
+            None => doc! {"$unset": {"role": ""}},
+        };
+
+        let res = match self
+            .collection
+            .update_one(doc! {"_id": user_object_id}, update_doc, None)
+            .await
+        {
+            Ok(res) => res,
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to set user role.")),
+        };
+
+        if res.matched_count == 0 {
+            return Err(resource_not_found_error(&name_string));
+        }
+        Ok(())
+    }
+
     async fn get_users_from_names(
         &self,
         names: Vec<UserName>,
     ) -> Result<Vec<Option<User>>, mongodb::error::Error> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "get_users_from_names");
         if names.is_empty() {
             return Ok(Vec::new());
         }
@@ -398,9 +470,11 @@ impl UserCollection for MongoUserCollection {
         user_name: UserName,
         custom_cardpack_name: CustomCardpackName,
     ) -> Result<(), Status> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "add_custom_cardpack_to_favorites");
         let res = match self.collection.update_one(doc!{"_id": user_name.take_object_id()}, doc!{"$addToSet": {"favoritedCardpackIds": custom_cardpack_name.take_object_ids().1}}, None).await {
             Ok(res) => res,
-            _ => return Err(Status::unknown("Failed to add custom cardpack to favorites.")),
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to add custom cardpack to favorites.")),
         };
         if res.modified_count == 0 {
             return Err(Status::unknown(
@@ -415,6 +489,10 @@ impl UserCollection for MongoUserCollection {
         user_name: UserName,
         custom_cardpack_name: CustomCardpackName,
     ) -> Result<(), Status> {
+        let _timer = start_mongo_operation_timer(
+            self.collection.name(),
+            "remove_custom_cardpack_from_favorites",
+        );
         let res = match self
             .collection
             .update_one(
@@ -425,8 +503,9 @@ impl UserCollection for MongoUserCollection {
             .await
         {
             Ok(res) => res,
-            _ => {
-                return Err(Status::unknown(
+            Err(err) => {
+                return Err(mongo_error_to_status(
+                    err,
                     "Failed to remove custom cardpack from favorites.",
                 ))
             }
@@ -444,9 +523,13 @@ impl UserCollection for MongoUserCollection {
         user_name: UserName,
         custom_cardpack_name: CustomCardpackName,
     ) -> Result<bool, Status> {
+        let _timer = start_mongo_operation_timer(
+            self.collection.name(),
+            "check_has_user_favorited_custom_cardpack",
+        );
         let count = match self.collection.count_documents(doc!{"_id": user_name.take_object_id(), "favoritedCardpackIds": doc!{"$elemMatch": doc!{"$eq": custom_cardpack_name.take_object_ids().1}}}, None).await {
             Ok(res) => res,
-            _ => return Err(Status::unknown("Failed to load favorited custom cardpacks.")),
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to load favorited custom cardpacks.")),
         };
         Ok(count > 0)
     }
@@ -457,6 +540,10 @@ impl UserCollection for MongoUserCollection {
         page_size: BoundedPageSize,
         start_index: usize,
     ) -> Result<(Vec<CustomCardpackName>, Option<usize>), Status> {
+        let _timer = start_mongo_operation_timer(
+            self.collection.name(),
+            "list_favorited_custom_cardpack_names",
+        );
         let page_size_i64 = page_size.take_i64();
         let options = mongodb::options::FindOneOptions::builder()
             .projection(doc!{"favoritedCardpackIds": doc!{"$slice": vec!{start_index as i32, (page_size_i64 + 1) as i32}}})
@@ -467,8 +554,9 @@ impl UserCollection for MongoUserCollection {
             .await
         {
             Ok(res) => res,
-            _ => {
-                return Err(Status::unknown(
+            Err(err) => {
+                return Err(mongo_error_to_status(
+                    err,
                     "Failed to remove custom cardpack from favorites.",
                 ))
             }
@@ -512,6 +600,7 @@ impl UserCollection for MongoUserCollection {
     }
 
     async fn user_stream(&self) -> Result<UserStream, mongodb::error::Error> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "user_stream");
         let options = mongodb::options::FindOptions::builder()
             .projection(user_projection_doc())
             .build();
@@ -551,7 +640,14 @@ fn document_to_user(doc: &Document) -> User {
     }
 }
 
-fn game_config_to_document(game_config: &GameConfig) -> Document {
+// Returns the game config as it would be read back after being stored,
+// since fields with default values aren't stored. This lets other
+// storage backends return exactly what this one would.
+pub fn get_stored_game_config(game_config: &GameConfig) -> GameConfig {
+    document_to_game_config(&game_config_to_document(game_config))
+}
+
+pub fn game_config_to_document(game_config: &GameConfig) -> Document {
     let mut doc = Document::new();
 
     if !game_config.display_name.is_empty() {
@@ -667,7 +763,7 @@ fn document_to_user_settings(doc: &Document) -> UserSettings {
     }
 }
 
-fn document_to_game_config(doc: &Document) -> GameConfig {
+pub fn document_to_game_config(doc: &Document) -> GameConfig {
     let mut end_condition = None;
 
     if let Ok(max_score) = doc.get_i32("maxScore") {
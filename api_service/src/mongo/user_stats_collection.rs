@@ -0,0 +1,211 @@
+use super::super::metrics::start_mongo_operation_timer;
+use super::helper::{create_indexes_in_background, mongo_error_to_status};
+use bson::{doc, Document};
+use mockall::automock;
+use mongodb::{Collection, Database, IndexModel};
+use shared::proto::crusty_cards_api::{UserStats, UserStatsIncrement};
+use shared::proto_validation::BoundedPageSize;
+use shared::resource_name::{UserName, UserStatsName};
+use tokio_stream::StreamExt;
+use tonic::Status;
+use tracing::error;
+
+// Players are ranked by games won, then by rounds won. User ids
+// break any remaining ties so that leaderboard pages are stable.
+fn leaderboard_sort_doc() -> Document {
+    doc! {
+      "gamesWon": -1,
+      "roundsWon": -1,
+      "_id": 1
+    }
+}
+
+#[automock]
+#[tonic::async_trait]
+pub trait UserStatsCollection: Send + Sync {
+    // Users who haven't played any games yet have all-zero stats.
+    async fn get_user_stats(&self, name: UserStatsName) -> Result<UserStats, Status>;
+
+    // Every increment is sent in a single write.
+    async fn increment_user_stats(
+        &self,
+        increments: Vec<(UserName, UserStatsIncrement)>,
+    ) -> Result<(), Status>;
+
+    async fn list_leaderboard(
+        &self,
+        page_size: BoundedPageSize,
+        start_index: usize,
+    ) -> Result<(Vec<UserStats>, Option<usize>, i64), Status>;
+
+    // WARNING - DO NOT USE IN PROD!!!
+    // Calling this method irreversable erases
+    // all mongo collections related to this service.
+    // This is meant to clear data between test runs.
+    async fn unsafe_wipe_data_and_reset(&self) -> Result<(), mongodb::error::Error>;
+}
+
+// Stats are stored in their own collection rather than on the user document, since
+// they're written after every round and ranking them needs its own indexes.
+pub struct MongoUserStatsCollection {
+    collection: Collection<Document>,
+    // Used to run commands that the driver doesn't have a method for.
+    database: Database,
+}
+
+impl MongoUserStatsCollection {
+    pub fn new(collection: Collection<Document>, database: Database) -> Self {
+        create_indexes_in_background(
+            &collection,
+            vec![IndexModel::builder().keys(leaderboard_sort_doc()).build()],
+        );
+        Self {
+            collection,
+            database,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl UserStatsCollection for MongoUserStatsCollection {
+    async fn get_user_stats(&self, name: UserStatsName) -> Result<UserStats, Status> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "get_user_stats");
+        let name_string = name.clone_str();
+        let user_object_id = name.take_object_id();
+
+        let res = match self
+            .collection
+            .find_one(doc! {"_id": user_object_id}, None)
+            .await
+        {
+            Ok(res) => res,
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to fetch user stats.")),
+        };
+
+        Ok(match res {
+            Some(doc) => document_to_user_stats(&doc),
+            None => UserStats {
+                name: name_string,
+                games_played: 0,
+                games_won: 0,
+                rounds_won: 0,
+                rounds_judged: 0,
+            },
+        })
+    }
+
+    async fn increment_user_stats(
+        &self,
+        increments: Vec<(UserName, UserStatsIncrement)>,
+    ) -> Result<(), Status> {
+        if increments.is_empty() {
+            return Ok(());
+        }
+        let _timer = start_mongo_operation_timer(self.collection.name(), "increment_user_stats");
+
+        // The driver doesn't support bulk writes, so every upsert is sent in one `update`
+        // command. Each `$inc` is atomic, so concurrent reports never overwrite each other.
+        let updates: Vec<Document> = increments
+            .into_iter()
+            .map(|(user_name, increment)| {
+                doc! {
+                    "q": doc! {"_id": user_name.take_object_id()},
+                    "u": doc! {
+                        "$inc": doc! {
+                            "gamesPlayed": increment.games_played,
+                            "gamesWon": increment.games_won,
+                            "roundsWon": increment.rounds_won,
+                            "roundsJudged": increment.rounds_judged
+                        }
+                    },
+                    "upsert": true
+                }
+            })
+            .collect();
+
+        let res = match self
+            .database
+            .run_command(
+                doc! {
+                    "update": self.collection.name(),
+                    "updates": updates
+                },
+                None,
+            )
+            .await
+        {
+            Ok(res) => res,
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to update user stats.")),
+        };
+
+        // Failed writes are listed in the response rather than failing the command.
+        if res.contains_key("writeErrors") {
+            error!(response = %res, "Failed to update user stats.");
+            return Err(Status::unknown("Failed to update user stats."));
+        }
+        Ok(())
+    }
+
+    async fn list_leaderboard(
+        &self,
+        page_size: BoundedPageSize,
+        start_index: usize,
+    ) -> Result<(Vec<UserStats>, Option<usize>, i64), Status> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "list_leaderboard");
+        let page_size_i64 = page_size.take_i64();
+
+        let total_size = match self.collection.count_documents(doc! {}, None).await {
+            Ok(count) => count as i64,
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to fetch leaderboard.")),
+        };
+
+        let options = mongodb::options::FindOptions::builder()
+            .sort(leaderboard_sort_doc())
+            .skip(start_index as u64)
+            .limit(page_size_i64 + 1)
+            .build();
+
+        let res = match self.collection.find(doc! {}, options).await {
+            Ok(res) => res,
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to fetch leaderboard.")),
+        };
+
+        let mut docs: Vec<Document> = match res
+            .collect::<Result<Vec<Document>, mongodb::error::Error>>()
+            .await
+        {
+            Ok(docs) => docs,
+            Err(err) => return Err(mongo_error_to_status(err, "Failed to fetch leaderboard.")),
+        };
+
+        let next_index_or = if docs.len() > page_size_i64 as usize {
+            docs.pop();
+            Some(start_index + page_size_i64 as usize)
+        } else {
+            None
+        };
+
+        Ok((
+            docs.iter().map(document_to_user_stats).collect(),
+            next_index_or,
+            total_size,
+        ))
+    }
+
+    async fn unsafe_wipe_data_and_reset(&self) -> Result<(), mongodb::error::Error> {
+        self.collection.drop(None).await
+    }
+}
+
+fn document_to_user_stats(doc: &Document) -> UserStats {
+    UserStats {
+        name: match doc.get_object_id("_id") {
+            Ok(object_id) => format!("users/{}/stats", object_id.to_hex()),
+            _ => String::from(""),
+        },
+        games_played: doc.get_i64("gamesPlayed").unwrap_or(0),
+        games_won: doc.get_i64("gamesWon").unwrap_or(0),
+        rounds_won: doc.get_i64("roundsWon").unwrap_or(0),
+        rounds_judged: doc.get_i64("roundsJudged").unwrap_or(0),
+    }
+}
@@ -0,0 +1,327 @@
+use super::super::metrics::start_mongo_operation_timer;
+use super::helper::{create_indexes_in_background, mongo_error_to_status};
+use bson::{doc, Bson, Document};
+use mockall::automock;
+use mongodb::options::FindOptions;
+use mongodb::{Collection, IndexModel};
+use shared::proto::crusty_cards_api::WhiteCardStats;
+use shared::proto_validation::BoundedPageSize;
+use std::collections::HashSet;
+use tokio_stream::StreamExt;
+use tonic::Status;
+
+// Cards that won the most rounds come first. Between cards with the same number
+// of wins, the one that needed fewer plays to get there has the higher win rate.
+fn top_white_cards_sort_doc() -> Document {
+    doc! {
+      "winCount": -1,
+      "playCount": 1,
+      "_id": 1
+    }
+}
+
+// Cards that have been passed over the most come first.
+fn never_picked_white_cards_sort_doc() -> Document {
+    doc! {
+      "playCount": -1,
+      "_id": 1
+    }
+}
+
+// Never-picked cards are listed in two parts. Cards that were played but never won
+// come first and are paged through in storage, while cards that were never played
+// at all have no stats and follow in name order. `played_page` must start at
+// `start_index` and hold up to one more item than the page size.
+pub fn take_never_picked_page(
+    played_page: Vec<WhiteCardStats>,
+    played_count: usize,
+    mut never_played_card_names: Vec<String>,
+    page_size_i64: i64,
+    start_index: usize,
+) -> (Vec<WhiteCardStats>, Option<usize>, i64) {
+    let page_size_usize = page_size_i64 as usize;
+    let total_size = (played_count + never_played_card_names.len()) as i64;
+    never_played_card_names.sort();
+
+    let mut page = played_page;
+    let remaining_size = (page_size_usize + 1).saturating_sub(page.len());
+    page.extend(
+        never_played_card_names
+            .into_iter()
+            .skip(start_index.saturating_sub(played_count))
+            .take(remaining_size)
+            .map(|card_name| WhiteCardStats {
+                card_name,
+                play_count: 0,
+                win_count: 0,
+            }),
+    );
+
+    let next_index_or = if page.len() > page_size_usize {
+        page.pop();
+        Some(start_index + page_size_usize)
+    } else {
+        None
+    };
+    (page, next_index_or, total_size)
+}
+
+#[automock]
+#[tonic::async_trait]
+pub trait WhiteCardStatsCollection: Send + Sync {
+    // Adds the play and win counts in `white_card_stats` to the
+    // card's running totals. `parent` is the card's cardpack name.
+    async fn increment_white_card_stats(
+        &self,
+        parent: String,
+        white_card_stats: WhiteCardStats,
+    ) -> Result<(), Status>;
+
+    // Lists cards from the cardpack that have won at least one round.
+    async fn list_top_white_cards(
+        &self,
+        parent: String,
+        page_size: BoundedPageSize,
+        start_index: usize,
+    ) -> Result<(Vec<WhiteCardStats>, Option<usize>, i64), Status>;
+
+    // Lists cards from `card_names` that have never won a round. `card_names`
+    // should hold every card in the cardpack, since cards that have never
+    // been played have no stats and are only known from this list.
+    async fn list_never_picked_white_cards(
+        &self,
+        parent: String,
+        card_names: Vec<String>,
+        page_size: BoundedPageSize,
+        start_index: usize,
+    ) -> Result<(Vec<WhiteCardStats>, Option<usize>, i64), Status>;
+
+    // WARNING - DO NOT USE IN PROD!!!
+    // Calling this method irreversable erases
+    // all mongo collections related to this service.
+    // This is meant to clear data between test runs.
+    async fn unsafe_wipe_data_and_reset(&self) -> Result<(), mongodb::error::Error>;
+}
+
+// Stats are keyed by card name rather than by object id,
+// since default cards aren't stored in the database.
+pub struct MongoWhiteCardStatsCollection {
+    collection: Collection<Document>,
+}
+
+impl MongoWhiteCardStatsCollection {
+    pub fn new(collection: Collection<Document>) -> Self {
+        let mut top_white_cards_index_doc = doc! {"parent": 1};
+        top_white_cards_index_doc.extend(top_white_cards_sort_doc());
+        let mut never_picked_white_cards_index_doc = doc! {"parent": 1, "winCount": 1};
+        never_picked_white_cards_index_doc.extend(never_picked_white_cards_sort_doc());
+        create_indexes_in_background(
+            &collection,
+            vec![
+                IndexModel::builder()
+                    .keys(top_white_cards_index_doc)
+                    .build(),
+                IndexModel::builder()
+                    .keys(never_picked_white_cards_index_doc)
+                    .build(),
+            ],
+        );
+        Self { collection }
+    }
+
+    async fn find_docs(
+        &self,
+        find_doc: Document,
+        find_options_or: Option<FindOptions>,
+    ) -> Result<Vec<Document>, Status> {
+        let res = match self.collection.find(find_doc, find_options_or).await {
+            Ok(res) => res,
+            Err(err) => {
+                return Err(mongo_error_to_status(
+                    err,
+                    "Failed to fetch white card stats.",
+                ))
+            }
+        };
+
+        match res
+            .collect::<Result<Vec<Document>, mongodb::error::Error>>()
+            .await
+        {
+            Ok(docs) => Ok(docs),
+            Err(err) => Err(mongo_error_to_status(
+                err,
+                "Failed to fetch white card stats.",
+            )),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl WhiteCardStatsCollection for MongoWhiteCardStatsCollection {
+    async fn increment_white_card_stats(
+        &self,
+        parent: String,
+        white_card_stats: WhiteCardStats,
+    ) -> Result<(), Status> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "increment_white_card_stats");
+        let options = mongodb::options::UpdateOptions::builder()
+            .upsert(true)
+            .build();
+
+        let update_doc = doc! {
+            "$set": doc! {
+                "parent": parent
+            },
+            "$inc": doc! {
+                "playCount": white_card_stats.play_count,
+                "winCount": white_card_stats.win_count
+            }
+        };
+
+        match self
+            .collection
+            .update_one(
+                doc! {"_id": white_card_stats.card_name},
+                update_doc,
+                options,
+            )
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => Err(mongo_error_to_status(
+                err,
+                "Failed to update white card stats.",
+            )),
+        }
+    }
+
+    async fn list_top_white_cards(
+        &self,
+        parent: String,
+        page_size: BoundedPageSize,
+        start_index: usize,
+    ) -> Result<(Vec<WhiteCardStats>, Option<usize>, i64), Status> {
+        let _timer = start_mongo_operation_timer(self.collection.name(), "list_top_white_cards");
+        let page_size_i64 = page_size.take_i64();
+        let find_doc = doc! {"parent": parent, "winCount": doc!{"$gt": 0_i64}};
+
+        let total_size = match self
+            .collection
+            .count_documents(find_doc.clone(), None)
+            .await
+        {
+            Ok(count) => count as i64,
+            Err(err) => {
+                return Err(mongo_error_to_status(
+                    err,
+                    "Failed to fetch white card stats.",
+                ))
+            }
+        };
+
+        let find_options = FindOptions::builder()
+            .sort(top_white_cards_sort_doc())
+            .skip(start_index as u64)
+            .limit(page_size_i64 + 1)
+            .build();
+
+        let mut docs = self.find_docs(find_doc, Some(find_options)).await?;
+
+        let next_index_or = if docs.len() > page_size_i64 as usize {
+            docs.pop();
+            Some(start_index + page_size_i64 as usize)
+        } else {
+            None
+        };
+
+        Ok((
+            docs.iter().map(document_to_white_card_stats).collect(),
+            next_index_or,
+            total_size,
+        ))
+    }
+
+    async fn list_never_picked_white_cards(
+        &self,
+        parent: String,
+        card_names: Vec<String>,
+        page_size: BoundedPageSize,
+        start_index: usize,
+    ) -> Result<(Vec<WhiteCardStats>, Option<usize>, i64), Status> {
+        let _timer =
+            start_mongo_operation_timer(self.collection.name(), "list_never_picked_white_cards");
+        let page_size_i64 = page_size.take_i64();
+        let mut find_doc = doc! {
+            "parent": parent,
+            "_id": doc! {"$in": card_names.clone()},
+            "playCount": doc! {"$gt": 0_i64}
+        };
+
+        let played_card_names: HashSet<String> = match self
+            .collection
+            .distinct("_id", find_doc.clone(), None)
+            .await
+        {
+            Ok(ids) => ids
+                .into_iter()
+                .filter_map(|id| match id {
+                    Bson::String(card_name) => Some(card_name),
+                    _ => None,
+                })
+                .collect(),
+            Err(err) => {
+                return Err(mongo_error_to_status(
+                    err,
+                    "Failed to fetch white card stats.",
+                ))
+            }
+        };
+
+        find_doc.insert("winCount", 0_i64);
+        let played_count = match self
+            .collection
+            .count_documents(find_doc.clone(), None)
+            .await
+        {
+            Ok(count) => count as usize,
+            Err(err) => {
+                return Err(mongo_error_to_status(
+                    err,
+                    "Failed to fetch white card stats.",
+                ))
+            }
+        };
+
+        let find_options = FindOptions::builder()
+            .sort(never_picked_white_cards_sort_doc())
+            .skip(start_index as u64)
+            .limit(page_size_i64 + 1)
+            .build();
+        let docs = self.find_docs(find_doc, Some(find_options)).await?;
+
+        Ok(take_never_picked_page(
+            docs.iter().map(document_to_white_card_stats).collect(),
+            played_count,
+            card_names
+                .into_iter()
+                .filter(|card_name| !played_card_names.contains(card_name))
+                .collect(),
+            page_size_i64,
+            start_index,
+        ))
+    }
+
+    async fn unsafe_wipe_data_and_reset(&self) -> Result<(), mongodb::error::Error> {
+        self.collection.drop(None).await
+    }
+}
+
+fn document_to_white_card_stats(doc: &Document) -> WhiteCardStats {
+    WhiteCardStats {
+        card_name: String::from(doc.get_str("_id").unwrap_or("")),
+        play_count: doc.get_i64("playCount").unwrap_or(0),
+        win_count: doc.get_i64("winCount").unwrap_or(0),
+    }
+}
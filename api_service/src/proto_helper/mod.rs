@@ -1 +0,0 @@
-pub mod page_token;
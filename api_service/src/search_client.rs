@@ -1,16 +1,28 @@
-use super::environment::EnvironmentVariables;
+use super::config::Config;
+use super::metrics::record_sonic_error;
 use mockall::automock;
 use shared::proto::crusty_cards_api::User;
 use shared::resource_name::{ParseNameError, UserName};
 use sonic_channel::*;
+use tonic::Status;
+use tracing::error;
 
 #[automock]
 pub trait SearchClient: Send + Sync {
     fn index_user(&self, user: &User, overwrite: bool) -> Result<(), IndexUserError>;
-    fn index_user_or_print_to_std_err(&self, user: &User, overwrite: bool);
+    fn index_user_or_log_error(&self, user: &User, overwrite: bool);
     fn wipe_user_index(&self) -> Result<(), sonic_channel::result::Error>;
     fn search_users(&self, query: &str) -> Result<Vec<UserName>, result::Error>;
     fn autocomplete_search_users(&self, query: &str) -> Result<Vec<String>, result::Error>;
+    // Checks that Sonic is reachable.
+    fn ping(&self) -> Result<(), result::Error>;
+}
+
+// The underlying error is logged rather than returned
+// so that search backend details aren't leaked to clients.
+pub fn sonic_error_to_status(err: result::Error, message: &str) -> Status {
+    error!(error = %err, "{}", message);
+    Status::unknown(message)
 }
 
 pub enum IndexUserError {
@@ -38,19 +50,30 @@ impl SonicSearchClient {
     const SONIC_USER_COLLECTION: &'static str = "users";
     const SONIC_USER_BUCKET_DEFAULT: &'static str = "default";
 
-    pub fn new(env_vars: &EnvironmentVariables) -> Result<Self, result::Error> {
+    pub fn new(config: &Config) -> Result<Self, result::Error> {
         Ok(Self {
             sonic_search_channel: SearchChannel::start(
-                env_vars.get_sonic_uri(),
-                env_vars.get_sonic_password(),
+                config.get_sonic_uri(),
+                config.get_sonic_password(),
             )?,
             sonic_ingest_channel: IngestChannel::start(
-                env_vars.get_sonic_uri(),
-                env_vars.get_sonic_password(),
+                config.get_sonic_uri(),
+                config.get_sonic_password(),
             )?,
         })
     }
 
+    // Counts failed requests so that Sonic outages show up in our metrics.
+    fn count_sonic_error<T>(
+        operation: &str,
+        result: Result<T, result::Error>,
+    ) -> Result<T, result::Error> {
+        if result.is_err() {
+            record_sonic_error(operation);
+        }
+        result
+    }
+
     fn get_user_text_tokens(user: &User) -> Result<String, ParseNameError> {
         let user_name_hex_string = UserName::new_from_str(&user.name)?
             .take_object_id()
@@ -66,20 +89,26 @@ impl SearchClient for SonicSearchClient {
             Err(err) => return Err(IndexUserError::ParseNameError(err)),
         };
         if overwrite {
-            match self.sonic_ingest_channel.flusho(
-                Self::SONIC_USER_COLLECTION,
-                Self::SONIC_USER_BUCKET_DEFAULT,
-                &user.name,
+            match Self::count_sonic_error(
+                "flush_user",
+                self.sonic_ingest_channel.flusho(
+                    Self::SONIC_USER_COLLECTION,
+                    Self::SONIC_USER_BUCKET_DEFAULT,
+                    &user.name,
+                ),
             ) {
                 Ok(_) => {}
                 Err(err) => return Err(IndexUserError::SonicError(err)),
             };
         }
-        match self.sonic_ingest_channel.push(
-            Self::SONIC_USER_COLLECTION,
-            Self::SONIC_USER_BUCKET_DEFAULT,
-            &user.name,
-            &user_text_tokens,
+        match Self::count_sonic_error(
+            "index_user",
+            self.sonic_ingest_channel.push(
+                Self::SONIC_USER_COLLECTION,
+                Self::SONIC_USER_BUCKET_DEFAULT,
+                &user.name,
+                &user_text_tokens,
+            ),
         ) {
             Ok(_) => {}
             Err(err) => return Err(IndexUserError::SonicError(err)),
@@ -87,24 +116,30 @@ impl SearchClient for SonicSearchClient {
         Ok(())
     }
 
-    fn index_user_or_print_to_std_err(&self, user: &User, overwrite: bool) {
+    fn index_user_or_log_error(&self, user: &User, overwrite: bool) {
         match self.index_user(user, overwrite) {
             Ok(_) => {}
-            Err(err) => eprintln!("Error updating sonic search index: {}", err),
+            Err(err) => error!(error = %err, "Failed to update Sonic search index."),
         };
     }
 
     fn wipe_user_index(&self) -> Result<(), sonic_channel::result::Error> {
-        self.sonic_ingest_channel
-            .flushc(Self::SONIC_USER_COLLECTION)?;
+        Self::count_sonic_error(
+            "wipe_user_index",
+            self.sonic_ingest_channel
+                .flushc(Self::SONIC_USER_COLLECTION),
+        )?;
         Ok(())
     }
 
     fn search_users(&self, query: &str) -> Result<Vec<UserName>, result::Error> {
-        let user_name_strings: Vec<String> = self.sonic_search_channel.query(
-            Self::SONIC_USER_COLLECTION,
-            Self::SONIC_USER_BUCKET_DEFAULT,
-            query,
+        let user_name_strings: Vec<String> = Self::count_sonic_error(
+            "search_users",
+            self.sonic_search_channel.query(
+                Self::SONIC_USER_COLLECTION,
+                Self::SONIC_USER_BUCKET_DEFAULT,
+                query,
+            ),
         )?;
         let mut user_names: Vec<UserName> = Vec::new();
         for user_name_string in &user_name_strings {
@@ -116,10 +151,19 @@ impl SearchClient for SonicSearchClient {
     }
 
     fn autocomplete_search_users(&self, query: &str) -> Result<Vec<String>, result::Error> {
-        self.sonic_search_channel.suggest(
-            Self::SONIC_USER_COLLECTION,
-            Self::SONIC_USER_BUCKET_DEFAULT,
-            query,
+        Self::count_sonic_error(
+            "autocomplete_search_users",
+            self.sonic_search_channel.suggest(
+                Self::SONIC_USER_COLLECTION,
+                Self::SONIC_USER_BUCKET_DEFAULT,
+                query,
+            ),
         )
     }
+
+    fn ping(&self) -> Result<(), result::Error> {
+        Self::count_sonic_error("ping", self.sonic_search_channel.ping())?;
+        Self::count_sonic_error("ping", self.sonic_ingest_channel.ping())?;
+        Ok(())
+    }
 }
@@ -1,3 +1,4 @@
+use super::super::authorization::{RoleAuthorizer, UserRole};
 use super::super::mongo::user_collection::UserCollection;
 use super::super::search_client::{IndexUserError, SearchClient};
 use futures_lite::StreamExt;
@@ -5,10 +6,13 @@ use shared::proto::crusty_cards_api::admin_service_server::AdminService;
 use shared::proto::google::protobuf::Empty;
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
+use tracing::error;
 
+// Every RPC is restricted to admins.
 pub struct AdminServiceImpl {
     user_collection: Arc<dyn UserCollection>,
     search_client: Arc<dyn SearchClient>,
+    role_authorizer: RoleAuthorizer,
 }
 
 impl AdminServiceImpl {
@@ -17,16 +21,19 @@ impl AdminServiceImpl {
         search_client: Arc<dyn SearchClient>,
     ) -> Self {
         Self {
+            role_authorizer: RoleAuthorizer::new(user_collection.clone()),
             user_collection,
             search_client,
         }
     }
 
     fn sonic_error_to_unknown_status(error: sonic_channel::result::Error) -> Status {
+        error!(error = %error, "Admin request failed with a Sonic error.");
         Status::unknown(format!("Unknown error from Sonic: {}.", error))
     }
 
     fn mongodb_error_to_unknown_status(error: mongodb::error::Error) -> Status {
+        error!(error = %error, "Admin request failed with a MongoDB error.");
         Status::unknown(format!("Unknown error from Mongodb: {}.", error))
     }
 }
@@ -35,8 +42,12 @@ impl AdminServiceImpl {
 impl AdminService for AdminServiceImpl {
     async fn clear_user_search_index(
         &self,
-        _request: Request<Empty>,
+        request: Request<Empty>,
     ) -> Result<Response<Empty>, Status> {
+        self.role_authorizer
+            .check_caller_has_role(&request, UserRole::Admin)
+            .await?;
+
         match self.search_client.wipe_user_index() {
             Ok(_) => Ok(Response::new(Empty {})),
             Err(err) => Err(Self::sonic_error_to_unknown_status(err)),
@@ -45,8 +56,12 @@ impl AdminService for AdminServiceImpl {
 
     async fn refresh_user_search_index(
         &self,
-        _request: Request<Empty>,
+        request: Request<Empty>,
     ) -> Result<Response<Empty>, Status> {
+        self.role_authorizer
+            .check_caller_has_role(&request, UserRole::Admin)
+            .await?;
+
         let mut user_stream = match self.user_collection.user_stream().await {
             Ok(user_stream) => user_stream,
             Err(err) => return Err(Self::mongodb_error_to_unknown_status(err)),
@@ -70,3 +85,53 @@ impl AdminService for AdminServiceImpl {
         Ok(Response::new(Empty {}))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::mongo::user_collection::MockUserCollection;
+    use super::super::super::search_client::MockSearchClient;
+    use super::*;
+    use shared::auth::create_request_authenticated_as;
+
+    #[tokio::test]
+    async fn clear_user_search_index_requires_admin_role() {
+        let mut mock_user_collection = MockUserCollection::new();
+        mock_user_collection
+            .expect_get_user_role()
+            .returning(|user_name| {
+                if user_name.clone_str() == "users/5d8c5ea3e3b0ab3ac6b8fac2" {
+                    Ok(UserRole::Admin)
+                } else {
+                    Ok(UserRole::Moderator)
+                }
+            });
+        let mut mock_search_client = MockSearchClient::new();
+        mock_search_client
+            .expect_wipe_user_index()
+            .times(1)
+            .returning(|| Ok(()));
+        let admin_service = AdminServiceImpl::new(
+            Arc::from(mock_user_collection),
+            Arc::from(mock_search_client),
+        );
+
+        assert_eq!(
+            admin_service
+                .clear_user_search_index(create_request_authenticated_as(
+                    Empty {},
+                    "users/5d8c5ea3e3b0ab3ac6b8fac3"
+                ))
+                .await
+                .unwrap_err()
+                .code(),
+            tonic::Code::PermissionDenied
+        );
+        assert!(admin_service
+            .clear_user_search_index(create_request_authenticated_as(
+                Empty {},
+                "users/5d8c5ea3e3b0ab3ac6b8fac2"
+            ))
+            .await
+            .is_ok());
+    }
+}
@@ -1,19 +1,27 @@
 use super::super::mongo::custom_black_card_collection::CustomBlackCardCollection;
 use super::super::mongo::custom_cardpack_collection::CustomCardpackCollection;
 use super::super::mongo::custom_white_card_collection::CustomWhiteCardCollection;
+use super::super::mongo::helper::{resource_not_found_error, ItemStream};
 use super::super::mongo::user_collection::UserCollection;
-use super::super::proto_helper::page_token::*;
+use super::super::mongo::white_card_stats_collection::WhiteCardStatsCollection;
 use super::default_cardpacks::DefaultCardpackHandler;
 use super::helper::*;
+use futures_lite::{stream, Stream, StreamExt};
+use shared::auth::{check_caller_is_internal, check_caller_owns_resource};
 use shared::basic_validation::{AnswerFieldCount, ValidatedStringField};
+use shared::grpc_error::negative_request_field_error;
+use shared::page_token::*;
 use shared::proto::crusty_cards_api::cardpack_service_server::CardpackService;
+use shared::proto::crusty_cards_api::stream_cardpack_cards_response::Card;
 use shared::proto::crusty_cards_api::*;
 use shared::proto::google::protobuf::Empty;
 use shared::proto_validation::BoundedPageSize;
 use shared::resource_name::*;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::pin::Pin;
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
+use tracing::warn;
 
 pub struct CardpackServiceImpl {
     custom_cardpack_collection: Box<dyn CustomCardpackCollection>,
@@ -21,6 +29,22 @@ pub struct CardpackServiceImpl {
     custom_white_card_collection: Box<dyn CustomWhiteCardCollection>,
     default_cardpack_handler: DefaultCardpackHandler,
     user_collection: Arc<dyn UserCollection>,
+    white_card_stats_collection: Box<dyn WhiteCardStatsCollection>,
+}
+
+// White card stats can be listed for both custom and default cardpacks.
+enum WhiteCardStatsParent {
+    Custom(CustomCardpackName),
+    Default(DefaultCardpackName),
+}
+
+impl WhiteCardStatsParent {
+    fn clone_str(&self) -> String {
+        match self {
+            Self::Custom(custom_cardpack_name) => custom_cardpack_name.clone_str(),
+            Self::Default(default_cardpack_name) => default_cardpack_name.clone_str(),
+        }
+    }
 }
 
 impl CardpackServiceImpl {
@@ -30,6 +54,7 @@ impl CardpackServiceImpl {
         custom_white_card_collection: Box<dyn CustomWhiteCardCollection>,
         default_cardpack_handler: DefaultCardpackHandler,
         user_collection: Arc<dyn UserCollection>,
+        white_card_stats_collection: Box<dyn WhiteCardStatsCollection>,
     ) -> Self {
         Self {
             custom_cardpack_collection,
@@ -37,6 +62,144 @@ impl CardpackServiceImpl {
             custom_white_card_collection,
             default_cardpack_handler,
             user_collection,
+            white_card_stats_collection,
+        }
+    }
+
+    async fn parse_white_card_stats_parent(
+        &self,
+        parent: &str,
+    ) -> Result<WhiteCardStatsParent, Status> {
+        let parent = ValidatedStringField::new(parent, "parent")?;
+        if parent.get_string().starts_with("defaultCardpacks/") {
+            let default_cardpack_name = match DefaultCardpackName::new(&parent) {
+                Ok(default_cardpack_name) => default_cardpack_name,
+                Err(err) => return Err(err.to_status()),
+            };
+            if self
+                .default_cardpack_handler
+                .get_pack_by_name(&default_cardpack_name)
+                .is_none()
+            {
+                return Err(Status::invalid_argument("Parent does not exist."));
+            }
+            Ok(WhiteCardStatsParent::Default(default_cardpack_name))
+        } else {
+            let custom_cardpack_name = match CustomCardpackName::new(&parent) {
+                Ok(custom_cardpack_name) => custom_cardpack_name,
+                Err(err) => return Err(err.to_status()),
+            };
+            // Stats of missing or deleted cardpacks are hidden the same way the cardpacks are.
+            self.custom_cardpack_collection
+                .get_custom_cardpack(custom_cardpack_name.clone())
+                .await?;
+            Ok(WhiteCardStatsParent::Custom(custom_cardpack_name))
+        }
+    }
+
+    // Returns the cardpack that the white card belongs to,
+    // or `None` if the name isn't a custom or default white card name.
+    fn get_white_card_parent(
+        &self,
+        white_card_name: &str,
+        field_name: &str,
+    ) -> Result<Option<WhiteCardStatsParent>, Status> {
+        let white_card_name = ValidatedStringField::new(white_card_name, field_name)?;
+        if let Ok(custom_white_card_name) = CustomWhiteCardName::new(&white_card_name) {
+            let (parent_user_object_id, parent_custom_cardpack_object_id, _) =
+                custom_white_card_name.take_object_ids();
+            return match CustomCardpackName::new_from_str(&format!(
+                "users/{}/cardpacks/{}",
+                parent_user_object_id.to_hex(),
+                parent_custom_cardpack_object_id.to_hex()
+            )) {
+                Ok(custom_cardpack_name) => {
+                    Ok(Some(WhiteCardStatsParent::Custom(custom_cardpack_name)))
+                }
+                Err(err) => Err(err.to_status()),
+            };
+        }
+
+        // Default white card names look like `defaultCardpacks/{}/defaultWhiteCards/{}`.
+        let tokens: Vec<&str> = white_card_name.get_string().split('/').collect();
+        if tokens.len() == 4
+            && tokens[0] == "defaultCardpacks"
+            && tokens[2] == "defaultWhiteCards"
+            && !tokens[3].is_empty()
+        {
+            if let Ok(default_cardpack_name) =
+                DefaultCardpackName::new_from_str(&format!("defaultCardpacks/{}", tokens[1]))
+            {
+                if self
+                    .default_cardpack_handler
+                    .get_pack_by_name(&default_cardpack_name)
+                    .is_some()
+                {
+                    return Ok(Some(WhiteCardStatsParent::Default(default_cardpack_name)));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Custom cardpacks can be deleted after their cards were dealt.
+    async fn check_white_card_stats_parent_exists(
+        &self,
+        parent: WhiteCardStatsParent,
+    ) -> Result<bool, Status> {
+        match parent {
+            WhiteCardStatsParent::Custom(custom_cardpack_name) => match self
+                .custom_cardpack_collection
+                .get_custom_cardpack(custom_cardpack_name)
+                .await
+            {
+                Ok(_) => Ok(true),
+                Err(err) if err.code() == tonic::Code::NotFound => Ok(false),
+                Err(err) => Err(err),
+            },
+            WhiteCardStatsParent::Default(_) => Ok(true),
+        }
+    }
+
+    async fn get_all_white_card_names(
+        &self,
+        parent: WhiteCardStatsParent,
+    ) -> Result<Vec<String>, Status> {
+        let custom_cardpack_name = match parent {
+            WhiteCardStatsParent::Custom(custom_cardpack_name) => custom_cardpack_name,
+            WhiteCardStatsParent::Default(default_cardpack_name) => {
+                return match self
+                    .default_cardpack_handler
+                    .get_pack_by_name(&default_cardpack_name)
+                {
+                    Some(pack) => Ok(pack
+                        .get_default_white_cards()
+                        .iter()
+                        .map(|card| card.name.clone())
+                        .collect()),
+                    None => Err(Status::invalid_argument("Parent does not exist.")),
+                };
+            }
+        };
+
+        let mut white_card_names = Vec::new();
+        let mut last_object_id_or = None;
+        loop {
+            let (custom_white_cards, next_object_id_or, _) = self
+                .custom_white_card_collection
+                .list_custom_white_cards(
+                    custom_cardpack_name.clone(),
+                    BoundedPageSize::new(1000)?,
+                    last_object_id_or,
+                    false,
+                )
+                .await?;
+            white_card_names.extend(custom_white_cards.into_iter().map(|card| card.name));
+            match next_object_id_or {
+                Some(next_object_id) => last_object_id_or = Some(next_object_id),
+                None => return Ok(white_card_names),
+            };
         }
     }
 
@@ -86,8 +249,22 @@ impl CardpackServiceImpl {
     }
 }
 
+fn to_card_response_stream<T: 'static>(
+    card_stream: ItemStream<T>,
+    to_card: fn(T) -> Card,
+) -> ItemStream<StreamCardpackCardsResponse> {
+    Box::from(card_stream.map(move |card_or| {
+        card_or.map(|card| StreamCardpackCardsResponse {
+            card: Some(to_card(card)),
+        })
+    }))
+}
+
 #[tonic::async_trait]
 impl CardpackService for CardpackServiceImpl {
+    type StreamCardpackCardsStream =
+        Pin<Box<dyn Stream<Item = Result<StreamCardpackCardsResponse, Status>> + Send>>;
+
     async fn create_custom_cardpack(
         &self,
         request: Request<CreateCustomCardpackRequest>,
@@ -100,6 +277,8 @@ impl CardpackService for CardpackServiceImpl {
             Err(err) => return Err(err.to_status()),
         };
 
+        check_caller_owns_resource(&request, &parent.clone_str())?;
+
         let custom_cardpack = match &request.get_ref().custom_cardpack {
             Some(custom_cardpack) => custom_cardpack,
             None => return Err(missing_request_field_error("custom_cardpack")),
@@ -209,6 +388,8 @@ impl CardpackService for CardpackServiceImpl {
             Err(err) => return Err(err.to_status()),
         };
 
+        check_caller_owns_resource(&request, &custom_cardpack_name.clone_str())?;
+
         let update_fields: HashSet<String> = update_mask.paths.iter().cloned().collect();
         let updated_display_name_or = if update_fields.contains("display_name") {
             match ValidatedStringField::new(
@@ -248,6 +429,8 @@ impl CardpackService for CardpackServiceImpl {
             Err(err) => return Err(err.to_status()),
         };
 
+        check_caller_owns_resource(&request, &custom_cardpack_name.clone_str())?;
+
         Ok(Response::new(
             self.custom_cardpack_collection
                 .soft_delete_custom_cardpack(custom_cardpack_name)
@@ -266,6 +449,8 @@ impl CardpackService for CardpackServiceImpl {
                 Err(grpc_err) => return Err(grpc_err),
             };
 
+        check_caller_owns_resource(&request, &parent.clone_str())?;
+
         let new_card = self
             .custom_black_card_collection
             .create_custom_black_card(parent, card_text, answer_fields)
@@ -285,6 +470,8 @@ impl CardpackService for CardpackServiceImpl {
                 Err(grpc_err) => return Err(grpc_err),
             };
 
+        check_caller_owns_resource(&request, &parent.clone_str())?;
+
         let new_card = self
             .custom_white_card_collection
             .create_custom_white_card(parent, card_text)
@@ -417,6 +604,8 @@ impl CardpackService for CardpackServiceImpl {
             Err(err) => return Err(err.to_status()),
         };
 
+        check_caller_owns_resource(&request, &custom_black_card_name.clone_str())?;
+
         let update_fields: HashSet<String> = update_mask.paths.iter().cloned().collect();
 
         let updated_card_text_or = if update_fields.contains("text") {
@@ -470,6 +659,8 @@ impl CardpackService for CardpackServiceImpl {
             Err(err) => return Err(err.to_status()),
         };
 
+        check_caller_owns_resource(&request, &custom_white_card_name.clone_str())?;
+
         let update_fields: HashSet<String> = update_mask.paths.iter().cloned().collect();
         let updated_card_text_or = if update_fields.contains("text") {
             match ValidatedStringField::new(&custom_white_card.text, "custom_white_card.text") {
@@ -506,6 +697,8 @@ impl CardpackService for CardpackServiceImpl {
             Err(err) => return Err(err.to_status()),
         };
 
+        check_caller_owns_resource(&request, &custom_black_card_name.clone_str())?;
+
         Ok(Response::new(
             self.custom_black_card_collection
                 .soft_delete_custom_black_card(custom_black_card_name)
@@ -525,6 +718,8 @@ impl CardpackService for CardpackServiceImpl {
             Err(err) => return Err(err.to_status()),
         };
 
+        check_caller_owns_resource(&request, &custom_white_card_name.clone_str())?;
+
         Ok(Response::new(
             self.custom_white_card_collection
                 .soft_delete_custom_white_card(custom_white_card_name)
@@ -544,6 +739,8 @@ impl CardpackService for CardpackServiceImpl {
             Err(err) => return Err(err.to_status()),
         };
 
+        check_caller_owns_resource(&request, &parent.clone_str())?;
+
         for req in &request.get_ref().requests {
             if !req.parent.is_empty() && req.parent != request.get_ref().parent {
                 return Err(batch_create_differing_parent_error());
@@ -591,6 +788,8 @@ impl CardpackService for CardpackServiceImpl {
             Err(err) => return Err(err.to_status()),
         };
 
+        check_caller_owns_resource(&request, &parent.clone_str())?;
+
         for req in &request.get_ref().requests {
             if !req.parent.is_empty() && req.parent != request.get_ref().parent {
                 return Err(batch_create_differing_parent_error());
@@ -663,16 +862,8 @@ impl CardpackService for CardpackServiceImpl {
         let mut start_index: usize = 0;
 
         if !request.get_ref().page_token.is_empty() {
-            start_index = match parse_page_token_string(
-                &request_without_page_token,
-                &request.get_ref().page_token,
-            ) {
-                Ok(index_string) => match index_string.parse::<usize>() {
-                    Ok(index) => index,
-                    Err(_) => return Err(invalid_page_token_error()),
-                },
-                Err(grpc_err) => return Err(grpc_err),
-            };
+            start_index =
+                parse_page_token_index(&request_without_page_token, &request.get_ref().page_token)?;
         }
 
         let end_index: usize = start_index + bounded_page_size.take_i64() as usize;
@@ -689,8 +880,7 @@ impl CardpackService for CardpackServiceImpl {
 
         let mut next_page_token = String::from("");
         if !default_cardpacks.is_empty() && all_packs.len() > end_index {
-            next_page_token =
-                create_page_token(&request_without_page_token, format!("{}", end_index));
+            next_page_token = create_page_token_for_index(&request_without_page_token, end_index);
         }
 
         Ok(Response::new(ListDefaultCardpacksResponse {
@@ -724,16 +914,8 @@ impl CardpackService for CardpackServiceImpl {
         let mut start_index: usize = 0;
 
         if !request.get_ref().page_token.is_empty() {
-            start_index = match parse_page_token_string(
-                &request_without_page_token,
-                &request.get_ref().page_token,
-            ) {
-                Ok(index_string) => match index_string.parse::<usize>() {
-                    Ok(index) => index,
-                    Err(_) => return Err(invalid_page_token_error()),
-                },
-                Err(grpc_err) => return Err(grpc_err),
-            };
+            start_index =
+                parse_page_token_index(&request_without_page_token, &request.get_ref().page_token)?;
         }
 
         let end_index: usize = start_index + bounded_page_size.take_i64() as usize;
@@ -756,8 +938,7 @@ impl CardpackService for CardpackServiceImpl {
 
         let mut next_page_token = String::from("");
         if !default_black_cards.is_empty() && all_cards_from_pack.len() > end_index {
-            next_page_token =
-                create_page_token(&request_without_page_token, format!("{}", end_index));
+            next_page_token = create_page_token_for_index(&request_without_page_token, end_index);
         }
 
         Ok(Response::new(ListDefaultBlackCardsResponse {
@@ -791,16 +972,8 @@ impl CardpackService for CardpackServiceImpl {
         let mut start_index: usize = 0;
 
         if !request.get_ref().page_token.is_empty() {
-            start_index = match parse_page_token_string(
-                &request_without_page_token,
-                &request.get_ref().page_token,
-            ) {
-                Ok(index_string) => match index_string.parse::<usize>() {
-                    Ok(index) => index,
-                    Err(_) => return Err(invalid_page_token_error()),
-                },
-                Err(grpc_err) => return Err(grpc_err),
-            };
+            start_index =
+                parse_page_token_index(&request_without_page_token, &request.get_ref().page_token)?;
         }
 
         let end_index: usize = start_index + bounded_page_size.take_i64() as usize;
@@ -823,8 +996,7 @@ impl CardpackService for CardpackServiceImpl {
 
         let mut next_page_token = String::from("");
         if !default_white_cards.is_empty() && all_cards_from_pack.len() > end_index {
-            next_page_token =
-                create_page_token(&request_without_page_token, format!("{}", end_index));
+            next_page_token = create_page_token_for_index(&request_without_page_token, end_index);
         }
 
         Ok(Response::new(ListDefaultWhiteCardsResponse {
@@ -834,6 +1006,80 @@ impl CardpackService for CardpackServiceImpl {
         }))
     }
 
+    // Unlike the list RPCs, this isn't paginated, so every card in the
+    // requested cardpacks can be fetched with a single call. Every cardpack
+    // is checked up front, so the stream never ends with a missing cardpack.
+    async fn stream_cardpack_cards(
+        &self,
+        request: Request<StreamCardpackCardsRequest>,
+    ) -> Result<Response<Self::StreamCardpackCardsStream>, Status> {
+        let mut custom_cardpack_names = Vec::new();
+        for (index, name) in request.get_ref().custom_cardpack_names.iter().enumerate() {
+            match CustomCardpackName::new(&ValidatedStringField::new(
+                name,
+                &format!("custom_cardpack_names[{}]", index),
+            )?) {
+                Ok(custom_cardpack_name) => custom_cardpack_names.push(custom_cardpack_name),
+                Err(err) => return Err(err.to_status()),
+            };
+        }
+
+        let mut card_streams: Vec<ItemStream<StreamCardpackCardsResponse>> = Vec::new();
+
+        for (index, name) in request.get_ref().default_cardpack_names.iter().enumerate() {
+            let default_cardpack_name = match DefaultCardpackName::new(&ValidatedStringField::new(
+                name,
+                &format!("default_cardpack_names[{}]", index),
+            )?) {
+                Ok(default_cardpack_name) => default_cardpack_name,
+                Err(err) => return Err(err.to_status()),
+            };
+            let pack = match self
+                .default_cardpack_handler
+                .get_pack_by_name(&default_cardpack_name)
+            {
+                Some(pack) => pack,
+                None => return Err(resource_not_found_error(&default_cardpack_name.clone_str())),
+            };
+            // Default cards are already in memory, so they're copied up front.
+            let default_cards: Vec<Result<StreamCardpackCardsResponse, Status>> = pack
+                .get_default_black_cards()
+                .iter()
+                .map(|card| Card::DefaultBlackCard(card.clone()))
+                .chain(
+                    pack.get_default_white_cards()
+                        .iter()
+                        .map(|card| Card::DefaultWhiteCard(card.clone())),
+                )
+                .map(|card| Ok(StreamCardpackCardsResponse { card: Some(card) }))
+                .collect();
+            card_streams.push(Box::from(stream::iter(default_cards)));
+        }
+
+        for custom_cardpack_name in custom_cardpack_names {
+            // Fails if the cardpack doesn't exist or has been deleted.
+            self.custom_cardpack_collection
+                .get_custom_cardpack(custom_cardpack_name.clone())
+                .await?;
+            card_streams.push(to_card_response_stream(
+                self.custom_black_card_collection
+                    .stream_custom_black_cards(custom_cardpack_name.clone())
+                    .await?,
+                Card::CustomBlackCard,
+            ));
+            card_streams.push(to_card_response_stream(
+                self.custom_white_card_collection
+                    .stream_custom_white_cards(custom_cardpack_name)
+                    .await?,
+                Card::CustomWhiteCard,
+            ));
+        }
+
+        Ok(Response::new(Box::pin(
+            stream::iter(card_streams).flatten(),
+        )))
+    }
+
     async fn undelete_custom_cardpack(
         &self,
         request: Request<UndeleteCustomCardpackRequest>,
@@ -846,6 +1092,8 @@ impl CardpackService for CardpackServiceImpl {
             Err(err) => return Err(err.to_status()),
         };
 
+        check_caller_owns_resource(&request, &custom_cardpack_name.clone_str())?;
+
         Ok(Response::new(
             self.custom_cardpack_collection
                 .undelete_custom_cardpack(custom_cardpack_name)
@@ -865,6 +1113,8 @@ impl CardpackService for CardpackServiceImpl {
             Err(err) => return Err(err.to_status()),
         };
 
+        check_caller_owns_resource(&request, &custom_black_card_name.clone_str())?;
+
         Ok(Response::new(
             self.custom_black_card_collection
                 .undelete_custom_black_card(custom_black_card_name)
@@ -884,6 +1134,8 @@ impl CardpackService for CardpackServiceImpl {
             Err(err) => return Err(err.to_status()),
         };
 
+        check_caller_owns_resource(&request, &custom_white_card_name.clone_str())?;
+
         Ok(Response::new(
             self.custom_white_card_collection
                 .undelete_custom_white_card(custom_white_card_name)
@@ -903,6 +1155,8 @@ impl CardpackService for CardpackServiceImpl {
             Err(err) => return Err(err.to_status()),
         };
 
+        check_caller_owns_resource(&request, &user_name.clone_str())?;
+
         let bounded_page_size = BoundedPageSize::new(request.get_ref().page_size)?;
 
         let request_without_page_token = {
@@ -914,16 +1168,8 @@ impl CardpackService for CardpackServiceImpl {
 
         let mut start_index: usize = 0;
         if !request.get_ref().page_token.is_empty() {
-            start_index = match parse_page_token_string(
-                &request_without_page_token,
-                &request.get_ref().page_token,
-            ) {
-                Ok(index_string) => match index_string.parse::<usize>() {
-                    Ok(index) => index,
-                    Err(_) => return Err(invalid_page_token_error()),
-                },
-                Err(grpc_err) => return Err(grpc_err),
-            };
+            start_index =
+                parse_page_token_index(&request_without_page_token, &request.get_ref().page_token)?;
         }
 
         let (custom_cardpack_names, next_index_or) = self
@@ -956,7 +1202,7 @@ impl CardpackService for CardpackServiceImpl {
 
         let next_page_token = match next_index_or {
             Some(next_index) => {
-                create_page_token(&request_without_page_token, format!("{}", next_index))
+                create_page_token_for_index(&request_without_page_token, next_index)
             }
             None => String::from(""),
         };
@@ -977,6 +1223,8 @@ impl CardpackService for CardpackServiceImpl {
                 Err(err) => return Err(err.to_status()),
             };
 
+        check_caller_owns_resource(&request, &user_name.clone_str())?;
+
         let custom_cardpack_name = match CustomCardpackName::new(&ValidatedStringField::new(
             &request.get_ref().custom_cardpack,
             "custom_cardpack",
@@ -1002,6 +1250,8 @@ impl CardpackService for CardpackServiceImpl {
                 Err(err) => return Err(err.to_status()),
             };
 
+        check_caller_owns_resource(&request, &user_name.clone_str())?;
+
         let custom_cardpack_name = match CustomCardpackName::new(&ValidatedStringField::new(
             &request.get_ref().custom_cardpack,
             "custom_cardpack",
@@ -1027,6 +1277,8 @@ impl CardpackService for CardpackServiceImpl {
                 Err(err) => return Err(err.to_status()),
             };
 
+        check_caller_owns_resource(&request, &user_name.clone_str())?;
+
         let custom_cardpack_name = match CustomCardpackName::new(&ValidatedStringField::new(
             &request.get_ref().custom_cardpack,
             "custom_cardpack",
@@ -1044,16 +1296,184 @@ impl CardpackService for CardpackServiceImpl {
             is_liked,
         }))
     }
+
+    async fn list_top_white_cards(
+        &self,
+        request: Request<ListTopWhiteCardsRequest>,
+    ) -> Result<Response<ListTopWhiteCardsResponse>, Status> {
+        let parent = self
+            .parse_white_card_stats_parent(&request.get_ref().parent)
+            .await?;
+
+        let bounded_page_size = BoundedPageSize::new(request.get_ref().page_size)?;
+
+        let request_without_page_token = {
+            let mut req = request.get_ref().clone();
+            req.page_token = String::from("");
+            req.page_size = 0;
+            req
+        };
+
+        let mut start_index: usize = 0;
+        if !request.get_ref().page_token.is_empty() {
+            start_index =
+                parse_page_token_index(&request_without_page_token, &request.get_ref().page_token)?;
+        }
+
+        let (white_card_stats, next_index_or, total_size) = self
+            .white_card_stats_collection
+            .list_top_white_cards(parent.clone_str(), bounded_page_size, start_index)
+            .await?;
+
+        let next_page_token = match next_index_or {
+            Some(next_index) => {
+                create_page_token_for_index(&request_without_page_token, next_index)
+            }
+            None => String::from(""),
+        };
+
+        Ok(Response::new(ListTopWhiteCardsResponse {
+            white_card_stats,
+            next_page_token,
+            total_size,
+        }))
+    }
+
+    async fn list_never_picked_white_cards(
+        &self,
+        request: Request<ListNeverPickedWhiteCardsRequest>,
+    ) -> Result<Response<ListNeverPickedWhiteCardsResponse>, Status> {
+        let parent = self
+            .parse_white_card_stats_parent(&request.get_ref().parent)
+            .await?;
+
+        let bounded_page_size = BoundedPageSize::new(request.get_ref().page_size)?;
+
+        let request_without_page_token = {
+            let mut req = request.get_ref().clone();
+            req.page_token = String::from("");
+            req.page_size = 0;
+            req
+        };
+
+        let mut start_index: usize = 0;
+        if !request.get_ref().page_token.is_empty() {
+            start_index =
+                parse_page_token_index(&request_without_page_token, &request.get_ref().page_token)?;
+        }
+
+        let parent_name = parent.clone_str();
+        let card_names = self.get_all_white_card_names(parent).await?;
+        let (white_card_stats, next_index_or, total_size) = self
+            .white_card_stats_collection
+            .list_never_picked_white_cards(parent_name, card_names, bounded_page_size, start_index)
+            .await?;
+
+        let next_page_token = match next_index_or {
+            Some(next_index) => {
+                create_page_token_for_index(&request_without_page_token, next_index)
+            }
+            None => String::from(""),
+        };
+
+        Ok(Response::new(ListNeverPickedWhiteCardsResponse {
+            white_card_stats,
+            next_page_token,
+            total_size,
+        }))
+    }
+
+    // Only called by the game service, which reports
+    // which white cards were played and won each round.
+    async fn report_white_card_stats(
+        &self,
+        request: Request<ReportWhiteCardStatsRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        check_caller_is_internal(&request)?;
+
+        let mut validated_white_card_stats = Vec::new();
+        for (index, white_card_stats) in request.get_ref().white_card_stats.iter().enumerate() {
+            let parent_or = self.get_white_card_parent(
+                &white_card_stats.card_name,
+                &format!("white_card_stats[{}].card_name", index),
+            )?;
+            if white_card_stats.play_count < 0 {
+                return Err(negative_request_field_error(&format!(
+                    "white_card_stats[{}].play_count",
+                    index
+                )));
+            }
+            if white_card_stats.win_count < 0 {
+                return Err(negative_request_field_error(&format!(
+                    "white_card_stats[{}].win_count",
+                    index
+                )));
+            }
+            if white_card_stats.win_count > white_card_stats.play_count {
+                return Err(Status::invalid_argument(format!(
+                    "Field `white_card_stats[{}].win_count` must not be greater than `white_card_stats[{}].play_count`.",
+                    index, index
+                )));
+            }
+            validated_white_card_stats.push((parent_or, white_card_stats.clone()));
+        }
+
+        // Cards can be deleted while a game is still using them, so stats
+        // for unknown cards are dropped instead of failing the whole report.
+        let mut parent_exists_by_name: HashMap<String, bool> = HashMap::new();
+        for (parent_or, white_card_stats) in validated_white_card_stats {
+            let parent_name_or = match parent_or {
+                Some(parent) => {
+                    let parent_name = parent.clone_str();
+                    let parent_exists = match parent_exists_by_name.get(&parent_name) {
+                        Some(parent_exists) => *parent_exists,
+                        None => {
+                            let parent_exists =
+                                self.check_white_card_stats_parent_exists(parent).await?;
+                            parent_exists_by_name.insert(parent_name.clone(), parent_exists);
+                            parent_exists
+                        }
+                    };
+                    if parent_exists {
+                        Some(parent_name)
+                    } else {
+                        None
+                    }
+                }
+                None => None,
+            };
+            match parent_name_or {
+                Some(parent_name) => {
+                    self.white_card_stats_collection
+                        .increment_white_card_stats(parent_name, white_card_stats)
+                        .await?
+                }
+                None => warn!(
+                    card_name = white_card_stats.card_name.as_str(),
+                    "Skipping stats for unknown white card."
+                ),
+            };
+        }
+
+        Ok(Response::new(Empty {}))
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::super::super::memory::custom_black_card_collection::InMemoryCustomBlackCardCollection;
+    use super::super::super::memory::custom_cardpack_collection::InMemoryCustomCardpackCollection;
+    use super::super::super::memory::custom_white_card_collection::InMemoryCustomWhiteCardCollection;
+    use super::super::super::memory::user_collection::InMemoryUserCollection;
+    use super::super::super::memory::white_card_stats_collection::InMemoryWhiteCardStatsCollection;
     use super::super::super::mongo::custom_black_card_collection::MockCustomBlackCardCollection;
     use super::super::super::mongo::custom_cardpack_collection::MockCustomCardpackCollection;
     use super::super::super::mongo::custom_white_card_collection::MockCustomWhiteCardCollection;
     use super::super::super::mongo::user_collection::MockUserCollection;
+    use super::super::super::mongo::white_card_stats_collection::MockWhiteCardStatsCollection;
     use super::super::default_cardpacks::DefaultCardpackData;
     use super::*;
+    use shared::auth::{create_internal_request, create_request_authenticated_as};
 
     async fn get_local_test_cardpack_service_with_custom_default_cardpacks(
         custom_default_cardpack_handler: DefaultCardpackHandler,
@@ -1064,6 +1484,18 @@ mod tests {
             Box::from(MockCustomWhiteCardCollection::new()),
             custom_default_cardpack_handler,
             Arc::from(MockUserCollection::new()),
+            Box::from(MockWhiteCardStatsCollection::new()),
+        )
+    }
+
+    fn get_in_memory_cardpack_service() -> CardpackServiceImpl {
+        CardpackServiceImpl::new(
+            Box::from(InMemoryCustomCardpackCollection::new()),
+            Box::from(InMemoryCustomBlackCardCollection::new()),
+            Box::from(InMemoryCustomWhiteCardCollection::new()),
+            DefaultCardpackHandler::new_with_hardcoded_packs(),
+            Arc::from(InMemoryUserCollection::new()),
+            Box::from(InMemoryWhiteCardStatsCollection::new()),
         )
     }
 
@@ -1666,4 +2098,551 @@ mod tests {
             "White Card 2"
         );
     }
+
+    #[tokio::test]
+    async fn list_never_picked_white_cards() {
+        let default_cardpack_data_list = DefaultCardpackData::create_list_from_raw_data(vec![(
+            String::from("Cardpack"),
+            Vec::new(),
+            vec![
+                String::from("White Card 1"),
+                String::from("White Card 2"),
+                String::from("White Card 3"),
+            ],
+        )]);
+        let default_cardpack_data = default_cardpack_data_list.first().unwrap();
+        let pack_name = default_cardpack_data.get_default_cardpack().name.clone();
+        let card_names: Vec<String> = default_cardpack_data
+            .get_default_white_cards()
+            .iter()
+            .map(|card| card.name.clone())
+            .collect();
+
+        let white_card_stats_collection = InMemoryWhiteCardStatsCollection::new();
+        white_card_stats_collection
+            .increment_white_card_stats(
+                pack_name.clone(),
+                WhiteCardStats {
+                    card_name: card_names[0].clone(),
+                    play_count: 3,
+                    win_count: 1,
+                },
+            )
+            .await
+            .unwrap();
+        white_card_stats_collection
+            .increment_white_card_stats(
+                pack_name.clone(),
+                WhiteCardStats {
+                    card_name: card_names[2].clone(),
+                    play_count: 5,
+                    win_count: 0,
+                },
+            )
+            .await
+            .unwrap();
+        let cardpack_service = CardpackServiceImpl::new(
+            Box::from(MockCustomCardpackCollection::new()),
+            Box::from(MockCustomBlackCardCollection::new()),
+            Box::from(MockCustomWhiteCardCollection::new()),
+            DefaultCardpackHandler::new_with_custom_packs(default_cardpack_data_list),
+            Arc::from(MockUserCollection::new()),
+            Box::from(white_card_stats_collection),
+        );
+
+        let mut request = ListNeverPickedWhiteCardsRequest {
+            parent: pack_name,
+            page_size: 50,
+            page_token: String::from(""),
+        };
+        let mut response = cardpack_service
+            .list_never_picked_white_cards(Request::new(request.clone()))
+            .await
+            .unwrap()
+            .into_inner();
+        // Cards that were played the most without winning come first.
+        assert_eq!(
+            response.white_card_stats,
+            vec![
+                WhiteCardStats {
+                    card_name: card_names[2].clone(),
+                    play_count: 5,
+                    win_count: 0,
+                },
+                WhiteCardStats {
+                    card_name: card_names[1].clone(),
+                    play_count: 0,
+                    win_count: 0,
+                },
+            ]
+        );
+        assert!(response.next_page_token.is_empty());
+        assert_eq!(response.total_size, 2);
+
+        // Played and unplayed cards are paged through as one list.
+        request.page_size = 1;
+        response = cardpack_service
+            .list_never_picked_white_cards(Request::new(request.clone()))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.white_card_stats.len(), 1);
+        assert_eq!(response.white_card_stats[0].card_name, card_names[2]);
+        assert_eq!(response.next_page_token.is_empty(), false);
+        assert_eq!(response.total_size, 2);
+        request.page_token = response.next_page_token;
+        response = cardpack_service
+            .list_never_picked_white_cards(Request::new(request))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.white_card_stats.len(), 1);
+        assert_eq!(response.white_card_stats[0].card_name, card_names[1]);
+        assert!(response.next_page_token.is_empty());
+        assert_eq!(response.total_size, 2);
+    }
+
+    #[tokio::test]
+    async fn white_card_stats_of_deleted_custom_cardpacks_are_hidden() {
+        let cardpack_service = get_in_memory_cardpack_service();
+        let user_name = "users/5d8c5ea3e3b0ab3ac6b8fac2";
+        let custom_cardpack_name = cardpack_service
+            .create_custom_cardpack(create_request_authenticated_as(
+                CreateCustomCardpackRequest {
+                    parent: String::from(user_name),
+                    custom_cardpack: Some(CustomCardpack {
+                        display_name: String::from("Cardpack"),
+                        ..Default::default()
+                    }),
+                },
+                user_name,
+            ))
+            .await
+            .unwrap()
+            .into_inner()
+            .name;
+        let custom_white_card_name = cardpack_service
+            .create_custom_white_card(create_request_authenticated_as(
+                CreateCustomWhiteCardRequest {
+                    parent: custom_cardpack_name.clone(),
+                    custom_white_card: Some(CustomWhiteCard {
+                        text: String::from("White Card"),
+                        ..Default::default()
+                    }),
+                },
+                user_name,
+            ))
+            .await
+            .unwrap()
+            .into_inner()
+            .name;
+        let white_card_stats = WhiteCardStats {
+            card_name: custom_white_card_name,
+            play_count: 2,
+            win_count: 1,
+        };
+        let list_top_white_cards_request = ListTopWhiteCardsRequest {
+            parent: custom_cardpack_name.clone(),
+            page_size: 50,
+            page_token: String::from(""),
+        };
+
+        // Stats for cards of missing cardpacks are skipped rather than rejected.
+        cardpack_service
+            .report_white_card_stats(create_internal_request(ReportWhiteCardStatsRequest {
+                white_card_stats: vec![
+                    WhiteCardStats {
+                        card_name: String::from("users/5d8c5ea3e3b0ab3ac6b8fac2/cardpacks/5d8c5ea3e3b0ab3ac6b8fac3/whiteCards/5d8c5ea3e3b0ab3ac6b8fac4"),
+                        play_count: 1,
+                        win_count: 1,
+                    },
+                    white_card_stats.clone(),
+                ],
+            }))
+            .await
+            .unwrap();
+        assert_eq!(
+            cardpack_service
+                .list_top_white_cards(Request::new(list_top_white_cards_request.clone()))
+                .await
+                .unwrap()
+                .into_inner()
+                .white_card_stats,
+            vec![white_card_stats.clone()]
+        );
+
+        cardpack_service
+            .delete_custom_cardpack(create_request_authenticated_as(
+                DeleteCustomCardpackRequest {
+                    name: custom_cardpack_name.clone(),
+                },
+                user_name,
+            ))
+            .await
+            .unwrap();
+        cardpack_service
+            .report_white_card_stats(create_internal_request(ReportWhiteCardStatsRequest {
+                white_card_stats: vec![white_card_stats],
+            }))
+            .await
+            .unwrap();
+        assert_eq!(
+            cardpack_service
+                .list_top_white_cards(Request::new(list_top_white_cards_request))
+                .await
+                .unwrap_err()
+                .code(),
+            tonic::Code::NotFound
+        );
+        assert_eq!(
+            cardpack_service
+                .list_never_picked_white_cards(Request::new(ListNeverPickedWhiteCardsRequest {
+                    parent: custom_cardpack_name,
+                    page_size: 50,
+                    page_token: String::from(""),
+                }))
+                .await
+                .unwrap_err()
+                .code(),
+            tonic::Code::NotFound
+        );
+    }
+
+    #[tokio::test]
+    async fn stream_cardpack_cards() {
+        let default_cardpack_data_list = DefaultCardpackData::create_list_from_raw_data(vec![(
+            String::from("Cardpack"),
+            vec![(String::from("Black Card _"), 1)],
+            vec![String::from("White Card 1"), String::from("White Card 2")],
+        )]);
+        let default_cardpack_data = default_cardpack_data_list.first().unwrap();
+        let default_cardpack_name = default_cardpack_data.get_default_cardpack().name.clone();
+        let default_black_card = default_cardpack_data.get_default_black_cards()[0].clone();
+        let default_white_cards = default_cardpack_data.get_default_white_cards().clone();
+
+        let custom_cardpack_name =
+            "users/5d8c5ea3e3b0ab3ac6b8fac2/cardpacks/5d8c5ea3e3b0ab3ac6b8fac3";
+        let deleted_custom_cardpack_name =
+            "users/5d8c5ea3e3b0ab3ac6b8fac2/cardpacks/5d8c5ea3e3b0ab3ac6b8fac4";
+        let custom_black_card = CustomBlackCard {
+            name: format!(
+                "{}/blackCards/5d8c5ea3e3b0ab3ac6b8fac5",
+                custom_cardpack_name
+            ),
+            text: String::from("Custom Black Card _"),
+            answer_fields: 1,
+            create_time: None,
+            update_time: None,
+            delete_time: None,
+        };
+        let custom_white_card = CustomWhiteCard {
+            name: format!(
+                "{}/whiteCards/5d8c5ea3e3b0ab3ac6b8fac6",
+                custom_cardpack_name
+            ),
+            text: String::from("Custom White Card"),
+            create_time: None,
+            update_time: None,
+            delete_time: None,
+        };
+
+        let mut mock_custom_cardpack_collection = MockCustomCardpackCollection::new();
+        mock_custom_cardpack_collection
+            .expect_get_custom_cardpack()
+            .returning(move |name| {
+                if name.clone_str() == deleted_custom_cardpack_name {
+                    Err(resource_not_found_error(&name.clone_str()))
+                } else {
+                    Ok(CustomCardpack {
+                        name: name.clone_str(),
+                        ..Default::default()
+                    })
+                }
+            });
+        let mut mock_custom_black_card_collection = MockCustomBlackCardCollection::new();
+        let streamed_custom_black_card = custom_black_card.clone();
+        mock_custom_black_card_collection
+            .expect_stream_custom_black_cards()
+            .withf(move |parent| parent.clone_str() == custom_cardpack_name)
+            .returning(move |_| {
+                Ok(Box::from(stream::iter(vec![Ok(
+                    streamed_custom_black_card.clone(),
+                )])))
+            });
+        let mut mock_custom_white_card_collection = MockCustomWhiteCardCollection::new();
+        let streamed_custom_white_card = custom_white_card.clone();
+        mock_custom_white_card_collection
+            .expect_stream_custom_white_cards()
+            .withf(move |parent| parent.clone_str() == custom_cardpack_name)
+            .returning(move |_| {
+                Ok(Box::from(stream::iter(vec![Ok(
+                    streamed_custom_white_card.clone(),
+                )])))
+            });
+        let cardpack_service = CardpackServiceImpl::new(
+            Box::from(mock_custom_cardpack_collection),
+            Box::from(mock_custom_black_card_collection),
+            Box::from(mock_custom_white_card_collection),
+            DefaultCardpackHandler::new_with_custom_packs(default_cardpack_data_list),
+            Arc::from(MockUserCollection::new()),
+            Box::from(MockWhiteCardStatsCollection::new()),
+        );
+
+        let cards: Vec<Card> = cardpack_service
+            .stream_cardpack_cards(Request::new(StreamCardpackCardsRequest {
+                custom_cardpack_names: vec![String::from(custom_cardpack_name)],
+                default_cardpack_names: vec![default_cardpack_name.clone()],
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .map(|response_or| response_or.unwrap().card.unwrap())
+            .collect()
+            .await;
+        assert_eq!(
+            cards,
+            vec![
+                Card::DefaultBlackCard(default_black_card),
+                Card::DefaultWhiteCard(default_white_cards[0].clone()),
+                Card::DefaultWhiteCard(default_white_cards[1].clone()),
+                Card::CustomBlackCard(custom_black_card),
+                Card::CustomWhiteCard(custom_white_card),
+            ]
+        );
+
+        // Nothing is streamed if any cardpack is missing.
+        let err = cardpack_service
+            .stream_cardpack_cards(Request::new(StreamCardpackCardsRequest {
+                custom_cardpack_names: vec![
+                    String::from(custom_cardpack_name),
+                    String::from(deleted_custom_cardpack_name),
+                ],
+                default_cardpack_names: Vec::new(),
+            }))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+
+        let err = cardpack_service
+            .stream_cardpack_cards(Request::new(StreamCardpackCardsRequest {
+                custom_cardpack_names: Vec::new(),
+                default_cardpack_names: vec![String::from("defaultCardpacks/1234")],
+            }))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+
+        let err = cardpack_service
+            .stream_cardpack_cards(Request::new(StreamCardpackCardsRequest {
+                custom_cardpack_names: vec![String::from("users/1234")],
+                default_cardpack_names: Vec::new(),
+            }))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn report_white_card_stats_rejects_invalid_stats() {
+        let default_cardpack_data_list = DefaultCardpackData::create_list_from_raw_data(vec![(
+            String::from("Cardpack"),
+            Vec::new(),
+            vec![String::from("White Card 1")],
+        )]);
+        let card_name = default_cardpack_data_list
+            .first()
+            .unwrap()
+            .get_default_white_cards()
+            .first()
+            .unwrap()
+            .name
+            .clone();
+        let cardpack_service = get_local_test_cardpack_service_with_custom_default_cardpacks(
+            DefaultCardpackHandler::new_with_custom_packs(default_cardpack_data_list),
+        )
+        .await;
+
+        let mut white_card_stats = WhiteCardStats {
+            card_name: String::from("defaultCardpacks/fake/defaultWhiteCards/fake"),
+            play_count: 1,
+            win_count: 1,
+        };
+        assert_eq!(
+            cardpack_service
+                .report_white_card_stats(Request::new(ReportWhiteCardStatsRequest {
+                    white_card_stats: vec![white_card_stats.clone()],
+                }))
+                .await
+                .unwrap_err()
+                .to_string(),
+            "status: PermissionDenied, message: \"Only internal services may call this method.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+        assert_eq!(
+            cardpack_service
+                .report_white_card_stats(create_internal_request(ReportWhiteCardStatsRequest {
+                    white_card_stats: vec![WhiteCardStats {
+                        card_name: String::from(""),
+                        ..white_card_stats.clone()
+                    }],
+                }))
+                .await
+                .unwrap_err()
+                .to_string(),
+            "status: InvalidArgument, message: \"Request field `white_card_stats[0].card_name` must not be blank.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+        // Unknown cards are skipped, so nothing is written to the stats collection.
+        cardpack_service
+            .report_white_card_stats(create_internal_request(ReportWhiteCardStatsRequest {
+                white_card_stats: vec![white_card_stats.clone()],
+            }))
+            .await
+            .unwrap();
+
+        white_card_stats.card_name = card_name;
+        white_card_stats.win_count = 2;
+        assert_eq!(
+            cardpack_service
+                .report_white_card_stats(create_internal_request(ReportWhiteCardStatsRequest {
+                    white_card_stats: vec![white_card_stats],
+                }))
+                .await
+                .unwrap_err()
+                .to_string(),
+            "status: InvalidArgument, message: \"Field `white_card_stats[0].win_count` must not be greater than `white_card_stats[0].play_count`.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_and_undelete_custom_cardpack() {
+        let cardpack_service = get_in_memory_cardpack_service();
+        let user_name = "users/5d8c5ea3e3b0ab3ac6b8fac2";
+        let custom_cardpack = cardpack_service
+            .create_custom_cardpack(create_request_authenticated_as(
+                CreateCustomCardpackRequest {
+                    parent: String::from(user_name),
+                    custom_cardpack: Some(CustomCardpack {
+                        display_name: String::from("Cardpack"),
+                        ..Default::default()
+                    }),
+                },
+                user_name,
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        let custom_cardpack_name = custom_cardpack.name.clone();
+        let not_found_error_message = format!(
+            "Resource with name `{}` does not exist.",
+            custom_cardpack_name
+        );
+
+        test_delete_and_undelete_item(
+            custom_cardpack,
+            &|| {
+                let (cardpack_service, name) = (&cardpack_service, custom_cardpack_name.clone());
+                async move {
+                    match cardpack_service
+                        .delete_custom_cardpack(create_request_authenticated_as(
+                            DeleteCustomCardpackRequest { name },
+                            user_name,
+                        ))
+                        .await
+                    {
+                        Ok(response) => Ok(response.into_inner()),
+                        Err(err) => Err(String::from(err.message())),
+                    }
+                }
+            },
+            &|| {
+                let (cardpack_service, name) = (&cardpack_service, custom_cardpack_name.clone());
+                async move {
+                    match cardpack_service
+                        .undelete_custom_cardpack(create_request_authenticated_as(
+                            UndeleteCustomCardpackRequest { name },
+                            user_name,
+                        ))
+                        .await
+                    {
+                        Ok(response) => Ok(response.into_inner()),
+                        Err(err) => Err(String::from(err.message())),
+                    }
+                }
+            },
+            not_found_error_message.clone(),
+            not_found_error_message,
+            &|show_deleted| ListCustomCardpacksRequest {
+                parent: String::from(user_name),
+                page_size: 0,
+                page_token: String::from(""),
+                show_deleted,
+            },
+            &|request| {
+                let cardpack_service = &cardpack_service;
+                async move {
+                    let response = cardpack_service
+                        .list_custom_cardpacks(Request::new(request))
+                        .await
+                        .unwrap()
+                        .into_inner();
+                    (response.custom_cardpacks, response.next_page_token)
+                }
+            },
+            // Deleted cardpacks can't be fetched by name.
+            false,
+            &|| async { CustomCardpack::default() },
+            &|custom_cardpack: &CustomCardpack| custom_cardpack.delete_time.is_some(),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn list_custom_cardpacks_with_pagination() {
+        let cardpack_service = get_in_memory_cardpack_service();
+        let user_name = "users/5d8c5ea3e3b0ab3ac6b8fac2";
+        let mut custom_cardpacks = Vec::new();
+        for i in 0..10 {
+            custom_cardpacks.push(
+                cardpack_service
+                    .create_custom_cardpack(create_request_authenticated_as(
+                        CreateCustomCardpackRequest {
+                            parent: String::from(user_name),
+                            custom_cardpack: Some(CustomCardpack {
+                                display_name: format!("Cardpack {}", i),
+                                ..Default::default()
+                            }),
+                        },
+                        user_name,
+                    ))
+                    .await
+                    .unwrap()
+                    .into_inner(),
+            );
+        }
+
+        test_list_pagination(
+            &custom_cardpacks,
+            &|page_size, page_token| ListCustomCardpacksRequest {
+                parent: String::from(user_name),
+                page_size,
+                page_token,
+                show_deleted: false,
+            },
+            &|request| {
+                let cardpack_service = &cardpack_service;
+                async move {
+                    let response = cardpack_service
+                        .list_custom_cardpacks(Request::new(request))
+                        .await
+                        .unwrap()
+                        .into_inner();
+                    assert_eq!(response.total_size, 10);
+                    (response.custom_cardpacks, response.next_page_token)
+                }
+            },
+        )
+        .await;
+    }
 }
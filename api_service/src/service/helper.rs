@@ -7,10 +7,6 @@ pub fn missing_request_field_error(field_name: &str) -> Status {
     ))
 }
 
-pub fn invalid_page_token_error() -> Status {
-    Status::invalid_argument("Page token is invalid.")
-}
-
 pub fn batch_create_differing_parent_error() -> Status {
     Status::invalid_argument("Since the `parent` field is specified, each member in `requests` should either have the same parent or no parent specified.")
 }
@@ -21,7 +17,9 @@ pub fn batch_request_exceeds_request_limit_error(limit: i32) -> Status {
 
 #[cfg(test)]
 pub mod test {
+    use super::super::super::mongo::user_achievement_collection::MockUserAchievementCollection;
     use super::super::super::mongo::user_collection::MockUserCollection;
+    use super::super::super::mongo::user_stats_collection::MockUserStatsCollection;
     use super::super::super::search_client::MockSearchClient;
     use super::super::user_service_impl::UserServiceImpl;
     use std::sync::Arc;
@@ -36,6 +34,8 @@ pub mod test {
         };
         let user_service_impl = UserServiceImpl::new(
             Arc::from(MockUserCollection::new()),
+            Box::from(MockUserStatsCollection::new()),
+            Box::from(MockUserAchievementCollection::new()),
             Arc::from(mock_search_client),
         );
         return user_service_impl;
@@ -1,12 +1,20 @@
+use super::super::mongo::user_achievement_collection::UserAchievementCollection;
 use super::super::mongo::user_collection::UserCollection;
-use super::super::search_client::SearchClient;
+use super::super::mongo::user_stats_collection::UserStatsCollection;
+use super::super::search_client::{sonic_error_to_status, SearchClient};
 use super::helper::*;
 use super::profile_image_handler::ProfileImageHandler;
+use shared::achievements::get_achievement_definition;
+use shared::auth::{check_caller_is_internal, check_caller_owns_resource};
 use shared::basic_validation::ValidatedStringField;
+use shared::grpc_error::negative_request_field_error;
+use shared::page_token::*;
 use shared::proto::crusty_cards_api::user_service_server::UserService;
 use shared::proto::crusty_cards_api::*;
+use shared::proto::google::protobuf::Empty;
 use shared::proto_validation::{
-    OptionalField, ValidatedColorScheme, ValidatedGameConfig, ValidatedOAuthCredentials,
+    BoundedPageSize, OptionalField, ValidatedColorScheme, ValidatedGameConfig,
+    ValidatedOAuthCredentials,
 };
 use shared::resource_name::*;
 use std::collections::HashSet;
@@ -15,6 +23,8 @@ use tonic::{Request, Response, Status};
 
 pub struct UserServiceImpl {
     user_collection: Arc<dyn UserCollection>,
+    user_stats_collection: Box<dyn UserStatsCollection>,
+    user_achievement_collection: Box<dyn UserAchievementCollection>,
     search_client: Arc<dyn SearchClient>,
     profile_image_handler: ProfileImageHandler,
 }
@@ -22,11 +32,15 @@ pub struct UserServiceImpl {
 impl UserServiceImpl {
     pub fn new(
         user_collection: Arc<dyn UserCollection>,
+        user_stats_collection: Box<dyn UserStatsCollection>,
+        user_achievement_collection: Box<dyn UserAchievementCollection>,
         search_client: Arc<dyn SearchClient>,
     ) -> UserServiceImpl {
         let profile_image_handler = ProfileImageHandler::new();
         UserServiceImpl {
             user_collection,
+            user_stats_collection,
+            user_achievement_collection,
             search_client,
             profile_image_handler,
         }
@@ -66,6 +80,8 @@ impl UserService for UserServiceImpl {
             Err(err) => return Err(err.to_status()),
         };
 
+        check_caller_owns_resource(&request, &user_name.clone_str())?;
+
         let update_fields: HashSet<String> = update_mask.paths.iter().cloned().collect();
         let updated_display_name_or = if update_fields.contains("display_name") {
             Some(ValidatedStringField::new(
@@ -86,7 +102,7 @@ impl UserService for UserServiceImpl {
         };
 
         self.search_client
-            .index_user_or_print_to_std_err(&updated_user, true);
+            .index_user_or_log_error(&updated_user, true);
 
         Ok(Response::new(updated_user))
     }
@@ -103,6 +119,8 @@ impl UserService for UserServiceImpl {
             Err(err) => return Err(err.to_status()),
         };
 
+        check_caller_owns_resource(&request, &user_settings_name.clone_str())?;
+
         Ok(Response::new(
             self.user_collection
                 .get_user_settings(user_settings_name)
@@ -132,6 +150,8 @@ impl UserService for UserServiceImpl {
             Err(err) => return Err(err.to_status()),
         };
 
+        check_caller_owns_resource(&request, &user_settings_name.clone_str())?;
+
         let update_fields: HashSet<String> = update_mask.paths.iter().cloned().collect();
 
         let color_scheme_or = if update_fields.contains("color_scheme") {
@@ -206,6 +226,8 @@ impl UserService for UserServiceImpl {
             Err(err) => return Err(err.to_status()),
         };
 
+        check_caller_owns_resource(&request, &user_profile_image_name.clone_str())?;
+
         self.user_collection
             .assert_user_exists(user_profile_image_name.to_user_name())
             .await?;
@@ -259,7 +281,7 @@ impl UserService for UserServiceImpl {
             .await?;
 
         self.search_client
-            .index_user_or_print_to_std_err(&updated_user, true);
+            .index_user_or_log_error(&updated_user, true);
 
         Ok(Response::new(updated_user))
     }
@@ -270,7 +292,12 @@ impl UserService for UserServiceImpl {
     ) -> Result<Response<UserSearchResponse>, Status> {
         let user_names = match self.search_client.search_users(&request.get_ref().query) {
             Ok(user_names) => user_names,
-            _ => return Err(Status::unknown("Failed to fetch search results.")),
+            Err(err) => {
+                return Err(sonic_error_to_status(
+                    err,
+                    "Failed to fetch search results.",
+                ))
+            }
         };
         let users = match self.user_collection.get_users_from_names(user_names).await {
             Ok(users) => users,
@@ -291,18 +318,261 @@ impl UserService for UserServiceImpl {
             .autocomplete_search_users(&request.get_ref().query)
         {
             Ok(autocomplete_entries) => autocomplete_entries,
-            _ => return Err(Status::unknown("Failed to fetch autocomplete results.")),
+            Err(err) => {
+                return Err(sonic_error_to_status(
+                    err,
+                    "Failed to fetch autocomplete results.",
+                ))
+            }
         };
         Ok(Response::new(AutocompleteUserSearchResponse {
             autocomplete_entries,
         }))
     }
+
+    async fn get_user_stats(
+        &self,
+        request: Request<GetUserStatsRequest>,
+    ) -> Result<Response<UserStats>, Status> {
+        let user_stats_name = match UserStatsName::new(&ValidatedStringField::new(
+            &request.get_ref().name,
+            "name",
+        )?) {
+            Ok(user_stats_name) => user_stats_name,
+            Err(err) => return Err(err.to_status()),
+        };
+
+        self.user_collection
+            .assert_user_exists(user_stats_name.to_user_name())
+            .await?;
+
+        Ok(Response::new(
+            self.user_stats_collection
+                .get_user_stats(user_stats_name)
+                .await?,
+        ))
+    }
+
+    async fn list_leaderboard(
+        &self,
+        request: Request<ListLeaderboardRequest>,
+    ) -> Result<Response<ListLeaderboardResponse>, Status> {
+        let bounded_page_size = BoundedPageSize::new(request.get_ref().page_size)?;
+
+        let request_without_page_token = {
+            let mut req = request.get_ref().clone();
+            req.page_token = String::from("");
+            req.page_size = 0;
+            req
+        };
+
+        let mut start_index: usize = 0;
+        if !request.get_ref().page_token.is_empty() {
+            start_index =
+                parse_page_token_index(&request_without_page_token, &request.get_ref().page_token)?;
+        }
+
+        let (user_stats_list, next_index_or, total_size) = self
+            .user_stats_collection
+            .list_leaderboard(bounded_page_size, start_index)
+            .await?;
+
+        let user_names = user_stats_list
+            .iter()
+            .map(
+                |user_stats| match UserStatsName::new_from_str(&user_stats.name) {
+                    Ok(user_stats_name) => Ok(user_stats_name.to_user_name()),
+                    Err(err) => Err(err.to_status()),
+                },
+            )
+            .collect::<Result<Vec<UserName>, Status>>()?;
+
+        let users = match self.user_collection.get_users_from_names(user_names).await {
+            Ok(users) => users,
+            _ => return Err(Status::unknown("Failed to fetch leaderboard.")),
+        };
+
+        let entries = user_stats_list
+            .into_iter()
+            .zip(users.into_iter())
+            .enumerate()
+            .map(|(index, (user_stats, user_or))| LeaderboardEntry {
+                rank: (start_index + index + 1) as i64,
+                user: user_or,
+                user_stats: Some(user_stats),
+            })
+            .collect();
+
+        let next_page_token = match next_index_or {
+            Some(next_index) => {
+                create_page_token_for_index(&request_without_page_token, next_index)
+            }
+            None => String::from(""),
+        };
+
+        Ok(Response::new(ListLeaderboardResponse {
+            entries,
+            next_page_token,
+            total_size,
+        }))
+    }
+
+    // Only called by the game service, which reports
+    // stats for each player as rounds and games finish.
+    async fn report_user_stats(
+        &self,
+        request: Request<ReportUserStatsRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        check_caller_is_internal(&request)?;
+
+        let mut validated_increments = Vec::new();
+        for (index, increment) in request.get_ref().increments.iter().enumerate() {
+            let user_name = match UserName::new(&ValidatedStringField::new(
+                &increment.user_name,
+                &format!("increments[{}].user_name", index),
+            )?) {
+                Ok(user_name) => user_name,
+                Err(err) => return Err(err.to_status()),
+            };
+            if increment.games_played < 0 {
+                return Err(negative_request_field_error(&format!(
+                    "increments[{}].games_played",
+                    index
+                )));
+            }
+            if increment.games_won < 0 {
+                return Err(negative_request_field_error(&format!(
+                    "increments[{}].games_won",
+                    index
+                )));
+            }
+            if increment.rounds_won < 0 {
+                return Err(negative_request_field_error(&format!(
+                    "increments[{}].rounds_won",
+                    index
+                )));
+            }
+            if increment.rounds_judged < 0 {
+                return Err(negative_request_field_error(&format!(
+                    "increments[{}].rounds_judged",
+                    index
+                )));
+            }
+            validated_increments.push((user_name, increment.clone()));
+        }
+
+        self.user_stats_collection
+            .increment_user_stats(validated_increments)
+            .await?;
+
+        Ok(Response::new(Empty {}))
+    }
+
+    async fn list_user_achievements(
+        &self,
+        request: Request<ListUserAchievementsRequest>,
+    ) -> Result<Response<ListUserAchievementsResponse>, Status> {
+        let user_name = match UserName::new(&ValidatedStringField::new(
+            &request.get_ref().parent,
+            "parent",
+        )?) {
+            Ok(user_name) => user_name,
+            Err(err) => return Err(err.to_status()),
+        };
+        let bounded_page_size = BoundedPageSize::new(request.get_ref().page_size)?;
+
+        let request_without_page_token = {
+            let mut req = request.get_ref().clone();
+            req.page_token = String::from("");
+            req.page_size = 0;
+            req
+        };
+
+        let mut start_index: usize = 0;
+        if !request.get_ref().page_token.is_empty() {
+            start_index =
+                parse_page_token_index(&request_without_page_token, &request.get_ref().page_token)?;
+        }
+
+        self.user_collection
+            .assert_user_exists(user_name.clone())
+            .await?;
+
+        let (user_achievements, next_index_or, total_size) = self
+            .user_achievement_collection
+            .list_unlocked_achievements(user_name, bounded_page_size, start_index)
+            .await?;
+
+        let next_page_token = match next_index_or {
+            Some(next_index) => {
+                create_page_token_for_index(&request_without_page_token, next_index)
+            }
+            None => String::from(""),
+        };
+
+        Ok(Response::new(ListUserAchievementsResponse {
+            user_achievements,
+            next_page_token,
+            total_size,
+        }))
+    }
+
+    // Only called by the game service, which evaluates the achievement
+    // rules against game events and reports each player's progress.
+    async fn report_achievement_progress(
+        &self,
+        request: Request<ReportAchievementProgressRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        check_caller_is_internal(&request)?;
+
+        let mut validated_progress = Vec::new();
+        for (index, achievement_progress) in request.get_ref().progress.iter().enumerate() {
+            let user_name = match UserName::new(&ValidatedStringField::new(
+                &achievement_progress.user_name,
+                &format!("progress[{}].user_name", index),
+            )?) {
+                Ok(user_name) => user_name,
+                Err(err) => return Err(err.to_status()),
+            };
+            let definition = match get_achievement_definition(&achievement_progress.achievement_id)
+            {
+                Some(definition) => definition,
+                None => {
+                    return Err(Status::invalid_argument(format!(
+                        "Field `progress[{}].achievement_id` must be the id of an existing achievement.",
+                        index
+                    )))
+                }
+            };
+            if achievement_progress.progress < 0 {
+                return Err(negative_request_field_error(&format!(
+                    "progress[{}].progress",
+                    index
+                )));
+            }
+            validated_progress.push((user_name, definition, achievement_progress.progress));
+        }
+
+        for (user_name, definition, progress) in validated_progress {
+            self.user_achievement_collection
+                .add_achievement_progress(
+                    user_name,
+                    String::from(definition.id),
+                    progress,
+                    definition.get_required_progress(),
+                )
+                .await?;
+        }
+
+        Ok(Response::new(Empty {}))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::super::helper::test::get_local_test_user_service;
     use super::*;
+    use shared::auth::create_internal_request;
 
     #[tokio::test]
     async fn create_and_retrieve_nonexistent_user() {
@@ -343,4 +613,94 @@ mod tests {
             );
         }
     }
+
+    #[tokio::test]
+    async fn report_user_stats_rejects_invalid_increments() {
+        let user_service = get_local_test_user_service(None).await;
+
+        let mut increment = UserStatsIncrement {
+            user_name: String::from("users/fake_user_name"),
+            games_played: 1,
+            games_won: 0,
+            rounds_won: 0,
+            rounds_judged: 0,
+        };
+        assert_eq!(
+            user_service
+                .report_user_stats(Request::new(ReportUserStatsRequest {
+                    increments: vec![increment.clone()],
+                }))
+                .await
+                .unwrap_err()
+                .to_string(),
+            "status: PermissionDenied, message: \"Only internal services may call this method.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+        assert_eq!(
+            user_service
+                .report_user_stats(create_internal_request(ReportUserStatsRequest {
+                    increments: vec![increment.clone()],
+                }))
+                .await
+                .unwrap_err()
+                .to_string(),
+            "status: NotFound, message: \"Resource with name `users/fake_user_name` does not exist.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+
+        increment.user_name = String::from("users/5d8c5ea3e3b0ab3ac6b8fac2");
+        increment.rounds_won = -1;
+        assert_eq!(
+            user_service
+                .report_user_stats(create_internal_request(ReportUserStatsRequest {
+                    increments: vec![increment],
+                }))
+                .await
+                .unwrap_err()
+                .to_string(),
+            "status: InvalidArgument, message: \"Request field `increments[0].rounds_won` must not be negative.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+    }
+
+    #[tokio::test]
+    async fn report_achievement_progress_rejects_invalid_progress() {
+        let user_service = get_local_test_user_service(None).await;
+
+        let mut achievement_progress = AchievementProgress {
+            user_name: String::from("users/5d8c5ea3e3b0ab3ac6b8fac2"),
+            achievement_id: String::from("fake-achievement"),
+            progress: 1,
+        };
+        assert_eq!(
+            user_service
+                .report_achievement_progress(Request::new(ReportAchievementProgressRequest {
+                    progress: vec![achievement_progress.clone()],
+                }))
+                .await
+                .unwrap_err()
+                .to_string(),
+            "status: PermissionDenied, message: \"Only internal services may call this method.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+        assert_eq!(
+            user_service
+                .report_achievement_progress(create_internal_request(ReportAchievementProgressRequest {
+                    progress: vec![achievement_progress.clone()],
+                }))
+                .await
+                .unwrap_err()
+                .to_string(),
+            "status: InvalidArgument, message: \"Field `progress[0].achievement_id` must be the id of an existing achievement.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+
+        achievement_progress.achievement_id = String::from("full-house");
+        achievement_progress.progress = -1;
+        assert_eq!(
+            user_service
+                .report_achievement_progress(create_internal_request(ReportAchievementProgressRequest {
+                    progress: vec![achievement_progress],
+                }))
+                .await
+                .unwrap_err()
+                .to_string(),
+            "status: InvalidArgument, message: \"Request field `progress[0].progress` must not be negative.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+    }
 }
@@ -0,0 +1,251 @@
+use super::super::mongo::custom_black_card_collection::CustomBlackCardCollection;
+use super::super::mongo::helper::{resource_not_found_error, ItemStream};
+use super::helper::*;
+use bson::oid::ObjectId;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, OptionalExtension, Row};
+use shared::basic_validation::{AnswerFieldCount, ValidatedStringField};
+use shared::proto::crusty_cards_api::*;
+use shared::proto_validation::BoundedPageSize;
+use shared::resource_name::{CustomBlackCardName, CustomCardpackName};
+use shared::time::object_id_to_timestamp_proto;
+use std::sync::Arc;
+use tonic::Status;
+
+const CUSTOM_BLACK_CARD_COLUMNS: &str =
+    "id, parent_user_id, parent_custom_cardpack_id, text, answer_fields, update_time, delete_time";
+
+fn row_to_custom_black_card(row: &Row) -> Result<CustomBlackCard, rusqlite::Error> {
+    let object_id = get_object_id(row, 0)?;
+    Ok(CustomBlackCard {
+        name: format!(
+            "users/{}/cardpacks/{}/blackCards/{}",
+            get_object_id(row, 1)?.to_hex(),
+            get_object_id(row, 2)?.to_hex(),
+            object_id.to_hex()
+        ),
+        text: row.get(3)?,
+        answer_fields: row.get(4)?,
+        create_time: Some(object_id_to_timestamp_proto(&object_id)),
+        update_time: get_optional_timestamp(row, 5)?,
+        delete_time: get_optional_timestamp(row, 6)?,
+    })
+}
+
+// Stores black cards in SQLite, with the same behavior as `MongoCustomBlackCardCollection`.
+pub struct SqliteCustomBlackCardCollection {
+    database: Arc<SqliteDatabase>,
+}
+
+impl SqliteCustomBlackCardCollection {
+    pub fn new(database: Arc<SqliteDatabase>) -> Self {
+        Self { database }
+    }
+
+    // Runs `update_sql_or` against the card if it exists and its deletion state
+    // matches `is_deleted`, in the same way as a Mongo `findOneAndUpdate`, and returns
+    // the card. `update_sql_or` is a `SET` clause whose parameters start at `?4`.
+    fn find_one_and_update(
+        &self,
+        name: CustomBlackCardName,
+        is_deleted: bool,
+        update_sql_or: Option<&str>,
+        update_params: &[Value],
+        error_message: &str,
+    ) -> Result<CustomBlackCard, Status> {
+        let name_string = name.clone_str();
+        let (user_object_id, custom_cardpack_object_id, custom_black_card_object_id) =
+            name.take_object_ids();
+        let filter = format!(
+            "id = ?1 AND parent_user_id = ?2 AND parent_custom_cardpack_id = ?3 AND delete_time IS {}",
+            if is_deleted { "NOT NULL" } else { "NULL" }
+        );
+        let sql = match update_sql_or {
+            Some(update_sql) => format!(
+                "UPDATE custom_black_cards SET {} WHERE {} RETURNING {}",
+                update_sql, filter, CUSTOM_BLACK_CARD_COLUMNS
+            ),
+            None => format!(
+                "SELECT {} FROM custom_black_cards WHERE {}",
+                CUSTOM_BLACK_CARD_COLUMNS, filter
+            ),
+        };
+        let mut params = vec![
+            Value::Text(custom_black_card_object_id.to_hex()),
+            Value::Text(user_object_id.to_hex()),
+            Value::Text(custom_cardpack_object_id.to_hex()),
+        ];
+        params.extend_from_slice(update_params);
+        let res = self
+            .database
+            .lock()
+            .query_row(
+                &sql,
+                params_from_iter(params.iter()),
+                row_to_custom_black_card,
+            )
+            .optional();
+        match res {
+            Ok(Some(custom_black_card)) => Ok(custom_black_card),
+            Ok(None) => Err(resource_not_found_error(&name_string)),
+            Err(err) => Err(sqlite_error_to_status(err, error_message)),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl CustomBlackCardCollection for SqliteCustomBlackCardCollection {
+    async fn create_custom_black_card(
+        &self,
+        parent: CustomCardpackName,
+        card_text: ValidatedStringField,
+        answer_fields: AnswerFieldCount,
+    ) -> Result<CustomBlackCard, Status> {
+        let (parent_user_id, parent_custom_cardpack_id) = parent.take_object_ids();
+        let res = self.database.lock().query_row(
+            &format!(
+                "INSERT INTO custom_black_cards (id, parent_user_id, parent_custom_cardpack_id, text, answer_fields) VALUES (?1, ?2, ?3, ?4, ?5) RETURNING {}",
+                CUSTOM_BLACK_CARD_COLUMNS
+            ),
+            params![
+                ObjectId::new().to_hex(),
+                parent_user_id.to_hex(),
+                parent_custom_cardpack_id.to_hex(),
+                card_text.take_string(),
+                answer_fields.take_value()
+            ],
+            row_to_custom_black_card,
+        );
+        res.map_err(|err| sqlite_error_to_status(err, "Failed to create card."))
+    }
+
+    async fn batch_create_custom_black_cards(
+        &self,
+        parent: CustomCardpackName,
+        data: Vec<(ValidatedStringField, AnswerFieldCount)>,
+    ) -> Result<Vec<Option<CustomBlackCard>>, Status> {
+        let mut created_black_cards = Vec::new();
+        for (card_text, answer_fields) in data {
+            created_black_cards.push(Some(
+                self.create_custom_black_card(parent.clone(), card_text, answer_fields)
+                    .await?,
+            ));
+        }
+        Ok(created_black_cards)
+    }
+
+    async fn get_custom_black_card(
+        &self,
+        name: CustomBlackCardName,
+    ) -> Result<CustomBlackCard, Status> {
+        self.find_one_and_update(name, false, None, &[], "Failed to fetch card.")
+    }
+
+    async fn soft_delete_custom_black_card(
+        &self,
+        name: CustomBlackCardName,
+    ) -> Result<CustomBlackCard, Status> {
+        self.find_one_and_update(
+            name,
+            false,
+            Some("delete_time = ?4"),
+            &[Value::Integer(get_current_time_millis())],
+            "Failed to delete card.",
+        )
+    }
+
+    async fn undelete_custom_black_card(
+        &self,
+        name: CustomBlackCardName,
+    ) -> Result<CustomBlackCard, Status> {
+        self.find_one_and_update(
+            name,
+            true,
+            Some("delete_time = NULL"),
+            &[],
+            "Failed to undelete card.",
+        )
+    }
+
+    async fn list_custom_black_cards(
+        &self,
+        parent: CustomCardpackName,
+        page_size: BoundedPageSize,
+        last_object_id_or: Option<ObjectId>,
+        show_deleted: bool,
+    ) -> Result<(Vec<CustomBlackCard>, Option<ObjectId>, i64), Status> {
+        let (parent_user_object_id, parent_custom_cardpack_object_id) = parent.take_object_ids();
+        list_items(
+            &self.database.lock(),
+            CUSTOM_BLACK_CARD_COLUMNS,
+            &format!(
+                "custom_black_cards WHERE parent_user_id = ? AND parent_custom_cardpack_id = ? AND delete_time IS {}",
+                if show_deleted { "NOT NULL" } else { "NULL" }
+            ),
+            vec![
+                Value::Text(parent_user_object_id.to_hex()),
+                Value::Text(parent_custom_cardpack_object_id.to_hex()),
+            ],
+            page_size,
+            last_object_id_or,
+            &row_to_custom_black_card,
+        )
+    }
+
+    async fn stream_custom_black_cards(
+        &self,
+        parent: CustomCardpackName,
+    ) -> Result<ItemStream<CustomBlackCard>, Status> {
+        let (parent_user_object_id, parent_custom_cardpack_object_id) = parent.take_object_ids();
+        Ok(stream_items(
+            self.database.clone(),
+            CUSTOM_BLACK_CARD_COLUMNS,
+            String::from("custom_black_cards WHERE parent_user_id = ? AND parent_custom_cardpack_id = ? AND delete_time IS NULL"),
+            vec![
+                Value::Text(parent_user_object_id.to_hex()),
+                Value::Text(parent_custom_cardpack_object_id.to_hex()),
+            ],
+            row_to_custom_black_card,
+        ))
+    }
+
+    async fn update_custom_black_card(
+        &self,
+        name: CustomBlackCardName,
+        updated_card_text_or: Option<ValidatedStringField>,
+        updated_answer_fields_or: Option<AnswerFieldCount>,
+    ) -> Result<CustomBlackCard, Status> {
+        if updated_card_text_or.is_none() && updated_answer_fields_or.is_none() {
+            return self.get_custom_black_card(name).await;
+        }
+
+        // Fields that aren't being updated are passed as null and left as they are.
+        self.find_one_and_update(
+            name,
+            false,
+            Some("text = COALESCE(?4, text), answer_fields = COALESCE(?5, answer_fields), update_time = ?6"),
+            &[
+                match updated_card_text_or {
+                    Some(updated_card_text) => Value::Text(updated_card_text.take_string()),
+                    None => Value::Null,
+                },
+                match updated_answer_fields_or {
+                    Some(updated_answer_fields) => {
+                        Value::Integer(updated_answer_fields.take_value() as i64)
+                    }
+                    None => Value::Null,
+                },
+                Value::Integer(get_current_time_millis()),
+            ],
+            "Failed to update card.",
+        )
+    }
+
+    async fn unsafe_wipe_data_and_reset(&self) -> Result<(), mongodb::error::Error> {
+        self.database
+            .lock()
+            .execute("DELETE FROM custom_black_cards", [])
+            .map_err(sqlite_error_to_mongo_error)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+use super::super::mongo::custom_cardpack_collection::CustomCardpackCollection;
+use super::super::mongo::helper::resource_not_found_error;
+use super::helper::*;
+use bson::oid::ObjectId;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, OptionalExtension, Row};
+use shared::basic_validation::ValidatedStringField;
+use shared::proto::crusty_cards_api::*;
+use shared::proto_validation::BoundedPageSize;
+use shared::resource_name::{CustomCardpackName, UserName};
+use shared::time::object_id_to_timestamp_proto;
+use std::sync::Arc;
+use tonic::Status;
+
+const CUSTOM_CARDPACK_COLUMNS: &str = "id, parent_user_id, display_name, update_time, delete_time";
+
+fn row_to_custom_cardpack(row: &Row) -> Result<CustomCardpack, rusqlite::Error> {
+    let object_id = get_object_id(row, 0)?;
+    Ok(CustomCardpack {
+        name: format!(
+            "users/{}/cardpacks/{}",
+            get_object_id(row, 1)?.to_hex(),
+            object_id.to_hex()
+        ),
+        display_name: row.get(2)?,
+        create_time: Some(object_id_to_timestamp_proto(&object_id)),
+        update_time: get_optional_timestamp(row, 3)?,
+        delete_time: get_optional_timestamp(row, 4)?,
+    })
+}
+
+// Stores cardpacks in SQLite, with the same behavior as `MongoCustomCardpackCollection`.
+pub struct SqliteCustomCardpackCollection {
+    database: Arc<SqliteDatabase>,
+}
+
+impl SqliteCustomCardpackCollection {
+    pub fn new(database: Arc<SqliteDatabase>) -> Self {
+        Self { database }
+    }
+
+    // Runs `update_sql_or` against the cardpack if it exists and its deletion state
+    // matches `is_deleted`, in the same way as a Mongo `findOneAndUpdate`, and returns
+    // the cardpack. `update_sql_or` is a `SET` clause whose parameters start at `?3`.
+    fn find_one_and_update(
+        &self,
+        name: CustomCardpackName,
+        is_deleted: bool,
+        update_sql_or: Option<&str>,
+        update_params: &[Value],
+        error_message: &str,
+    ) -> Result<CustomCardpack, Status> {
+        let name_string = name.clone_str();
+        let (user_object_id, custom_cardpack_object_id) = name.take_object_ids();
+        let filter = format!(
+            "id = ?1 AND parent_user_id = ?2 AND delete_time IS {}",
+            if is_deleted { "NOT NULL" } else { "NULL" }
+        );
+        let sql = match update_sql_or {
+            Some(update_sql) => format!(
+                "UPDATE custom_cardpacks SET {} WHERE {} RETURNING {}",
+                update_sql, filter, CUSTOM_CARDPACK_COLUMNS
+            ),
+            None => format!(
+                "SELECT {} FROM custom_cardpacks WHERE {}",
+                CUSTOM_CARDPACK_COLUMNS, filter
+            ),
+        };
+        let mut params = vec![
+            Value::Text(custom_cardpack_object_id.to_hex()),
+            Value::Text(user_object_id.to_hex()),
+        ];
+        params.extend_from_slice(update_params);
+        let res = self
+            .database
+            .lock()
+            .query_row(
+                &sql,
+                params_from_iter(params.iter()),
+                row_to_custom_cardpack,
+            )
+            .optional();
+        match res {
+            Ok(Some(custom_cardpack)) => Ok(custom_cardpack),
+            Ok(None) => Err(resource_not_found_error(&name_string)),
+            Err(err) => Err(sqlite_error_to_status(err, error_message)),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl CustomCardpackCollection for SqliteCustomCardpackCollection {
+    async fn create_custom_cardpack(
+        &self,
+        parent: UserName,
+        display_name: ValidatedStringField,
+    ) -> Result<CustomCardpack, Status> {
+        let res = self.database.lock().query_row(
+            &format!(
+                "INSERT INTO custom_cardpacks (id, parent_user_id, display_name) VALUES (?1, ?2, ?3) RETURNING {}",
+                CUSTOM_CARDPACK_COLUMNS
+            ),
+            params![
+                ObjectId::new().to_hex(),
+                parent.take_object_id().to_hex(),
+                display_name.take_string()
+            ],
+            row_to_custom_cardpack,
+        );
+        res.map_err(|err| sqlite_error_to_status(err, "Failed to create cardpack."))
+    }
+
+    async fn batch_create_custom_cardpacks(
+        &self,
+        parent: UserName,
+        display_names: Vec<ValidatedStringField>,
+    ) -> Result<Vec<Option<CustomCardpack>>, Status> {
+        let mut created_cardpacks = Vec::new();
+        for display_name in display_names {
+            created_cardpacks.push(Some(
+                self.create_custom_cardpack(parent.clone(), display_name)
+                    .await?,
+            ));
+        }
+        Ok(created_cardpacks)
+    }
+
+    async fn get_custom_cardpack(
+        &self,
+        name: CustomCardpackName,
+    ) -> Result<CustomCardpack, Status> {
+        self.find_one_and_update(name, false, None, &[], "Failed to fetch cardpack.")
+    }
+
+    async fn soft_delete_custom_cardpack(
+        &self,
+        name: CustomCardpackName,
+    ) -> Result<CustomCardpack, Status> {
+        self.find_one_and_update(
+            name,
+            false,
+            Some("delete_time = ?3"),
+            &[Value::Integer(get_current_time_millis())],
+            "Failed to delete cardpack.",
+        )
+    }
+
+    async fn undelete_custom_cardpack(
+        &self,
+        name: CustomCardpackName,
+    ) -> Result<CustomCardpack, Status> {
+        self.find_one_and_update(
+            name,
+            true,
+            Some("delete_time = NULL"),
+            &[],
+            "Failed to undelete cardpack.",
+        )
+    }
+
+    async fn list_custom_cardpacks(
+        &self,
+        parent: UserName,
+        page_size: BoundedPageSize,
+        last_object_id_or: Option<ObjectId>,
+        show_deleted: bool,
+    ) -> Result<(Vec<CustomCardpack>, Option<ObjectId>, i64), Status> {
+        list_items(
+            &self.database.lock(),
+            CUSTOM_CARDPACK_COLUMNS,
+            &format!(
+                "custom_cardpacks WHERE parent_user_id = ? AND delete_time IS {}",
+                if show_deleted { "NOT NULL" } else { "NULL" }
+            ),
+            vec![Value::Text(parent.take_object_id().to_hex())],
+            page_size,
+            last_object_id_or,
+            &row_to_custom_cardpack,
+        )
+    }
+
+    async fn update_custom_cardpack(
+        &self,
+        name: CustomCardpackName,
+        updated_display_name: ValidatedStringField,
+    ) -> Result<CustomCardpack, Status> {
+        self.find_one_and_update(
+            name,
+            false,
+            Some("display_name = ?3, update_time = ?4"),
+            &[
+                Value::Text(updated_display_name.take_string()),
+                Value::Integer(get_current_time_millis()),
+            ],
+            "Failed to update cardpack.",
+        )
+    }
+
+    // Like the Mongo implementation, this also returns deleted cardpacks.
+    async fn get_cardpacks_from_names(
+        &self,
+        names: Vec<CustomCardpackName>,
+    ) -> Result<Vec<Option<CustomCardpack>>, mongodb::error::Error> {
+        let connection = self.database.lock();
+        let mut statement = connection
+            .prepare(&format!(
+                "SELECT {} FROM custom_cardpacks WHERE id = ?1 AND parent_user_id = ?2",
+                CUSTOM_CARDPACK_COLUMNS
+            ))
+            .map_err(sqlite_error_to_mongo_error)?;
+        let mut cardpacks = Vec::new();
+        for name in names {
+            let (user_object_id, custom_cardpack_object_id) = name.take_object_ids();
+            cardpacks.push(
+                statement
+                    .query_row(
+                        params![custom_cardpack_object_id.to_hex(), user_object_id.to_hex()],
+                        row_to_custom_cardpack,
+                    )
+                    .optional()
+                    .map_err(sqlite_error_to_mongo_error)?,
+            );
+        }
+        Ok(cardpacks)
+    }
+
+    async fn unsafe_wipe_data_and_reset(&self) -> Result<(), mongodb::error::Error> {
+        self.database
+            .lock()
+            .execute("DELETE FROM custom_cardpacks", [])
+            .map_err(sqlite_error_to_mongo_error)?;
+        Ok(())
+    }
+}
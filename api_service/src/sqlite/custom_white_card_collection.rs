@@ -0,0 +1,233 @@
+use super::super::mongo::custom_white_card_collection::CustomWhiteCardCollection;
+use super::super::mongo::helper::{resource_not_found_error, ItemStream};
+use super::helper::*;
+use bson::oid::ObjectId;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, OptionalExtension, Row};
+use shared::basic_validation::ValidatedStringField;
+use shared::proto::crusty_cards_api::*;
+use shared::proto_validation::BoundedPageSize;
+use shared::resource_name::{CustomCardpackName, CustomWhiteCardName};
+use shared::time::object_id_to_timestamp_proto;
+use std::sync::Arc;
+use tonic::Status;
+
+const CUSTOM_WHITE_CARD_COLUMNS: &str =
+    "id, parent_user_id, parent_custom_cardpack_id, text, update_time, delete_time";
+
+fn row_to_custom_white_card(row: &Row) -> Result<CustomWhiteCard, rusqlite::Error> {
+    let object_id = get_object_id(row, 0)?;
+    Ok(CustomWhiteCard {
+        name: format!(
+            "users/{}/cardpacks/{}/whiteCards/{}",
+            get_object_id(row, 1)?.to_hex(),
+            get_object_id(row, 2)?.to_hex(),
+            object_id.to_hex()
+        ),
+        text: row.get(3)?,
+        create_time: Some(object_id_to_timestamp_proto(&object_id)),
+        update_time: get_optional_timestamp(row, 4)?,
+        delete_time: get_optional_timestamp(row, 5)?,
+    })
+}
+
+// Stores white cards in SQLite, with the same behavior as `MongoCustomWhiteCardCollection`.
+pub struct SqliteCustomWhiteCardCollection {
+    database: Arc<SqliteDatabase>,
+}
+
+impl SqliteCustomWhiteCardCollection {
+    pub fn new(database: Arc<SqliteDatabase>) -> Self {
+        Self { database }
+    }
+
+    // Runs `update_sql_or` against the card if it exists and its deletion state
+    // matches `is_deleted`, in the same way as a Mongo `findOneAndUpdate`, and returns
+    // the card. `update_sql_or` is a `SET` clause whose parameters start at `?4`.
+    fn find_one_and_update(
+        &self,
+        name: CustomWhiteCardName,
+        is_deleted: bool,
+        update_sql_or: Option<&str>,
+        update_params: &[Value],
+        error_message: &str,
+    ) -> Result<CustomWhiteCard, Status> {
+        let name_string = name.clone_str();
+        let (user_object_id, custom_cardpack_object_id, custom_white_card_object_id) =
+            name.take_object_ids();
+        let filter = format!(
+            "id = ?1 AND parent_user_id = ?2 AND parent_custom_cardpack_id = ?3 AND delete_time IS {}",
+            if is_deleted { "NOT NULL" } else { "NULL" }
+        );
+        let sql = match update_sql_or {
+            Some(update_sql) => format!(
+                "UPDATE custom_white_cards SET {} WHERE {} RETURNING {}",
+                update_sql, filter, CUSTOM_WHITE_CARD_COLUMNS
+            ),
+            None => format!(
+                "SELECT {} FROM custom_white_cards WHERE {}",
+                CUSTOM_WHITE_CARD_COLUMNS, filter
+            ),
+        };
+        let mut params = vec![
+            Value::Text(custom_white_card_object_id.to_hex()),
+            Value::Text(user_object_id.to_hex()),
+            Value::Text(custom_cardpack_object_id.to_hex()),
+        ];
+        params.extend_from_slice(update_params);
+        let res = self
+            .database
+            .lock()
+            .query_row(
+                &sql,
+                params_from_iter(params.iter()),
+                row_to_custom_white_card,
+            )
+            .optional();
+        match res {
+            Ok(Some(custom_white_card)) => Ok(custom_white_card),
+            Ok(None) => Err(resource_not_found_error(&name_string)),
+            Err(err) => Err(sqlite_error_to_status(err, error_message)),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl CustomWhiteCardCollection for SqliteCustomWhiteCardCollection {
+    async fn create_custom_white_card(
+        &self,
+        parent: CustomCardpackName,
+        card_text: ValidatedStringField,
+    ) -> Result<CustomWhiteCard, Status> {
+        let (parent_user_id, parent_custom_cardpack_id) = parent.take_object_ids();
+        let res = self.database.lock().query_row(
+            &format!(
+                "INSERT INTO custom_white_cards (id, parent_user_id, parent_custom_cardpack_id, text) VALUES (?1, ?2, ?3, ?4) RETURNING {}",
+                CUSTOM_WHITE_CARD_COLUMNS
+            ),
+            params![
+                ObjectId::new().to_hex(),
+                parent_user_id.to_hex(),
+                parent_custom_cardpack_id.to_hex(),
+                card_text.take_string()
+            ],
+            row_to_custom_white_card,
+        );
+        res.map_err(|err| sqlite_error_to_status(err, "Failed to create card."))
+    }
+
+    async fn batch_create_custom_white_cards(
+        &self,
+        parent: CustomCardpackName,
+        card_texts: Vec<ValidatedStringField>,
+    ) -> Result<Vec<Option<CustomWhiteCard>>, Status> {
+        let mut created_white_cards = Vec::new();
+        for card_text in card_texts {
+            created_white_cards.push(Some(
+                self.create_custom_white_card(parent.clone(), card_text)
+                    .await?,
+            ));
+        }
+        Ok(created_white_cards)
+    }
+
+    async fn get_custom_white_card(
+        &self,
+        name: CustomWhiteCardName,
+    ) -> Result<CustomWhiteCard, Status> {
+        self.find_one_and_update(name, false, None, &[], "Failed to fetch card.")
+    }
+
+    async fn soft_delete_custom_white_card(
+        &self,
+        name: CustomWhiteCardName,
+    ) -> Result<CustomWhiteCard, Status> {
+        self.find_one_and_update(
+            name,
+            false,
+            Some("delete_time = ?4"),
+            &[Value::Integer(get_current_time_millis())],
+            "Failed to delete card.",
+        )
+    }
+
+    async fn undelete_custom_white_card(
+        &self,
+        name: CustomWhiteCardName,
+    ) -> Result<CustomWhiteCard, Status> {
+        self.find_one_and_update(
+            name,
+            true,
+            Some("delete_time = NULL"),
+            &[],
+            "Failed to undelete card.",
+        )
+    }
+
+    async fn list_custom_white_cards(
+        &self,
+        parent: CustomCardpackName,
+        page_size: BoundedPageSize,
+        last_object_id_or: Option<ObjectId>,
+        show_deleted: bool,
+    ) -> Result<(Vec<CustomWhiteCard>, Option<ObjectId>, i64), Status> {
+        let (parent_user_object_id, parent_custom_cardpack_object_id) = parent.take_object_ids();
+        list_items(
+            &self.database.lock(),
+            CUSTOM_WHITE_CARD_COLUMNS,
+            &format!(
+                "custom_white_cards WHERE parent_user_id = ? AND parent_custom_cardpack_id = ? AND delete_time IS {}",
+                if show_deleted { "NOT NULL" } else { "NULL" }
+            ),
+            vec![
+                Value::Text(parent_user_object_id.to_hex()),
+                Value::Text(parent_custom_cardpack_object_id.to_hex()),
+            ],
+            page_size,
+            last_object_id_or,
+            &row_to_custom_white_card,
+        )
+    }
+
+    async fn stream_custom_white_cards(
+        &self,
+        parent: CustomCardpackName,
+    ) -> Result<ItemStream<CustomWhiteCard>, Status> {
+        let (parent_user_object_id, parent_custom_cardpack_object_id) = parent.take_object_ids();
+        Ok(stream_items(
+            self.database.clone(),
+            CUSTOM_WHITE_CARD_COLUMNS,
+            String::from("custom_white_cards WHERE parent_user_id = ? AND parent_custom_cardpack_id = ? AND delete_time IS NULL"),
+            vec![
+                Value::Text(parent_user_object_id.to_hex()),
+                Value::Text(parent_custom_cardpack_object_id.to_hex()),
+            ],
+            row_to_custom_white_card,
+        ))
+    }
+
+    async fn update_custom_white_card(
+        &self,
+        name: CustomWhiteCardName,
+        updated_card_text: ValidatedStringField,
+    ) -> Result<CustomWhiteCard, Status> {
+        self.find_one_and_update(
+            name,
+            false,
+            Some("text = ?4, update_time = ?5"),
+            &[
+                Value::Text(updated_card_text.take_string()),
+                Value::Integer(get_current_time_millis()),
+            ],
+            "Failed to update card.",
+        )
+    }
+
+    async fn unsafe_wipe_data_and_reset(&self) -> Result<(), mongodb::error::Error> {
+        self.database
+            .lock()
+            .execute("DELETE FROM custom_white_cards", [])
+            .map_err(sqlite_error_to_mongo_error)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,404 @@
+use super::super::mongo::helper::ItemStream;
+use bson::oid::ObjectId;
+use rusqlite::types::{Type, Value};
+use rusqlite::{params_from_iter, Connection, Row};
+use shared::proto::google::protobuf::Timestamp;
+use shared::proto_validation::BoundedPageSize;
+use shared::time::chrono_timestamp_to_timestamp_proto;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tonic::Status;
+use tracing::error;
+
+// Each migration brings the schema from the version before it to its own
+// version, which is its index plus one. The current version is stored in
+// `PRAGMA user_version`. Never edit a migration once it has been released,
+// since existing databases won't run it again. Add a new one instead.
+//
+// Ids are ObjectIds stored as hex strings, which sort the same way as the
+// ObjectIds themselves, so pagination works the same as with Mongo.
+// Times are stored as milliseconds since the Unix epoch.
+const MIGRATIONS: &[&str] = &["
+    CREATE TABLE users (
+        id TEXT PRIMARY KEY,
+        oauth_id TEXT NOT NULL,
+        oauth_provider TEXT NOT NULL,
+        display_name TEXT NOT NULL,
+        update_time INTEGER,
+        color_scheme INTEGER NOT NULL,
+        quick_start_game_config BLOB,
+        role TEXT,
+        UNIQUE (oauth_provider, oauth_id)
+    );
+
+    CREATE TABLE user_favorited_cardpacks (
+        position INTEGER PRIMARY KEY,
+        user_id TEXT NOT NULL,
+        cardpack_id TEXT NOT NULL,
+        UNIQUE (user_id, cardpack_id)
+    );
+
+    CREATE TABLE custom_cardpacks (
+        id TEXT PRIMARY KEY,
+        parent_user_id TEXT NOT NULL,
+        display_name TEXT NOT NULL,
+        update_time INTEGER,
+        delete_time INTEGER
+    );
+    CREATE INDEX custom_cardpacks_by_parent ON custom_cardpacks (parent_user_id, id);
+
+    CREATE TABLE custom_black_cards (
+        id TEXT PRIMARY KEY,
+        parent_user_id TEXT NOT NULL,
+        parent_custom_cardpack_id TEXT NOT NULL,
+        text TEXT NOT NULL,
+        answer_fields INTEGER NOT NULL,
+        update_time INTEGER,
+        delete_time INTEGER
+    );
+    CREATE INDEX custom_black_cards_by_parent
+        ON custom_black_cards (parent_custom_cardpack_id, id);
+
+    CREATE TABLE custom_white_cards (
+        id TEXT PRIMARY KEY,
+        parent_user_id TEXT NOT NULL,
+        parent_custom_cardpack_id TEXT NOT NULL,
+        text TEXT NOT NULL,
+        update_time INTEGER,
+        delete_time INTEGER
+    );
+    CREATE INDEX custom_white_cards_by_parent
+        ON custom_white_cards (parent_custom_cardpack_id, id);
+
+    CREATE TABLE user_stats (
+        user_id TEXT PRIMARY KEY,
+        games_played INTEGER NOT NULL,
+        games_won INTEGER NOT NULL,
+        rounds_won INTEGER NOT NULL,
+        rounds_judged INTEGER NOT NULL
+    );
+    CREATE INDEX user_stats_by_rank ON user_stats (games_won DESC, rounds_won DESC, user_id);
+
+    CREATE TABLE user_achievements (
+        user_id TEXT NOT NULL,
+        achievement_id TEXT NOT NULL,
+        progress INTEGER NOT NULL,
+        unlock_time INTEGER,
+        PRIMARY KEY (user_id, achievement_id)
+    );
+
+    CREATE TABLE white_card_stats (
+        card_name TEXT PRIMARY KEY,
+        parent TEXT NOT NULL,
+        play_count INTEGER NOT NULL,
+        win_count INTEGER NOT NULL
+    );
+    CREATE INDEX white_card_stats_by_parent ON white_card_stats (parent);
+"];
+
+// An embedded SQLite database that every SQLite collection shares. Queries
+// are run on a single connection, one at a time. They're run directly on the
+// async runtime since SQLite is embedded and queries are small.
+pub struct SqliteDatabase {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteDatabase {
+    // Creates the database file if it doesn't exist, and migrates it to the latest schema.
+    pub fn open(path: &str) -> Result<Self, rusqlite::Error> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch("PRAGMA journal_mode = WAL;")?;
+        Self::from_connection(connection)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self, rusqlite::Error> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(mut connection: Connection) -> Result<Self, rusqlite::Error> {
+        run_migrations(&mut connection)?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    pub fn lock(&self) -> MutexGuard<Connection> {
+        self.connection.lock().unwrap()
+    }
+}
+
+fn run_migrations(connection: &mut Connection) -> Result<(), rusqlite::Error> {
+    let version: i64 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(migration)?;
+        transaction.execute_batch(&format!("PRAGMA user_version = {};", index + 1))?;
+        transaction.commit()?;
+    }
+    Ok(())
+}
+
+// The underlying error is logged rather than returned
+// so that database details aren't leaked to clients.
+pub fn sqlite_error_to_status(err: rusqlite::Error, message: &str) -> Status {
+    error!(error = %err, "{}", message);
+    Status::unknown(message)
+}
+
+// A few collection methods return Mongo errors directly, so SQLite errors are wrapped in one.
+pub fn sqlite_error_to_mongo_error(err: rusqlite::Error) -> mongodb::error::Error {
+    std::io::Error::new(std::io::ErrorKind::Other, err.to_string()).into()
+}
+
+pub fn get_object_id(row: &Row, index: usize) -> Result<ObjectId, rusqlite::Error> {
+    let hex: String = row.get(index)?;
+    ObjectId::parse_str(&hex)
+        .map_err(|err| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(err)))
+}
+
+pub fn get_current_time_millis() -> i64 {
+    bson::DateTime::now().timestamp_millis()
+}
+
+pub fn get_optional_timestamp(
+    row: &Row,
+    index: usize,
+) -> Result<Option<Timestamp>, rusqlite::Error> {
+    let millis_or: Option<i64> = row.get(index)?;
+    Ok(millis_or.map(|millis| {
+        chrono_timestamp_to_timestamp_proto(&bson::DateTime::from_millis(millis).to_chrono())
+    }))
+}
+
+pub fn query_rows<T>(
+    connection: &Connection,
+    sql: &str,
+    params: Vec<Value>,
+    convert_row: &dyn Fn(&Row) -> Result<T, rusqlite::Error>,
+) -> Result<Vec<T>, rusqlite::Error> {
+    let mut statement = connection.prepare(sql)?;
+    let rows = statement
+        .query_map(params_from_iter(params.iter()), convert_row)?
+        .collect();
+    rows
+}
+
+// SQLite equivalent of `mongo::helper::list_items`. `columns` must start with `id`, and
+// `table_and_filter` is a table followed by a `WHERE` clause, such as
+// `custom_cardpacks WHERE parent_user_id = ?`, whose parameters are `filter_params`.
+pub fn list_items<T>(
+    connection: &Connection,
+    columns: &str,
+    table_and_filter: &str,
+    filter_params: Vec<Value>,
+    page_size: BoundedPageSize,
+    previous_item_id_or: Option<ObjectId>,
+    convert_row: &dyn Fn(&Row) -> Result<T, rusqlite::Error>,
+) -> Result<(Vec<T>, Option<ObjectId>, i64), Status> {
+    query_page(
+        connection,
+        columns,
+        table_and_filter,
+        filter_params,
+        page_size,
+        previous_item_id_or,
+        convert_row,
+    )
+    .map_err(|err| sqlite_error_to_status(err, "Failed to fetch items."))
+}
+
+fn query_page<T>(
+    connection: &Connection,
+    columns: &str,
+    table_and_filter: &str,
+    filter_params: Vec<Value>,
+    page_size: BoundedPageSize,
+    previous_item_id_or: Option<ObjectId>,
+    convert_row: &dyn Fn(&Row) -> Result<T, rusqlite::Error>,
+) -> Result<(Vec<T>, Option<ObjectId>, i64), rusqlite::Error> {
+    let page_size_i64 = page_size.take_i64();
+
+    let matching_item_count: i64 = connection.query_row(
+        &format!("SELECT COUNT(*) FROM {}", table_and_filter),
+        params_from_iter(filter_params.iter()),
+        |row| row.get(0),
+    )?;
+
+    let mut page_params = filter_params;
+    // Every id sorts after an empty string, so the first page starts from the beginning.
+    page_params.push(Value::Text(match previous_item_id_or {
+        Some(previous_item_id) => previous_item_id.to_hex(),
+        None => String::new(),
+    }));
+    page_params.push(Value::Integer(page_size_i64 + 1));
+    let mut items = query_rows(
+        connection,
+        &format!(
+            "SELECT {} FROM {} AND id > ? ORDER BY id LIMIT ?",
+            columns, table_and_filter
+        ),
+        page_params,
+        &|row| Ok((get_object_id(row, 0)?, convert_row(row)?)),
+    )?;
+
+    let next_item_id_or = if items.len() > page_size_i64 as usize {
+        items.pop();
+        items.last().map(|(object_id, _)| *object_id)
+    } else {
+        None
+    };
+
+    Ok((
+        items.into_iter().map(|(_, item)| item).collect(),
+        next_item_id_or,
+        matching_item_count,
+    ))
+}
+
+// Streams read this many rows at a time, so that the database
+// isn't locked for as long as it takes to read the whole stream.
+const STREAM_CHUNK_SIZE: i64 = 500;
+
+// SQLite equivalent of `mongo::helper::stream_items`, with the same arguments
+// as `list_items`. Like a Mongo cursor, rows are read in chunks in id order,
+// so rows written while the stream is being read may or may not be included.
+pub fn stream_items<T: Send + 'static>(
+    database: Arc<SqliteDatabase>,
+    columns: &'static str,
+    table_and_filter: String,
+    filter_params: Vec<Value>,
+    convert_row: fn(&Row) -> Result<T, rusqlite::Error>,
+) -> ItemStream<T> {
+    Box::from(futures_lite::stream::iter(ChunkedRows {
+        database,
+        columns,
+        table_and_filter,
+        filter_params,
+        convert_row,
+        previous_item_id: String::new(),
+        chunk: Vec::new().into_iter(),
+        is_finished: false,
+    }))
+}
+
+struct ChunkedRows<T> {
+    database: Arc<SqliteDatabase>,
+    columns: &'static str,
+    table_and_filter: String,
+    filter_params: Vec<Value>,
+    convert_row: fn(&Row) -> Result<T, rusqlite::Error>,
+    previous_item_id: String,
+    chunk: std::vec::IntoIter<T>,
+    is_finished: bool,
+}
+
+impl<T> ChunkedRows<T> {
+    fn query_next_chunk(&self) -> Result<Vec<(ObjectId, T)>, rusqlite::Error> {
+        let convert_row = self.convert_row;
+        let mut params = self.filter_params.clone();
+        params.push(Value::Text(self.previous_item_id.clone()));
+        params.push(Value::Integer(STREAM_CHUNK_SIZE));
+        query_rows(
+            &self.database.lock(),
+            &format!(
+                "SELECT {} FROM {} AND id > ? ORDER BY id LIMIT ?",
+                self.columns, self.table_and_filter
+            ),
+            params,
+            &|row| Ok((get_object_id(row, 0)?, convert_row(row)?)),
+        )
+    }
+}
+
+impl<T> Iterator for ChunkedRows<T> {
+    type Item = Result<T, Status>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(item) = self.chunk.next() {
+            return Some(Ok(item));
+        }
+        if self.is_finished {
+            return None;
+        }
+        match self.query_next_chunk() {
+            Ok(rows) => {
+                self.is_finished = (rows.len() as i64) < STREAM_CHUNK_SIZE;
+                if let Some((last_item_id, _)) = rows.last() {
+                    self.previous_item_id = last_item_id.to_hex();
+                }
+                let items: Vec<T> = rows.into_iter().map(|(_, item)| item).collect();
+                self.chunk = items.into_iter();
+                self.chunk.next().map(Ok)
+            }
+            Err(err) => {
+                self.is_finished = true;
+                Some(Err(sqlite_error_to_status(err, "Failed to fetch items.")))
+            }
+        }
+    }
+}
+
+// For lists that are paginated by index. `items` must have been fetched
+// with a limit of one more than the page size, starting at `start_index`.
+pub fn get_next_index<T>(
+    items: &mut Vec<T>,
+    page_size_i64: i64,
+    start_index: usize,
+) -> Option<usize> {
+    let page_size_usize = page_size_i64 as usize;
+    if items.len() > page_size_usize {
+        items.pop();
+        Some(start_index + page_size_usize)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_lite::StreamExt;
+
+    #[test]
+    fn migrations_are_only_run_once() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        run_migrations(&mut connection).unwrap();
+        // Running them again would fail, since every table already exists.
+        run_migrations(&mut connection).unwrap();
+        let version: i64 = connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len() as i64);
+    }
+
+    #[tokio::test]
+    async fn streams_read_every_chunk() {
+        let database = Arc::new(SqliteDatabase::open_in_memory().unwrap());
+        let parent_id = ObjectId::new().to_hex();
+        let mut ids: Vec<String> = (0..STREAM_CHUNK_SIZE * 2 + 1)
+            .map(|_| ObjectId::new().to_hex())
+            .collect();
+        for id in &ids {
+            database
+                .lock()
+                .execute(
+                    "INSERT INTO custom_white_cards (id, parent_user_id, parent_custom_cardpack_id, text) VALUES (?1, ?2, ?2, 'Card')",
+                    [id, &parent_id],
+                )
+                .unwrap();
+        }
+        ids.sort();
+
+        let streamed_ids: Vec<String> = stream_items(
+            database,
+            "id",
+            String::from("custom_white_cards WHERE parent_custom_cardpack_id = ?"),
+            vec![Value::Text(parent_id)],
+            |row| row.get(0),
+        )
+        .map(|id_or| id_or.unwrap())
+        .collect()
+        .await;
+        assert_eq!(streamed_ids, ids);
+    }
+}
@@ -0,0 +1,120 @@
+use super::super::mongo::user_achievement_collection::UserAchievementCollection;
+use super::helper::*;
+use rusqlite::params;
+use rusqlite::types::Value;
+use shared::achievements::get_achievement_definition;
+use shared::proto::crusty_cards_api::UserAchievement;
+use shared::proto_validation::BoundedPageSize;
+use shared::resource_name::UserName;
+use std::sync::Arc;
+use tonic::Status;
+
+// Stores achievement progress in SQLite, with the same behavior as `MongoUserAchievementCollection`.
+pub struct SqliteUserAchievementCollection {
+    database: Arc<SqliteDatabase>,
+}
+
+impl SqliteUserAchievementCollection {
+    pub fn new(database: Arc<SqliteDatabase>) -> Self {
+        Self { database }
+    }
+}
+
+#[tonic::async_trait]
+impl UserAchievementCollection for SqliteUserAchievementCollection {
+    async fn add_achievement_progress(
+        &self,
+        user_name: UserName,
+        achievement_id: String,
+        progress: i64,
+        required_progress: i64,
+    ) -> Result<(), Status> {
+        let user_id = user_name.take_object_id().to_hex();
+        let connection = self.database.lock();
+        if let Err(err) = connection.execute(
+            "INSERT INTO user_achievements (user_id, achievement_id, progress) VALUES (?1, ?2, ?3) ON CONFLICT (user_id, achievement_id) DO UPDATE SET progress = progress + excluded.progress",
+            params![user_id, achievement_id, progress],
+        ) {
+            return Err(sqlite_error_to_status(
+                err,
+                "Failed to update achievement progress.",
+            ));
+        }
+
+        // The unlock time is only ever set once, by whichever
+        // update first pushes the progress past the requirement.
+        match connection.execute(
+            "UPDATE user_achievements SET unlock_time = ?1 WHERE user_id = ?2 AND achievement_id = ?3 AND progress >= ?4 AND unlock_time IS NULL",
+            params![
+                get_current_time_millis(),
+                user_id,
+                achievement_id,
+                required_progress
+            ],
+        ) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(sqlite_error_to_status(err, "Failed to unlock achievement.")),
+        }
+    }
+
+    async fn list_unlocked_achievements(
+        &self,
+        user_name: UserName,
+        page_size: BoundedPageSize,
+        start_index: usize,
+    ) -> Result<(Vec<UserAchievement>, Option<usize>, i64), Status> {
+        let page_size_i64 = page_size.take_i64();
+        let user_name_string = user_name.clone_str();
+        let user_id = user_name.take_object_id().to_hex();
+        let connection = self.database.lock();
+        let res = connection
+            .query_row(
+                "SELECT COUNT(*) FROM user_achievements WHERE user_id = ?1 AND unlock_time IS NOT NULL",
+                params![user_id],
+                |row| row.get(0),
+            )
+            .and_then(|total_size: i64| {
+                // Same order as `unlocked_achievements_sort_doc`.
+                let achievements = query_rows(
+                    &connection,
+                    "SELECT achievement_id, unlock_time FROM user_achievements WHERE user_id = ?1 AND unlock_time IS NOT NULL ORDER BY unlock_time, achievement_id LIMIT ?2 OFFSET ?3",
+                    vec![
+                        Value::Text(user_id),
+                        Value::Integer(page_size_i64 + 1),
+                        Value::Integer(start_index as i64),
+                    ],
+                    &|row| {
+                        // Display fields come from the achievement definitions,
+                        // the same as in `MongoUserAchievementCollection`.
+                        let achievement_id: String = row.get(0)?;
+                        let (display_name, description) =
+                            match get_achievement_definition(&achievement_id) {
+                                Some(definition) => (definition.display_name, definition.description),
+                                None => ("", ""),
+                            };
+                        Ok(UserAchievement {
+                            name: format!("{}/achievements/{}", user_name_string, achievement_id),
+                            display_name: String::from(display_name),
+                            description: String::from(description),
+                            unlock_time: get_optional_timestamp(row, 1)?,
+                        })
+                    },
+                )?;
+                Ok((achievements, total_size))
+            });
+        let (mut achievements, total_size) = match res {
+            Ok(res) => res,
+            Err(err) => return Err(sqlite_error_to_status(err, "Failed to fetch achievements.")),
+        };
+        let next_index_or = get_next_index(&mut achievements, page_size_i64, start_index);
+        Ok((achievements, next_index_or, total_size))
+    }
+
+    async fn unsafe_wipe_data_and_reset(&self) -> Result<(), mongodb::error::Error> {
+        self.database
+            .lock()
+            .execute("DELETE FROM user_achievements", [])
+            .map_err(sqlite_error_to_mongo_error)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,427 @@
+use super::super::authorization::UserRole;
+use super::super::mongo::helper::resource_not_found_error;
+use super::super::mongo::user_collection::{
+    document_to_game_config, game_config_to_document, UserCollection, UserStream,
+};
+use super::helper::*;
+use bson::oid::ObjectId;
+use bson::Document;
+use rusqlite::types::{Type, Value};
+use rusqlite::{params, params_from_iter, OptionalExtension, Row};
+use shared::basic_validation::ValidatedStringField;
+use shared::proto::crusty_cards_api::*;
+use shared::proto_validation::{
+    BoundedPageSize, OptionalField, ValidatedColorScheme, ValidatedGameConfig,
+    ValidatedOAuthCredentials,
+};
+use shared::resource_name::{CustomCardpackName, UserName, UserSettingsName};
+use shared::time::object_id_to_timestamp_proto;
+use std::sync::Arc;
+use tonic::Status;
+
+const USER_COLUMNS: &str = "id, display_name, update_time";
+const USER_SETTINGS_COLUMNS: &str = "id, color_scheme, quick_start_game_config";
+
+fn row_to_user(row: &Row) -> Result<User, rusqlite::Error> {
+    let object_id = get_object_id(row, 0)?;
+    Ok(User {
+        name: format!("users/{}", object_id.to_hex()),
+        display_name: row.get(1)?,
+        create_time: Some(object_id_to_timestamp_proto(&object_id)),
+        update_time: get_optional_timestamp(row, 2)?,
+    })
+}
+
+fn row_to_user_settings(row: &Row) -> Result<UserSettings, rusqlite::Error> {
+    let quick_start_game_config_bytes_or: Option<Vec<u8>> = row.get(2)?;
+    Ok(UserSettings {
+        name: format!("users/{}/settings", get_object_id(row, 0)?.to_hex()),
+        color_scheme: row.get(1)?,
+        quick_start_game_config: match quick_start_game_config_bytes_or {
+            Some(bytes) => Some(document_to_game_config(
+                &Document::from_reader(&mut bytes.as_slice()).map_err(|err| {
+                    rusqlite::Error::FromSqlConversionFailure(2, Type::Blob, Box::new(err))
+                })?,
+            )),
+            None => None,
+        },
+    })
+}
+
+// Game configs are stored as BSON, in the same format as the Mongo
+// implementation stores them, so that they're read back the same way.
+fn game_config_to_bytes(game_config: &GameConfig) -> Result<Vec<u8>, Status> {
+    let mut bytes = Vec::new();
+    match game_config_to_document(game_config).to_writer(&mut bytes) {
+        Ok(_) => Ok(bytes),
+        Err(_) => Err(Status::unknown("Failed to store quick start game config.")),
+    }
+}
+
+// Stores users in SQLite, with the same behavior as `MongoUserCollection`.
+// Favorited cardpacks are stored in their own table, in the order they were favorited.
+pub struct SqliteUserCollection {
+    database: Arc<SqliteDatabase>,
+}
+
+impl SqliteUserCollection {
+    pub fn new(database: Arc<SqliteDatabase>) -> Self {
+        Self { database }
+    }
+
+    // Runs `update_or` against the user if it exists, and returns the user's
+    // `columns` converted by `convert_row`, or a not found error for `name_string`.
+    // `update_or` is a `SET` clause and its parameters, which start at `?2`.
+    fn find_one_and_update<T>(
+        &self,
+        user_object_id: &ObjectId,
+        name_string: &str,
+        update_or: Option<(&str, Vec<Value>)>,
+        columns: &str,
+        convert_row: &dyn Fn(&Row) -> Result<T, rusqlite::Error>,
+        error_message: &str,
+    ) -> Result<T, Status> {
+        let mut params = vec![Value::Text(user_object_id.to_hex())];
+        let sql = match update_or {
+            Some((update_sql, update_params)) => {
+                params.extend(update_params);
+                format!(
+                    "UPDATE users SET {} WHERE id = ?1 RETURNING {}",
+                    update_sql, columns
+                )
+            }
+            None => format!("SELECT {} FROM users WHERE id = ?1", columns),
+        };
+        let res = self
+            .database
+            .lock()
+            .query_row(&sql, params_from_iter(params.iter()), convert_row)
+            .optional();
+        match res {
+            Ok(Some(item)) => Ok(item),
+            Ok(None) => Err(resource_not_found_error(name_string)),
+            Err(err) => Err(sqlite_error_to_status(err, error_message)),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl UserCollection for SqliteUserCollection {
+    async fn get_user(&self, name: UserName) -> Result<User, Status> {
+        let name_string = name.clone_str();
+        self.find_one_and_update(
+            &name.take_object_id(),
+            &name_string,
+            None,
+            USER_COLUMNS,
+            &row_to_user,
+            "Failed to fetch user.",
+        )
+    }
+
+    async fn update_user(
+        &self,
+        name: UserName,
+        updated_display_name: ValidatedStringField,
+    ) -> Result<User, Status> {
+        let name_string = name.clone_str();
+        self.find_one_and_update(
+            &name.take_object_id(),
+            &name_string,
+            Some((
+                "display_name = ?2, update_time = ?3",
+                vec![
+                    Value::Text(updated_display_name.take_string()),
+                    Value::Integer(get_current_time_millis()),
+                ],
+            )),
+            USER_COLUMNS,
+            &row_to_user,
+            "Failed to update user.",
+        )
+    }
+
+    async fn get_user_settings(&self, name: UserSettingsName) -> Result<UserSettings, Status> {
+        let name_string = name.clone_str();
+        self.find_one_and_update(
+            &name.take_object_id(),
+            &name_string,
+            None,
+            USER_SETTINGS_COLUMNS,
+            &row_to_user_settings,
+            "Failed to fetch user settings.",
+        )
+    }
+
+    async fn update_user_settings(
+        &self,
+        name: UserSettingsName,
+        color_scheme_or: Option<ValidatedColorScheme>,
+        quick_start_game_config_or: Option<OptionalField<ValidatedGameConfig>>,
+    ) -> Result<UserSettings, Status> {
+        if color_scheme_or.is_none() && quick_start_game_config_or.is_none() {
+            return self.get_user_settings(name).await;
+        }
+
+        let mut set_clauses = Vec::new();
+        let mut update_params = Vec::new();
+        if let Some(color_scheme) = color_scheme_or {
+            update_params.push(Value::Integer(color_scheme as i64));
+            set_clauses.push(format!("color_scheme = ?{}", update_params.len() + 1));
+        }
+        if let Some(optional_quick_start_game_config) = quick_start_game_config_or {
+            update_params.push(match optional_quick_start_game_config {
+                OptionalField::Set(quick_start_game_config) => {
+                    Value::Blob(game_config_to_bytes(&quick_start_game_config.raw_config())?)
+                }
+                OptionalField::Unset => Value::Null,
+            });
+            set_clauses.push(format!(
+                "quick_start_game_config = ?{}",
+                update_params.len() + 1
+            ));
+        }
+
+        let name_string = name.clone_str();
+        self.find_one_and_update(
+            &name.take_object_id(),
+            &name_string,
+            Some((&set_clauses.join(", "), update_params)),
+            USER_SETTINGS_COLUMNS,
+            &row_to_user_settings,
+            "Failed to update user settings.",
+        )
+    }
+
+    async fn get_or_create_user(
+        &self,
+        validated_oauth_credentials: ValidatedOAuthCredentials,
+        display_name: ValidatedStringField,
+    ) -> Result<User, Status> {
+        let oauth_credentials = validated_oauth_credentials.take_oauth_credentials();
+        let connection = self.database.lock();
+        // Like an upsert with `$setOnInsert`, an existing user is left as it is.
+        if let Err(err) = connection.execute(
+            "INSERT INTO users (id, oauth_id, oauth_provider, display_name, color_scheme) VALUES (?1, ?2, ?3, ?4, ?5) ON CONFLICT (oauth_provider, oauth_id) DO NOTHING",
+            params![
+                ObjectId::new().to_hex(),
+                oauth_credentials.oauth_id,
+                oauth_credentials.oauth_provider,
+                display_name.take_string(),
+                ValidatedColorScheme::DefaultLight as i32
+            ],
+        ) {
+            return Err(sqlite_error_to_status(err, "Failed to create/fetch user."));
+        }
+        connection
+            .query_row(
+                &format!(
+                    "SELECT {} FROM users WHERE oauth_provider = ?1 AND oauth_id = ?2",
+                    USER_COLUMNS
+                ),
+                params![oauth_credentials.oauth_provider, oauth_credentials.oauth_id],
+                row_to_user,
+            )
+            .map_err(|err| sqlite_error_to_status(err, "Failed to create/fetch user."))
+    }
+
+    async fn assert_user_exists(&self, name: UserName) -> Result<(), Status> {
+        let res = self
+            .database
+            .lock()
+            .query_row(
+                "SELECT 1 FROM users WHERE id = ?1",
+                params![name.take_object_id().to_hex()],
+                |_| Ok(()),
+            )
+            .optional();
+        match res {
+            Ok(Some(_)) => Ok(()),
+            Ok(None) => Err(Status::not_found("User does not exist.")),
+            Err(err) => Err(sqlite_error_to_status(
+                err,
+                "Failed to check if user exists.",
+            )),
+        }
+    }
+
+    async fn get_user_role(&self, name: UserName) -> Result<UserRole, Status> {
+        let name_string = name.clone_str();
+        self.find_one_and_update(
+            &name.take_object_id(),
+            &name_string,
+            None,
+            "role",
+            &|row| {
+                let role_or: Option<String> = row.get(0)?;
+                Ok(UserRole::from_document_value(role_or.as_deref()))
+            },
+            "Failed to fetch user role.",
+        )
+    }
+
+    async fn set_user_role(&self, name: UserName, role: UserRole) -> Result<(), Status> {
+        let name_string = name.clone_str();
+        self.find_one_and_update(
+            &name.take_object_id(),
+            &name_string,
+            Some((
+                "role = ?2",
+                vec![match role.to_document_value() {
+                    Some(role_value) => Value::Text(String::from(role_value)),
+                    None => Value::Null,
+                }],
+            )),
+            "id",
+            &|_| Ok(()),
+            "Failed to set user role.",
+        )
+    }
+
+    async fn get_users_from_names(
+        &self,
+        names: Vec<UserName>,
+    ) -> Result<Vec<Option<User>>, mongodb::error::Error> {
+        let connection = self.database.lock();
+        let mut statement = connection
+            .prepare(&format!("SELECT {} FROM users WHERE id = ?1", USER_COLUMNS))
+            .map_err(sqlite_error_to_mongo_error)?;
+        let mut users = Vec::new();
+        for name in names {
+            users.push(
+                statement
+                    .query_row(params![name.take_object_id().to_hex()], row_to_user)
+                    .optional()
+                    .map_err(sqlite_error_to_mongo_error)?,
+            );
+        }
+        Ok(users)
+    }
+
+    async fn add_custom_cardpack_to_favorites(
+        &self,
+        user_name: UserName,
+        custom_cardpack_name: CustomCardpackName,
+    ) -> Result<(), Status> {
+        // Like `$addToSet`, favoriting a cardpack twice doesn't modify anything.
+        let res = self.database.lock().execute(
+            "INSERT OR IGNORE INTO user_favorited_cardpacks (user_id, cardpack_id) SELECT ?1, ?2 WHERE EXISTS (SELECT 1 FROM users WHERE id = ?1)",
+            params![
+                user_name.take_object_id().to_hex(),
+                custom_cardpack_name.take_object_ids().1.to_hex()
+            ],
+        );
+        match res {
+            Ok(1) => Ok(()),
+            Ok(_) => Err(Status::unknown(
+                "Failed to add custom cardpack to favorites.",
+            )),
+            Err(err) => Err(sqlite_error_to_status(
+                err,
+                "Failed to add custom cardpack to favorites.",
+            )),
+        }
+    }
+
+    async fn remove_custom_cardpack_from_favorites(
+        &self,
+        user_name: UserName,
+        custom_cardpack_name: CustomCardpackName,
+    ) -> Result<(), Status> {
+        let res = self.database.lock().execute(
+            "DELETE FROM user_favorited_cardpacks WHERE user_id = ?1 AND cardpack_id = ?2",
+            params![
+                user_name.take_object_id().to_hex(),
+                custom_cardpack_name.take_object_ids().1.to_hex()
+            ],
+        );
+        match res {
+            Ok(1) => Ok(()),
+            Ok(_) => Err(Status::unknown(
+                "Failed to remove custom cardpack from favorites.",
+            )),
+            Err(err) => Err(sqlite_error_to_status(
+                err,
+                "Failed to remove custom cardpack from favorites.",
+            )),
+        }
+    }
+
+    async fn check_has_user_favorited_custom_cardpack(
+        &self,
+        user_name: UserName,
+        custom_cardpack_name: CustomCardpackName,
+    ) -> Result<bool, Status> {
+        self.database
+            .lock()
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM user_favorited_cardpacks WHERE user_id = ?1 AND cardpack_id = ?2)",
+                params![
+                    user_name.take_object_id().to_hex(),
+                    custom_cardpack_name.take_object_ids().1.to_hex()
+                ],
+                |row| row.get(0),
+            )
+            .map_err(|err| {
+                sqlite_error_to_status(err, "Failed to load favorited custom cardpacks.")
+            })
+    }
+
+    async fn list_favorited_custom_cardpack_names(
+        &self,
+        user_name: UserName,
+        page_size: BoundedPageSize,
+        start_index: usize,
+    ) -> Result<(Vec<CustomCardpackName>, Option<usize>), Status> {
+        self.assert_user_exists(user_name.clone()).await?;
+        let page_size_i64 = page_size.take_i64();
+        let res = query_rows(
+            &self.database.lock(),
+            "SELECT cardpack_id FROM user_favorited_cardpacks WHERE user_id = ?1 ORDER BY position LIMIT ?2 OFFSET ?3",
+            vec![
+                Value::Text(user_name.get_object_id().to_hex()),
+                Value::Integer(page_size_i64 + 1),
+                Value::Integer(start_index as i64),
+            ],
+            &|row| get_object_id(row, 0),
+        );
+        let mut cardpack_object_ids = match res {
+            Ok(cardpack_object_ids) => cardpack_object_ids,
+            Err(err) => {
+                return Err(sqlite_error_to_status(
+                    err,
+                    "Failed to load favorited custom cardpacks.",
+                ))
+            }
+        };
+        let next_index_or = get_next_index(&mut cardpack_object_ids, page_size_i64, start_index);
+        Ok((
+            cardpack_object_ids
+                .into_iter()
+                .map(|object_id| CustomCardpackName::new_from_parent(user_name.clone(), object_id))
+                .collect(),
+            next_index_or,
+        ))
+    }
+
+    async fn user_stream(&self) -> Result<UserStream, mongodb::error::Error> {
+        let users: Vec<Result<User, mongodb::error::Error>> = query_rows(
+            &self.database.lock(),
+            &format!("SELECT {} FROM users ORDER BY id", USER_COLUMNS),
+            Vec::new(),
+            &row_to_user,
+        )
+        .map_err(sqlite_error_to_mongo_error)?
+        .into_iter()
+        .map(Ok)
+        .collect();
+        Ok(Box::from(futures_lite::stream::iter(users)))
+    }
+
+    async fn unsafe_wipe_data_and_reset(&self) -> Result<(), mongodb::error::Error> {
+        self.database
+            .lock()
+            .execute_batch("DELETE FROM users; DELETE FROM user_favorited_cardpacks;")
+            .map_err(sqlite_error_to_mongo_error)
+    }
+}
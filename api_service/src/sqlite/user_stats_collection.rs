@@ -0,0 +1,128 @@
+use super::super::mongo::user_stats_collection::UserStatsCollection;
+use super::helper::*;
+use rusqlite::types::Value;
+use rusqlite::{params, OptionalExtension, Row};
+use shared::proto::crusty_cards_api::{UserStats, UserStatsIncrement};
+use shared::proto_validation::BoundedPageSize;
+use shared::resource_name::{UserName, UserStatsName};
+use std::sync::Arc;
+use tonic::Status;
+
+const USER_STATS_COLUMNS: &str = "user_id, games_played, games_won, rounds_won, rounds_judged";
+
+fn row_to_user_stats(row: &Row) -> Result<UserStats, rusqlite::Error> {
+    Ok(UserStats {
+        name: format!("users/{}/stats", get_object_id(row, 0)?.to_hex()),
+        games_played: row.get(1)?,
+        games_won: row.get(2)?,
+        rounds_won: row.get(3)?,
+        rounds_judged: row.get(4)?,
+    })
+}
+
+// Stores user stats in SQLite, with the same behavior as `MongoUserStatsCollection`.
+pub struct SqliteUserStatsCollection {
+    database: Arc<SqliteDatabase>,
+}
+
+impl SqliteUserStatsCollection {
+    pub fn new(database: Arc<SqliteDatabase>) -> Self {
+        Self { database }
+    }
+}
+
+#[tonic::async_trait]
+impl UserStatsCollection for SqliteUserStatsCollection {
+    async fn get_user_stats(&self, name: UserStatsName) -> Result<UserStats, Status> {
+        let name_string = name.clone_str();
+        let res = self
+            .database
+            .lock()
+            .query_row(
+                &format!(
+                    "SELECT {} FROM user_stats WHERE user_id = ?1",
+                    USER_STATS_COLUMNS
+                ),
+                params![name.take_object_id().to_hex()],
+                row_to_user_stats,
+            )
+            .optional();
+        match res {
+            Ok(user_stats_or) => Ok(user_stats_or.unwrap_or(UserStats {
+                name: name_string,
+                games_played: 0,
+                games_won: 0,
+                rounds_won: 0,
+                rounds_judged: 0,
+            })),
+            Err(err) => Err(sqlite_error_to_status(err, "Failed to fetch user stats.")),
+        }
+    }
+
+    async fn increment_user_stats(
+        &self,
+        increments: Vec<(UserName, UserStatsIncrement)>,
+    ) -> Result<(), Status> {
+        let mut connection = self.database.lock();
+        let res = connection.transaction().and_then(|transaction| {
+            for (user_name, increment) in increments {
+                transaction.execute(
+                    "INSERT INTO user_stats (user_id, games_played, games_won, rounds_won, rounds_judged) VALUES (?1, ?2, ?3, ?4, ?5) ON CONFLICT (user_id) DO UPDATE SET games_played = games_played + excluded.games_played, games_won = games_won + excluded.games_won, rounds_won = rounds_won + excluded.rounds_won, rounds_judged = rounds_judged + excluded.rounds_judged",
+                    params![
+                        user_name.take_object_id().to_hex(),
+                        increment.games_played,
+                        increment.games_won,
+                        increment.rounds_won,
+                        increment.rounds_judged
+                    ],
+                )?;
+            }
+            transaction.commit()
+        });
+        match res {
+            Ok(_) => Ok(()),
+            Err(err) => Err(sqlite_error_to_status(err, "Failed to update user stats.")),
+        }
+    }
+
+    async fn list_leaderboard(
+        &self,
+        page_size: BoundedPageSize,
+        start_index: usize,
+    ) -> Result<(Vec<UserStats>, Option<usize>, i64), Status> {
+        let page_size_i64 = page_size.take_i64();
+        let connection = self.database.lock();
+        let res = connection
+            .query_row("SELECT COUNT(*) FROM user_stats", [], |row| row.get(0))
+            .and_then(|total_size: i64| {
+                // Same order as `leaderboard_sort_doc`.
+                let leaderboard = query_rows(
+                    &connection,
+                    &format!(
+                        "SELECT {} FROM user_stats ORDER BY games_won DESC, rounds_won DESC, user_id LIMIT ?1 OFFSET ?2",
+                        USER_STATS_COLUMNS
+                    ),
+                    vec![
+                        Value::Integer(page_size_i64 + 1),
+                        Value::Integer(start_index as i64),
+                    ],
+                    &row_to_user_stats,
+                )?;
+                Ok((leaderboard, total_size))
+            });
+        let (mut leaderboard, total_size) = match res {
+            Ok(res) => res,
+            Err(err) => return Err(sqlite_error_to_status(err, "Failed to fetch leaderboard.")),
+        };
+        let next_index_or = get_next_index(&mut leaderboard, page_size_i64, start_index);
+        Ok((leaderboard, next_index_or, total_size))
+    }
+
+    async fn unsafe_wipe_data_and_reset(&self) -> Result<(), mongodb::error::Error> {
+        self.database
+            .lock()
+            .execute("DELETE FROM user_stats", [])
+            .map_err(sqlite_error_to_mongo_error)?;
+        Ok(())
+    }
+}
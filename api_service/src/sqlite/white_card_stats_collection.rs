@@ -0,0 +1,174 @@
+use super::super::mongo::white_card_stats_collection::{
+    take_never_picked_page, WhiteCardStatsCollection,
+};
+use super::helper::*;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Row};
+use shared::proto::crusty_cards_api::WhiteCardStats;
+use shared::proto_validation::BoundedPageSize;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tonic::Status;
+
+fn row_to_white_card_stats(row: &Row) -> Result<WhiteCardStats, rusqlite::Error> {
+    Ok(WhiteCardStats {
+        card_name: row.get(0)?,
+        play_count: row.get(1)?,
+        win_count: row.get(2)?,
+    })
+}
+
+// Stores white card stats in SQLite, with the same behavior as `MongoWhiteCardStatsCollection`.
+pub struct SqliteWhiteCardStatsCollection {
+    database: Arc<SqliteDatabase>,
+}
+
+impl SqliteWhiteCardStatsCollection {
+    pub fn new(database: Arc<SqliteDatabase>) -> Self {
+        Self { database }
+    }
+}
+
+#[tonic::async_trait]
+impl WhiteCardStatsCollection for SqliteWhiteCardStatsCollection {
+    async fn increment_white_card_stats(
+        &self,
+        parent: String,
+        white_card_stats: WhiteCardStats,
+    ) -> Result<(), Status> {
+        let res = self.database.lock().execute(
+            "INSERT INTO white_card_stats (card_name, parent, play_count, win_count) VALUES (?1, ?2, ?3, ?4) ON CONFLICT (card_name) DO UPDATE SET parent = excluded.parent, play_count = play_count + excluded.play_count, win_count = win_count + excluded.win_count",
+            params![
+                white_card_stats.card_name,
+                parent,
+                white_card_stats.play_count,
+                white_card_stats.win_count
+            ],
+        );
+        match res {
+            Ok(_) => Ok(()),
+            Err(err) => Err(sqlite_error_to_status(
+                err,
+                "Failed to update white card stats.",
+            )),
+        }
+    }
+
+    async fn list_top_white_cards(
+        &self,
+        parent: String,
+        page_size: BoundedPageSize,
+        start_index: usize,
+    ) -> Result<(Vec<WhiteCardStats>, Option<usize>, i64), Status> {
+        let page_size_i64 = page_size.take_i64();
+        let connection = self.database.lock();
+        let res = connection
+            .query_row(
+                "SELECT COUNT(*) FROM white_card_stats WHERE parent = ?1 AND win_count > 0",
+                params![parent],
+                |row| row.get(0),
+            )
+            .and_then(|total_size: i64| {
+                // Same order as `top_white_cards_sort_doc`.
+                let top_white_cards = query_rows(
+                    &connection,
+                    "SELECT card_name, play_count, win_count FROM white_card_stats WHERE parent = ?1 AND win_count > 0 ORDER BY win_count DESC, play_count, card_name LIMIT ?2 OFFSET ?3",
+                    vec![
+                        Value::Text(parent),
+                        Value::Integer(page_size_i64 + 1),
+                        Value::Integer(start_index as i64),
+                    ],
+                    &row_to_white_card_stats,
+                )?;
+                Ok((top_white_cards, total_size))
+            });
+        let (mut top_white_cards, total_size) = match res {
+            Ok(res) => res,
+            Err(err) => {
+                return Err(sqlite_error_to_status(
+                    err,
+                    "Failed to fetch white card stats.",
+                ))
+            }
+        };
+        let next_index_or = get_next_index(&mut top_white_cards, page_size_i64, start_index);
+        Ok((top_white_cards, next_index_or, total_size))
+    }
+
+    async fn list_never_picked_white_cards(
+        &self,
+        parent: String,
+        card_names: Vec<String>,
+        page_size: BoundedPageSize,
+        start_index: usize,
+    ) -> Result<(Vec<WhiteCardStats>, Option<usize>, i64), Status> {
+        let page_size_i64 = page_size.take_i64();
+        let filter = format!(
+            "parent = ? AND play_count > 0 AND card_name IN ({})",
+            vec!["?"; card_names.len()].join(", ")
+        );
+        let mut filter_params = vec![Value::Text(parent)];
+        filter_params.extend(card_names.iter().cloned().map(Value::Text));
+
+        let connection = self.database.lock();
+        let res = query_rows(
+            &connection,
+            &format!("SELECT card_name FROM white_card_stats WHERE {}", filter),
+            filter_params.clone(),
+            &|row: &Row| row.get(0),
+        )
+        .and_then(|played_card_names: Vec<String>| {
+            let played_count: i64 = connection.query_row(
+                &format!(
+                    "SELECT COUNT(*) FROM white_card_stats WHERE {} AND win_count = 0",
+                    filter
+                ),
+                params_from_iter(filter_params.iter()),
+                |row| row.get(0),
+            )?;
+            let mut page_params = filter_params.clone();
+            page_params.push(Value::Integer(page_size_i64 + 1));
+            page_params.push(Value::Integer(start_index as i64));
+            // Same order as `never_picked_white_cards_sort_doc`.
+            let played_page = query_rows(
+                &connection,
+                &format!(
+                    "SELECT card_name, play_count, win_count FROM white_card_stats WHERE {} AND win_count = 0 ORDER BY play_count DESC, card_name LIMIT ? OFFSET ?",
+                    filter
+                ),
+                page_params,
+                &row_to_white_card_stats,
+            )?;
+            Ok((played_card_names, played_count, played_page))
+        });
+        let (played_card_names, played_count, played_page) = match res {
+            Ok(res) => res,
+            Err(err) => {
+                return Err(sqlite_error_to_status(
+                    err,
+                    "Failed to fetch white card stats.",
+                ))
+            }
+        };
+
+        let played_card_names: HashSet<String> = played_card_names.into_iter().collect();
+        Ok(take_never_picked_page(
+            played_page,
+            played_count as usize,
+            card_names
+                .into_iter()
+                .filter(|card_name| !played_card_names.contains(card_name))
+                .collect(),
+            page_size_i64,
+            start_index,
+        ))
+    }
+
+    async fn unsafe_wipe_data_and_reset(&self) -> Result<(), mongodb::error::Error> {
+        self.database
+            .lock()
+            .execute("DELETE FROM white_card_stats", [])
+            .map_err(sqlite_error_to_mongo_error)?;
+        Ok(())
+    }
+}
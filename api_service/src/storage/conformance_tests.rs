@@ -0,0 +1,1016 @@
+// Checks that every storage backend behaves the same way. Each check is written
+// against the collection traits and is run once per backend. Every check uses
+// newly generated ids, so checks never see each other's data.
+use super::super::authorization::UserRole;
+use super::super::mongo::custom_black_card_collection::CustomBlackCardCollection;
+use super::super::mongo::custom_cardpack_collection::CustomCardpackCollection;
+use super::super::mongo::custom_white_card_collection::CustomWhiteCardCollection;
+use super::super::mongo::user_achievement_collection::UserAchievementCollection;
+use super::super::mongo::user_collection::UserCollection;
+use super::super::mongo::user_stats_collection::UserStatsCollection;
+use super::super::mongo::white_card_stats_collection::WhiteCardStatsCollection;
+use super::super::sqlite::helper::SqliteDatabase;
+use super::Collections;
+use bson::oid::ObjectId;
+use futures_lite::StreamExt;
+use shared::achievements::get_achievement_definition;
+use shared::basic_validation::{AnswerFieldCount, ValidatedStringField};
+use shared::proto::crusty_cards_api::*;
+use shared::proto_validation::{
+    BoundedPageSize, OptionalField, ValidatedColorScheme, ValidatedOAuthCredentials,
+};
+use shared::resource_name::{
+    CustomBlackCardName, CustomCardpackName, CustomWhiteCardName, UserName, UserSettingsName,
+    UserStatsName,
+};
+use std::sync::Arc;
+use tonic::Status;
+
+fn new_user_name() -> UserName {
+    UserName::new_from_str(&format!("users/{}", ObjectId::new().to_hex())).unwrap()
+}
+
+fn new_custom_cardpack_name() -> CustomCardpackName {
+    CustomCardpackName::new_from_parent(new_user_name(), ObjectId::new())
+}
+
+fn string_field(value: &str) -> ValidatedStringField {
+    ValidatedStringField::new(value, "field").unwrap()
+}
+
+fn page_size(page_size: i32) -> BoundedPageSize {
+    BoundedPageSize::new(page_size).unwrap()
+}
+
+fn oauth_credentials(oauth_id: &str) -> ValidatedOAuthCredentials {
+    ValidatedOAuthCredentials::new(
+        &OAuthCredentials {
+            oauth_provider: String::from("google"),
+            oauth_id: String::from(oauth_id),
+        },
+        "oauth_credentials",
+    )
+    .unwrap()
+}
+
+fn assert_not_found<T: std::fmt::Debug>(res: Result<T, Status>, name: &str) {
+    let err = res.unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+    assert_eq!(
+        err.message(),
+        format!("Resource with name `{}` does not exist.", name)
+    );
+}
+
+async fn check_custom_cardpack_collection(collection: &dyn CustomCardpackCollection) {
+    let user_name = new_user_name();
+    let cardpack = collection
+        .create_custom_cardpack(user_name.clone(), string_field("Cardpack"))
+        .await
+        .unwrap();
+    let cardpack_name = CustomCardpackName::new_from_str(&cardpack.name).unwrap();
+    assert!(cardpack
+        .name
+        .starts_with(&format!("{}/cardpacks/", user_name.clone_str())));
+    assert_eq!(cardpack.display_name, "Cardpack");
+    assert!(cardpack.create_time.is_some());
+    assert_eq!(cardpack.update_time, None);
+    assert_eq!(cardpack.delete_time, None);
+    assert_eq!(
+        collection
+            .get_custom_cardpack(cardpack_name.clone())
+            .await
+            .unwrap(),
+        cardpack
+    );
+
+    let updated_cardpack = collection
+        .update_custom_cardpack(cardpack_name.clone(), string_field("Renamed"))
+        .await
+        .unwrap();
+    assert_eq!(updated_cardpack.name, cardpack.name);
+    assert_eq!(updated_cardpack.display_name, "Renamed");
+    assert_eq!(updated_cardpack.create_time, cardpack.create_time);
+    assert!(updated_cardpack.update_time.is_some());
+    assert_eq!(
+        collection
+            .get_custom_cardpack(cardpack_name.clone())
+            .await
+            .unwrap(),
+        updated_cardpack
+    );
+
+    // Cardpacks are only found under their owner.
+    let wrong_owner_name =
+        CustomCardpackName::new_from_parent(new_user_name(), *cardpack_name.get_object_ids().1);
+    assert_not_found(
+        collection
+            .get_custom_cardpack(wrong_owner_name.clone())
+            .await,
+        &wrong_owner_name.clone_str(),
+    );
+    assert_not_found(
+        collection
+            .update_custom_cardpack(wrong_owner_name.clone(), string_field("Renamed"))
+            .await,
+        &wrong_owner_name.clone_str(),
+    );
+
+    let deleted_cardpack = collection
+        .soft_delete_custom_cardpack(cardpack_name.clone())
+        .await
+        .unwrap();
+    assert!(deleted_cardpack.delete_time.is_some());
+    assert_not_found(
+        collection.get_custom_cardpack(cardpack_name.clone()).await,
+        &cardpack.name,
+    );
+    assert_not_found(
+        collection
+            .soft_delete_custom_cardpack(cardpack_name.clone())
+            .await,
+        &cardpack.name,
+    );
+    assert_not_found(
+        collection
+            .update_custom_cardpack(cardpack_name.clone(), string_field("Renamed"))
+            .await,
+        &cardpack.name,
+    );
+    assert_eq!(
+        collection
+            .list_custom_cardpacks(user_name.clone(), page_size(10), None, true)
+            .await
+            .unwrap(),
+        (vec![deleted_cardpack.clone()], None, 1)
+    );
+    assert_eq!(
+        collection
+            .list_custom_cardpacks(user_name.clone(), page_size(10), None, false)
+            .await
+            .unwrap(),
+        (Vec::new(), None, 0)
+    );
+
+    // Deleted cardpacks are still returned when fetching by name.
+    assert_eq!(
+        collection
+            .get_cardpacks_from_names(vec![
+                wrong_owner_name,
+                cardpack_name.clone(),
+                new_custom_cardpack_name()
+            ])
+            .await
+            .unwrap(),
+        vec![None, Some(deleted_cardpack), None]
+    );
+
+    assert_eq!(
+        collection
+            .undelete_custom_cardpack(cardpack_name.clone())
+            .await
+            .unwrap(),
+        updated_cardpack
+    );
+    assert_not_found(
+        collection.undelete_custom_cardpack(cardpack_name).await,
+        &cardpack.name,
+    );
+
+    // Cardpacks are listed in the order they were created.
+    let user_name = new_user_name();
+    let cardpacks: Vec<CustomCardpack> = collection
+        .batch_create_custom_cardpacks(
+            user_name.clone(),
+            vec![
+                string_field("First"),
+                string_field("Second"),
+                string_field("Third"),
+            ],
+        )
+        .await
+        .unwrap()
+        .into_iter()
+        .map(Option::unwrap)
+        .collect();
+    assert_eq!(
+        cardpacks
+            .iter()
+            .map(|cardpack| cardpack.display_name.as_str())
+            .collect::<Vec<&str>>(),
+        vec!["First", "Second", "Third"]
+    );
+    let (first_page, next_object_id_or, total_size) = collection
+        .list_custom_cardpacks(user_name.clone(), page_size(2), None, false)
+        .await
+        .unwrap();
+    assert_eq!(first_page, cardpacks[..2]);
+    assert!(next_object_id_or.is_some());
+    assert_eq!(total_size, 3);
+    assert_eq!(
+        collection
+            .list_custom_cardpacks(user_name, page_size(2), next_object_id_or, false)
+            .await
+            .unwrap(),
+        (cardpacks[2..].to_vec(), None, 3)
+    );
+}
+
+async fn check_custom_black_card_collection(collection: &dyn CustomBlackCardCollection) {
+    let cardpack_name = new_custom_cardpack_name();
+    let card = collection
+        .create_custom_black_card(
+            cardpack_name.clone(),
+            string_field("Card _"),
+            AnswerFieldCount::new(1, "answer_fields").unwrap(),
+        )
+        .await
+        .unwrap();
+    let card_name = CustomBlackCardName::new(&string_field(&card.name)).unwrap();
+    assert!(card
+        .name
+        .starts_with(&format!("{}/blackCards/", cardpack_name.clone_str())));
+    assert_eq!(card.text, "Card _");
+    assert_eq!(card.answer_fields, 1);
+    assert!(card.create_time.is_some());
+    assert_eq!(card.update_time, None);
+    assert_eq!(card.delete_time, None);
+    assert_eq!(
+        collection
+            .get_custom_black_card(card_name.clone())
+            .await
+            .unwrap(),
+        card
+    );
+
+    // Cards are only found under their cardpack.
+    let wrong_cardpack_name = CustomBlackCardName::new_from_parent(
+        new_custom_cardpack_name(),
+        card_name.clone().take_object_ids().2,
+    );
+    assert_not_found(
+        collection
+            .get_custom_black_card(wrong_cardpack_name.clone())
+            .await,
+        &wrong_cardpack_name.clone_str(),
+    );
+
+    // Updating nothing leaves the card as it is.
+    assert_eq!(
+        collection
+            .update_custom_black_card(card_name.clone(), None, None)
+            .await
+            .unwrap(),
+        card
+    );
+    let updated_card = collection
+        .update_custom_black_card(
+            card_name.clone(),
+            None,
+            Some(AnswerFieldCount::new(2, "answer_fields").unwrap()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(updated_card.text, "Card _");
+    assert_eq!(updated_card.answer_fields, 2);
+    assert!(updated_card.update_time.is_some());
+    let updated_card = collection
+        .update_custom_black_card(card_name.clone(), Some(string_field("_ and _")), None)
+        .await
+        .unwrap();
+    assert_eq!(updated_card.text, "_ and _");
+    assert_eq!(updated_card.answer_fields, 2);
+
+    let other_card = collection
+        .create_custom_black_card(
+            cardpack_name.clone(),
+            string_field("Other card _"),
+            AnswerFieldCount::new(1, "answer_fields").unwrap(),
+        )
+        .await
+        .unwrap();
+    collection
+        .batch_create_custom_black_cards(
+            new_custom_cardpack_name(),
+            vec![(
+                string_field("Card from another cardpack _"),
+                AnswerFieldCount::new(1, "answer_fields").unwrap(),
+            )],
+        )
+        .await
+        .unwrap();
+    let (first_page, next_object_id_or, total_size) = collection
+        .list_custom_black_cards(cardpack_name.clone(), page_size(1), None, false)
+        .await
+        .unwrap();
+    assert_eq!(first_page, vec![updated_card.clone()]);
+    assert!(next_object_id_or.is_some());
+    assert_eq!(total_size, 2);
+    assert_eq!(
+        collection
+            .list_custom_black_cards(
+                cardpack_name.clone(),
+                page_size(1),
+                next_object_id_or,
+                false
+            )
+            .await
+            .unwrap(),
+        (vec![other_card.clone()], None, 2)
+    );
+
+    let deleted_card = collection
+        .soft_delete_custom_black_card(card_name.clone())
+        .await
+        .unwrap();
+    assert!(deleted_card.delete_time.is_some());
+    assert_not_found(
+        collection.get_custom_black_card(card_name.clone()).await,
+        &card.name,
+    );
+    assert_not_found(
+        collection
+            .soft_delete_custom_black_card(card_name.clone())
+            .await,
+        &card.name,
+    );
+    assert_not_found(
+        collection
+            .update_custom_black_card(card_name.clone(), Some(string_field("_")), None)
+            .await,
+        &card.name,
+    );
+    assert_eq!(
+        collection
+            .list_custom_black_cards(cardpack_name.clone(), page_size(10), None, true)
+            .await
+            .unwrap(),
+        (vec![deleted_card], None, 1)
+    );
+    // Deleted cards are left out of streams.
+    let streamed_cards: Vec<CustomBlackCard> = collection
+        .stream_custom_black_cards(cardpack_name.clone())
+        .await
+        .unwrap()
+        .map(|card_or| card_or.unwrap())
+        .collect()
+        .await;
+    assert_eq!(streamed_cards, vec![other_card]);
+    assert_eq!(
+        collection
+            .undelete_custom_black_card(card_name.clone())
+            .await
+            .unwrap(),
+        updated_card
+    );
+    assert_not_found(
+        collection.undelete_custom_black_card(card_name).await,
+        &card.name,
+    );
+}
+
+async fn check_custom_white_card_collection(collection: &dyn CustomWhiteCardCollection) {
+    let cardpack_name = new_custom_cardpack_name();
+    let card = collection
+        .create_custom_white_card(cardpack_name.clone(), string_field("Card"))
+        .await
+        .unwrap();
+    let card_name = CustomWhiteCardName::new(&string_field(&card.name)).unwrap();
+    assert!(card
+        .name
+        .starts_with(&format!("{}/whiteCards/", cardpack_name.clone_str())));
+    assert_eq!(card.text, "Card");
+    assert!(card.create_time.is_some());
+    assert_eq!(card.update_time, None);
+    assert_eq!(card.delete_time, None);
+    assert_eq!(
+        collection
+            .get_custom_white_card(card_name.clone())
+            .await
+            .unwrap(),
+        card
+    );
+
+    // Cards are only found under their cardpack.
+    let wrong_cardpack_name = CustomWhiteCardName::new_from_parent(
+        new_custom_cardpack_name(),
+        card_name.clone().take_object_ids().2,
+    );
+    assert_not_found(
+        collection
+            .get_custom_white_card(wrong_cardpack_name.clone())
+            .await,
+        &wrong_cardpack_name.clone_str(),
+    );
+
+    let updated_card = collection
+        .update_custom_white_card(card_name.clone(), string_field("Updated card"))
+        .await
+        .unwrap();
+    assert_eq!(updated_card.text, "Updated card");
+    assert_eq!(updated_card.create_time, card.create_time);
+    assert!(updated_card.update_time.is_some());
+
+    let other_cards: Vec<CustomWhiteCard> = collection
+        .batch_create_custom_white_cards(
+            cardpack_name.clone(),
+            vec![string_field("Second card"), string_field("Third card")],
+        )
+        .await
+        .unwrap()
+        .into_iter()
+        .map(Option::unwrap)
+        .collect();
+    let (first_page, next_object_id_or, total_size) = collection
+        .list_custom_white_cards(cardpack_name.clone(), page_size(2), None, false)
+        .await
+        .unwrap();
+    assert_eq!(
+        first_page,
+        vec![updated_card.clone(), other_cards[0].clone()]
+    );
+    assert!(next_object_id_or.is_some());
+    assert_eq!(total_size, 3);
+    assert_eq!(
+        collection
+            .list_custom_white_cards(
+                cardpack_name.clone(),
+                page_size(2),
+                next_object_id_or,
+                false
+            )
+            .await
+            .unwrap(),
+        (vec![other_cards[1].clone()], None, 3)
+    );
+
+    let deleted_card = collection
+        .soft_delete_custom_white_card(card_name.clone())
+        .await
+        .unwrap();
+    assert!(deleted_card.delete_time.is_some());
+    assert_not_found(
+        collection.get_custom_white_card(card_name.clone()).await,
+        &card.name,
+    );
+    assert_not_found(
+        collection
+            .update_custom_white_card(card_name.clone(), string_field("Card"))
+            .await,
+        &card.name,
+    );
+    assert_eq!(
+        collection
+            .list_custom_white_cards(cardpack_name.clone(), page_size(10), None, true)
+            .await
+            .unwrap(),
+        (vec![deleted_card], None, 1)
+    );
+    assert_eq!(
+        collection
+            .list_custom_white_cards(cardpack_name.clone(), page_size(10), None, false)
+            .await
+            .unwrap(),
+        (other_cards.clone(), None, 2)
+    );
+    // Deleted cards are left out of streams.
+    let streamed_cards: Vec<CustomWhiteCard> = collection
+        .stream_custom_white_cards(cardpack_name)
+        .await
+        .unwrap()
+        .map(|card_or| card_or.unwrap())
+        .collect()
+        .await;
+    assert_eq!(streamed_cards, other_cards);
+    assert_eq!(
+        collection
+            .undelete_custom_white_card(card_name.clone())
+            .await
+            .unwrap(),
+        updated_card
+    );
+    assert_not_found(
+        collection.undelete_custom_white_card(card_name).await,
+        &card.name,
+    );
+}
+
+async fn check_user_collection(collection: &dyn UserCollection) {
+    let oauth_id = ObjectId::new().to_hex();
+    let user = collection
+        .get_or_create_user(oauth_credentials(&oauth_id), string_field("Tommy"))
+        .await
+        .unwrap();
+    let user_name = UserName::new_from_str(&user.name).unwrap();
+    assert_eq!(user.display_name, "Tommy");
+    assert!(user.create_time.is_some());
+    assert_eq!(user.update_time, None);
+
+    // Existing users are returned as they are.
+    assert_eq!(
+        collection
+            .get_or_create_user(oauth_credentials(&oauth_id), string_field("Someone else"))
+            .await
+            .unwrap(),
+        user
+    );
+    let other_user = collection
+        .get_or_create_user(
+            oauth_credentials(&ObjectId::new().to_hex()),
+            string_field("Tommy"),
+        )
+        .await
+        .unwrap();
+    assert_ne!(other_user.name, user.name);
+
+    assert_eq!(collection.get_user(user_name.clone()).await.unwrap(), user);
+    collection
+        .assert_user_exists(user_name.clone())
+        .await
+        .unwrap();
+    let missing_user_name = new_user_name();
+    assert_not_found(
+        collection.get_user(missing_user_name.clone()).await,
+        &missing_user_name.clone_str(),
+    );
+    assert_eq!(
+        collection
+            .assert_user_exists(missing_user_name.clone())
+            .await
+            .unwrap_err()
+            .code(),
+        tonic::Code::NotFound
+    );
+
+    let updated_user = collection
+        .update_user(user_name.clone(), string_field("Tom"))
+        .await
+        .unwrap();
+    assert_eq!(updated_user.display_name, "Tom");
+    assert_eq!(updated_user.create_time, user.create_time);
+    assert!(updated_user.update_time.is_some());
+    assert_eq!(
+        collection.get_user(user_name.clone()).await.unwrap(),
+        updated_user
+    );
+    assert_eq!(
+        collection
+            .get_users_from_names(vec![
+                user_name.clone(),
+                missing_user_name.clone(),
+                UserName::new_from_str(&other_user.name).unwrap()
+            ])
+            .await
+            .unwrap(),
+        vec![Some(updated_user.clone()), None, Some(other_user.clone())]
+    );
+
+    let user_settings_name =
+        UserSettingsName::new_from_str(&format!("{}/settings", user.name)).unwrap();
+    let user_settings = collection
+        .get_user_settings(user_settings_name.clone())
+        .await
+        .unwrap();
+    assert_eq!(
+        user_settings,
+        UserSettings {
+            name: user_settings_name.clone_str(),
+            color_scheme: ValidatedColorScheme::DefaultLight as i32,
+            quick_start_game_config: None,
+        }
+    );
+    let updated_user_settings = collection
+        .update_user_settings(
+            user_settings_name.clone(),
+            Some(ValidatedColorScheme::DefaultDark),
+            Some(OptionalField::Unset),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        updated_user_settings.color_scheme,
+        ValidatedColorScheme::DefaultDark as i32
+    );
+    assert_eq!(updated_user_settings.quick_start_game_config, None);
+    assert_eq!(
+        collection
+            .update_user_settings(user_settings_name.clone(), None, None)
+            .await
+            .unwrap(),
+        updated_user_settings
+    );
+    assert_eq!(
+        collection
+            .get_user_settings(user_settings_name)
+            .await
+            .unwrap(),
+        updated_user_settings
+    );
+
+    assert_eq!(
+        collection.get_user_role(user_name.clone()).await.unwrap(),
+        UserRole::User
+    );
+    collection
+        .set_user_role(user_name.clone(), UserRole::Admin)
+        .await
+        .unwrap();
+    assert_eq!(
+        collection.get_user_role(user_name.clone()).await.unwrap(),
+        UserRole::Admin
+    );
+    collection
+        .set_user_role(user_name.clone(), UserRole::User)
+        .await
+        .unwrap();
+    assert_eq!(
+        collection.get_user_role(user_name.clone()).await.unwrap(),
+        UserRole::User
+    );
+    assert_not_found(
+        collection
+            .set_user_role(missing_user_name.clone(), UserRole::Admin)
+            .await,
+        &missing_user_name.clone_str(),
+    );
+
+    // Favorites are listed in the order they were added.
+    let cardpack_names = [
+        new_custom_cardpack_name(),
+        new_custom_cardpack_name(),
+        new_custom_cardpack_name(),
+    ];
+    for cardpack_name in &cardpack_names {
+        collection
+            .add_custom_cardpack_to_favorites(user_name.clone(), cardpack_name.clone())
+            .await
+            .unwrap();
+    }
+    assert!(collection
+        .add_custom_cardpack_to_favorites(user_name.clone(), cardpack_names[0].clone())
+        .await
+        .is_err());
+    assert!(collection
+        .add_custom_cardpack_to_favorites(missing_user_name.clone(), cardpack_names[0].clone())
+        .await
+        .is_err());
+    assert!(collection
+        .check_has_user_favorited_custom_cardpack(user_name.clone(), cardpack_names[1].clone())
+        .await
+        .unwrap());
+    assert!(!collection
+        .check_has_user_favorited_custom_cardpack(user_name.clone(), new_custom_cardpack_name())
+        .await
+        .unwrap());
+
+    // Favorited cardpacks are named as if they belong to the user who favorited them.
+    let favorited_names = |indices: &[usize]| -> Vec<CustomCardpackName> {
+        indices
+            .iter()
+            .map(|index| {
+                CustomCardpackName::new_from_parent(
+                    user_name.clone(),
+                    *cardpack_names[*index].get_object_ids().1,
+                )
+            })
+            .collect()
+    };
+    let (page, next_index_or) = collection
+        .list_favorited_custom_cardpack_names(user_name.clone(), page_size(2), 0)
+        .await
+        .unwrap();
+    assert!(page == favorited_names(&[0, 1]));
+    assert_eq!(next_index_or, Some(2));
+    let (page, next_index_or) = collection
+        .list_favorited_custom_cardpack_names(user_name.clone(), page_size(2), 2)
+        .await
+        .unwrap();
+    assert!(page == favorited_names(&[2]));
+    assert_eq!(next_index_or, None);
+
+    collection
+        .remove_custom_cardpack_from_favorites(user_name.clone(), cardpack_names[1].clone())
+        .await
+        .unwrap();
+    assert!(collection
+        .remove_custom_cardpack_from_favorites(user_name.clone(), cardpack_names[1].clone())
+        .await
+        .is_err());
+    let (page, next_index_or) = collection
+        .list_favorited_custom_cardpack_names(user_name.clone(), page_size(2), 0)
+        .await
+        .unwrap();
+    assert!(page == favorited_names(&[0, 2]));
+    assert_eq!(next_index_or, None);
+    assert_eq!(
+        collection
+            .list_favorited_custom_cardpack_names(missing_user_name, page_size(2), 0)
+            .await
+            .unwrap_err()
+            .code(),
+        tonic::Code::NotFound
+    );
+
+    let streamed_users: Vec<User> = collection
+        .user_stream()
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert!(streamed_users.contains(&updated_user));
+    assert!(streamed_users.contains(&other_user));
+}
+
+async fn check_user_stats_collection(collection: &dyn UserStatsCollection) {
+    let user_name = new_user_name();
+    let other_user_name = new_user_name();
+    let user_stats_name =
+        UserStatsName::new_from_str(&format!("{}/stats", user_name.clone_str())).unwrap();
+    let other_user_stats_name =
+        UserStatsName::new_from_str(&format!("{}/stats", other_user_name.clone_str())).unwrap();
+
+    // Users who haven't played yet have all-zero stats.
+    assert_eq!(
+        collection
+            .get_user_stats(user_stats_name.clone())
+            .await
+            .unwrap(),
+        UserStats {
+            name: user_stats_name.clone_str(),
+            games_played: 0,
+            games_won: 0,
+            rounds_won: 0,
+            rounds_judged: 0,
+        }
+    );
+
+    collection.increment_user_stats(Vec::new()).await.unwrap();
+
+    // Increments for the same user in one write add up.
+    collection
+        .increment_user_stats(vec![
+            (
+                user_name.clone(),
+                UserStatsIncrement {
+                    user_name: user_name.clone_str(),
+                    games_played: 1,
+                    games_won: 1,
+                    rounds_won: 3,
+                    rounds_judged: 2,
+                },
+            ),
+            (
+                other_user_name.clone(),
+                UserStatsIncrement {
+                    user_name: other_user_name.clone_str(),
+                    games_played: 1,
+                    games_won: 0,
+                    rounds_won: 1,
+                    rounds_judged: 1,
+                },
+            ),
+            (
+                user_name.clone(),
+                UserStatsIncrement {
+                    user_name: user_name.clone_str(),
+                    games_played: 1,
+                    games_won: 0,
+                    rounds_won: 2,
+                    rounds_judged: 0,
+                },
+            ),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(
+        collection
+            .get_user_stats(user_stats_name.clone())
+            .await
+            .unwrap(),
+        UserStats {
+            name: user_stats_name.clone_str(),
+            games_played: 2,
+            games_won: 1,
+            rounds_won: 5,
+            rounds_judged: 2,
+        }
+    );
+    assert_eq!(
+        collection
+            .get_user_stats(other_user_stats_name.clone())
+            .await
+            .unwrap(),
+        UserStats {
+            name: other_user_stats_name.clone_str(),
+            games_played: 1,
+            games_won: 0,
+            rounds_won: 1,
+            rounds_judged: 1,
+        }
+    );
+}
+
+async fn check_white_card_stats_collection(collection: &dyn WhiteCardStatsCollection) {
+    let parent = new_custom_cardpack_name().clone_str();
+    let card_names: Vec<String> = (0..4)
+        .map(|_| format!("{}/whiteCards/{}", parent, ObjectId::new().to_hex()))
+        .collect();
+    let white_card_stats = |index: usize, play_count: i64, win_count: i64| WhiteCardStats {
+        card_name: card_names[index].clone(),
+        play_count,
+        win_count,
+    };
+
+    for (index, play_count, win_count) in [(0, 2, 1), (1, 3, 0), (2, 1, 1), (0, 1, 0)] {
+        collection
+            .increment_white_card_stats(
+                parent.clone(),
+                white_card_stats(index, play_count, win_count),
+            )
+            .await
+            .unwrap();
+    }
+
+    // Cards with the same number of wins are ordered by fewest plays.
+    let (top_white_cards, next_index_or, total_size) = collection
+        .list_top_white_cards(parent.clone(), page_size(1), 0)
+        .await
+        .unwrap();
+    assert_eq!(top_white_cards, vec![white_card_stats(2, 1, 1)]);
+    assert_eq!(next_index_or, Some(1));
+    assert_eq!(total_size, 2);
+    let (top_white_cards, next_index_or, _) = collection
+        .list_top_white_cards(parent.clone(), page_size(1), 1)
+        .await
+        .unwrap();
+    assert_eq!(top_white_cards, vec![white_card_stats(0, 3, 1)]);
+    assert_eq!(next_index_or, None);
+
+    // Cards that were never played follow the ones that were played but never won.
+    let (never_picked_white_cards, next_index_or, total_size) = collection
+        .list_never_picked_white_cards(parent.clone(), card_names.clone(), page_size(1), 0)
+        .await
+        .unwrap();
+    assert_eq!(never_picked_white_cards, vec![white_card_stats(1, 3, 0)]);
+    assert_eq!(next_index_or, Some(1));
+    assert_eq!(total_size, 2);
+    let (never_picked_white_cards, next_index_or, _) = collection
+        .list_never_picked_white_cards(parent.clone(), card_names.clone(), page_size(1), 1)
+        .await
+        .unwrap();
+    assert_eq!(never_picked_white_cards, vec![white_card_stats(3, 0, 0)]);
+    assert_eq!(next_index_or, None);
+
+    // Only the given cards are listed.
+    let (never_picked_white_cards, _, total_size) = collection
+        .list_never_picked_white_cards(parent, vec![card_names[3].clone()], page_size(5), 0)
+        .await
+        .unwrap();
+    assert_eq!(never_picked_white_cards, vec![white_card_stats(3, 0, 0)]);
+    assert_eq!(total_size, 1);
+}
+
+async fn check_user_achievement_collection(collection: &dyn UserAchievementCollection) {
+    let user_name = new_user_name();
+    let other_user_name = new_user_name();
+    let list_achievement_names = |page_size_i32: i32, start_index: usize| {
+        let user_name = user_name.clone();
+        async move {
+            let (achievements, next_index_or, total_size) = collection
+                .list_unlocked_achievements(user_name, page_size(page_size_i32), start_index)
+                .await
+                .unwrap();
+            (
+                achievements
+                    .into_iter()
+                    .map(|achievement| achievement.name)
+                    .collect::<Vec<String>>(),
+                next_index_or,
+                total_size,
+            )
+        }
+    };
+    let achievement_name =
+        |achievement_id: &str| format!("{}/achievements/{}", user_name.clone_str(), achievement_id);
+
+    // Progress accumulates, but achievements aren't listed until they're unlocked.
+    collection
+        .add_achievement_progress(user_name.clone(), String::from("on-a-roll"), 2, 5)
+        .await
+        .unwrap();
+    collection
+        .add_achievement_progress(user_name.clone(), String::from("on-a-roll"), 2, 5)
+        .await
+        .unwrap();
+    assert_eq!(list_achievement_names(5, 0).await, (vec![], None, 0));
+
+    // Reaching the required progress unlocks the achievement, even in a single step.
+    // Achievements are unlocked in the same order as their ids, so that the listing
+    // order doesn't depend on whether unlock times are distinct.
+    collection
+        .add_achievement_progress(user_name.clone(), String::from("blank-slate"), 1, 1)
+        .await
+        .unwrap();
+    collection
+        .add_achievement_progress(user_name.clone(), String::from("full-house"), 3, 3)
+        .await
+        .unwrap();
+    collection
+        .add_achievement_progress(user_name.clone(), String::from("on-a-roll"), 1, 5)
+        .await
+        .unwrap();
+    collection
+        .add_achievement_progress(other_user_name.clone(), String::from("full-house"), 2, 3)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        list_achievement_names(2, 0).await,
+        (
+            vec![
+                achievement_name("blank-slate"),
+                achievement_name("full-house")
+            ],
+            Some(2),
+            3
+        )
+    );
+    assert_eq!(
+        list_achievement_names(2, 2).await,
+        (vec![achievement_name("on-a-roll")], None, 3)
+    );
+
+    // Display fields come from the achievement definition, and progress past
+    // the requirement doesn't change when the achievement was unlocked.
+    let (achievements, _, _) = collection
+        .list_unlocked_achievements(user_name.clone(), page_size(1), 0)
+        .await
+        .unwrap();
+    let achievement = achievements[0].clone();
+    let definition = get_achievement_definition("blank-slate").unwrap();
+    assert_eq!(achievement.display_name, definition.display_name);
+    assert_eq!(achievement.description, definition.description);
+    assert!(achievement.unlock_time.is_some());
+    collection
+        .add_achievement_progress(user_name.clone(), String::from("blank-slate"), 1, 1)
+        .await
+        .unwrap();
+    let (achievements, _, total_size) = collection
+        .list_unlocked_achievements(user_name.clone(), page_size(1), 0)
+        .await
+        .unwrap();
+    assert_eq!(achievements, vec![achievement]);
+    assert_eq!(total_size, 3);
+
+    // Other users' achievements are tracked separately.
+    let (achievements, next_index_or, total_size) = collection
+        .list_unlocked_achievements(other_user_name, page_size(5), 0)
+        .await
+        .unwrap();
+    assert!(achievements.is_empty());
+    assert_eq!(next_index_or, None);
+    assert_eq!(total_size, 0);
+}
+
+async fn check_collections(collections: Collections) {
+    check_custom_cardpack_collection(collections.custom_cardpack_collection.as_ref()).await;
+    check_custom_black_card_collection(collections.custom_black_card_collection.as_ref()).await;
+    check_custom_white_card_collection(collections.custom_white_card_collection.as_ref()).await;
+    check_user_collection(collections.user_collection.as_ref()).await;
+    check_user_stats_collection(collections.user_stats_collection.as_ref()).await;
+    check_white_card_stats_collection(collections.white_card_stats_collection.as_ref()).await;
+    check_user_achievement_collection(collections.user_achievement_collection.as_ref()).await;
+}
+
+#[tokio::test]
+async fn in_memory_storage_conforms() {
+    check_collections(Collections::new_in_memory()).await;
+}
+
+#[tokio::test]
+async fn sqlite_storage_conforms() {
+    check_collections(Collections::new_sqlite(Arc::new(
+        SqliteDatabase::open_in_memory().unwrap(),
+    )))
+    .await;
+}
+
+// Mongo needs a running server, so it's only checked when `TEST_MONGO_URI`
+// is set, e.g. `TEST_MONGO_URI=mongodb://localhost:27017/ cargo test`.
+// The test database is dropped before and after the check, so that
+// repeated runs don't pile up data or depend on earlier runs.
+#[tokio::test]
+async fn mongo_storage_conforms() {
+    let mongo_uri = match std::env::var("TEST_MONGO_URI") {
+        Ok(mongo_uri) => mongo_uri,
+        Err(_) => return,
+    };
+    let mongo_database = mongodb::Client::with_uri_str(&mongo_uri)
+        .await
+        .unwrap()
+        .database("crustyCardsConformanceTest");
+    mongo_database.drop(None).await.unwrap();
+    check_collections(Collections::from_mongo_database(mongo_database.clone())).await;
+    mongo_database.drop(None).await.unwrap();
+}
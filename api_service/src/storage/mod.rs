@@ -0,0 +1,139 @@
+use super::config::{Config, StorageBackend};
+use super::health::MongoReadinessCheck;
+use super::memory::custom_black_card_collection::InMemoryCustomBlackCardCollection;
+use super::memory::custom_cardpack_collection::InMemoryCustomCardpackCollection;
+use super::memory::custom_white_card_collection::InMemoryCustomWhiteCardCollection;
+use super::memory::user_achievement_collection::InMemoryUserAchievementCollection;
+use super::memory::user_collection::InMemoryUserCollection;
+use super::memory::user_stats_collection::InMemoryUserStatsCollection;
+use super::memory::white_card_stats_collection::InMemoryWhiteCardStatsCollection;
+use super::mongo::custom_black_card_collection::{
+    CustomBlackCardCollection, MongoCustomBlackCardCollection,
+};
+use super::mongo::custom_cardpack_collection::{
+    CustomCardpackCollection, MongoCustomCardpackCollection,
+};
+use super::mongo::custom_white_card_collection::{
+    CustomWhiteCardCollection, MongoCustomWhiteCardCollection,
+};
+use super::mongo::helper::get_mongo_database_or_panic;
+use super::mongo::user_achievement_collection::{
+    MongoUserAchievementCollection, UserAchievementCollection,
+};
+use super::mongo::user_collection::{MongoUserCollection, UserCollection};
+use super::mongo::user_stats_collection::{MongoUserStatsCollection, UserStatsCollection};
+use super::mongo::white_card_stats_collection::{
+    MongoWhiteCardStatsCollection, WhiteCardStatsCollection,
+};
+use super::sqlite::custom_black_card_collection::SqliteCustomBlackCardCollection;
+use super::sqlite::custom_cardpack_collection::SqliteCustomCardpackCollection;
+use super::sqlite::custom_white_card_collection::SqliteCustomWhiteCardCollection;
+use super::sqlite::helper::SqliteDatabase;
+use super::sqlite::user_achievement_collection::SqliteUserAchievementCollection;
+use super::sqlite::user_collection::SqliteUserCollection;
+use super::sqlite::user_stats_collection::SqliteUserStatsCollection;
+use super::sqlite::white_card_stats_collection::SqliteWhiteCardStatsCollection;
+use mongodb::Database;
+use shared::health::ReadinessCheck;
+use std::sync::Arc;
+use tracing::info;
+
+#[cfg(test)]
+mod conformance_tests;
+
+// Every collection that api service reads from and writes
+// to, backed by whichever storage `storage` is set to.
+pub struct Collections {
+    pub user_collection: Arc<dyn UserCollection>,
+    pub user_stats_collection: Box<dyn UserStatsCollection>,
+    pub user_achievement_collection: Box<dyn UserAchievementCollection>,
+    pub custom_cardpack_collection: Box<dyn CustomCardpackCollection>,
+    pub custom_black_card_collection: Box<dyn CustomBlackCardCollection>,
+    pub custom_white_card_collection: Box<dyn CustomWhiteCardCollection>,
+    pub white_card_stats_collection: Box<dyn WhiteCardStatsCollection>,
+    // Checks that the database can be reached. In-memory and SQLite storage are always reachable.
+    pub readiness_check_or: Option<Box<dyn ReadinessCheck>>,
+}
+
+impl Collections {
+    pub async fn new(config: &Config) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(match config.get_storage() {
+            StorageBackend::Mongo => Self::new_mongo(config).await,
+            StorageBackend::Memory => Self::new_in_memory(),
+            StorageBackend::Sqlite => {
+                info!(path = config.get_sqlite_path(), "Opening SQLite database.");
+                Self::new_sqlite(Arc::new(SqliteDatabase::open(config.get_sqlite_path())?))
+            }
+        })
+    }
+
+    async fn new_mongo(config: &Config) -> Self {
+        Self::from_mongo_database(get_mongo_database_or_panic(config).await)
+    }
+
+    fn from_mongo_database(mongo_database: Database) -> Self {
+        Self {
+            user_collection: Arc::from(MongoUserCollection::new(
+                mongo_database.collection("users"),
+            )),
+            user_stats_collection: Box::from(MongoUserStatsCollection::new(
+                mongo_database.collection("userStats"),
+                mongo_database.clone(),
+            )),
+            user_achievement_collection: Box::from(MongoUserAchievementCollection::new(
+                mongo_database.collection("userAchievements"),
+            )),
+            custom_cardpack_collection: Box::from(MongoCustomCardpackCollection::new(
+                mongo_database.collection("cardpacks"),
+            )),
+            custom_black_card_collection: Box::from(MongoCustomBlackCardCollection::new(
+                mongo_database.collection("blackCards"),
+            )),
+            custom_white_card_collection: Box::from(MongoCustomWhiteCardCollection::new(
+                mongo_database.collection("whiteCards"),
+            )),
+            white_card_stats_collection: Box::from(MongoWhiteCardStatsCollection::new(
+                mongo_database.collection("whiteCardStats"),
+            )),
+            // Mongo connects lazily, so startup succeeds even if it's unreachable.
+            // Instead, the server reports itself as not ready until it can be reached.
+            readiness_check_or: Some(Box::from(MongoReadinessCheck::new(mongo_database))),
+        }
+    }
+
+    fn new_in_memory() -> Self {
+        Self {
+            user_collection: Arc::from(InMemoryUserCollection::new()),
+            user_stats_collection: Box::from(InMemoryUserStatsCollection::new()),
+            user_achievement_collection: Box::from(InMemoryUserAchievementCollection::new()),
+            custom_cardpack_collection: Box::from(InMemoryCustomCardpackCollection::new()),
+            custom_black_card_collection: Box::from(InMemoryCustomBlackCardCollection::new()),
+            custom_white_card_collection: Box::from(InMemoryCustomWhiteCardCollection::new()),
+            white_card_stats_collection: Box::from(InMemoryWhiteCardStatsCollection::new()),
+            readiness_check_or: None,
+        }
+    }
+
+    // Unlike Mongo, the database is opened and migrated up front, so
+    // startup fails if the file can't be opened or migrated.
+    fn new_sqlite(database: Arc<SqliteDatabase>) -> Self {
+        Self {
+            user_collection: Arc::from(SqliteUserCollection::new(database.clone())),
+            user_stats_collection: Box::from(SqliteUserStatsCollection::new(database.clone())),
+            user_achievement_collection: Box::from(SqliteUserAchievementCollection::new(
+                database.clone(),
+            )),
+            custom_cardpack_collection: Box::from(SqliteCustomCardpackCollection::new(
+                database.clone(),
+            )),
+            custom_black_card_collection: Box::from(SqliteCustomBlackCardCollection::new(
+                database.clone(),
+            )),
+            custom_white_card_collection: Box::from(SqliteCustomWhiteCardCollection::new(
+                database.clone(),
+            )),
+            white_card_stats_collection: Box::from(SqliteWhiteCardStatsCollection::new(database)),
+            readiness_check_or: None,
+        }
+    }
+}
@@ -4,6 +4,7 @@ use lapin::{
 
 static GAME_QUEUE_NAME: &str = "GAME";
 
+#[derive(Clone)]
 pub struct MessageQueue {
     channel: Channel,
 }
@@ -25,13 +26,26 @@ impl MessageQueue {
         MessageQueue { channel }
     }
 
+    pub fn is_connected(&self) -> bool {
+        self.channel.status().connected()
+    }
+
     fn construct_game_update_message(user_names: Vec<impl ToString>) -> String {
+        MessageQueue::construct_message("GAME_UPDATED", user_names)
+    }
+
+    fn construct_server_restarting_message(user_names: Vec<impl ToString>) -> String {
+        MessageQueue::construct_message("SERVER_RESTARTING", user_names)
+    }
+
+    fn construct_message(message_type: &str, user_names: Vec<impl ToString>) -> String {
         let user_names_with_surrounding_quotes: Vec<String> = user_names
             .into_iter()
             .map(|name| format!("\"{}\"", name.to_string()))
             .collect();
         return format!(
-            "{{\"type\": \"GAME_UPDATED\", \"payload\": [{}]}}",
+            "{{\"type\": \"{}\", \"payload\": [{}]}}",
+            message_type,
             user_names_with_surrounding_quotes.join(", ")
         );
     }
@@ -39,6 +53,25 @@ impl MessageQueue {
     pub async fn game_updated_for_users(
         &self,
         user_names: Vec<impl ToString>,
+    ) -> Result<lapin::publisher_confirm::Confirmation, lapin::Error> {
+        self.publish(MessageQueue::construct_game_update_message(user_names))
+            .await
+    }
+
+    // Warns users that the instance hosting their game is shutting down.
+    pub async fn server_restarting_for_users(
+        &self,
+        user_names: Vec<impl ToString>,
+    ) -> Result<lapin::publisher_confirm::Confirmation, lapin::Error> {
+        self.publish(MessageQueue::construct_server_restarting_message(
+            user_names,
+        ))
+        .await
+    }
+
+    async fn publish(
+        &self,
+        message: String,
     ) -> Result<lapin::publisher_confirm::Confirmation, lapin::Error> {
         match self
             .channel
@@ -46,7 +79,7 @@ impl MessageQueue {
                 "",
                 GAME_QUEUE_NAME,
                 BasicPublishOptions::default(),
-                &MessageQueue::construct_game_update_message(user_names).into_bytes(),
+                &message.into_bytes(),
                 BasicProperties::default(),
             )
             .await
@@ -75,4 +108,12 @@ mod tests {
             "{\"type\": \"GAME_UPDATED\", \"payload\": [\"users/1234\", \"users/5678\"]}"
         );
     }
+
+    #[test]
+    fn construct_server_restarting_message() {
+        assert_eq!(
+            MessageQueue::construct_server_restarting_message(vec!("users/1234", "users/5678")),
+            "{\"type\": \"SERVER_RESTARTING\", \"payload\": [\"users/1234\", \"users/5678\"]}"
+        );
+    }
 }
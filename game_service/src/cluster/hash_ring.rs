@@ -0,0 +1,126 @@
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+// Each instance is placed on the ring this many times. Spreading every
+// instance across many points keeps the share of games owned by each
+// instance roughly even, and means that adding or removing an instance
+// only moves a small slice of the keyspace.
+const VIRTUAL_NODES_PER_INSTANCE: usize = 128;
+
+pub struct HashRing {
+    ring: BTreeMap<u64, String>,
+}
+
+impl HashRing {
+    pub fn new(instance_addresses: &[String]) -> HashRing {
+        let mut ring = BTreeMap::new();
+        for instance_address in instance_addresses {
+            for i in 0..VIRTUAL_NODES_PER_INSTANCE {
+                ring.insert(
+                    Self::hash(&format!("{}#{}", instance_address, i)),
+                    String::from(instance_address),
+                );
+            }
+        }
+        HashRing { ring }
+    }
+
+    fn hash(key: &str) -> u64 {
+        let digest: [u8; 32] = Sha256::digest(key.as_bytes()).into();
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&digest[..8]);
+        u64::from_be_bytes(bytes)
+    }
+
+    // Returns the address of the instance that owns the given key,
+    // or None if the ring doesn't contain any instances.
+    pub fn get_owner(&self, key: &str) -> Option<&str> {
+        let key_hash = Self::hash(key);
+        match self.ring.range(key_hash..).next() {
+            Some((_, instance_address)) => Some(instance_address),
+            // Wrap around to the start of the ring.
+            None => self
+                .ring
+                .values()
+                .next()
+                .map(|instance_address| &instance_address[..]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn get_instance_addresses(count: usize) -> Vec<String> {
+        (0..count)
+            .map(|i| format!("http://127.0.0.1:{}", 50052 + i))
+            .collect()
+    }
+
+    #[test]
+    fn empty_ring_has_no_owner() {
+        let hash_ring = HashRing::new(&[]);
+        assert_eq!(hash_ring.get_owner("game_id"), None);
+    }
+
+    #[test]
+    fn single_instance_owns_every_key() {
+        let hash_ring = HashRing::new(&get_instance_addresses(1));
+        for i in 0..1000 {
+            assert_eq!(
+                hash_ring.get_owner(&format!("game_{}", i)),
+                Some("http://127.0.0.1:50052")
+            );
+        }
+    }
+
+    #[test]
+    fn ownership_does_not_depend_on_instance_order() {
+        let instance_addresses = get_instance_addresses(5);
+        let mut reversed_instance_addresses = instance_addresses.clone();
+        reversed_instance_addresses.reverse();
+        let hash_ring = HashRing::new(&instance_addresses);
+        let reversed_hash_ring = HashRing::new(&reversed_instance_addresses);
+        for i in 0..1000 {
+            let key = format!("game_{}", i);
+            assert_eq!(
+                hash_ring.get_owner(&key),
+                reversed_hash_ring.get_owner(&key)
+            );
+        }
+    }
+
+    #[test]
+    fn spreads_keys_across_instances() {
+        let instance_addresses = get_instance_addresses(4);
+        let hash_ring = HashRing::new(&instance_addresses);
+        let mut key_counts: HashMap<&str, usize> = HashMap::new();
+        for i in 0..10000 {
+            let owner = hash_ring.get_owner(&format!("game_{}", i)).unwrap();
+            *key_counts.entry(owner).or_insert(0) += 1;
+        }
+        assert_eq!(key_counts.len(), 4);
+        for count in key_counts.values() {
+            // A perfectly even split would be 2500 keys per instance.
+            assert!(*count > 1500);
+            assert!(*count < 3500);
+        }
+    }
+
+    #[test]
+    fn adding_instance_only_moves_keys_to_new_instance() {
+        let mut instance_addresses = get_instance_addresses(3);
+        let hash_ring = HashRing::new(&instance_addresses);
+        instance_addresses.push(String::from("http://127.0.0.1:60000"));
+        let bigger_hash_ring = HashRing::new(&instance_addresses);
+        for i in 0..1000 {
+            let key = format!("game_{}", i);
+            let new_owner = bigger_hash_ring.get_owner(&key).unwrap();
+            if new_owner != "http://127.0.0.1:60000" {
+                assert_eq!(hash_ring.get_owner(&key).unwrap(), new_owner);
+            }
+        }
+    }
+}
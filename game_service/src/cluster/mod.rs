@@ -0,0 +1,502 @@
+mod hash_ring;
+
+use hash_ring::HashRing;
+use shared::auth::{
+    attach_caller_session_token, is_internal_caller, InternalServiceChannel,
+    InternalServiceSecretInterceptor,
+};
+use shared::proto::crusty_cards_api::{
+    game_service_client::GameServiceClient, GameInfo, GetGameViewRequest,
+    GetUserDirectoryEntryRequest, SearchGamesRequest, UpdateUserDirectoryEntryRequest,
+    UserDirectoryEntry,
+};
+use shared::request_tracing::create_request_with_request_id;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use tonic::metadata::MetadataValue;
+use tonic::transport::Endpoint;
+use tonic::{Code, Request, Response, Status};
+use tracing::warn;
+
+// Set on every request that one game service instance sends to another.
+// Instances always answer forwarded requests locally, which guarantees
+// that a request can never bounce back and forth between two instances.
+// It's only trusted alongside a valid internal service secret, which
+// peer clients send with every request.
+const FORWARDED_REQUEST_METADATA_KEY: &str = "x-crusty-cards-forwarded";
+
+// Attached to errors caused by an unreachable owner instance so that
+// clients are able to retry directly against the instance that owns the game.
+pub const GAME_OWNER_ADDRESS_METADATA_KEY: &str = "x-crusty-cards-game-owner";
+
+// Used as the address of this instance when the service isn't part of a cluster.
+const SINGLE_INSTANCE_ADDRESS: &str = "local";
+
+// Decides which game service instance owns each game and relays requests
+// between instances. Games are assigned to instances by consistent hashing
+// of their game id, so every instance agrees on the owner of every game as
+// long as they're all started with the same list of instance addresses.
+//
+// The router also holds part of the user directory, which records the game
+// that each user is in. A user's entry lives on the instance that their user
+// name hashes to, and is only updated by the instance hosting their game.
+pub struct ClusterRouter {
+    self_address: String,
+    hash_ring: HashRing,
+    peer_clients: HashMap<String, GameServiceClient<InternalServiceChannel>>,
+    // Maps user names to game ids, for the users whose entries live on this instance.
+    user_directory: Mutex<HashMap<String, String>>,
+}
+
+impl ClusterRouter {
+    pub fn new(
+        self_address: String,
+        peer_addresses: Vec<String>,
+        internal_service_secret_interceptor: InternalServiceSecretInterceptor,
+    ) -> Result<ClusterRouter, tonic::transport::Error> {
+        let mut peer_clients = HashMap::new();
+        for peer_address in peer_addresses {
+            if peer_address == self_address {
+                continue;
+            }
+            // Connecting lazily allows instances to start in any order.
+            let channel = Endpoint::from_shared(peer_address.clone())?.connect_lazy();
+            peer_clients.insert(
+                peer_address,
+                GameServiceClient::with_interceptor(
+                    channel,
+                    internal_service_secret_interceptor.clone(),
+                ),
+            );
+        }
+
+        let mut instance_addresses: Vec<String> = peer_clients.keys().cloned().collect();
+        instance_addresses.push(self_address.clone());
+
+        Ok(ClusterRouter {
+            self_address,
+            hash_ring: HashRing::new(&instance_addresses),
+            peer_clients,
+            user_directory: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn new_single_instance() -> ClusterRouter {
+        ClusterRouter {
+            self_address: String::from(SINGLE_INSTANCE_ADDRESS),
+            hash_ring: HashRing::new(&[String::from(SINGLE_INSTANCE_ADDRESS)]),
+            peer_clients: HashMap::new(),
+            user_directory: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get_game_owner_address(&self, game_id: &str) -> &str {
+        // Unwrap is safe here because the ring always contains this instance.
+        self.hash_ring.get_owner(game_id).unwrap()
+    }
+
+    pub fn owns_game(&self, game_id: &str) -> bool {
+        self.get_game_owner_address(game_id) == self.self_address
+    }
+
+    fn get_directory_owner_address(&self, user_name: &str) -> &str {
+        // Unwrap is safe here because the ring always contains this instance.
+        self.hash_ring.get_owner(user_name).unwrap()
+    }
+
+    pub fn is_forwarded_request<T>(request: &Request<T>) -> bool {
+        is_internal_caller(request)
+            && request
+                .metadata()
+                .get(FORWARDED_REQUEST_METADATA_KEY)
+                .is_some()
+    }
+
+    pub fn check_request_is_forwarded<T>(request: &Request<T>) -> Result<(), Status> {
+        if Self::is_forwarded_request(request) {
+            Ok(())
+        } else {
+            Err(Status::permission_denied(
+                "Only other game service instances may call this method.",
+            ))
+        }
+    }
+
+    fn create_forwarded_request<T>(message: T) -> Request<T> {
+        let mut request = create_request_with_request_id(message);
+        request.metadata_mut().insert(
+            FORWARDED_REQUEST_METADATA_KEY,
+            MetadataValue::from_static("true"),
+        );
+        // The owner instance authenticates the caller itself.
+        attach_caller_session_token(&mut request);
+        request
+    }
+
+    fn get_peer_client(
+        &self,
+        address: &str,
+    ) -> Result<GameServiceClient<InternalServiceChannel>, Status> {
+        match self.peer_clients.get(address) {
+            Some(client) => Ok(client.clone()),
+            None => Err(Status::internal(format!(
+                "No connection to game service instance `{}`.",
+                address
+            ))),
+        }
+    }
+
+    // Sends a request to another instance and relays its response.
+    pub async fn forward<T, R, F, Fut>(
+        &self,
+        address: &str,
+        message: T,
+        rpc: F,
+    ) -> Result<Response<R>, Status>
+    where
+        F: FnOnce(GameServiceClient<InternalServiceChannel>, Request<T>) -> Fut,
+        Fut: Future<Output = Result<Response<R>, Status>>,
+    {
+        let client = self.get_peer_client(address)?;
+        match rpc(client, Self::create_forwarded_request(message)).await {
+            Ok(response) => Ok(response),
+            Err(mut status) => {
+                if status.code() == Code::Unavailable {
+                    if let Ok(address_metadata_value) = address.parse() {
+                        status
+                            .metadata_mut()
+                            .insert(GAME_OWNER_ADDRESS_METADATA_KEY, address_metadata_value);
+                    }
+                }
+                Err(status)
+            }
+        }
+    }
+
+    // Relays a request about a game to the instance that owns it. Returns None
+    // when this instance should handle the request itself, which is the case
+    // when the request was already forwarded or the game is owned here.
+    pub async fn forward_to_game_owner<T, R, F, Fut>(
+        &self,
+        request: &Request<T>,
+        game_id: &str,
+        rpc: F,
+    ) -> Option<Result<Response<R>, Status>>
+    where
+        T: Clone,
+        F: FnOnce(GameServiceClient<InternalServiceChannel>, Request<T>) -> Fut,
+        Fut: Future<Output = Result<Response<R>, Status>>,
+    {
+        if Self::is_forwarded_request(request) || self.owns_game(game_id) {
+            return None;
+        }
+        Some(
+            self.forward(
+                self.get_game_owner_address(game_id),
+                request.get_ref().clone(),
+                rpc,
+            )
+            .await,
+        )
+    }
+
+    // Relays a request about the user's game to the instance hosting it. Returns
+    // None when this instance should handle the request itself, which is also the
+    // case when the user isn't in a game, so that the usual error is returned.
+    pub async fn forward_to_user_game_host<T, R, F, Fut>(
+        &self,
+        request: &Request<T>,
+        user_name: &str,
+        user_is_in_local_game: bool,
+        rpc: F,
+    ) -> Option<Result<Response<R>, Status>>
+    where
+        T: Clone,
+        F: FnOnce(GameServiceClient<InternalServiceChannel>, Request<T>) -> Fut,
+        Fut: Future<Output = Result<Response<R>, Status>>,
+    {
+        if user_is_in_local_game || Self::is_forwarded_request(request) {
+            return None;
+        }
+        match self.get_user_game_id(user_name).await {
+            Ok(Some(game_id)) => self.forward_to_game_owner(request, &game_id, rpc).await,
+            Ok(None) => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+
+    // Returns the id of the game that the user directory has the user in. Entries
+    // can outlive their game, such as when a game is removed for inactivity, so
+    // only the instance hosting the game knows for sure whether the user is in it.
+    pub async fn get_user_game_id(&self, user_name: &str) -> Result<Option<String>, Status> {
+        let directory_owner_address = self.get_directory_owner_address(user_name);
+        let entry = if directory_owner_address == self.self_address {
+            self.get_local_user_directory_entry(user_name)
+        } else {
+            self.get_peer_client(directory_owner_address)?
+                .get_user_directory_entry(Self::create_forwarded_request(
+                    GetUserDirectoryEntryRequest {
+                        user_name: String::from(user_name),
+                    },
+                ))
+                .await?
+                .into_inner()
+        };
+        if entry.game_id.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(entry.game_id))
+        }
+    }
+
+    // Points the user's directory entry at `game_id`, but only if it's currently
+    // empty or points at `expected_game_id`. An empty `game_id` clears the entry.
+    // Returns the game id that the entry points at afterwards, so callers can
+    // tell whether the update happened.
+    pub async fn update_user_directory_entry(
+        &self,
+        user_name: &str,
+        expected_game_id: &str,
+        game_id: &str,
+    ) -> Result<String, Status> {
+        let directory_owner_address = self.get_directory_owner_address(user_name);
+        let entry = if directory_owner_address == self.self_address {
+            self.update_local_user_directory_entry(user_name, expected_game_id, game_id)
+        } else {
+            self.get_peer_client(directory_owner_address)?
+                .update_user_directory_entry(Self::create_forwarded_request(
+                    UpdateUserDirectoryEntryRequest {
+                        user_name: String::from(user_name),
+                        expected_game_id: String::from(expected_game_id),
+                        game_id: String::from(game_id),
+                    },
+                ))
+                .await?
+                .into_inner()
+        };
+        Ok(entry.game_id)
+    }
+
+    pub fn get_local_user_directory_entry(&self, user_name: &str) -> UserDirectoryEntry {
+        UserDirectoryEntry {
+            user_name: String::from(user_name),
+            game_id: self
+                .user_directory
+                .lock()
+                .unwrap()
+                .get(user_name)
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn update_local_user_directory_entry(
+        &self,
+        user_name: &str,
+        expected_game_id: &str,
+        game_id: &str,
+    ) -> UserDirectoryEntry {
+        let mut user_directory = self.user_directory.lock().unwrap();
+        let current_game_id = user_directory.get(user_name).cloned().unwrap_or_default();
+        let game_id = if current_game_id.is_empty() || current_game_id == expected_game_id {
+            if game_id.is_empty() {
+                user_directory.remove(user_name);
+            } else {
+                user_directory.insert(String::from(user_name), String::from(game_id));
+            }
+            String::from(game_id)
+        } else {
+            current_game_id
+        };
+        UserDirectoryEntry {
+            user_name: String::from(user_name),
+            game_id,
+        }
+    }
+
+    // Asks the instance that owns the game whether the user is still in it.
+    pub async fn peer_game_contains_user(
+        &self,
+        game_id: &str,
+        user_name: &str,
+    ) -> Result<bool, Status> {
+        let request = Self::create_forwarded_request(GetGameViewRequest {
+            user_name: String::from(user_name),
+        });
+        match self
+            .get_peer_client(self.get_game_owner_address(game_id))?
+            .get_game_view(request)
+            .await
+        {
+            Ok(response) => Ok(response.get_ref().game_id == game_id),
+            // Instances respond with `InvalidArgument` when the user isn't in one of their games.
+            Err(status) if status.code() == Code::InvalidArgument => Ok(false),
+            Err(status) => Err(status),
+        }
+    }
+
+    // Collects search results from every other instance. Instances
+    // that can't be reached are skipped rather than failing the search.
+    pub async fn search_peer_games(&self, request: &SearchGamesRequest) -> Vec<GameInfo> {
+        let mut games = Vec::new();
+        for (peer_address, peer_client) in &self.peer_clients {
+            match peer_client
+                .clone()
+                .search_games(Self::create_forwarded_request(request.clone()))
+                .await
+            {
+                Ok(mut response) => games.append(&mut response.get_mut().games),
+                Err(status) => warn!(
+                    peer_address = peer_address.as_str(),
+                    error = %status,
+                    "Failed to search games on game service instance."
+                ),
+            };
+        }
+        games
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::auth::create_internal_request;
+
+    #[tokio::test]
+    async fn single_instance_owns_every_game() {
+        let cluster_router = ClusterRouter::new_single_instance();
+        for i in 0..100 {
+            assert!(cluster_router.owns_game(&format!("game_{}", i)));
+        }
+        assert_eq!(
+            cluster_router.get_user_game_id("users/1234").await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn splits_games_between_instances() {
+        let cluster_router = ClusterRouter::new(
+            String::from("http://127.0.0.1:50052"),
+            vec![
+                String::from("http://127.0.0.1:50052"),
+                String::from("http://127.0.0.1:50053"),
+            ],
+            InternalServiceSecretInterceptor::new("secret"),
+        )
+        .unwrap();
+        let owned_game_count = (0..1000)
+            .filter(|i| cluster_router.owns_game(&format!("game_{}", i)))
+            .count();
+        assert!(owned_game_count > 0);
+        assert!(owned_game_count < 1000);
+        for i in 0..1000 {
+            let game_id = format!("game_{}", i);
+            if !cluster_router.owns_game(&game_id) {
+                assert_eq!(
+                    cluster_router.get_game_owner_address(&game_id),
+                    "http://127.0.0.1:50053"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn detects_forwarded_requests() {
+        assert!(!ClusterRouter::is_forwarded_request(&Request::new(())));
+
+        // Forwarded metadata is ignored unless the internal service secret was verified.
+        let mut forwarded_request = ClusterRouter::create_forwarded_request(());
+        assert!(!ClusterRouter::is_forwarded_request(&forwarded_request));
+        let mut internal_request = create_internal_request(());
+        *internal_request.metadata_mut() = std::mem::take(forwarded_request.metadata_mut());
+        assert!(ClusterRouter::is_forwarded_request(&internal_request));
+        assert!(!ClusterRouter::is_forwarded_request(
+            &create_internal_request(())
+        ));
+        assert_eq!(
+            ClusterRouter::check_request_is_forwarded(&Request::new(()))
+                .unwrap_err()
+                .code(),
+            Code::PermissionDenied
+        );
+    }
+
+    #[tokio::test]
+    async fn user_directory_only_replaces_expected_entries() {
+        let cluster_router = ClusterRouter::new_single_instance();
+        assert_eq!(
+            cluster_router
+                .update_user_directory_entry("users/1234", "", "game_1")
+                .await
+                .unwrap(),
+            "game_1"
+        );
+        assert_eq!(
+            cluster_router
+                .get_user_game_id("users/1234")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("game_1")
+        );
+
+        // A different game can't take over the entry unless it's expected.
+        assert_eq!(
+            cluster_router
+                .update_user_directory_entry("users/1234", "", "game_2")
+                .await
+                .unwrap(),
+            "game_1"
+        );
+        assert_eq!(
+            cluster_router
+                .update_user_directory_entry("users/1234", "game_1", "game_2")
+                .await
+                .unwrap(),
+            "game_2"
+        );
+
+        // Clearing is ignored once the entry points at another game.
+        assert_eq!(
+            cluster_router
+                .update_user_directory_entry("users/1234", "game_1", "")
+                .await
+                .unwrap(),
+            "game_2"
+        );
+        assert_eq!(
+            cluster_router
+                .update_user_directory_entry("users/1234", "game_2", "")
+                .await
+                .unwrap(),
+            ""
+        );
+        assert_eq!(
+            cluster_router.get_user_game_id("users/1234").await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn user_directory_is_split_between_instances() {
+        let cluster_router = ClusterRouter::new(
+            String::from("http://127.0.0.1:50052"),
+            vec![
+                String::from("http://127.0.0.1:50052"),
+                String::from("http://127.0.0.1:50053"),
+            ],
+            InternalServiceSecretInterceptor::new("secret"),
+        )
+        .unwrap();
+        let local_entry_count = (0..1000)
+            .filter(|i| {
+                cluster_router.get_directory_owner_address(&format!("users/{}", i))
+                    == "http://127.0.0.1:50052"
+            })
+            .count();
+        assert!(local_entry_count > 0);
+        assert!(local_entry_count < 1000);
+    }
+}
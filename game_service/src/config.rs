@@ -0,0 +1,304 @@
+use super::game::DEFAULT_MAX_CHAT_MESSAGES_PER_GAME;
+use shared::config::{AuthConfig, ConfigError, ConfigLoader};
+use shared::rate_limit::RateLimit;
+use std::collections::HashMap;
+use std::time::Duration;
+
+// See `ConfigLoader` for how values are loaded. Every key below
+// can also be set with its uppercase environment variable.
+pub struct Config {
+    api_uri: String,
+    api_request_timeout_millis: i64,
+    api_max_retries: i64,
+    api_circuit_breaker_failure_threshold: i64,
+    api_circuit_breaker_open_seconds: i64,
+    amqp_uri: String,
+    auth_config: AuthConfig,
+    rate_limits: HashMap<String, RateLimit>,
+    port: u16,
+    metrics_port: u16,
+    instance_address_or: Option<String>,
+    cluster_instance_addresses: Vec<String>,
+    unused_game_ttl_minutes: i64,
+    max_chat_messages_per_game: i64,
+    custom_cardpack_cache_ttl_seconds: i64,
+    max_cached_custom_cardpacks: i64,
+    default_page_size: i64,
+    max_page_size: i64,
+}
+
+impl Config {
+    pub fn load() -> Result<Self, ConfigError> {
+        Self::from_loader(ConfigLoader::from_env()?)
+    }
+
+    fn from_loader(mut loader: ConfigLoader) -> Result<Self, ConfigError> {
+        let (default_page_size, max_page_size) = loader.get_page_size_limits();
+        let config = Self {
+            api_uri: loader.get_required_uri("api_uri", &["http://", "https://"]),
+            api_request_timeout_millis: loader.get_integer(
+                "api_request_timeout_millis",
+                5000,
+                1,
+                1000 * 60 * 10,
+            ),
+            api_max_retries: loader.get_integer("api_max_retries", 2, 0, 10),
+            api_circuit_breaker_failure_threshold: loader.get_integer(
+                "api_circuit_breaker_failure_threshold",
+                5,
+                1,
+                1000,
+            ),
+            api_circuit_breaker_open_seconds: loader.get_integer(
+                "api_circuit_breaker_open_seconds",
+                10,
+                1,
+                60 * 60,
+            ),
+            amqp_uri: loader.get_required_uri("amqp_uri", &["amqp://", "amqps://"]),
+            auth_config: loader.get_auth_config(),
+            rate_limits: loader.get_rate_limits(),
+            port: loader.get_port("port", 50052),
+            metrics_port: loader.get_port("metrics_port", 9090),
+            instance_address_or: loader.get_optional_string("instance_address"),
+            cluster_instance_addresses: loader.get_string_list("cluster_instance_addresses"),
+            unused_game_ttl_minutes: loader.get_integer(
+                "unused_game_ttl_minutes",
+                60 * 4,
+                1,
+                60 * 24 * 7,
+            ),
+            max_chat_messages_per_game: loader.get_integer(
+                "max_chat_messages_per_game",
+                DEFAULT_MAX_CHAT_MESSAGES_PER_GAME as i64,
+                1,
+                10000,
+            ),
+            custom_cardpack_cache_ttl_seconds: loader.get_integer(
+                "custom_cardpack_cache_ttl_seconds",
+                60 * 5,
+                0,
+                60 * 60 * 24,
+            ),
+            max_cached_custom_cardpacks: loader.get_integer(
+                "max_cached_custom_cardpacks",
+                1000,
+                0,
+                1000000,
+            ),
+            default_page_size,
+            max_page_size,
+        };
+
+        if config.port == config.metrics_port {
+            loader.add_error(String::from("`port` and `metrics_port` must be different."));
+        }
+        // Every instance must be started with the same list of addresses, so
+        // an instance that isn't in its own list would disagree with its peers
+        // about which instance owns each game.
+        match &config.instance_address_or {
+            Some(instance_address) => {
+                if !config.cluster_instance_addresses.contains(instance_address) {
+                    loader.add_error(String::from(
+                        "`cluster_instance_addresses` must include `instance_address`.",
+                    ));
+                }
+            }
+            None => {
+                if !config.cluster_instance_addresses.is_empty() {
+                    loader.add_error(String::from(
+                        "`instance_address` must be set when `cluster_instance_addresses` is set.",
+                    ));
+                }
+            }
+        };
+
+        loader.finish()?;
+        Ok(config)
+    }
+
+    pub fn get_api_uri(&self) -> &str {
+        &self.api_uri
+    }
+
+    // How long each attempt at calling api service may take before it's abandoned.
+    pub fn get_api_request_timeout(&self) -> Duration {
+        Duration::from_millis(self.api_request_timeout_millis as u64)
+    }
+
+    // How many times failed reads from api service are retried. Writes are never retried.
+    pub fn get_api_max_retries(&self) -> u32 {
+        self.api_max_retries as u32
+    }
+
+    // Calls to api service fail fast for `get_api_circuit_breaker_open_duration`
+    // once this many calls in a row have failed.
+    pub fn get_api_circuit_breaker_failure_threshold(&self) -> u32 {
+        self.api_circuit_breaker_failure_threshold as u32
+    }
+
+    pub fn get_api_circuit_breaker_open_duration(&self) -> Duration {
+        Duration::from_secs(self.api_circuit_breaker_open_seconds as u64)
+    }
+
+    pub fn get_amqp_uri(&self) -> &str {
+        &self.amqp_uri
+    }
+
+    pub fn get_auth_config(&self) -> &AuthConfig {
+        &self.auth_config
+    }
+
+    // Keyed by method, such as `UserService/UserSearch`.
+    pub fn get_rate_limits(&self) -> &HashMap<String, RateLimit> {
+        &self.rate_limits
+    }
+
+    pub fn get_port(&self) -> u16 {
+        self.port
+    }
+
+    // Port for the HTTP server that exposes Prometheus metrics.
+    pub fn get_metrics_port(&self) -> u16 {
+        self.metrics_port
+    }
+
+    // The address that other game service instances use to reach this one.
+    // This is only set when running as part of a multi-instance cluster.
+    pub fn get_instance_address(&self) -> Option<&str> {
+        self.instance_address_or.as_deref()
+    }
+
+    // Addresses of every instance in the cluster, including this instance.
+    pub fn get_cluster_instance_addresses(&self) -> &[String] {
+        &self.cluster_instance_addresses
+    }
+
+    // Games that haven't had any activity for this long are removed.
+    pub fn get_unused_game_ttl(&self) -> Duration {
+        Duration::from_secs(self.unused_game_ttl_minutes as u64 * 60)
+    }
+
+    // Older messages are dropped once a game's chat reaches this size.
+    pub fn get_max_chat_messages_per_game(&self) -> usize {
+        self.max_chat_messages_per_game as usize
+    }
+
+    // Custom cardpacks can be edited at any time, so their cards are
+    // only cached for this long. Zero disables custom cardpack caching.
+    pub fn get_custom_cardpack_cache_ttl(&self) -> Duration {
+        Duration::from_secs(self.custom_cardpack_cache_ttl_seconds as u64)
+    }
+
+    pub fn get_max_cached_custom_cardpacks(&self) -> usize {
+        self.max_cached_custom_cardpacks as usize
+    }
+
+    // Returns the default and maximum page sizes for paginated list requests.
+    pub fn get_page_size_limits(&self) -> (i64, i64) {
+        (self.default_page_size, self.max_page_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn load_config(
+        file_contents_or: Option<&str>,
+        env_values: &[(&str, &str)],
+    ) -> Result<Config, ConfigError> {
+        let env_values: HashMap<String, String> = env_values
+            .iter()
+            .map(|(key, value)| (String::from(*key), String::from(*value)))
+            .collect();
+        Config::from_loader(ConfigLoader::new(file_contents_or, env_values)?)
+    }
+
+    #[test]
+    fn load_with_defaults() {
+        let config = load_config(
+            None,
+            &[
+                ("API_URI", "http://localhost:50052"),
+                ("AMQP_URI", "amqp://localhost:5672"),
+                ("AUTH_TEST_MODE", "true"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(config.get_port(), 50052);
+        assert_eq!(config.get_metrics_port(), 9090);
+        assert_eq!(config.get_api_request_timeout(), Duration::from_secs(5));
+        assert_eq!(config.get_api_max_retries(), 2);
+        assert_eq!(config.get_api_circuit_breaker_failure_threshold(), 5);
+        assert_eq!(
+            config.get_api_circuit_breaker_open_duration(),
+            Duration::from_secs(10)
+        );
+        assert_eq!(config.get_instance_address(), None);
+        assert!(config.get_cluster_instance_addresses().is_empty());
+        assert_eq!(
+            config.get_unused_game_ttl(),
+            Duration::from_secs(60 * 60 * 4)
+        );
+        assert_eq!(config.get_max_chat_messages_per_game(), 100);
+        assert_eq!(
+            config.get_custom_cardpack_cache_ttl(),
+            Duration::from_secs(60 * 5)
+        );
+        assert_eq!(config.get_max_cached_custom_cardpacks(), 1000);
+        assert_eq!(config.get_page_size_limits(), (50, 1000));
+        assert!(config
+            .get_rate_limits()
+            .contains_key("GameService/CreateChatMessage"));
+    }
+
+    #[test]
+    fn load_cluster_from_config_file() {
+        let config = load_config(
+            Some(
+                "api_uri = \"http://api:50052\"\n\
+                 amqp_uri = \"amqp://rabbitmq:5672\"\n\
+                 session_token_secret = \"0123456789abcdef0123456789abcdef\"\n\
+                 internal_service_secret = \"fedcba9876543210fedcba9876543210\"\n\
+                 instance_address = \"http://game-0:50052\"\n\
+                 cluster_instance_addresses = [\"http://game-0:50052\", \"http://game-1:50052\"]",
+            ),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(config.get_instance_address(), Some("http://game-0:50052"));
+        assert_eq!(
+            config.get_cluster_instance_addresses(),
+            &["http://game-0:50052", "http://game-1:50052"]
+        );
+    }
+
+    #[test]
+    fn reports_every_invalid_value() {
+        let err = load_config(
+            None,
+            &[
+                ("PORT", "9090"),
+                ("INSTANCE_ADDRESS", "http://game-2:50052"),
+                ("CLUSTER_INSTANCE_ADDRESSES", "http://game-0:50052"),
+                ("MAX_CHAT_MESSAGES_PER_GAME", "0"),
+            ],
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.get_messages(),
+            &[
+                "`api_uri` must be set, either in the config file or with the `API_URI` environment variable.",
+                "`amqp_uri` must be set, either in the config file or with the `AMQP_URI` environment variable.",
+                "`session_token_secret` must be set unless `auth_test_mode` is enabled.",
+                "`internal_service_secret` must be set unless `auth_test_mode` is enabled.",
+                "`max_chat_messages_per_game` must be an integer from 1 to 10000.",
+                "`port` and `metrics_port` must be different.",
+                "`cluster_instance_addresses` must include `instance_address`."
+            ]
+        );
+    }
+}
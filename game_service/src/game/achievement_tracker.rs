@@ -0,0 +1,134 @@
+use shared::achievements::{AchievementRule, GameEvent, ACHIEVEMENT_DEFINITIONS};
+use shared::proto::crusty_cards_api::AchievementProgress;
+use std::collections::HashMap;
+
+// Evaluates the achievement rules against the events of a single game. Progress
+// is buffered until it's taken and reported to the API, which keeps each user's
+// running totals and decides when an achievement is unlocked.
+pub struct AchievementTracker {
+    // Keyed by user name and then achievement id.
+    streaks: HashMap<(String, &'static str), i64>,
+    unreported_progress: HashMap<(String, &'static str), i64>,
+}
+
+impl AchievementTracker {
+    pub fn new() -> AchievementTracker {
+        AchievementTracker {
+            streaks: HashMap::new(),
+            unreported_progress: HashMap::new(),
+        }
+    }
+
+    pub fn record_event(&mut self, user_name: &str, event: GameEvent) {
+        for definition in ACHIEVEMENT_DEFINITIONS {
+            let key = (String::from(user_name), definition.id);
+            match definition.rule {
+                AchievementRule::Count {
+                    event: rule_event, ..
+                } => {
+                    if event == rule_event {
+                        *self.unreported_progress.entry(key).or_insert(0) += 1;
+                    }
+                }
+                AchievementRule::Streak {
+                    event: rule_event,
+                    reset_event,
+                    count,
+                } => {
+                    if event == reset_event {
+                        self.streaks.remove(&key);
+                    } else if event == rule_event {
+                        let streak = self.streaks.entry(key.clone()).or_insert(0);
+                        *streak += 1;
+                        if *streak >= count {
+                            *streak = 0;
+                            *self.unreported_progress.entry(key).or_insert(0) += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Streaks never carry over from one game to the next.
+    pub fn reset_streaks(&mut self) {
+        self.streaks.clear();
+    }
+
+    pub fn take_unreported_progress(&mut self) -> Vec<AchievementProgress> {
+        let mut progress_list: Vec<AchievementProgress> = self
+            .unreported_progress
+            .drain()
+            .map(
+                |((user_name, achievement_id), progress)| AchievementProgress {
+                    user_name,
+                    achievement_id: String::from(achievement_id),
+                    progress,
+                },
+            )
+            .collect();
+        progress_list.sort_by(|a, b| {
+            a.user_name
+                .cmp(&b.user_name)
+                .then_with(|| a.achievement_id.cmp(&b.achievement_id))
+        });
+        progress_list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_progress(tracker: &mut AchievementTracker, achievement_id: &str) -> Vec<(String, i64)> {
+        tracker
+            .take_unreported_progress()
+            .into_iter()
+            .filter(|progress| progress.achievement_id == achievement_id)
+            .map(|progress| (progress.user_name, progress.progress))
+            .collect()
+    }
+
+    #[test]
+    fn counts_events() {
+        let mut tracker = AchievementTracker::new();
+        tracker.record_event("users/1", GameEvent::GameJudged);
+        tracker.record_event("users/1", GameEvent::GameJudged);
+        tracker.record_event("users/2", GameEvent::GameJudged);
+        assert_eq!(
+            get_progress(&mut tracker, "seasoned-judge"),
+            vec![(String::from("users/1"), 2), (String::from("users/2"), 1)]
+        );
+        assert!(tracker.take_unreported_progress().is_empty());
+    }
+
+    #[test]
+    fn completes_streaks() {
+        let mut tracker = AchievementTracker::new();
+        for _ in 0..4 {
+            tracker.record_event("users/1", GameEvent::RoundWon);
+        }
+        tracker.record_event("users/1", GameEvent::RoundLost);
+        tracker.record_event("users/1", GameEvent::RoundWon);
+        assert!(get_progress(&mut tracker, "on-a-roll").is_empty());
+
+        for _ in 0..4 {
+            tracker.record_event("users/1", GameEvent::RoundWon);
+        }
+        assert_eq!(
+            get_progress(&mut tracker, "on-a-roll"),
+            vec![(String::from("users/1"), 1)]
+        );
+    }
+
+    #[test]
+    fn resets_streaks() {
+        let mut tracker = AchievementTracker::new();
+        for _ in 0..4 {
+            tracker.record_event("users/1", GameEvent::RoundWon);
+        }
+        tracker.reset_streaks();
+        tracker.record_event("users/1", GameEvent::RoundWon);
+        assert!(get_progress(&mut tracker, "on-a-roll").is_empty());
+    }
+}
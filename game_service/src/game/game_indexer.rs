@@ -20,6 +20,10 @@ impl GameIndexer {
         &self.games_by_insert_time[..]
     }
 
+    pub fn get_games_by_insert_time_mut(&mut self) -> &mut [Game] {
+        &mut self.games_by_insert_time[..]
+    }
+
     pub fn get_game_by_game_id(&mut self, game_id: &str) -> Option<&mut Game> {
         self.games_by_insert_time
             .iter_mut()
@@ -70,6 +74,7 @@ impl GameIndexer {
 
 #[cfg(test)]
 mod tests {
+    use super::super::DEFAULT_MAX_CHAT_MESSAGES_PER_GAME;
     use super::*;
     use rand::seq::SliceRandom;
     use shared::proto_validation::ValidatedGameConfig;
@@ -93,6 +98,7 @@ mod tests {
                     generate_test_custom_white_cards(100),
                     generate_test_default_black_cards(1),
                     generate_test_default_white_cards(100),
+                    DEFAULT_MAX_CHAT_MESSAGES_PER_GAME,
                 )
                 .unwrap(),
             );
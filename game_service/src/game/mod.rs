@@ -1,3 +1,4 @@
+mod achievement_tracker;
 mod black_card_deck;
 mod chat_message_handler;
 pub mod game_indexer;
@@ -7,6 +8,7 @@ mod text_query_handler;
 mod white_card_deck;
 mod white_card_gameplay_manager;
 
+use achievement_tracker::AchievementTracker;
 use black_card_deck::BlackCardDeck;
 use chat_message_handler::ChatMessageHandler;
 use player_id::PlayerId;
@@ -14,15 +16,17 @@ use player_manager::PlayerManager;
 use rand::prelude::SliceRandom;
 use rand::SeedableRng;
 use sha2::{Digest, Sha256};
+use shared::achievements::GameEvent;
 use shared::constants::*;
 use shared::proto::crusty_cards_api::{
     game_config::EndCondition, game_view::Stage, playable_white_card::Card, player::Identifier,
-    ArtificialUser, ChatMessage, CustomBlackCard, CustomWhiteCard, DefaultBlackCard,
-    DefaultWhiteCard, GameInfo, GameView, PastRound, PlayableWhiteCard, Player, User,
-    WhiteCardsPlayed,
+    AchievementProgress, ArtificialUser, ChatMessage, CustomBlackCard, CustomWhiteCard,
+    DefaultBlackCard, DefaultWhiteCard, GameInfo, GameView, PastRound, PlayableWhiteCard, Player,
+    User, UserStatsIncrement, WhiteCardStats, WhiteCardsPlayed,
 };
 use shared::proto_validation::ValidatedGameConfig;
 use shared::time::{get_current_timestamp_proto, system_time_to_timestamp_proto};
+use std::collections::{HashMap, HashSet};
 use std::time::SystemTime;
 use text_query_handler::TextQueryHandler;
 use tonic::Status;
@@ -30,7 +34,7 @@ use uuid::Uuid;
 use white_card_deck::WhiteCardDeck;
 use white_card_gameplay_manager::WhiteCardGameplayManager;
 
-const MAX_CHAT_MESSAGES_PER_GAME: usize = 100;
+pub const DEFAULT_MAX_CHAT_MESSAGES_PER_GAME: usize = 100;
 
 // TODO - Move this helper function to a more appropriate place.
 fn get_text_from_playable_white_card(card: &PlayableWhiteCard) -> &str {
@@ -44,6 +48,23 @@ fn get_text_from_playable_white_card(card: &PlayableWhiteCard) -> &str {
     }
 }
 
+// Returns None for blank white cards, since they don't belong to a cardpack.
+fn get_name_from_playable_white_card(card: &PlayableWhiteCard) -> Option<&str> {
+    match &card.card {
+        Some(Card::CustomWhiteCard(custom_white_card)) => Some(&custom_white_card.name),
+        Some(Card::DefaultWhiteCard(default_white_card)) => Some(&default_white_card.name),
+        _ => None,
+    }
+}
+
+// Every card from a game's cardpacks, as fetched from the API.
+pub struct GameCards {
+    pub custom_black_cards: Vec<CustomBlackCard>,
+    pub custom_white_cards: Vec<CustomWhiteCard>,
+    pub default_black_cards: Vec<DefaultBlackCard>,
+    pub default_white_cards: Vec<DefaultWhiteCard>,
+}
+
 pub struct Game {
     game_id: String,
     config: ValidatedGameConfig,
@@ -58,6 +79,15 @@ pub struct Game {
     black_card_deck: BlackCardDeck,
     white_card_gameplay_manager: WhiteCardGameplayManager,
     white_card_text_query_handler: TextQueryHandler,
+    black_card_text_query_handler: TextQueryHandler,
+    rounds_judged_since_start: usize,
+    // Users who have judged at least one round since the game was started.
+    judges_since_start: HashSet<String>,
+    achievement_tracker: AchievementTracker,
+    // Stats that players have earned but that haven't been reported to the API yet.
+    unreported_user_stats: HashMap<String, UserStatsIncrement>,
+    // Play and win counts for each white card that haven't been reported to the API yet.
+    unreported_white_card_stats: HashMap<String, WhiteCardStats>,
 }
 
 impl Game {
@@ -68,16 +98,14 @@ impl Game {
         custom_white_cards: Vec<CustomWhiteCard>,
         default_black_cards: Vec<DefaultBlackCard>,
         default_white_cards: Vec<DefaultWhiteCard>,
+        max_chat_messages: usize,
     ) -> Result<Game, Status> {
         let time_now = SystemTime::now();
 
-        let white_card_text_query_handler = TextQueryHandler::new(
-            custom_white_cards
-                .iter()
-                .map(|card| card.text.clone())
-                .chain(default_white_cards.iter().map(|card| card.text.clone()))
-                .collect(),
-        );
+        let white_card_text_query_handler =
+            Game::create_white_card_text_query_handler(&custom_white_cards, &default_white_cards);
+        let black_card_text_query_handler =
+            Game::create_black_card_text_query_handler(&custom_black_cards, &default_black_cards);
 
         let white_card_deck = WhiteCardDeck::new(
             custom_white_cards,
@@ -92,7 +120,7 @@ impl Game {
             create_time: time_now,
             last_activity_time: time_now,
             stage: Stage::NotRunning,
-            chat_messages: ChatMessageHandler::new(MAX_CHAT_MESSAGES_PER_GAME),
+            chat_messages: ChatMessageHandler::new(max_chat_messages),
             past_rounds: Vec::new(),
             player_manager: PlayerManager::new(),
             banned_users: Vec::new(),
@@ -100,6 +128,12 @@ impl Game {
             black_card_deck: BlackCardDeck::new(custom_black_cards, default_black_cards)?,
             white_card_gameplay_manager: WhiteCardGameplayManager::new(white_card_deck, hand_size),
             white_card_text_query_handler,
+            black_card_text_query_handler,
+            rounds_judged_since_start: 0,
+            judges_since_start: HashSet::new(),
+            achievement_tracker: AchievementTracker::new(),
+            unreported_user_stats: HashMap::new(),
+            unreported_white_card_stats: HashMap::new(),
         };
 
         Ok(game)
@@ -113,6 +147,7 @@ impl Game {
         default_black_cards: Vec<DefaultBlackCard>,
         default_white_cards: Vec<DefaultWhiteCard>,
         owner: User,
+        max_chat_messages: usize,
     ) -> Result<Game, Status> {
         let mut game = Game::new(
             game_id,
@@ -121,6 +156,7 @@ impl Game {
             custom_white_cards,
             default_black_cards,
             default_white_cards,
+            max_chat_messages,
         )?;
 
         match game.join(owner) {
@@ -131,6 +167,32 @@ impl Game {
         }
     }
 
+    fn create_white_card_text_query_handler(
+        custom_white_cards: &[CustomWhiteCard],
+        default_white_cards: &[DefaultWhiteCard],
+    ) -> TextQueryHandler {
+        TextQueryHandler::new(
+            custom_white_cards
+                .iter()
+                .map(|card| card.text.clone())
+                .chain(default_white_cards.iter().map(|card| card.text.clone()))
+                .collect(),
+        )
+    }
+
+    fn create_black_card_text_query_handler(
+        custom_black_cards: &[CustomBlackCard],
+        default_black_cards: &[DefaultBlackCard],
+    ) -> TextQueryHandler {
+        TextQueryHandler::new(
+            custom_black_cards
+                .iter()
+                .map(|card| card.text.clone())
+                .chain(default_black_cards.iter().map(|card| card.text.clone()))
+                .collect(),
+        )
+    }
+
     fn update_last_activity_time(&mut self) {
         self.last_activity_time = SystemTime::now();
     }
@@ -188,10 +250,100 @@ impl Game {
     fn increment_score_and_maybe_stop_game(&mut self, player_id: &PlayerId) {
         self.player_manager.increment_player_score(player_id);
         if self.player_has_won(player_id) {
+            if let PlayerId::RealUser(user_name) = player_id {
+                self.get_unreported_user_stats(user_name).games_won += 1;
+            }
             self.force_stop();
         }
     }
 
+    fn get_unreported_user_stats(&mut self, user_name: &str) -> &mut UserStatsIncrement {
+        self.unreported_user_stats
+            .entry(String::from(user_name))
+            .or_insert_with(|| UserStatsIncrement {
+                user_name: String::from(user_name),
+                games_played: 0,
+                games_won: 0,
+                rounds_won: 0,
+                rounds_judged: 0,
+            })
+    }
+
+    fn record_white_card_plays(&mut self, winner_id_or: Option<&PlayerId>) {
+        for (player_id, cards) in self.white_card_gameplay_manager.get_played_cards() {
+            let is_winner = winner_id_or == Some(player_id);
+            for card in cards {
+                if let Some(card_name) = get_name_from_playable_white_card(card) {
+                    let white_card_stats = self
+                        .unreported_white_card_stats
+                        .entry(String::from(card_name))
+                        .or_insert_with(|| WhiteCardStats {
+                            card_name: String::from(card_name),
+                            play_count: 0,
+                            win_count: 0,
+                        });
+                    white_card_stats.play_count += 1;
+                    if is_winner {
+                        white_card_stats.win_count += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    fn record_round_achievement_events(&mut self, winner_id_or: Option<&PlayerId>) {
+        let mut events = Vec::new();
+        for (player_id, cards) in self.white_card_gameplay_manager.get_played_cards() {
+            if let PlayerId::RealUser(user_name) = player_id {
+                if winner_id_or == Some(player_id) {
+                    events.push((user_name.clone(), GameEvent::RoundWon));
+                    if cards
+                        .iter()
+                        .any(|card| matches!(card.card, Some(Card::BlankWhiteCard(_))))
+                    {
+                        events.push((user_name.clone(), GameEvent::RoundWonWithBlankCard));
+                    }
+                } else {
+                    events.push((user_name.clone(), GameEvent::RoundLost));
+                }
+            }
+        }
+        if self.is_full() {
+            for user_name in self.get_user_names_for_all_real_players() {
+                events.push((user_name, GameEvent::RoundPlayedInFullGame));
+            }
+        }
+        for (user_name, event) in events {
+            self.achievement_tracker.record_event(&user_name, event);
+        }
+    }
+
+    pub fn take_unreported_achievement_progress(&mut self) -> Vec<AchievementProgress> {
+        self.achievement_tracker.take_unreported_progress()
+    }
+
+    pub fn take_unreported_white_card_stats(&mut self) -> Vec<WhiteCardStats> {
+        let mut white_card_stats: Vec<WhiteCardStats> = self
+            .unreported_white_card_stats
+            .drain()
+            .map(|(_, white_card_stats)| white_card_stats)
+            .collect();
+        white_card_stats.sort_by(|a, b| a.card_name.cmp(&b.card_name));
+        white_card_stats
+    }
+
+    // Returns the stats that players have earned since the last call, so
+    // that the caller can report them once it has released the game.
+    pub fn take_unreported_user_stats(&mut self) -> Vec<UserStatsIncrement> {
+        let mut user_stats: Vec<UserStatsIncrement> = self
+            .unreported_user_stats
+            .drain()
+            .map(|(_, user_stats)| user_stats)
+            .collect();
+        user_stats.sort_by(|a, b| a.user_name.cmp(&b.user_name));
+        user_stats
+    }
+
     fn player_has_won(&mut self, player_id: &PlayerId) -> bool {
         match self.config.get_end_condition() {
             EndCondition::MaxScore(max_score) => {
@@ -220,6 +372,9 @@ impl Game {
         self.past_rounds.clear();
         self.black_card_deck.shuffle_and_reset();
         self.player_manager.reset_player_scores();
+        self.rounds_judged_since_start = 0;
+        self.judges_since_start.clear();
+        self.achievement_tracker.reset_streaks();
         self.white_card_gameplay_manager
             .discard_played_cards_and_draw_to_full();
         self.white_card_gameplay_manager
@@ -242,11 +397,115 @@ impl Game {
         Ok(())
     }
 
+    pub fn get_config(&self) -> &ValidatedGameConfig {
+        &self.config
+    }
+
+    pub fn check_can_update_config(&self, user_name: &str) -> Result<(), Status> {
+        if !self.player_manager.is_owner(user_name) {
+            return Err(Status::invalid_argument(
+                "Must be game owner to update game config.",
+            ));
+        }
+        if self.is_running() {
+            return Err(Status::invalid_argument(
+                "Game config can only be updated while the game is not running.",
+            ));
+        }
+        Ok(())
+    }
+
+    // Whether switching to the given config requires refetching every card,
+    // since the set of cards in the game depends on these config fields.
+    pub fn config_change_requires_new_cards(&self, config: &ValidatedGameConfig) -> bool {
+        !Game::configs_have_same_cards(&self.config, config)
+    }
+
+    pub fn configs_have_same_cards(a: &ValidatedGameConfig, b: &ValidatedGameConfig) -> bool {
+        a.get_custom_cardpack_names() == b.get_custom_cardpack_names()
+            && a.get_default_cardpack_names() == b.get_default_cardpack_names()
+            && a.get_blank_white_card_config() == b.get_blank_white_card_config()
+    }
+
+    // Replaces the game config without removing any players. Decks are rebuilt
+    // from `new_cards_or` if provided, which is required whenever the config
+    // change affects which cards are in the game.
+    pub fn update_config(
+        &mut self,
+        user_name: &str,
+        config: ValidatedGameConfig,
+        new_cards_or: Option<GameCards>,
+    ) -> Result<(), Status> {
+        self.check_can_update_config(user_name)?;
+
+        let player_count = self.player_manager.get_real_players().len()
+            + self.player_manager.get_queued_real_players().len();
+        if config.get_max_players() < player_count {
+            return Err(Status::invalid_argument(format!(
+                "Game config property `max_players` cannot be less than the {} players already in the game.",
+                player_count
+            )));
+        }
+
+        match new_cards_or {
+            Some(new_cards) => {
+                let white_card_text_query_handler = Game::create_white_card_text_query_handler(
+                    &new_cards.custom_white_cards,
+                    &new_cards.default_white_cards,
+                );
+                let black_card_text_query_handler = Game::create_black_card_text_query_handler(
+                    &new_cards.custom_black_cards,
+                    &new_cards.default_black_cards,
+                );
+                self.black_card_deck = BlackCardDeck::new(
+                    new_cards.custom_black_cards,
+                    new_cards.default_black_cards,
+                )?;
+                self.white_card_gameplay_manager.replace_deck(
+                    WhiteCardDeck::new(
+                        new_cards.custom_white_cards,
+                        new_cards.default_white_cards,
+                        config.get_blank_white_card_config(),
+                    ),
+                    config.get_hand_size(),
+                );
+                self.white_card_text_query_handler = white_card_text_query_handler;
+                self.black_card_text_query_handler = black_card_text_query_handler;
+            }
+            None => {
+                // The caller decided that no new cards were needed based on
+                // an older config, so the config was changed concurrently.
+                if self.config_change_requires_new_cards(&config) {
+                    return Err(Status::aborted(
+                        "Game config was changed by another request. Please try again.",
+                    ));
+                }
+                self.white_card_gameplay_manager
+                    .set_hand_size(config.get_hand_size());
+            }
+        };
+
+        self.config = config;
+        self.update_last_activity_time();
+        Ok(())
+    }
+
     fn force_stop(&mut self) {
         if !self.is_running() {
             return;
         }
 
+        // Games that are stopped before a single round is judged don't count as played.
+        if self.rounds_judged_since_start > 0 {
+            for user_name in self.get_user_names_for_all_real_players() {
+                self.get_unreported_user_stats(&user_name).games_played += 1;
+            }
+        }
+        for user_name in self.judges_since_start.drain() {
+            self.achievement_tracker
+                .record_event(&user_name, GameEvent::GameJudged);
+        }
+
         // TODO - Finish implementing.
         self.white_card_gameplay_manager.discard_player_hands();
         self.add_queued_players_to_game();
@@ -570,16 +829,24 @@ impl Game {
             None => return Err(Status::invalid_argument("Invalid selection.")),
         };
 
-        let winner_or = &voted_cards.player;
-        if let Some(winner) = &winner_or {
-            if let Some(winner_id) = PlayerId::from_player_proto(winner) {
-                self.increment_score_and_maybe_stop_game(&winner_id);
+        let winner_or = voted_cards.player.take();
+        let winner_id_or = winner_or.as_ref().and_then(PlayerId::from_player_proto);
+
+        self.rounds_judged_since_start += 1;
+        self.get_unreported_user_stats(user_name).rounds_judged += 1;
+        self.judges_since_start.insert(String::from(user_name));
+        self.record_white_card_plays(winner_id_or.as_ref());
+        self.record_round_achievement_events(winner_id_or.as_ref());
+        if let Some(winner_id) = &winner_id_or {
+            if let PlayerId::RealUser(winner_user_name) = winner_id {
+                self.get_unreported_user_stats(winner_user_name).rounds_won += 1;
             }
+            self.increment_score_and_maybe_stop_game(winner_id);
         }
 
         self.stage = Stage::RoundEndPhase;
 
-        self.winner = winner_or.clone(); // TODO - I don't think we need to clone `winner` here. Let's find a way to move it from `winner_or` above instead.
+        self.winner = winner_or;
 
         self.update_last_activity_time();
         Ok(())
@@ -645,6 +912,16 @@ impl Game {
         Ok(())
     }
 
+    // Posts a message that isn't from any player, such as a server announcement.
+    pub fn post_system_message(&mut self, message_text: String) {
+        let message = ChatMessage {
+            user: None,
+            text: message_text,
+            create_time: Some(get_current_timestamp_proto()),
+        };
+        self.chat_messages.add_new_message(message);
+    }
+
     pub fn get_user_view(&self, user_name: &str) -> Result<GameView, Status> {
         Ok(GameView {
             game_id: String::from(&self.game_id),
@@ -711,6 +988,22 @@ impl Game {
         &self.game_id
     }
 
+    pub fn get_stage(&self) -> Stage {
+        self.stage
+    }
+
+    // Includes players who are queued to join at the start of the next round.
+    pub fn get_real_player_count(&self) -> usize {
+        self.player_manager.get_real_players().len()
+            + self.player_manager.get_queued_real_players().len()
+    }
+
+    // Includes players who are queued to join at the start of the next round.
+    pub fn get_artificial_player_count(&self) -> usize {
+        self.player_manager.get_artificial_players().len()
+            + self.player_manager.get_queued_artificial_players().len()
+    }
+
     fn contains_player(&self, player_id: &PlayerId) -> bool {
         self.player_manager.get_player(player_id).is_some()
     }
@@ -734,7 +1027,7 @@ impl Game {
                 >= MINIMUM_PLAYERS_REQUIRED_TO_PLAY
     }
 
-    fn user_is_banned(&self, user_name: &str) -> bool {
+    pub fn user_is_banned(&self, user_name: &str) -> bool {
         self.banned_users.iter().any(|user| user.name == user_name)
     }
 
@@ -771,14 +1064,18 @@ impl Game {
         skip: usize,
         filter: &str,
     ) -> (Vec<String>, bool, usize) {
-        let (texts, has_next_page) = self
-            .white_card_text_query_handler
-            .query(filter, page_size, skip);
-        (
-            texts,
-            has_next_page,
-            self.white_card_text_query_handler.total_size(),
-        )
+        self.white_card_text_query_handler
+            .query(filter, page_size, skip)
+    }
+
+    pub fn search_black_card_texts(
+        &self,
+        page_size: usize,
+        skip: usize,
+        filter: &str,
+    ) -> (Vec<String>, bool, usize) {
+        self.black_card_text_query_handler
+            .query(filter, page_size, skip)
     }
 }
 
@@ -811,6 +1108,7 @@ mod tests {
             generate_test_custom_white_cards(500),
             generate_test_default_black_cards(50),
             generate_test_default_white_cards(500),
+            DEFAULT_MAX_CHAT_MESSAGES_PER_GAME,
         )?;
 
         for i in 0..player_count {
@@ -933,6 +1231,218 @@ mod tests {
         assert_eq!(game.is_running(), false);
     }
 
+    #[test]
+    fn tracks_user_stats() {
+        let mut game: Game =
+            get_basic_endless_game_with_players(MINIMUM_PLAYERS_REQUIRED_TO_PLAY).unwrap();
+
+        // Games stopped before any rounds are judged don't count as played.
+        assert_eq!(game.start("users/0").is_ok(), true);
+        assert_eq!(game.stop("users/0").is_ok(), true);
+        assert!(game.take_unreported_user_stats().is_empty());
+
+        assert_eq!(game.start("users/0").is_ok(), true);
+        play_for_all_real_players(&mut game);
+        let judge_name = String::from(&game.player_manager.get_judge().unwrap().name);
+        let winner_name = match game.get_pseudorandom_ordered_white_cards_played_list()[0]
+            .player
+            .as_ref()
+            .unwrap()
+            .identifier
+            .as_ref()
+            .unwrap()
+        {
+            Identifier::User(user) => user.name.clone(),
+            _ => panic!("Winner should be a real user."),
+        };
+        assert_eq!(game.vote_card(&judge_name, 1).is_ok(), true);
+        assert_eq!(game.stop("users/0").is_ok(), true);
+
+        let user_stats = game.take_unreported_user_stats();
+        assert_eq!(user_stats.len(), MINIMUM_PLAYERS_REQUIRED_TO_PLAY);
+        for user_stats in user_stats {
+            assert_eq!(user_stats.games_played, 1);
+            assert_eq!(user_stats.games_won, 0);
+            assert_eq!(
+                user_stats.rounds_won,
+                (user_stats.user_name == winner_name) as i64
+            );
+            assert_eq!(
+                user_stats.rounds_judged,
+                (user_stats.user_name == judge_name) as i64
+            );
+        }
+        assert!(game.take_unreported_user_stats().is_empty());
+    }
+
+    #[test]
+    fn tracks_white_card_stats() {
+        let mut game: Game =
+            get_basic_endless_game_with_players(MINIMUM_PLAYERS_REQUIRED_TO_PLAY).unwrap();
+        assert_eq!(game.start("users/0").is_ok(), true);
+        play_for_all_real_players(&mut game);
+        let answer_fields = get_answer_fields_from_black_card_in_round(
+            game.black_card_deck.get_current_black_card(),
+        );
+        let judge_name = String::from(&game.player_manager.get_judge().unwrap().name);
+        assert_eq!(game.vote_card(&judge_name, 1).is_ok(), true);
+
+        // Every player except the judge played cards, and only the winner's cards won.
+        let white_card_stats = game.take_unreported_white_card_stats();
+        assert_eq!(
+            white_card_stats.len(),
+            (MINIMUM_PLAYERS_REQUIRED_TO_PLAY - 1) * answer_fields
+        );
+        assert_eq!(
+            white_card_stats
+                .iter()
+                .filter(|white_card_stats| white_card_stats.win_count == 1)
+                .count(),
+            answer_fields
+        );
+        for white_card_stats in &white_card_stats {
+            assert_eq!(white_card_stats.play_count, 1);
+        }
+        assert!(game.take_unreported_white_card_stats().is_empty());
+    }
+
+    #[test]
+    fn tracks_achievement_progress() {
+        let mut game: Game =
+            get_basic_endless_game_with_players(MINIMUM_PLAYERS_REQUIRED_TO_PLAY).unwrap();
+        assert_eq!(game.start("users/0").is_ok(), true);
+        play_for_all_real_players(&mut game);
+        let judge_name = String::from(&game.player_manager.get_judge().unwrap().name);
+        assert_eq!(game.vote_card(&judge_name, 1).is_ok(), true);
+        assert_eq!(game.stop("users/0").is_ok(), true);
+
+        // The test game is full, so every player makes progress towards `full-house`,
+        // and the judge also makes progress towards `seasoned-judge` once the game ends.
+        let achievement_progress = game.take_unreported_achievement_progress();
+        let get_user_names = |achievement_id: &str| -> Vec<String> {
+            achievement_progress
+                .iter()
+                .filter(|progress| progress.achievement_id == achievement_id)
+                .map(|progress| progress.user_name.clone())
+                .collect()
+        };
+        let mut all_user_names = game.get_user_names_for_all_real_players();
+        all_user_names.sort();
+        assert_eq!(get_user_names("full-house"), all_user_names);
+        assert_eq!(get_user_names("seasoned-judge"), vec![judge_name]);
+        assert!(game.take_unreported_achievement_progress().is_empty());
+    }
+
+    #[test]
+    fn update_config() {
+        let mut game: Game = get_basic_game_with_players(MINIMUM_PLAYERS_REQUIRED_TO_PLAY).unwrap();
+        let mut update = get_valid_test_game_config();
+        update.display_name = String::from("Renamed Game");
+        update.hand_size = 5;
+        let update_mask_paths = vec![String::from("display_name"), String::from("hand_size")];
+
+        let config = game
+            .get_config()
+            .with_updates(&update, &update_mask_paths)
+            .unwrap();
+        assert_eq!(game.config_change_requires_new_cards(&config), false);
+        assert_eq!(
+            format!("{}", game.update_config("users/1", config, None).unwrap_err()),
+            "status: InvalidArgument, message: \"Must be game owner to update game config.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+
+        let config = game
+            .get_config()
+            .with_updates(&update, &update_mask_paths)
+            .unwrap();
+        assert_eq!(game.update_config("users/0", config, None).is_ok(), true);
+        assert_eq!(
+            game.get_game_info().config.unwrap().display_name,
+            "Renamed Game"
+        );
+        assert_eq!(
+            game.get_game_info().player_count,
+            MINIMUM_PLAYERS_REQUIRED_TO_PLAY as i32
+        );
+
+        // Changing cardpacks requires new cards.
+        update.custom_cardpack_names = vec![String::from("other_custom_cardpack_name")];
+        let config = game
+            .get_config()
+            .with_updates(&update, &[String::from("custom_cardpack_names")])
+            .unwrap();
+        assert_eq!(game.config_change_requires_new_cards(&config), true);
+        assert_eq!(
+            Game::configs_have_same_cards(game.get_config(), &config),
+            false
+        );
+        assert_eq!(
+            format!("{}", game.update_config("users/0", config, None).unwrap_err()),
+            "status: Aborted, message: \"Game config was changed by another request. Please try again.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+        let config = game
+            .get_config()
+            .with_updates(&update, &[String::from("custom_cardpack_names")])
+            .unwrap();
+        let new_cards = GameCards {
+            custom_black_cards: generate_test_custom_black_cards(10),
+            custom_white_cards: generate_test_custom_white_cards(100),
+            default_black_cards: Vec::new(),
+            default_white_cards: Vec::new(),
+        };
+        assert_eq!(
+            game.update_config("users/0", config, Some(new_cards))
+                .is_ok(),
+            true
+        );
+        assert_eq!(game.search_white_card_texts(10, 0, "").2, 100);
+        assert_eq!(game.search_black_card_texts(10, 0, "").2, 10);
+        assert_eq!(
+            game.get_game_info().player_count,
+            MINIMUM_PLAYERS_REQUIRED_TO_PLAY as i32
+        );
+
+        // Can't drop `max_players` below the current player count.
+        update.max_players = MINIMUM_PLAYERS_REQUIRED_TO_PLAY as i32 - 1;
+        let config = game
+            .get_config()
+            .with_updates(&update, &[String::from("max_players")])
+            .unwrap();
+        assert_eq!(
+            format!("{}", game.update_config("users/0", config, None).unwrap_err()),
+            format!("status: InvalidArgument, message: \"Game config property `max_players` cannot be less than the {} players already in the game.\", details: [], metadata: MetadataMap {{ headers: {{}} }}", MINIMUM_PLAYERS_REQUIRED_TO_PLAY)
+        );
+
+        // Can't update config while the game is running.
+        assert_eq!(game.start("users/0").is_ok(), true);
+        assert_eq!(
+            format!("{}", game.check_can_update_config("users/0").unwrap_err()),
+            "status: InvalidArgument, message: \"Game config can only be updated while the game is not running.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+    }
+
+    #[test]
+    fn search_card_texts() {
+        let game: Game = get_basic_game_with_players(1).unwrap();
+
+        let (texts, has_next_page, total_size) = game.search_white_card_texts(10, 0, "");
+        assert_eq!(texts.len(), 10);
+        assert_eq!(has_next_page, true);
+        assert_eq!(total_size, 1000);
+
+        let (texts, has_next_page, total_size) = game.search_white_card_texts(10, 0, "CUSTOM");
+        assert_eq!(texts.len(), 10);
+        assert_eq!(has_next_page, true);
+        assert_eq!(total_size, 500);
+
+        // Matches "default_card_4" exactly and "default_card_40" through "default_card_49" by prefix.
+        let (texts, has_next_page, total_size) = game.search_black_card_texts(5, 0, "default 4");
+        assert_eq!(texts.first().unwrap(), "default_card_4");
+        assert_eq!(texts.len(), 5);
+        assert_eq!(has_next_page, true);
+        assert_eq!(total_size, 11);
+    }
+
     #[test]
     fn create_basic_game_and_add_players() {
         let game: Game = get_basic_game_with_players(MIN_PLAYER_LIMIT as usize).unwrap();
@@ -1004,6 +1514,17 @@ mod tests {
         assert_eq!(round_nonces.insert(nonce), true);
     }
 
+    #[test]
+    fn post_system_message() {
+        let mut game: Game =
+            get_basic_endless_game_with_players(MINIMUM_PLAYERS_REQUIRED_TO_PLAY).unwrap();
+        game.post_system_message(String::from("Server is restarting."));
+        let game_view = game.get_user_view("users/0").unwrap();
+        assert_eq!(game_view.chat_messages.len(), 1);
+        assert_eq!(game_view.chat_messages[0].user, None);
+        assert_eq!(game_view.chat_messages[0].text, "Server is restarting.");
+    }
+
     #[test]
     fn judge_leaves_during_judge_phase() {
         let mut game: Game =
@@ -1,31 +1,234 @@
+use crate::helper::fold_text;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
+
+// A query term that matches a whole word counts for more
+// than one that only matches the start of a longer word.
+const EXACT_MATCH_WEIGHT: f64 = 2.0;
+const PREFIX_MATCH_WEIGHT: f64 = 1.0;
+
+fn tokenize(text: &str) -> Vec<String> {
+    fold_text(text)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+// Full-text index over a fixed list of texts. The index is never updated in place,
+// so `Game::update_config` rebuilds it whenever the game's cards are replaced.
 pub struct TextQueryHandler {
     texts: Vec<String>,
+    // Maps each folded word to the sorted indices of the texts
+    // that contain it. A BTreeMap lets us scan words by prefix.
+    inverted_index: BTreeMap<String, Vec<usize>>,
 }
 
 impl TextQueryHandler {
     pub fn new(texts: Vec<String>) -> Self {
-        Self { texts }
+        let mut inverted_index: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (text_index, text) in texts.iter().enumerate() {
+            for token in tokenize(text) {
+                let text_indices = inverted_index.entry(token).or_default();
+                if text_indices.last() != Some(&text_index) {
+                    text_indices.push(text_index);
+                }
+            }
+        }
+        Self {
+            texts,
+            inverted_index,
+        }
     }
 
-    pub fn query(&self, query: &str, page_size: usize, skip: usize) -> (Vec<String>, bool) {
-        let mut texts: Vec<&str> = (self.texts)
+    // Returns a page of texts matching the query with the most relevant texts first,
+    // whether there is a next page, and the total number of matching texts.
+    // Every word in the query must match the start of some word in a text, so
+    // partially typed queries still return results. An empty query matches
+    // every text in its original order.
+    pub fn query(&self, query: &str, page_size: usize, skip: usize) -> (Vec<String>, bool, usize) {
+        let ranked_text_indices = self.get_ranked_text_indices(query);
+        let texts = ranked_text_indices
             .iter()
-            .filter(|text| text.contains(query))
             .skip(skip)
-            .take(page_size + 1)
-            .map(|text| &text[..])
+            .take(page_size)
+            .map(|text_index| self.texts[*text_index].clone())
             .collect();
-        let has_next_page = texts.len() == page_size + 1;
-        if has_next_page {
-            texts.pop();
+        let has_next_page = ranked_text_indices.len() > skip.saturating_add(page_size);
+        (texts, has_next_page, ranked_text_indices.len())
+    }
+
+    fn get_ranked_text_indices(&self, query: &str) -> Vec<usize> {
+        let query_terms = tokenize(query);
+        if query_terms.is_empty() {
+            return (0..self.texts.len()).collect();
+        }
+
+        let mut scores_or: Option<HashMap<usize, f64>> = None;
+        for query_term in &query_terms {
+            let term_scores = self.score_query_term(query_term);
+            scores_or = Some(match scores_or {
+                None => term_scores,
+                // Only keep texts that match every query term.
+                Some(scores) => scores
+                    .into_iter()
+                    .filter_map(|(text_index, score)| {
+                        term_scores
+                            .get(&text_index)
+                            .map(|term_score| (text_index, score + term_score))
+                    })
+                    .collect(),
+            });
+        }
+
+        let mut ranked_texts: Vec<(usize, f64)> =
+            scores_or.unwrap_or_default().into_iter().collect();
+        // Ties are broken by original position so that paging is stable.
+        ranked_texts.sort_by(|(a_index, a_score), (b_index, b_score)| {
+            b_score
+                .partial_cmp(a_score)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a_index.cmp(b_index))
+        });
+        ranked_texts
+            .into_iter()
+            .map(|(text_index, _)| text_index)
+            .collect()
+    }
+
+    // Scores every text containing a word that starts with the query term.
+    // Rarer words are weighted higher since they say more about what the user
+    // is looking for, similar to the inverse document frequency in tf-idf.
+    fn score_query_term(&self, query_term: &str) -> HashMap<usize, f64> {
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        let matching_words = self
+            .inverted_index
+            .range::<str, _>((Bound::Included(query_term), Bound::Unbounded))
+            .take_while(|(word, _)| word.starts_with(query_term));
+        for (word, text_indices) in matching_words {
+            let match_weight = if word == query_term {
+                EXACT_MATCH_WEIGHT
+            } else {
+                PREFIX_MATCH_WEIGHT
+            };
+            let inverse_document_frequency =
+                (self.texts.len() as f64 / text_indices.len() as f64).ln() + 1.0;
+            let score = match_weight * inverse_document_frequency;
+            for text_index in text_indices {
+                let best_score = scores.entry(*text_index).or_insert(0.0);
+                if score > *best_score {
+                    *best_score = score;
+                }
+            }
         }
-        (
-            texts.iter().map(|text| String::from(*text)).collect(),
-            has_next_page,
-        )
+        scores
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_text_query_handler(texts: &[&str]) -> TextQueryHandler {
+        TextQueryHandler::new(texts.iter().map(|text| String::from(*text)).collect())
+    }
+
+    #[test]
+    fn empty_query_returns_every_text_in_order() {
+        let text_query_handler = create_text_query_handler(&["Foo", "Bar", "Baz"]);
+        assert_eq!(
+            text_query_handler.query("", 10, 0),
+            (
+                vec![
+                    String::from("Foo"),
+                    String::from("Bar"),
+                    String::from("Baz")
+                ],
+                false,
+                3
+            )
+        );
+        assert_eq!(
+            text_query_handler.query("  ", 2, 0),
+            (vec![String::from("Foo"), String::from("Bar")], true, 3)
+        );
+        assert_eq!(
+            text_query_handler.query("", 2, 2),
+            (vec![String::from("Baz")], false, 3)
+        );
+    }
+
+    #[test]
+    fn ignores_case_and_accents() {
+        let text_query_handler = create_text_query_handler(&["Crème Brûlée", "Ice cream"]);
+        assert_eq!(
+            text_query_handler.query("CREME brulee", 10, 0),
+            (vec![String::from("Crème Brûlée")], false, 1)
+        );
+    }
+
+    #[test]
+    fn matches_word_prefixes() {
+        let text_query_handler =
+            create_text_query_handler(&["A disappointing birthday party.", "Dad's party."]);
+        assert_eq!(
+            text_query_handler.query("birth", 10, 0),
+            (
+                vec![String::from("A disappointing birthday party.")],
+                false,
+                1
+            )
+        );
+        // Only matches the start of words.
+        assert_eq!(text_query_handler.query("day", 10, 0), (vec![], false, 0));
+        // Every query term must match.
+        assert_eq!(
+            text_query_handler.query("dad part", 10, 0),
+            (vec![String::from("Dad's party.")], false, 1)
+        );
+        assert_eq!(
+            text_query_handler.query("dad birthday", 10, 0),
+            (vec![], false, 0)
+        );
+    }
+
+    #[test]
+    fn ranks_by_relevance() {
+        let text_query_handler =
+            create_text_query_handler(&["Catapults.", "A cat.", "Another cat.", "A cat in a hat."]);
+        // Exact word matches rank above prefix matches.
+        assert_eq!(
+            text_query_handler.query("cat", 10, 0),
+            (
+                vec![
+                    String::from("A cat."),
+                    String::from("Another cat."),
+                    String::from("A cat in a hat."),
+                    String::from("Catapults.")
+                ],
+                false,
+                4
+            )
+        );
+        // Rare words carry more weight than common ones.
+        assert_eq!(
+            text_query_handler.query("a hat", 10, 0).0[0],
+            "A cat in a hat."
+        );
     }
 
-    pub fn total_size(&self) -> usize {
-        self.texts.len()
+    #[test]
+    fn paginates_ranked_results() {
+        let text_query_handler = create_text_query_handler(&["Cat 1", "Cat 2", "Cat 3", "Dog"]);
+        assert_eq!(
+            text_query_handler.query("cat", 2, 0),
+            (vec![String::from("Cat 1"), String::from("Cat 2")], true, 3)
+        );
+        assert_eq!(
+            text_query_handler.query("cat", 2, 2),
+            (vec![String::from("Cat 3")], false, 3)
+        );
+        assert_eq!(text_query_handler.query("cat", 2, 4), (vec![], false, 3));
     }
 }
@@ -33,6 +33,21 @@ impl WhiteCardGameplayManager {
         }
     }
 
+    // Swaps in a new deck while keeping every player.
+    // Any cards in players' hands are dropped along with the old deck.
+    pub fn replace_deck(&mut self, white_card_deck: WhiteCardDeck, hand_size: usize) {
+        for hand in self.hands_and_played_cards.values_mut() {
+            hand.clear();
+        }
+        self.played_cards.clear();
+        self.white_card_deck = white_card_deck;
+        self.hand_size = hand_size;
+    }
+
+    pub fn set_hand_size(&mut self, hand_size: usize) {
+        self.hand_size = hand_size;
+    }
+
     pub fn add_player(&mut self, player_id: PlayerId) {
         self.hands_and_played_cards.insert(player_id, Vec::new());
     }
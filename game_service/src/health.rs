@@ -0,0 +1,69 @@
+use super::amqp::MessageQueue;
+use shared::health::ReadinessCheck;
+use tonic::transport::Channel;
+use tonic_health::proto::health_check_response::ServingStatus;
+use tonic_health::proto::health_client::HealthClient;
+use tonic_health::proto::HealthCheckRequest;
+
+// Game service depends on api service for cardpacks and users,
+// so it's only ready when api service reports itself as ready.
+pub struct ApiServiceReadinessCheck {
+    health_client: HealthClient<Channel>,
+}
+
+impl ApiServiceReadinessCheck {
+    pub fn new(health_client: HealthClient<Channel>) -> Self {
+        Self { health_client }
+    }
+}
+
+#[tonic::async_trait]
+impl ReadinessCheck for ApiServiceReadinessCheck {
+    fn get_dependency_name(&self) -> &'static str {
+        "api_service"
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        let response = match self
+            .health_client
+            .clone()
+            .check(HealthCheckRequest {
+                service: String::new(),
+            })
+            .await
+        {
+            Ok(response) => response,
+            Err(status) => return Err(String::from(status.message())),
+        };
+        if response.get_ref().status == ServingStatus::Serving as i32 {
+            Ok(())
+        } else {
+            Err(String::from("Api service is not serving."))
+        }
+    }
+}
+
+pub struct AmqpReadinessCheck {
+    message_queue: MessageQueue,
+}
+
+impl AmqpReadinessCheck {
+    pub fn new(message_queue: MessageQueue) -> Self {
+        Self { message_queue }
+    }
+}
+
+#[tonic::async_trait]
+impl ReadinessCheck for AmqpReadinessCheck {
+    fn get_dependency_name(&self) -> &'static str {
+        "amqp"
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        if self.message_queue.is_connected() {
+            Ok(())
+        } else {
+            Err(String::from("AMQP channel is not connected."))
+        }
+    }
+}
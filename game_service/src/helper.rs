@@ -2,6 +2,8 @@ use shared::proto::crusty_cards_api::{
     black_card_in_round::Card, playable_white_card::Card as PlayableCard, BlackCardInRound,
     PlayableWhiteCard,
 };
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
 
 #[derive(PartialEq)]
 enum PlayableCardType {
@@ -78,3 +80,25 @@ pub fn get_answer_fields_from_black_card_in_round(card: &BlackCardInRound) -> us
         None => 0,
     }
 }
+
+// Lowercases text and strips accents so that user-entered
+// search queries match regardless of case or diacritics.
+pub fn fold_text(text: &str) -> String {
+    text.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fold_text_ignores_case_and_accents() {
+        assert_eq!(fold_text(""), "");
+        assert_eq!(fold_text("Hello World"), "hello world");
+        assert_eq!(fold_text("Crème BRÛLÉE"), "creme brulee");
+        assert_eq!(fold_text("Ñandú"), "nandu");
+    }
+}
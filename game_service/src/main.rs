@@ -1,35 +1,194 @@
 mod amqp;
-mod environment;
+mod cluster;
+mod config;
 mod game;
+mod health;
 mod helper;
+mod metrics;
 mod service;
 
 use amqp::MessageQueue;
+use cluster::ClusterRouter;
+use config::Config;
+use health::{AmqpReadinessCheck, ApiServiceReadinessCheck};
+use service::api_call_policy::{ApiCallPolicy, CircuitBreakerSettings, RetryPolicy};
 use service::api_resource_fetcher::GrpcApiResourceFetcher;
-use service::game_service_impl::GameServiceImpl;
+use service::caching_api_resource_fetcher::{CachingApiResourceFetcher, CardpackCacheLimits};
+use service::game_service_impl::{GameLimits, GameServiceImpl};
+use shared::auth::{
+    InternalServiceSecretInterceptor, SessionAuthInterceptor, SessionTokenKey,
+    SessionTokenPropagationLayer,
+};
+use shared::health::{report_not_serving, spawn_readiness_monitor, ReadinessCheck};
+use shared::metrics::{serve_metrics, GrpcMetricsLayer};
 use shared::proto::crusty_cards_api::cardpack_service_client::CardpackServiceClient;
 use shared::proto::crusty_cards_api::game_service_server::GameServiceServer;
 use shared::proto::crusty_cards_api::user_service_client::UserServiceClient;
-use tonic::transport::Server;
+use shared::proto_validation::BoundedPageSize;
+use shared::rate_limit::{RateLimitLayer, RateLimiter};
+use shared::request_tracing::{init_tracing, RequestTracingLayer};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::signal::unix::{signal, SignalKind};
+use tonic::codegen::InterceptedService;
+use tonic::transport::{Channel, NamedService, Server};
+use tonic_health::proto::health_client::HealthClient;
+use tracing::{error, info, warn};
+
+// How long to keep accepting requests after reporting as not serving. This gives
+// Kubernetes a few readiness probes (every 5 seconds) to notice and stop routing
+// new requests here before the server stops accepting them.
+const SHUTDOWN_READINESS_DELAY: Duration = Duration::from_secs(10);
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let env_vars = environment::EnvironmentVariables::new();
-    let port: u16 = 50052;
+    init_tracing();
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            error!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    let (default_page_size, max_page_size) = config.get_page_size_limits();
+    BoundedPageSize::set_limits(default_page_size, max_page_size);
+    let port = config.get_port();
     let address = format!("0.0.0.0:{}", port).parse().unwrap();
+    let metrics_port = config.get_metrics_port();
+    let metrics_address = format!("0.0.0.0:{}", metrics_port).parse().unwrap();
+
+    let auth_config = config.get_auth_config();
+    if auth_config.is_test_mode() {
+        warn!("Auth test mode is enabled, so anyone can sign session tokens or act as an internal service. Never enable it in production.");
+    }
+    let session_token_key = Arc::new(SessionTokenKey::new(auth_config.get_session_token_secret()));
+    // Other game service instances call this one as internal callers.
+    let session_auth_interceptor = SessionAuthInterceptor::new_with_internal_service_secret(
+        session_token_key.clone(),
+        auth_config.get_internal_service_secret(),
+    );
+    let rate_limit_layer = RateLimitLayer::new(
+        Arc::new(RateLimiter::new(config.get_rate_limits().clone())),
+        session_token_key,
+        auth_config.get_internal_service_secret(),
+    );
+
+    // Connecting lazily lets game service start while api service is down. The
+    // channel reconnects on its own, and readiness reports api service's health.
+    let api_channel = Channel::from_shared(String::from(config.get_api_uri()))?
+        .connect_timeout(config.get_api_request_timeout())
+        .connect_lazy();
+    let internal_service_secret_interceptor =
+        InternalServiceSecretInterceptor::new(auth_config.get_internal_service_secret());
+    let cardpack_service = CardpackServiceClient::with_interceptor(
+        api_channel.clone(),
+        internal_service_secret_interceptor.clone(),
+    );
+    let user_service = UserServiceClient::with_interceptor(
+        api_channel.clone(),
+        internal_service_secret_interceptor.clone(),
+    );
+    let api_health_client = HealthClient::new(api_channel);
+    let message_queue = MessageQueue::new(config.get_amqp_uri()).await;
+
+    let cluster_router = match config.get_instance_address() {
+        Some(instance_address) => ClusterRouter::new(
+            String::from(instance_address),
+            config.get_cluster_instance_addresses().to_vec(),
+            internal_service_secret_interceptor,
+        )?,
+        None => ClusterRouter::new_single_instance(),
+    };
 
-    let cardpack_service =
-        CardpackServiceClient::connect(String::from(env_vars.get_api_uri())).await?;
-    let user_service = UserServiceClient::connect(String::from(env_vars.get_api_uri())).await?;
-    let message_queue = MessageQueue::new(env_vars.get_amqp_uri()).await;
+    let readiness_checks: Vec<Box<dyn ReadinessCheck>> = vec![
+        Box::from(ApiServiceReadinessCheck::new(api_health_client)),
+        Box::from(AmqpReadinessCheck::new(message_queue.clone())),
+    ];
+    let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
+    let health_service_names = vec![<GameServiceServer<GameServiceImpl> as NamedService>::NAME];
+    let readiness_monitor = spawn_readiness_monitor(
+        health_reporter.clone(),
+        health_service_names.clone(),
+        readiness_checks,
+    );
 
-    println!("Starting server on port {}", port);
+    info!(port = metrics_port, "Serving metrics.");
+    tokio::spawn(async move {
+        if let Err(err) = serve_metrics(metrics_address).await {
+            error!(error = %err, "Metrics server failed.");
+        }
+    });
+
+    let game_service = Arc::new(GameServiceImpl::new(
+        Box::from(CachingApiResourceFetcher::new(
+            Box::from(GrpcApiResourceFetcher::new(
+                cardpack_service,
+                user_service,
+                ApiCallPolicy::new(
+                    RetryPolicy {
+                        max_retries: config.get_api_max_retries(),
+                        attempt_timeout: config.get_api_request_timeout(),
+                        ..Default::default()
+                    },
+                    CircuitBreakerSettings {
+                        failure_threshold: config.get_api_circuit_breaker_failure_threshold(),
+                        open_duration: config.get_api_circuit_breaker_open_duration(),
+                    },
+                ),
+            )),
+            CardpackCacheLimits {
+                custom_cardpack_ttl: config.get_custom_cardpack_cache_ttl(),
+                max_custom_cardpacks: config.get_max_cached_custom_cardpacks(),
+            },
+        )),
+        Some(message_queue),
+        cluster_router,
+        GameLimits {
+            unused_game_ttl: config.get_unused_game_ttl(),
+            max_chat_messages_per_game: config.get_max_chat_messages_per_game(),
+        },
+    ));
+    let game_service_shutdown_clone = game_service.clone();
+    let shutdown_signal = async move {
+        wait_for_shutdown_signal().await;
+        info!("Received shutdown signal, reporting as not serving.");
+        // The monitor would report the server as serving again if readiness changed.
+        readiness_monitor.abort();
+        report_not_serving(&mut health_reporter, &health_service_names).await;
+        game_service_shutdown_clone.begin_shutdown().await;
+        tokio::time::sleep(SHUTDOWN_READINESS_DELAY).await;
+        info!("Waiting for in-flight requests to finish.");
+    };
+
+    info!(port, "Starting server.");
     Server::builder()
-        .add_service(GameServiceServer::new(GameServiceImpl::new(
-            Box::from(GrpcApiResourceFetcher::new(cardpack_service, user_service)),
-            Some(message_queue),
-        )))
-        .serve(address)
+        .layer(RequestTracingLayer)
+        .layer(GrpcMetricsLayer)
+        .layer(rate_limit_layer)
+        .layer(SessionTokenPropagationLayer)
+        .add_service(health_service)
+        .add_service(InterceptedService::new(
+            GameServiceServer::from_arc(game_service),
+            session_auth_interceptor,
+        ))
+        .serve_with_shutdown(address, shutdown_signal)
         .await?;
+    info!("Server shut down.");
     Ok(())
 }
+
+// Kubernetes sends SIGTERM when stopping a pod, and ctrl-c sends SIGINT when running locally.
+async fn wait_for_shutdown_signal() {
+    let mut sigterm = match signal(SignalKind::terminate()) {
+        Ok(sigterm) => sigterm,
+        Err(err) => {
+            error!(error = %err, "Failed to listen for SIGTERM.");
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        }
+    };
+    tokio::select! {
+        _ = sigterm.recv() => {},
+        _ = tokio::signal::ctrl_c() => {},
+    };
+}
@@ -0,0 +1,77 @@
+use super::game::Game;
+use lazy_static::lazy_static;
+use prometheus::{
+    register_int_counter, register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
+};
+use prometheus::{IntCounter, IntCounterVec, IntGauge, IntGaugeVec};
+use shared::proto::crusty_cards_api::game_view::Stage;
+use std::collections::HashMap;
+
+const ALL_STAGES: [Stage; 4] = [
+    Stage::NotRunning,
+    Stage::PlayPhase,
+    Stage::JudgePhase,
+    Stage::RoundEndPhase,
+];
+
+lazy_static! {
+    static ref ACTIVE_GAMES: IntGaugeVec = register_int_gauge_vec!(
+        "crusty_cards_active_games",
+        "Number of games hosted by this instance, by stage.",
+        &["stage"]
+    )
+    .unwrap();
+    static ref PLAYERS: IntGauge = register_int_gauge!(
+        "crusty_cards_players",
+        "Number of real players in games hosted by this instance."
+    )
+    .unwrap();
+    static ref ARTIFICIAL_PLAYERS: IntGauge = register_int_gauge!(
+        "crusty_cards_artificial_players",
+        "Number of artificial players in games hosted by this instance."
+    )
+    .unwrap();
+    static ref AMQP_PUBLISH_FAILURES: IntCounter = register_int_counter!(
+        "crusty_cards_amqp_publish_failures_total",
+        "Number of game update messages that failed to publish."
+    )
+    .unwrap();
+    static ref CARDPACK_CACHE_LOOKUPS: IntCounterVec = register_int_counter_vec!(
+        "crusty_cards_cardpack_cache_lookups_total",
+        "Number of cardpack card lookups, by cardpack type and whether they were cached.",
+        &["cardpack_type", "result"]
+    )
+    .unwrap();
+}
+
+// Takes a snapshot of every game hosted by this instance. Counts are
+// totalled before any gauge is set so that a scrape never sees a partial update.
+pub fn record_game_metrics(games: &[Game]) {
+    let mut game_counts_by_stage: HashMap<Stage, i64> = HashMap::new();
+    let mut player_count = 0;
+    let mut artificial_player_count = 0;
+    for game in games {
+        *game_counts_by_stage.entry(game.get_stage()).or_insert(0) += 1;
+        player_count += game.get_real_player_count() as i64;
+        artificial_player_count += game.get_artificial_player_count() as i64;
+    }
+
+    for stage in &ALL_STAGES {
+        ACTIVE_GAMES
+            .with_label_values(&[&format!("{:?}", stage)])
+            .set(*game_counts_by_stage.get(stage).unwrap_or(&0));
+    }
+    PLAYERS.set(player_count);
+    ARTIFICIAL_PLAYERS.set(artificial_player_count);
+}
+
+pub fn record_amqp_publish_failure() {
+    AMQP_PUBLISH_FAILURES.inc();
+}
+
+// `cardpack_type` is either `custom` or `default`.
+pub fn record_cardpack_cache_lookup(cardpack_type: &str, is_hit: bool) {
+    CARDPACK_CACHE_LOOKUPS
+        .with_label_values(&[cardpack_type, if is_hit { "hit" } else { "miss" }])
+        .inc();
+}
@@ -0,0 +1,351 @@
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tonic::{Code, Status};
+use tracing::warn;
+
+// How long each attempt at calling api service may take, and how failed
+// idempotent calls are retried. Retries wait for a random duration of up to
+// the current backoff, which doubles after each retry up to `max_backoff`.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub attempt_timeout: Duration,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            attempt_timeout: Duration::from_secs(5),
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+impl RetryPolicy {
+    // Jitter keeps instances that failed at the same time from all retrying at the same time.
+    fn get_backoff(&self, retry_number: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry_number))
+            .min(self.max_backoff);
+        backoff.mul_f64(rand::random::<f64>())
+    }
+}
+
+// Once `failure_threshold` calls in a row have failed, every call fails
+// fast for `open_duration`. After that, a single trial call is let through,
+// and the circuit closes again if it succeeds.
+#[derive(Clone, Copy, Debug)]
+pub struct CircuitBreakerSettings {
+    pub failure_threshold: u32,
+    pub open_duration: Duration,
+}
+
+impl Default for CircuitBreakerSettings {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_duration: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum CircuitState {
+    Closed { consecutive_failures: u32 },
+    Open { open_time: Instant },
+    // A trial call is in flight. If it never finishes, such as when its
+    // request is dropped, another trial is let through after `open_duration`.
+    HalfOpen { trial_start_time: Instant },
+}
+
+struct CircuitBreaker {
+    settings: CircuitBreakerSettings,
+    state: Mutex<CircuitState>,
+}
+
+impl CircuitBreaker {
+    fn new(settings: CircuitBreakerSettings) -> Self {
+        Self {
+            settings,
+            state: Mutex::new(CircuitState::Closed {
+                consecutive_failures: 0,
+            }),
+        }
+    }
+
+    fn try_acquire(&self, now: Instant) -> Result<(), Status> {
+        let mut state = self.state.lock().unwrap();
+        let is_allowed = match *state {
+            CircuitState::Closed { .. } => true,
+            CircuitState::Open { open_time: since }
+            | CircuitState::HalfOpen {
+                trial_start_time: since,
+            } => now.saturating_duration_since(since) >= self.settings.open_duration,
+        };
+        if !is_allowed {
+            return Err(Status::unavailable(
+                "Api service is unhealthy, so requests to it are failing fast.",
+            ));
+        }
+        if !matches!(*state, CircuitState::Closed { .. }) {
+            *state = CircuitState::HalfOpen {
+                trial_start_time: now,
+            };
+        }
+        Ok(())
+    }
+
+    fn record_result(&self, is_failure: bool, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        if !is_failure {
+            *state = CircuitState::Closed {
+                consecutive_failures: 0,
+            };
+            return;
+        }
+        let consecutive_failures = match *state {
+            CircuitState::Closed {
+                consecutive_failures,
+            } => consecutive_failures + 1,
+            // A failed trial call reopens the circuit straight away.
+            CircuitState::HalfOpen { .. } => self.settings.failure_threshold,
+            CircuitState::Open { .. } => return,
+        };
+        if consecutive_failures >= self.settings.failure_threshold {
+            if matches!(*state, CircuitState::Closed { .. }) {
+                warn!("Opening circuit breaker for api service.");
+            }
+            *state = CircuitState::Open { open_time: now };
+        } else {
+            *state = CircuitState::Closed {
+                consecutive_failures,
+            };
+        }
+    }
+}
+
+// Errors that mean api service itself is struggling, rather than that a request was bad.
+fn is_api_service_failure(code: Code) -> bool {
+    matches!(
+        code,
+        Code::Unavailable | Code::DeadlineExceeded | Code::Internal | Code::Unknown
+    )
+}
+
+fn is_retryable(code: Code) -> bool {
+    matches!(code, Code::Unavailable | Code::DeadlineExceeded)
+}
+
+// Applies deadlines, retries and circuit breaking to calls to api service.
+pub struct ApiCallPolicy {
+    retry_policy: RetryPolicy,
+    circuit_breaker: CircuitBreaker,
+}
+
+impl ApiCallPolicy {
+    pub fn new(
+        retry_policy: RetryPolicy,
+        circuit_breaker_settings: CircuitBreakerSettings,
+    ) -> Self {
+        Self {
+            retry_policy,
+            circuit_breaker: CircuitBreaker::new(circuit_breaker_settings),
+        }
+    }
+
+    // Sent to api service so that it can give up on requests that we've already given up on.
+    pub fn get_attempt_timeout(&self) -> Duration {
+        self.retry_policy.attempt_timeout
+    }
+
+    // For calls that aren't safe to repeat, such as reporting stats.
+    pub async fn call<T, Fut>(&self, call: Fut) -> Result<T, Status>
+    where
+        Fut: Future<Output = Result<T, Status>>,
+    {
+        self.circuit_breaker.try_acquire(Instant::now())?;
+        self.attempt(call).await
+    }
+
+    // For reads, which can safely be repeated. `make_call` is called once per attempt.
+    pub async fn call_with_retries<T, F, Fut>(&self, mut make_call: F) -> Result<T, Status>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Status>>,
+    {
+        let mut retry_number = 0;
+        loop {
+            self.circuit_breaker.try_acquire(Instant::now())?;
+            match self.attempt(make_call()).await {
+                Err(err)
+                    if retry_number < self.retry_policy.max_retries && is_retryable(err.code()) =>
+                {
+                    tokio::time::sleep(self.retry_policy.get_backoff(retry_number)).await;
+                    retry_number += 1;
+                }
+                result => return result,
+            };
+        }
+    }
+
+    async fn attempt<T, Fut>(&self, call: Fut) -> Result<T, Status>
+    where
+        Fut: Future<Output = Result<T, Status>>,
+    {
+        let result = match tokio::time::timeout(self.retry_policy.attempt_timeout, call).await {
+            Ok(result) => result,
+            Err(_) => Err(Status::deadline_exceeded(format!(
+                "Api service did not respond within {:?}.",
+                self.retry_policy.attempt_timeout
+            ))),
+        };
+        let is_failure = match &result {
+            Ok(_) => false,
+            Err(err) => is_api_service_failure(err.code()),
+        };
+        self.circuit_breaker
+            .record_result(is_failure, Instant::now());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn create_test_retry_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 2,
+            attempt_timeout: Duration::from_millis(50),
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+        }
+    }
+
+    #[test]
+    fn circuit_breaker_opens_and_recovers() {
+        let circuit_breaker = CircuitBreaker::new(CircuitBreakerSettings {
+            failure_threshold: 2,
+            open_duration: Duration::from_secs(10),
+        });
+        let start_time = Instant::now();
+
+        assert!(circuit_breaker.try_acquire(start_time).is_ok());
+        circuit_breaker.record_result(true, start_time);
+        assert!(circuit_breaker.try_acquire(start_time).is_ok());
+        circuit_breaker.record_result(true, start_time);
+        assert_eq!(
+            circuit_breaker.try_acquire(start_time).unwrap_err().code(),
+            Code::Unavailable
+        );
+
+        // Only one trial call is let through once the circuit has been open for long enough.
+        let trial_time = start_time + Duration::from_secs(10);
+        assert!(circuit_breaker.try_acquire(trial_time).is_ok());
+        assert!(circuit_breaker.try_acquire(trial_time).is_err());
+        circuit_breaker.record_result(true, trial_time);
+        assert!(circuit_breaker.try_acquire(trial_time).is_err());
+
+        let second_trial_time = trial_time + Duration::from_secs(10);
+        assert!(circuit_breaker.try_acquire(second_trial_time).is_ok());
+        circuit_breaker.record_result(false, second_trial_time);
+        assert_eq!(
+            *circuit_breaker.state.lock().unwrap(),
+            CircuitState::Closed {
+                consecutive_failures: 0
+            }
+        );
+    }
+
+    #[test]
+    fn backoff_is_bounded() {
+        let retry_policy = create_test_retry_policy();
+        for retry_number in 0..40 {
+            assert!(retry_policy.get_backoff(retry_number) <= retry_policy.max_backoff);
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_only_retryable_errors() {
+        let api_call_policy = ApiCallPolicy::new(
+            create_test_retry_policy(),
+            CircuitBreakerSettings::default(),
+        );
+
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), Status> = api_call_policy
+            .call_with_retries(|| {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                async { Err(Status::unavailable("")) }
+            })
+            .await;
+        assert_eq!(result.unwrap_err().code(), Code::Unavailable);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        let attempts = AtomicU32::new(0);
+        let result = api_call_policy
+            .call_with_retries(|| {
+                let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if attempt == 0 {
+                        Err(Status::unavailable(""))
+                    } else {
+                        Ok(attempt)
+                    }
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 1);
+
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), Status> = api_call_policy
+            .call_with_retries(|| {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                async { Err(Status::not_found("")) }
+            })
+            .await;
+        assert_eq!(result.unwrap_err().code(), Code::NotFound);
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn slow_calls_exceed_their_deadline() {
+        let api_call_policy = ApiCallPolicy::new(
+            create_test_retry_policy(),
+            CircuitBreakerSettings::default(),
+        );
+        let result: Result<(), Status> = api_call_policy.call(std::future::pending()).await;
+        assert_eq!(result.unwrap_err().code(), Code::DeadlineExceeded);
+    }
+
+    #[tokio::test]
+    async fn open_circuit_fails_fast() {
+        let api_call_policy = ApiCallPolicy::new(
+            create_test_retry_policy(),
+            CircuitBreakerSettings {
+                failure_threshold: 1,
+                open_duration: Duration::from_secs(60),
+            },
+        );
+        let _ = api_call_policy
+            .call(async { Err::<(), Status>(Status::internal("")) })
+            .await;
+
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), Status> = api_call_policy
+            .call_with_retries(|| {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                async { Ok(()) }
+            })
+            .await;
+        assert_eq!(result.unwrap_err().code(), Code::Unavailable);
+        assert_eq!(attempts.load(Ordering::SeqCst), 0);
+    }
+}
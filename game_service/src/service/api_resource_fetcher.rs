@@ -1,168 +1,239 @@
+use super::api_call_policy::ApiCallPolicy;
 use async_trait::async_trait;
+use futures_util::stream::{self, StreamExt};
 use mockall::automock;
+use shared::auth::{attach_caller_session_token, InternalServiceChannel};
 use shared::proto::crusty_cards_api::{
-    cardpack_service_client::CardpackServiceClient, user_service_client::UserServiceClient,
-    CustomBlackCard, CustomWhiteCard, DefaultBlackCard, DefaultWhiteCard, GetUserRequest,
-    ListCustomBlackCardsRequest, ListCustomWhiteCardsRequest, ListDefaultBlackCardsRequest,
-    ListDefaultWhiteCardsRequest, User,
+    cardpack_service_client::CardpackServiceClient, stream_cardpack_cards_response::Card,
+    user_service_client::UserServiceClient, AchievementProgress, CustomBlackCard, CustomCardpack,
+    CustomWhiteCard, DefaultBlackCard, DefaultWhiteCard, GetCustomCardpackRequest,
+    GetDefaultCardpackRequest, GetUserRequest, GetUserSettingsRequest,
+    ReportAchievementProgressRequest, ReportUserStatsRequest, ReportWhiteCardStatsRequest,
+    StreamCardpackCardsRequest, User, UserSettings, UserStatsIncrement, WhiteCardStats,
 };
-use tonic::transport::Channel;
-use tonic::{Request, Status};
+use shared::request_tracing::create_request_with_request_id;
+use tonic::{Code, Request, Status};
+
+// Limits how many cardpacks are fetched at once, so that a game with
+// lots of cardpacks doesn't flood api service with concurrent streams.
+pub const MAX_CONCURRENT_CARDPACK_FETCHES: usize = 8;
+
+// Set on cardpack fetch errors to a comma-separated list of every cardpack that
+// couldn't be fetched, along with why, such as `users/1/cardpacks/2=NotFound`.
+pub const FAILED_CARDPACKS_METADATA_KEY: &str = "x-crusty-cards-failed-cardpacks";
+
+// Why a single cardpack couldn't be fetched.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CardpackFetchFailure {
+    pub cardpack_name: String,
+    pub code: Code,
+    pub message: String,
+}
+
+impl CardpackFetchFailure {
+    pub fn new(cardpack_name: &str, err: &Status) -> Self {
+        Self {
+            cardpack_name: String::from(cardpack_name),
+            code: err.code(),
+            message: String::from(err.message()),
+        }
+    }
+}
+
+// Combines every cardpack failure into a single error. If any cardpack failed
+// for a reason other than the cardpack being missing or inaccessible, that
+// failure's code is used so that callers know the request is worth retrying.
+pub fn cardpack_fetch_error(failures: &[CardpackFetchFailure]) -> Status {
+    let code = match failures.iter().find(|failure| {
+        !matches!(
+            failure.code,
+            Code::NotFound | Code::PermissionDenied | Code::InvalidArgument
+        )
+    }) {
+        Some(failure) => failure.code,
+        None => match failures.first() {
+            Some(failure) => failure.code,
+            None => Code::Unknown,
+        },
+    };
+    let descriptions: Vec<String> = failures
+        .iter()
+        .map(|failure| {
+            format!(
+                "`{}` ({:?}: {})",
+                failure.cardpack_name, failure.code, failure.message
+            )
+        })
+        .collect();
+    let mut status = Status::new(
+        code,
+        format!(
+            "Failed to fetch {} cardpack(s): {}.",
+            failures.len(),
+            descriptions.join(", ")
+        ),
+    );
+    let failed_cardpacks: Vec<String> = failures
+        .iter()
+        .map(|failure| format!("{}={:?}", failure.cardpack_name, failure.code))
+        .collect();
+    if let Ok(metadata_value) = failed_cardpacks.join(",").parse() {
+        status
+            .metadata_mut()
+            .insert(FAILED_CARDPACKS_METADATA_KEY, metadata_value);
+    }
+    status
+}
 
 #[automock]
 #[async_trait]
 pub trait ApiResourceFetcher: Send + Sync {
     async fn get_user(&self, user_name: String) -> Result<User, Status>;
 
-    // Retrieves all black and white custom cards from multiple custom cardpacks, or returns None if any error is encountered.
+    async fn get_user_settings(&self, user_name: String) -> Result<UserSettings, Status>;
+
+    // Adds to the persistent stats of each user, such as games played and rounds won.
+    async fn report_user_stats(&self, increments: Vec<UserStatsIncrement>) -> Result<(), Status>;
+
+    // Adds to the persistent play and win counts of each white card.
+    async fn report_white_card_stats(
+        &self,
+        white_card_stats: Vec<WhiteCardStats>,
+    ) -> Result<(), Status>;
+
+    // Adds to each user's progress towards unlocking achievements.
+    async fn report_achievement_progress(
+        &self,
+        achievement_progress: Vec<AchievementProgress>,
+    ) -> Result<(), Status>;
+
+    // Returns false if the custom cardpack doesn't exist or has been deleted.
+    async fn custom_cardpack_exists(&self, custom_cardpack_name: String) -> Result<bool, Status>;
+
+    // Returns None if the custom cardpack doesn't exist or has been deleted.
+    async fn get_custom_cardpack(
+        &self,
+        custom_cardpack_name: String,
+    ) -> Result<Option<CustomCardpack>, Status>;
+
+    // Returns false if the default cardpack doesn't exist.
+    async fn default_cardpack_exists(&self, default_cardpack_name: String) -> Result<bool, Status>;
+
+    // Retrieves all black and white custom cards from multiple custom cardpacks. If any
+    // cardpack can't be fetched, returns an error listing every cardpack that failed.
     async fn get_custom_cards_from_multiple_custom_cardpacks(
         &self,
         cardpack_names: &[String],
     ) -> Result<(Vec<CustomBlackCard>, Vec<CustomWhiteCard>), Status>;
 
-    // Retrieves all black and white default cards from multiple default cardpacks, or returns None if any error is encountered.
+    // Retrieves all black and white default cards from multiple default cardpacks. If any
+    // cardpack can't be fetched, returns an error listing every cardpack that failed.
     async fn get_default_cards_from_multiple_default_cardpacks(
         &self,
         default_cardpack_names: &[String],
     ) -> Result<(Vec<DefaultBlackCard>, Vec<DefaultWhiteCard>), Status>;
 }
 
+// Both clients authenticate as an internal caller, which lets game service
+// call internal-only RPCs and read data that's private to individual users.
 pub struct GrpcApiResourceFetcher {
-    cardpack_service_client: CardpackServiceClient<Channel>,
-    user_service_client: UserServiceClient<Channel>,
+    cardpack_service_client: CardpackServiceClient<InternalServiceChannel>,
+    user_service_client: UserServiceClient<InternalServiceChannel>,
+    call_policy: ApiCallPolicy,
 }
 
 impl GrpcApiResourceFetcher {
     pub fn new(
-        cardpack_service: CardpackServiceClient<Channel>,
-        user_service: UserServiceClient<Channel>,
+        cardpack_service: CardpackServiceClient<InternalServiceChannel>,
+        user_service: UserServiceClient<InternalServiceChannel>,
+        call_policy: ApiCallPolicy,
     ) -> GrpcApiResourceFetcher {
         GrpcApiResourceFetcher {
             cardpack_service_client: cardpack_service,
             user_service_client: user_service,
+            call_policy,
         }
     }
 
-    async fn get_custom_black_cards_from_custom_cardpack(
-        &self,
-        custom_cardpack_name: &str,
-    ) -> Result<Vec<CustomBlackCard>, Status> {
-        let mut cards = Vec::new();
-        let mut next_page_token = String::new();
-        let mut has_finished_first_call = false;
-        while !next_page_token.is_empty() || !has_finished_first_call {
-            let request = ListCustomBlackCardsRequest {
-                parent: String::from(custom_cardpack_name),
-                page_size: 1000,
-                page_token: next_page_token,
-                show_deleted: false,
-            };
-            // Cloning Tonic generated clients is extremely cheap, and allows multithreading like we need here.
-            let mut response = match self
-                .cardpack_service_client
-                .clone()
-                .list_custom_black_cards(Request::new(request))
-                .await
-            {
-                Ok(response) => response,
-                Err(err) => return Err(err),
-            };
-            has_finished_first_call = true;
-            next_page_token = String::from(&response.get_ref().next_page_token);
-            cards.append(&mut response.get_mut().custom_black_cards);
-        }
-        Ok(cards)
+    // Requests to api service are made on behalf of whoever
+    // called the game service RPC that's currently being handled.
+    fn create_request<T>(&self, message: T) -> Request<T> {
+        let mut request = create_request_with_request_id(message);
+        attach_caller_session_token(&mut request);
+        request.set_timeout(self.call_policy.get_attempt_timeout());
+        request
     }
 
-    async fn get_custom_white_cards_from_custom_cardpack(
+    // Streams every card in a single cardpack. A failed attempt is retried from the start
+    // of the stream, so cards from an attempt that failed partway through are discarded.
+    async fn stream_cards_from_cardpack(
         &self,
-        custom_cardpack_name: &str,
-    ) -> Result<Vec<CustomWhiteCard>, Status> {
-        let mut cards = Vec::new();
-        let mut next_page_token = String::new();
-        let mut has_finished_first_call = false;
-        while !next_page_token.is_empty() || !has_finished_first_call {
-            let request = ListCustomWhiteCardsRequest {
-                parent: String::from(custom_cardpack_name),
-                page_size: 1000,
-                page_token: next_page_token,
-                show_deleted: false,
-            };
-            // Cloning Tonic generated clients is extremely cheap, and allows multithreading like we need here.
-            let mut response = match self
-                .cardpack_service_client
-                .clone()
-                .list_custom_white_cards(Request::new(request))
-                .await
-            {
-                Ok(response) => response,
-                Err(err) => return Err(err),
-            };
-            has_finished_first_call = true;
-            next_page_token = String::from(&response.get_ref().next_page_token);
-            cards.append(&mut response.get_mut().custom_white_cards);
-        }
-        Ok(cards)
+        request: StreamCardpackCardsRequest,
+    ) -> Result<Vec<Card>, Status> {
+        self.call_policy
+            .call_with_retries(|| {
+                // Cloning Tonic generated clients is extremely cheap, and allows multithreading like we need here.
+                let mut cardpack_service_client = self.cardpack_service_client.clone();
+                let request = self.create_request(request.clone());
+                async move {
+                    let mut card_stream = cardpack_service_client
+                        .stream_cardpack_cards(request)
+                        .await?
+                        .into_inner();
+                    let mut cards = Vec::new();
+                    while let Some(response) = card_stream.message().await? {
+                        if let Some(card) = response.card {
+                            cards.push(card);
+                        }
+                    }
+                    Ok(cards)
+                }
+            })
+            .await
     }
 
-    async fn get_default_black_cards_from_default_cardpack(
+    async fn get_cards_from_custom_cardpack(
         &self,
-        default_cardpack_name: &str,
-    ) -> Result<Vec<DefaultBlackCard>, Status> {
-        let mut cards = Vec::new();
-        let mut next_page_token = String::new();
-        let mut has_finished_first_call = false;
-        while !next_page_token.is_empty() || !has_finished_first_call {
-            let request = ListDefaultBlackCardsRequest {
-                parent: String::from(default_cardpack_name),
-                page_size: 1000,
-                page_token: next_page_token,
-            };
-            // Cloning Tonic generated clients is extremely cheap, and allows multithreading like we need here.
-            let mut response = match self
-                .cardpack_service_client
-                .clone()
-                .list_default_black_cards(Request::new(request))
-                .await
-            {
-                Ok(response) => response,
-                Err(err) => return Err(err),
+        custom_cardpack_name: String,
+    ) -> Result<(Vec<CustomBlackCard>, Vec<CustomWhiteCard>), Status> {
+        let cards = self
+            .stream_cards_from_cardpack(StreamCardpackCardsRequest {
+                custom_cardpack_names: vec![custom_cardpack_name],
+                default_cardpack_names: Vec::new(),
+            })
+            .await?;
+        let mut black_cards = Vec::new();
+        let mut white_cards = Vec::new();
+        for card in cards {
+            match card {
+                Card::CustomBlackCard(black_card) => black_cards.push(black_card),
+                Card::CustomWhiteCard(white_card) => white_cards.push(white_card),
+                _ => return Err(Status::internal("Received a card from the wrong cardpack.")),
             };
-            has_finished_first_call = true;
-            next_page_token = String::from(&response.get_ref().next_page_token);
-            cards.append(&mut response.get_mut().default_black_cards);
         }
-        Ok(cards)
+        Ok((black_cards, white_cards))
     }
 
-    async fn get_default_white_cards_from_default_cardpack(
+    async fn get_cards_from_default_cardpack(
         &self,
-        default_cardpack_name: &str,
-    ) -> Result<Vec<DefaultWhiteCard>, Status> {
-        let mut cards = Vec::new();
-        let mut next_page_token = String::new();
-        let mut has_finished_first_call = false;
-        while !next_page_token.is_empty() || !has_finished_first_call {
-            let request = ListDefaultWhiteCardsRequest {
-                parent: String::from(default_cardpack_name),
-                page_size: 1000,
-                page_token: next_page_token,
-            };
-            // Cloning Tonic generated clients is extremely cheap, and allows multithreading like we need here.
-            let mut response = match self
-                .cardpack_service_client
-                .clone()
-                .list_default_white_cards(Request::new(request))
-                .await
-            {
-                Ok(response) => response,
-                Err(err) => return Err(err),
+        default_cardpack_name: String,
+    ) -> Result<(Vec<DefaultBlackCard>, Vec<DefaultWhiteCard>), Status> {
+        let cards = self
+            .stream_cards_from_cardpack(StreamCardpackCardsRequest {
+                custom_cardpack_names: Vec::new(),
+                default_cardpack_names: vec![default_cardpack_name],
+            })
+            .await?;
+        let mut black_cards = Vec::new();
+        let mut white_cards = Vec::new();
+        for card in cards {
+            match card {
+                Card::DefaultBlackCard(black_card) => black_cards.push(black_card),
+                Card::DefaultWhiteCard(white_card) => white_cards.push(white_card),
+                _ => return Err(Status::internal("Received a card from the wrong cardpack.")),
             };
-            has_finished_first_call = true;
-            next_page_token = String::from(&response.get_ref().next_page_token);
-            cards.append(&mut response.get_mut().default_white_cards);
         }
-        Ok(cards)
+        Ok((black_cards, white_cards))
     }
 }
 
@@ -170,11 +241,14 @@ impl GrpcApiResourceFetcher {
 impl ApiResourceFetcher for GrpcApiResourceFetcher {
     async fn get_user(&self, user_name: String) -> Result<User, Status> {
         let request = GetUserRequest { name: user_name };
-        // Cloning Tonic generated clients is extremely cheap, and allows multithreading like we need here.
         match self
-            .user_service_client
-            .clone()
-            .get_user(Request::new(request))
+            .call_policy
+            .call_with_retries(|| {
+                // Cloning Tonic generated clients is extremely cheap, and allows multithreading like we need here.
+                let mut user_service_client = self.user_service_client.clone();
+                let request = self.create_request(request.clone());
+                async move { user_service_client.get_user(request).await }
+            })
             .await
         {
             Ok(response) => Ok(response.into_inner()),
@@ -182,28 +256,164 @@ impl ApiResourceFetcher for GrpcApiResourceFetcher {
         }
     }
 
+    async fn get_user_settings(&self, user_name: String) -> Result<UserSettings, Status> {
+        let request = GetUserSettingsRequest {
+            name: format!("{}/settings", user_name),
+        };
+        match self
+            .call_policy
+            .call_with_retries(|| {
+                // Cloning Tonic generated clients is extremely cheap, and allows multithreading like we need here.
+                let mut user_service_client = self.user_service_client.clone();
+                let request = self.create_request(request.clone());
+                async move { user_service_client.get_user_settings(request).await }
+            })
+            .await
+        {
+            Ok(response) => Ok(response.into_inner()),
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn report_user_stats(&self, increments: Vec<UserStatsIncrement>) -> Result<(), Status> {
+        let request = ReportUserStatsRequest { increments };
+        match self
+            .call_policy
+            .call({
+                let mut user_service_client = self.user_service_client.clone();
+                let request = self.create_request(request);
+                async move { user_service_client.report_user_stats(request).await }
+            })
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn report_achievement_progress(
+        &self,
+        achievement_progress: Vec<AchievementProgress>,
+    ) -> Result<(), Status> {
+        let request = ReportAchievementProgressRequest {
+            progress: achievement_progress,
+        };
+        match self
+            .call_policy
+            .call({
+                let mut user_service_client = self.user_service_client.clone();
+                let request = self.create_request(request);
+                async move {
+                    user_service_client
+                        .report_achievement_progress(request)
+                        .await
+                }
+            })
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn report_white_card_stats(
+        &self,
+        white_card_stats: Vec<WhiteCardStats>,
+    ) -> Result<(), Status> {
+        let request = ReportWhiteCardStatsRequest { white_card_stats };
+        match self
+            .call_policy
+            .call({
+                let mut cardpack_service_client = self.cardpack_service_client.clone();
+                let request = self.create_request(request);
+                async move {
+                    cardpack_service_client
+                        .report_white_card_stats(request)
+                        .await
+                }
+            })
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn custom_cardpack_exists(&self, custom_cardpack_name: String) -> Result<bool, Status> {
+        match self.get_custom_cardpack(custom_cardpack_name).await {
+            Ok(custom_cardpack_or) => Ok(custom_cardpack_or.is_some()),
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn get_custom_cardpack(
+        &self,
+        custom_cardpack_name: String,
+    ) -> Result<Option<CustomCardpack>, Status> {
+        let request = GetCustomCardpackRequest {
+            name: custom_cardpack_name,
+        };
+        // Deleted cardpacks are reported as not found.
+        match self
+            .call_policy
+            .call_with_retries(|| {
+                // Cloning Tonic generated clients is extremely cheap, and allows multithreading like we need here.
+                let mut cardpack_service_client = self.cardpack_service_client.clone();
+                let request = self.create_request(request.clone());
+                async move { cardpack_service_client.get_custom_cardpack(request).await }
+            })
+            .await
+        {
+            Ok(response) => Ok(Some(response.into_inner())),
+            Err(err) if err.code() == Code::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn default_cardpack_exists(&self, default_cardpack_name: String) -> Result<bool, Status> {
+        let request = GetDefaultCardpackRequest {
+            name: default_cardpack_name,
+        };
+        match self
+            .call_policy
+            .call_with_retries(|| {
+                // Cloning Tonic generated clients is extremely cheap, and allows multithreading like we need here.
+                let mut cardpack_service_client = self.cardpack_service_client.clone();
+                let request = self.create_request(request.clone());
+                async move { cardpack_service_client.get_default_cardpack(request).await }
+            })
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(err) if err.code() == Code::NotFound => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
     async fn get_custom_cards_from_multiple_custom_cardpacks(
         &self,
         cardpack_names: &[String],
     ) -> Result<(Vec<CustomBlackCard>, Vec<CustomWhiteCard>), Status> {
-        // TODO - Do this work in multiple threads.
+        let results: Vec<Result<(Vec<CustomBlackCard>, Vec<CustomWhiteCard>), Status>> =
+            stream::iter(cardpack_names.iter().cloned())
+                .map(|cardpack_name| self.get_cards_from_custom_cardpack(cardpack_name))
+                .buffered(MAX_CONCURRENT_CARDPACK_FETCHES)
+                .collect()
+                .await;
         let mut custom_black_cards = Vec::new();
         let mut custom_white_cards = Vec::new();
-        for cardpack_name in cardpack_names {
-            custom_black_cards.append(&mut match self
-                .get_custom_black_cards_from_custom_cardpack(cardpack_name)
-                .await
-            {
-                Ok(cards) => cards,
-                Err(err) => return Err(err),
-            });
-            custom_white_cards.append(&mut match self
-                .get_custom_white_cards_from_custom_cardpack(cardpack_name)
-                .await
-            {
-                Ok(cards) => cards,
-                Err(err) => return Err(err),
-            });
+        let mut failures = Vec::new();
+        for (cardpack_name, result) in cardpack_names.iter().zip(results) {
+            match result {
+                Ok((mut black_cards, mut white_cards)) => {
+                    custom_black_cards.append(&mut black_cards);
+                    custom_white_cards.append(&mut white_cards);
+                }
+                Err(err) => failures.push(CardpackFetchFailure::new(cardpack_name, &err)),
+            };
+        }
+        if !failures.is_empty() {
+            return Err(cardpack_fetch_error(&failures));
         }
         Ok((custom_black_cards, custom_white_cards))
     }
@@ -212,25 +422,64 @@ impl ApiResourceFetcher for GrpcApiResourceFetcher {
         &self,
         default_cardpack_names: &[String],
     ) -> Result<(Vec<DefaultBlackCard>, Vec<DefaultWhiteCard>), Status> {
-        // TODO - Do this work in multiple threads.
+        let results: Vec<Result<(Vec<DefaultBlackCard>, Vec<DefaultWhiteCard>), Status>> =
+            stream::iter(default_cardpack_names.iter().cloned())
+                .map(|default_cardpack_name| {
+                    self.get_cards_from_default_cardpack(default_cardpack_name)
+                })
+                .buffered(MAX_CONCURRENT_CARDPACK_FETCHES)
+                .collect()
+                .await;
         let mut default_black_cards = Vec::new();
         let mut default_white_cards = Vec::new();
-        for default_cardpack_name in default_cardpack_names {
-            default_black_cards.append(&mut match self
-                .get_default_black_cards_from_default_cardpack(default_cardpack_name)
-                .await
-            {
-                Ok(cards) => cards,
-                Err(err) => return Err(err),
-            });
-            default_white_cards.append(&mut match self
-                .get_default_white_cards_from_default_cardpack(default_cardpack_name)
-                .await
-            {
-                Ok(cards) => cards,
-                Err(err) => return Err(err),
-            });
+        let mut failures = Vec::new();
+        for (default_cardpack_name, result) in default_cardpack_names.iter().zip(results) {
+            match result {
+                Ok((mut black_cards, mut white_cards)) => {
+                    default_black_cards.append(&mut black_cards);
+                    default_white_cards.append(&mut white_cards);
+                }
+                Err(err) => failures.push(CardpackFetchFailure::new(default_cardpack_name, &err)),
+            };
+        }
+        if !failures.is_empty() {
+            return Err(cardpack_fetch_error(&failures));
         }
         Ok((default_black_cards, default_white_cards))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cardpack_fetch_error_lists_every_failure() {
+        let err = cardpack_fetch_error(&[
+            CardpackFetchFailure::new(
+                "users/1/cardpacks/1",
+                &Status::not_found("Custom cardpack does not exist or has been deleted."),
+            ),
+            CardpackFetchFailure::new(
+                "users/1/cardpacks/2",
+                &Status::permission_denied("Caller does not have access."),
+            ),
+        ]);
+        assert_eq!(err.code(), Code::NotFound);
+        assert_eq!(
+            err.message(),
+            "Failed to fetch 2 cardpack(s): `users/1/cardpacks/1` (NotFound: Custom cardpack does not exist or has been deleted.), `users/1/cardpacks/2` (PermissionDenied: Caller does not have access.)."
+        );
+        assert_eq!(
+            err.metadata().get(FAILED_CARDPACKS_METADATA_KEY).unwrap(),
+            "users/1/cardpacks/1=NotFound,users/1/cardpacks/2=PermissionDenied"
+        );
+
+        // Failures that are worth retrying take precedence.
+        let err = cardpack_fetch_error(&[
+            CardpackFetchFailure::new("defaultCardpacks/1", &Status::not_found("")),
+            CardpackFetchFailure::new("defaultCardpacks/2", &Status::unavailable("")),
+        ]);
+        assert_eq!(err.code(), Code::Unavailable);
+    }
+}
@@ -0,0 +1,742 @@
+use super::super::metrics::record_cardpack_cache_lookup;
+use super::api_resource_fetcher::{ApiResourceFetcher, MAX_CONCURRENT_CARDPACK_FETCHES};
+use async_trait::async_trait;
+use futures_util::stream::{self, StreamExt};
+use shared::proto::crusty_cards_api::{
+    AchievementProgress, CustomBlackCard, CustomCardpack, CustomWhiteCard, DefaultBlackCard,
+    DefaultWhiteCard, User, UserSettings, UserStatsIncrement, WhiteCardStats,
+};
+use shared::proto::google::protobuf::Timestamp;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tonic::Status;
+
+// Bounds on how many custom cardpacks are cached and for how long.
+pub struct CardpackCacheLimits {
+    // Custom cardpacks can be edited at any time, so
+    // their cards are only cached for this long.
+    pub custom_cardpack_ttl: Duration,
+    pub max_custom_cardpacks: usize,
+}
+
+impl Default for CardpackCacheLimits {
+    fn default() -> Self {
+        Self {
+            custom_cardpack_ttl: Duration::from_secs(60 * 5),
+            max_custom_cardpacks: 1000,
+        }
+    }
+}
+
+struct CachedCustomCardpack {
+    black_cards: Vec<CustomBlackCard>,
+    white_cards: Vec<CustomWhiteCard>,
+    // The cardpack's update time from before its cards were fetched.
+    update_time: Option<Timestamp>,
+    fetch_time: Instant,
+}
+
+// Wraps another `ApiResourceFetcher`, caching the cards in each cardpack so
+// that popular cardpacks aren't refetched for every game. Default cardpacks
+// never change, so they're cached for as long as the service is running.
+// Cached custom cardpacks are looked up again before their cards are used,
+// so that deleted or updated cardpacks aren't served from the cache.
+// Everything other than cardpack cards is passed straight through.
+pub struct CachingApiResourceFetcher {
+    inner: Box<dyn ApiResourceFetcher>,
+    limits: CardpackCacheLimits,
+    custom_cardpacks: Mutex<HashMap<String, CachedCustomCardpack>>,
+    default_cardpacks: Mutex<HashMap<String, (Vec<DefaultBlackCard>, Vec<DefaultWhiteCard>)>>,
+}
+
+impl CachingApiResourceFetcher {
+    pub fn new(inner: Box<dyn ApiResourceFetcher>, limits: CardpackCacheLimits) -> Self {
+        Self {
+            inner,
+            limits,
+            custom_cardpacks: Mutex::new(HashMap::new()),
+            default_cardpacks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Drops a custom cardpack's cards from the cache, so that
+    // the next game using it sees the cardpack's current cards.
+    fn invalidate_custom_cardpack(&self, custom_cardpack_name: &str) {
+        self.custom_cardpacks
+            .lock()
+            .unwrap()
+            .remove(custom_cardpack_name);
+    }
+
+    // Only returns cached cards if the cardpack still exists and hasn't
+    // been updated since its cards were fetched. Cardpacks that were
+    // deleted or updated are dropped from the cache.
+    fn get_cached_custom_cardpack(
+        &self,
+        custom_cardpack_name: &str,
+        current_cardpack: &Option<CustomCardpack>,
+    ) -> Option<(Vec<CustomBlackCard>, Vec<CustomWhiteCard>)> {
+        let mut custom_cardpacks = self.custom_cardpacks.lock().unwrap();
+        let cached_cardpack = custom_cardpacks.get(custom_cardpack_name)?;
+        if cached_cardpack.fetch_time.elapsed() >= self.limits.custom_cardpack_ttl {
+            return None;
+        }
+        match current_cardpack {
+            Some(current_cardpack)
+                if current_cardpack.update_time == cached_cardpack.update_time =>
+            {
+                Some((
+                    cached_cardpack.black_cards.clone(),
+                    cached_cardpack.white_cards.clone(),
+                ))
+            }
+            _ => {
+                custom_cardpacks.remove(custom_cardpack_name);
+                None
+            }
+        }
+    }
+
+    // Looks up every cardpack concurrently. Cardpacks that couldn't be looked
+    // up are left out, so they're neither served from nor added to the cache.
+    async fn get_current_custom_cardpacks(
+        &self,
+        cardpack_names: &[String],
+    ) -> HashMap<String, Option<CustomCardpack>> {
+        let results: Vec<Result<Option<CustomCardpack>, Status>> =
+            stream::iter(cardpack_names.iter().cloned())
+                .map(|cardpack_name| self.inner.get_custom_cardpack(cardpack_name))
+                .buffered(MAX_CONCURRENT_CARDPACK_FETCHES)
+                .collect()
+                .await;
+        cardpack_names
+            .iter()
+            .zip(results)
+            .filter_map(|(cardpack_name, result)| match result {
+                Ok(custom_cardpack_or) => Some((cardpack_name.clone(), custom_cardpack_or)),
+                Err(_) => None,
+            })
+            .collect()
+    }
+
+    fn cache_custom_cardpack(
+        &self,
+        custom_cardpack_name: &str,
+        black_cards: &[CustomBlackCard],
+        white_cards: &[CustomWhiteCard],
+        update_time: Option<Timestamp>,
+    ) {
+        if self.limits.max_custom_cardpacks == 0 || self.limits.custom_cardpack_ttl.is_zero() {
+            return;
+        }
+        let mut custom_cardpacks = self.custom_cardpacks.lock().unwrap();
+        if !custom_cardpacks.contains_key(custom_cardpack_name)
+            && custom_cardpacks.len() >= self.limits.max_custom_cardpacks
+        {
+            let ttl = self.limits.custom_cardpack_ttl;
+            custom_cardpacks
+                .retain(|_, cached_cardpack| cached_cardpack.fetch_time.elapsed() < ttl);
+            // If nothing has expired, make room by evicting whichever cardpack was fetched first.
+            if custom_cardpacks.len() >= self.limits.max_custom_cardpacks {
+                let oldest_cardpack_name_or = custom_cardpacks
+                    .iter()
+                    .min_by_key(|(_, cached_cardpack)| cached_cardpack.fetch_time)
+                    .map(|(name, _)| name.clone());
+                if let Some(oldest_cardpack_name) = oldest_cardpack_name_or {
+                    custom_cardpacks.remove(&oldest_cardpack_name);
+                }
+            }
+        }
+        custom_cardpacks.insert(
+            String::from(custom_cardpack_name),
+            CachedCustomCardpack {
+                black_cards: black_cards.to_vec(),
+                white_cards: white_cards.to_vec(),
+                update_time,
+                fetch_time: Instant::now(),
+            },
+        );
+    }
+}
+
+#[async_trait]
+impl ApiResourceFetcher for CachingApiResourceFetcher {
+    async fn get_user(&self, user_name: String) -> Result<User, Status> {
+        self.inner.get_user(user_name).await
+    }
+
+    async fn get_user_settings(&self, user_name: String) -> Result<UserSettings, Status> {
+        self.inner.get_user_settings(user_name).await
+    }
+
+    async fn report_user_stats(&self, increments: Vec<UserStatsIncrement>) -> Result<(), Status> {
+        self.inner.report_user_stats(increments).await
+    }
+
+    async fn report_white_card_stats(
+        &self,
+        white_card_stats: Vec<WhiteCardStats>,
+    ) -> Result<(), Status> {
+        self.inner.report_white_card_stats(white_card_stats).await
+    }
+
+    async fn report_achievement_progress(
+        &self,
+        achievement_progress: Vec<AchievementProgress>,
+    ) -> Result<(), Status> {
+        self.inner
+            .report_achievement_progress(achievement_progress)
+            .await
+    }
+
+    // Cardpacks that turn out to have been deleted are dropped from the cache.
+    async fn custom_cardpack_exists(&self, custom_cardpack_name: String) -> Result<bool, Status> {
+        let exists = match self
+            .inner
+            .custom_cardpack_exists(custom_cardpack_name.clone())
+            .await
+        {
+            Ok(exists) => exists,
+            Err(err) => return Err(err),
+        };
+        if !exists {
+            self.invalidate_custom_cardpack(&custom_cardpack_name);
+        }
+        Ok(exists)
+    }
+
+    async fn get_custom_cardpack(
+        &self,
+        custom_cardpack_name: String,
+    ) -> Result<Option<CustomCardpack>, Status> {
+        self.inner.get_custom_cardpack(custom_cardpack_name).await
+    }
+
+    async fn default_cardpack_exists(&self, default_cardpack_name: String) -> Result<bool, Status> {
+        if self
+            .default_cardpacks
+            .lock()
+            .unwrap()
+            .contains_key(&default_cardpack_name)
+        {
+            return Ok(true);
+        }
+        self.inner
+            .default_cardpack_exists(default_cardpack_name)
+            .await
+    }
+
+    // Every uncached cardpack is fetched in a single call, so that they're fetched
+    // concurrently and a failure reports every cardpack that couldn't be fetched.
+    async fn get_custom_cards_from_multiple_custom_cardpacks(
+        &self,
+        cardpack_names: &[String],
+    ) -> Result<(Vec<CustomBlackCard>, Vec<CustomWhiteCard>), Status> {
+        // Cardpacks are looked up before their cards are fetched, so if a cardpack is
+        // updated in between, the cached update time is stale and the next lookup
+        // refetches its cards rather than serving outdated ones.
+        let mut current_cardpacks =
+            if self.limits.max_custom_cardpacks == 0 || self.limits.custom_cardpack_ttl.is_zero() {
+                HashMap::new()
+            } else {
+                self.get_current_custom_cardpacks(cardpack_names).await
+            };
+
+        let mut cards_by_cardpack = HashMap::new();
+        let mut uncached_cardpack_names = Vec::new();
+        for cardpack_name in cardpack_names {
+            let cached_cards_or = match current_cardpacks.get(cardpack_name) {
+                Some(current_cardpack) => {
+                    self.get_cached_custom_cardpack(cardpack_name, current_cardpack)
+                }
+                None => None,
+            };
+            match cached_cards_or {
+                Some(cards) => {
+                    record_cardpack_cache_lookup("custom", true);
+                    cards_by_cardpack.insert(cardpack_name.clone(), cards);
+                }
+                None => {
+                    record_cardpack_cache_lookup("custom", false);
+                    cards_by_cardpack.insert(cardpack_name.clone(), (Vec::new(), Vec::new()));
+                    uncached_cardpack_names.push(cardpack_name.clone());
+                }
+            };
+        }
+
+        if !uncached_cardpack_names.is_empty() {
+            let (black_cards, white_cards) = match self
+                .inner
+                .get_custom_cards_from_multiple_custom_cardpacks(&uncached_cardpack_names)
+                .await
+            {
+                Ok(cards) => cards,
+                Err(err) => return Err(err),
+            };
+            for black_card in black_cards {
+                if let Some(cards) =
+                    cards_by_cardpack.get_mut(get_card_parent_name(&black_card.name))
+                {
+                    cards.0.push(black_card);
+                }
+            }
+            for white_card in white_cards {
+                if let Some(cards) =
+                    cards_by_cardpack.get_mut(get_card_parent_name(&white_card.name))
+                {
+                    cards.1.push(white_card);
+                }
+            }
+            for cardpack_name in &uncached_cardpack_names {
+                if let (Some((black_cards, white_cards)), Some(Some(current_cardpack))) = (
+                    cards_by_cardpack.get(cardpack_name),
+                    current_cardpacks.remove(cardpack_name),
+                ) {
+                    self.cache_custom_cardpack(
+                        cardpack_name,
+                        black_cards,
+                        white_cards,
+                        current_cardpack.update_time,
+                    );
+                }
+            }
+        }
+
+        // Cards are returned in the same order as their cardpacks were requested.
+        let mut custom_black_cards = Vec::new();
+        let mut custom_white_cards = Vec::new();
+        for cardpack_name in cardpack_names {
+            if let Some((mut black_cards, mut white_cards)) =
+                cards_by_cardpack.remove(cardpack_name)
+            {
+                custom_black_cards.append(&mut black_cards);
+                custom_white_cards.append(&mut white_cards);
+            }
+        }
+        Ok((custom_black_cards, custom_white_cards))
+    }
+
+    async fn get_default_cards_from_multiple_default_cardpacks(
+        &self,
+        default_cardpack_names: &[String],
+    ) -> Result<(Vec<DefaultBlackCard>, Vec<DefaultWhiteCard>), Status> {
+        let mut cards_by_cardpack = HashMap::new();
+        let mut uncached_cardpack_names = Vec::new();
+        for default_cardpack_name in default_cardpack_names {
+            let cached_cards_or = self
+                .default_cardpacks
+                .lock()
+                .unwrap()
+                .get(default_cardpack_name)
+                .cloned();
+            match cached_cards_or {
+                Some(cards) => {
+                    record_cardpack_cache_lookup("default", true);
+                    cards_by_cardpack.insert(default_cardpack_name.clone(), cards);
+                }
+                None => {
+                    record_cardpack_cache_lookup("default", false);
+                    cards_by_cardpack
+                        .insert(default_cardpack_name.clone(), (Vec::new(), Vec::new()));
+                    uncached_cardpack_names.push(default_cardpack_name.clone());
+                }
+            };
+        }
+
+        if !uncached_cardpack_names.is_empty() {
+            let (black_cards, white_cards) = match self
+                .inner
+                .get_default_cards_from_multiple_default_cardpacks(&uncached_cardpack_names)
+                .await
+            {
+                Ok(cards) => cards,
+                Err(err) => return Err(err),
+            };
+            for black_card in black_cards {
+                if let Some(cards) =
+                    cards_by_cardpack.get_mut(get_card_parent_name(&black_card.name))
+                {
+                    cards.0.push(black_card);
+                }
+            }
+            for white_card in white_cards {
+                if let Some(cards) =
+                    cards_by_cardpack.get_mut(get_card_parent_name(&white_card.name))
+                {
+                    cards.1.push(white_card);
+                }
+            }
+            let mut default_cardpacks = self.default_cardpacks.lock().unwrap();
+            for default_cardpack_name in &uncached_cardpack_names {
+                if let Some(cards) = cards_by_cardpack.get(default_cardpack_name) {
+                    default_cardpacks.insert(default_cardpack_name.clone(), cards.clone());
+                }
+            }
+        }
+
+        // Cards are returned in the same order as their cardpacks were requested.
+        let mut default_black_cards = Vec::new();
+        let mut default_white_cards = Vec::new();
+        for default_cardpack_name in default_cardpack_names {
+            if let Some((mut black_cards, mut white_cards)) =
+                cards_by_cardpack.remove(default_cardpack_name)
+            {
+                default_black_cards.append(&mut black_cards);
+                default_white_cards.append(&mut white_cards);
+            }
+        }
+        Ok((default_black_cards, default_white_cards))
+    }
+}
+
+// Card names are nested under their cardpack, such as `users/1/cardpacks/2/customBlackCards/3`.
+fn get_card_parent_name(card_name: &str) -> &str {
+    card_name.rsplitn(3, '/').nth(2).unwrap_or("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::api_resource_fetcher::MockApiResourceFetcher;
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+    use std::sync::Arc;
+
+    fn create_custom_cards(
+        cardpack_names: &[String],
+    ) -> Result<(Vec<CustomBlackCard>, Vec<CustomWhiteCard>), Status> {
+        Ok((
+            cardpack_names
+                .iter()
+                .map(|cardpack_name| CustomBlackCard {
+                    name: format!("{}/customBlackCards/1", cardpack_name),
+                    ..Default::default()
+                })
+                .collect(),
+            cardpack_names
+                .iter()
+                .map(|cardpack_name| CustomWhiteCard {
+                    name: format!("{}/customWhiteCards/1", cardpack_name),
+                    ..Default::default()
+                })
+                .collect(),
+        ))
+    }
+
+    fn create_custom_cardpack(
+        custom_cardpack_name: String,
+    ) -> Result<Option<CustomCardpack>, Status> {
+        Ok(Some(CustomCardpack {
+            name: custom_cardpack_name,
+            update_time: Some(Timestamp {
+                seconds: 1,
+                nanos: 0,
+            }),
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn get_card_parent_name() {
+        assert_eq!(
+            super::get_card_parent_name("users/1/cardpacks/2/customBlackCards/3"),
+            "users/1/cardpacks/2"
+        );
+        assert_eq!(
+            super::get_card_parent_name("defaultCardpacks/1/defaultWhiteCards/2"),
+            "defaultCardpacks/1"
+        );
+    }
+
+    #[tokio::test]
+    async fn default_cardpacks_are_cached_permanently() {
+        let mut mock_api_resource_fetcher = MockApiResourceFetcher::new();
+        mock_api_resource_fetcher
+            .expect_get_default_cards_from_multiple_default_cardpacks()
+            .times(1)
+            .returning(|default_cardpack_names| {
+                Ok((
+                    default_cardpack_names
+                        .iter()
+                        .map(|default_cardpack_name| DefaultBlackCard {
+                            name: format!("{}/defaultBlackCards/1", default_cardpack_name),
+                            ..Default::default()
+                        })
+                        .collect(),
+                    Vec::new(),
+                ))
+            });
+        mock_api_resource_fetcher
+            .expect_default_cardpack_exists()
+            .times(0);
+        let caching_api_resource_fetcher = CachingApiResourceFetcher::new(
+            Box::from(mock_api_resource_fetcher),
+            CardpackCacheLimits::default(),
+        );
+
+        let cardpack_names = vec![
+            String::from("defaultCardpacks/1"),
+            String::from("defaultCardpacks/2"),
+        ];
+        for _ in 0..3 {
+            let (black_cards, white_cards) = caching_api_resource_fetcher
+                .get_default_cards_from_multiple_default_cardpacks(&cardpack_names)
+                .await
+                .unwrap();
+            assert_eq!(
+                black_cards
+                    .iter()
+                    .map(|card| card.name.as_str())
+                    .collect::<Vec<&str>>(),
+                vec![
+                    "defaultCardpacks/1/defaultBlackCards/1",
+                    "defaultCardpacks/2/defaultBlackCards/1"
+                ]
+            );
+            assert!(white_cards.is_empty());
+        }
+        assert!(caching_api_resource_fetcher
+            .default_cardpack_exists(String::from("defaultCardpacks/1"))
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn custom_cardpacks_are_cached_until_they_expire() {
+        let mut mock_api_resource_fetcher = MockApiResourceFetcher::new();
+        mock_api_resource_fetcher
+            .expect_get_custom_cards_from_multiple_custom_cardpacks()
+            .times(2)
+            .returning(create_custom_cards);
+        mock_api_resource_fetcher
+            .expect_get_custom_cardpack()
+            .returning(create_custom_cardpack);
+        let caching_api_resource_fetcher = CachingApiResourceFetcher::new(
+            Box::from(mock_api_resource_fetcher),
+            CardpackCacheLimits {
+                custom_cardpack_ttl: Duration::from_millis(50),
+                max_custom_cardpacks: 10,
+            },
+        );
+
+        let cardpack_names = vec![String::from("users/1/cardpacks/1")];
+        for _ in 0..2 {
+            let (black_cards, white_cards) = caching_api_resource_fetcher
+                .get_custom_cards_from_multiple_custom_cardpacks(&cardpack_names)
+                .await
+                .unwrap();
+            assert_eq!(black_cards.len(), 1);
+            assert_eq!(white_cards.len(), 1);
+        }
+        std::thread::sleep(Duration::from_millis(60));
+        caching_api_resource_fetcher
+            .get_custom_cards_from_multiple_custom_cardpacks(&cardpack_names)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn only_uncached_custom_cardpacks_are_fetched() {
+        let mut mock_api_resource_fetcher = MockApiResourceFetcher::new();
+        mock_api_resource_fetcher
+            .expect_get_custom_cards_from_multiple_custom_cardpacks()
+            .withf(|cardpack_names| {
+                cardpack_names.len() == 1 && cardpack_names[0] == "users/1/cardpacks/1"
+            })
+            .times(1)
+            .returning(create_custom_cards);
+        mock_api_resource_fetcher
+            .expect_get_custom_cards_from_multiple_custom_cardpacks()
+            .withf(|cardpack_names| {
+                cardpack_names.len() == 1 && cardpack_names[0] == "users/1/cardpacks/2"
+            })
+            .times(1)
+            .returning(create_custom_cards);
+        mock_api_resource_fetcher
+            .expect_get_custom_cardpack()
+            .returning(create_custom_cardpack);
+        let caching_api_resource_fetcher = CachingApiResourceFetcher::new(
+            Box::from(mock_api_resource_fetcher),
+            CardpackCacheLimits::default(),
+        );
+
+        caching_api_resource_fetcher
+            .get_custom_cards_from_multiple_custom_cardpacks(&[String::from("users/1/cardpacks/1")])
+            .await
+            .unwrap();
+        let (black_cards, _) = caching_api_resource_fetcher
+            .get_custom_cards_from_multiple_custom_cardpacks(&[
+                String::from("users/1/cardpacks/2"),
+                String::from("users/1/cardpacks/1"),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(
+            black_cards
+                .iter()
+                .map(|card| card.name.as_str())
+                .collect::<Vec<&str>>(),
+            vec![
+                "users/1/cardpacks/2/customBlackCards/1",
+                "users/1/cardpacks/1/customBlackCards/1"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn custom_cardpack_cache_is_bounded() {
+        let mut mock_api_resource_fetcher = MockApiResourceFetcher::new();
+        mock_api_resource_fetcher
+            .expect_get_custom_cards_from_multiple_custom_cardpacks()
+            .times(3)
+            .returning(create_custom_cards);
+        mock_api_resource_fetcher
+            .expect_get_custom_cardpack()
+            .returning(create_custom_cardpack);
+        let caching_api_resource_fetcher = CachingApiResourceFetcher::new(
+            Box::from(mock_api_resource_fetcher),
+            CardpackCacheLimits {
+                custom_cardpack_ttl: Duration::from_secs(60),
+                max_custom_cardpacks: 1,
+            },
+        );
+
+        // The second cardpack evicts the first, so the first is fetched again.
+        for cardpack_name in [
+            "users/1/cardpacks/1",
+            "users/1/cardpacks/2",
+            "users/1/cardpacks/1",
+        ] {
+            caching_api_resource_fetcher
+                .get_custom_cards_from_multiple_custom_cardpacks(&[String::from(cardpack_name)])
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn deleted_custom_cardpacks_are_invalidated() {
+        let mut mock_api_resource_fetcher = MockApiResourceFetcher::new();
+        mock_api_resource_fetcher
+            .expect_get_custom_cards_from_multiple_custom_cardpacks()
+            .times(2)
+            .returning(create_custom_cards);
+        mock_api_resource_fetcher
+            .expect_custom_cardpack_exists()
+            .return_once(|_| Ok(false));
+        mock_api_resource_fetcher
+            .expect_get_custom_cardpack()
+            .returning(create_custom_cardpack);
+        let caching_api_resource_fetcher = CachingApiResourceFetcher::new(
+            Box::from(mock_api_resource_fetcher),
+            CardpackCacheLimits::default(),
+        );
+
+        let cardpack_names = vec![String::from("users/1/cardpacks/1")];
+        caching_api_resource_fetcher
+            .get_custom_cards_from_multiple_custom_cardpacks(&cardpack_names)
+            .await
+            .unwrap();
+        assert!(!caching_api_resource_fetcher
+            .custom_cardpack_exists(cardpack_names[0].clone())
+            .await
+            .unwrap());
+        caching_api_resource_fetcher
+            .get_custom_cards_from_multiple_custom_cardpacks(&cardpack_names)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn deleted_custom_cardpacks_are_not_served_from_cache() {
+        let is_deleted = Arc::new(AtomicBool::new(false));
+        let mut mock_api_resource_fetcher = MockApiResourceFetcher::new();
+        let is_deleted_clone = is_deleted.clone();
+        mock_api_resource_fetcher
+            .expect_get_custom_cards_from_multiple_custom_cardpacks()
+            .times(2)
+            .returning(move |cardpack_names| {
+                if is_deleted_clone.load(Ordering::SeqCst) {
+                    Err(Status::not_found(
+                        "Custom cardpack does not exist or has been deleted.",
+                    ))
+                } else {
+                    create_custom_cards(cardpack_names)
+                }
+            });
+        let is_deleted_clone = is_deleted.clone();
+        mock_api_resource_fetcher
+            .expect_get_custom_cardpack()
+            .returning(move |custom_cardpack_name| {
+                if is_deleted_clone.load(Ordering::SeqCst) {
+                    Ok(None)
+                } else {
+                    create_custom_cardpack(custom_cardpack_name)
+                }
+            });
+        let caching_api_resource_fetcher = CachingApiResourceFetcher::new(
+            Box::from(mock_api_resource_fetcher),
+            CardpackCacheLimits::default(),
+        );
+
+        let cardpack_names = vec![String::from("users/1/cardpacks/1")];
+        caching_api_resource_fetcher
+            .get_custom_cards_from_multiple_custom_cardpacks(&cardpack_names)
+            .await
+            .unwrap();
+        is_deleted.store(true, Ordering::SeqCst);
+        assert_eq!(
+            caching_api_resource_fetcher
+                .get_custom_cards_from_multiple_custom_cardpacks(&cardpack_names)
+                .await
+                .unwrap_err()
+                .code(),
+            tonic::Code::NotFound
+        );
+        assert!(caching_api_resource_fetcher
+            .custom_cardpacks
+            .lock()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn updated_custom_cardpacks_are_refetched() {
+        let update_time_seconds = Arc::new(AtomicI64::new(1));
+        let mut mock_api_resource_fetcher = MockApiResourceFetcher::new();
+        mock_api_resource_fetcher
+            .expect_get_custom_cards_from_multiple_custom_cardpacks()
+            .times(2)
+            .returning(create_custom_cards);
+        let update_time_seconds_clone = update_time_seconds.clone();
+        mock_api_resource_fetcher
+            .expect_get_custom_cardpack()
+            .returning(move |custom_cardpack_name| {
+                Ok(Some(CustomCardpack {
+                    name: custom_cardpack_name,
+                    update_time: Some(Timestamp {
+                        seconds: update_time_seconds_clone.load(Ordering::SeqCst),
+                        nanos: 0,
+                    }),
+                    ..Default::default()
+                }))
+            });
+        let caching_api_resource_fetcher = CachingApiResourceFetcher::new(
+            Box::from(mock_api_resource_fetcher),
+            CardpackCacheLimits::default(),
+        );
+
+        // The second call is served from the cache, and the third
+        // refetches the cards since the cardpack was updated.
+        let cardpack_names = vec![String::from("users/1/cardpacks/1")];
+        for i in 0..3 {
+            if i == 2 {
+                update_time_seconds.store(2, Ordering::SeqCst);
+            }
+            caching_api_resource_fetcher
+                .get_custom_cards_from_multiple_custom_cardpacks(&cardpack_names)
+                .await
+                .unwrap();
+        }
+    }
+}
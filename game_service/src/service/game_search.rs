@@ -0,0 +1,257 @@
+use crate::helper::fold_text;
+use shared::proto::crusty_cards_api::search_games_request::{GameStageFilter, OrderBy};
+use shared::proto::crusty_cards_api::{GameInfo, SearchGamesRequest};
+
+pub fn game_matches_search_request(
+    game_info: &GameInfo,
+    request: &SearchGamesRequest,
+    game_stage_filter: GameStageFilter,
+) -> bool {
+    let config = match &game_info.config {
+        Some(config) => config,
+        None => return false,
+    };
+
+    if !fold_text(&config.display_name).contains(&fold_text(&request.query)) {
+        return false;
+    }
+
+    let open_player_slots = config.max_players - game_info.player_count;
+    if open_player_slots < request.min_available_player_slots {
+        return false;
+    }
+    if request.has_open_seats && open_player_slots <= 0 {
+        return false;
+    }
+
+    let passes_game_stage_filter = match game_stage_filter {
+        GameStageFilter::Unspecified => false,
+        GameStageFilter::FilterNone => true,
+        GameStageFilter::FilterStopped => !game_info.is_running,
+        GameStageFilter::FilterRunning => game_info.is_running,
+    };
+    if !passes_game_stage_filter {
+        return false;
+    }
+
+    if !request.owner_filter.is_empty() {
+        match &game_info.owner {
+            Some(owner) if owner.name == request.owner_filter => {}
+            _ => return false,
+        };
+    }
+
+    if !request.cardpack_filter.is_empty()
+        && !config
+            .custom_cardpack_names
+            .iter()
+            .chain(config.default_cardpack_names.iter())
+            .any(|cardpack_name| cardpack_name == &request.cardpack_filter)
+    {
+        return false;
+    }
+
+    true
+}
+
+// Game ids are used as a tiebreaker so that the order is
+// stable across requests, which keeps pagination consistent.
+pub fn sort_games(games: &mut [GameInfo], order_by: OrderBy) {
+    match order_by {
+        // Games are returned in the order they were created, oldest first, unless the
+        // caller asks for something else. Sorting by create time rather than keeping the
+        // local insertion order also interleaves games from peer instances correctly.
+        OrderBy::Unspecified => games.sort_by(|a, b| {
+            get_create_time_key(a)
+                .cmp(&get_create_time_key(b))
+                .then_with(|| a.game_id.cmp(&b.game_id))
+        }),
+        OrderBy::Newest => games.sort_by(|a, b| {
+            get_create_time_key(b)
+                .cmp(&get_create_time_key(a))
+                .then_with(|| a.game_id.cmp(&b.game_id))
+        }),
+        OrderBy::MostPlayers => games.sort_by(|a, b| {
+            b.player_count
+                .cmp(&a.player_count)
+                .then_with(|| get_create_time_key(b).cmp(&get_create_time_key(a)))
+                .then_with(|| a.game_id.cmp(&b.game_id))
+        }),
+        OrderBy::RecentActivity => games.sort_by(|a, b| {
+            get_last_activity_time_key(b)
+                .cmp(&get_last_activity_time_key(a))
+                .then_with(|| a.game_id.cmp(&b.game_id))
+        }),
+    };
+}
+
+fn get_create_time_key(game_info: &GameInfo) -> Option<(i64, i32)> {
+    game_info
+        .create_time
+        .as_ref()
+        .map(|create_time| (create_time.seconds, create_time.nanos))
+}
+
+fn get_last_activity_time_key(game_info: &GameInfo) -> Option<(i64, i32)> {
+    game_info
+        .last_activity_time
+        .as_ref()
+        .map(|last_activity_time| (last_activity_time.seconds, last_activity_time.nanos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::proto::crusty_cards_api::User;
+    use shared::proto::google::protobuf::Timestamp;
+    use shared::test_helper::get_valid_test_game_config;
+
+    fn create_game_info(
+        game_id: &str,
+        player_count: i32,
+        create_time_seconds: i64,
+        last_activity_time_seconds: i64,
+    ) -> GameInfo {
+        GameInfo {
+            game_id: String::from(game_id),
+            config: Some(get_valid_test_game_config()),
+            player_count,
+            owner: Some(User {
+                name: String::from("users/owner"),
+                display_name: String::from(""),
+                create_time: None,
+                update_time: None,
+            }),
+            is_running: false,
+            create_time: Some(Timestamp {
+                seconds: create_time_seconds,
+                nanos: 0,
+            }),
+            last_activity_time: Some(Timestamp {
+                seconds: last_activity_time_seconds,
+                nanos: 0,
+            }),
+        }
+    }
+
+    fn create_search_games_request() -> SearchGamesRequest {
+        SearchGamesRequest {
+            query: String::from(""),
+            min_available_player_slots: 0,
+            game_stage_filter: GameStageFilter::FilterNone.into(),
+            owner_filter: String::from(""),
+            cardpack_filter: String::from(""),
+            has_open_seats: false,
+            exclude_banned_user: String::from(""),
+            order_by: OrderBy::Unspecified.into(),
+            page_size: 0,
+            page_token: String::from(""),
+        }
+    }
+
+    fn get_game_ids(games: &[GameInfo]) -> Vec<&str> {
+        games.iter().map(|game| &game.game_id[..]).collect()
+    }
+
+    #[test]
+    fn query_ignores_case_and_accents() {
+        let mut game_info = create_game_info("1", 1, 0, 0);
+        game_info.config.as_mut().unwrap().display_name = String::from("Café Crüsty");
+        let mut request = create_search_games_request();
+
+        request.query = String::from("cafe");
+        assert!(game_matches_search_request(
+            &game_info,
+            &request,
+            GameStageFilter::FilterNone
+        ));
+        request.query = String::from("CRUSTY");
+        assert!(game_matches_search_request(
+            &game_info,
+            &request,
+            GameStageFilter::FilterNone
+        ));
+        request.query = String::from("tea");
+        assert!(!game_matches_search_request(
+            &game_info,
+            &request,
+            GameStageFilter::FilterNone
+        ));
+    }
+
+    #[test]
+    fn filters_by_owner_cardpack_and_open_seats() {
+        // Test game config has 3 max players.
+        let game_info = create_game_info("1", 3, 0, 0);
+        let mut request = create_search_games_request();
+        assert!(game_matches_search_request(
+            &game_info,
+            &request,
+            GameStageFilter::FilterNone
+        ));
+
+        request.has_open_seats = true;
+        assert!(!game_matches_search_request(
+            &game_info,
+            &request,
+            GameStageFilter::FilterNone
+        ));
+
+        request = create_search_games_request();
+        request.owner_filter = String::from("users/owner");
+        assert!(game_matches_search_request(
+            &game_info,
+            &request,
+            GameStageFilter::FilterNone
+        ));
+        request.owner_filter = String::from("users/someone_else");
+        assert!(!game_matches_search_request(
+            &game_info,
+            &request,
+            GameStageFilter::FilterNone
+        ));
+
+        request = create_search_games_request();
+        request.cardpack_filter = String::from("test_custom_cardpack_name");
+        assert!(game_matches_search_request(
+            &game_info,
+            &request,
+            GameStageFilter::FilterNone
+        ));
+        request.cardpack_filter = String::from("test_default_cardpack_name");
+        assert!(game_matches_search_request(
+            &game_info,
+            &request,
+            GameStageFilter::FilterNone
+        ));
+        request.cardpack_filter = String::from("some_other_cardpack_name");
+        assert!(!game_matches_search_request(
+            &game_info,
+            &request,
+            GameStageFilter::FilterNone
+        ));
+    }
+
+    #[test]
+    fn sorts_games() {
+        let mut games = vec![
+            create_game_info("a", 2, 100, 500),
+            create_game_info("b", 3, 300, 400),
+            create_game_info("c", 1, 200, 600),
+            create_game_info("d", 3, 100, 400),
+        ];
+
+        sort_games(&mut games, OrderBy::Newest);
+        assert_eq!(get_game_ids(&games), vec!["b", "c", "a", "d"]);
+
+        sort_games(&mut games, OrderBy::MostPlayers);
+        assert_eq!(get_game_ids(&games), vec!["b", "d", "a", "c"]);
+
+        sort_games(&mut games, OrderBy::RecentActivity);
+        assert_eq!(get_game_ids(&games), vec!["c", "a", "b", "d"]);
+
+        // Defaults to oldest first.
+        sort_games(&mut games, OrderBy::Unspecified);
+        assert_eq!(get_game_ids(&games), vec!["a", "d", "c", "b"]);
+    }
+}
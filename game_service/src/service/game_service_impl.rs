@@ -1,33 +1,69 @@
 use super::super::game::game_indexer::GameIndexer;
 use super::super::game::player_id::PlayerId;
-use super::super::game::Game;
+use super::super::game::{Game, GameCards, DEFAULT_MAX_CHAT_MESSAGES_PER_GAME};
+use super::super::metrics::{record_amqp_publish_failure, record_game_metrics};
 use super::api_resource_fetcher::ApiResourceFetcher;
+use super::game_search::{game_matches_search_request, sort_games};
+use super::quick_join::rank_games_for_quick_join;
+use super::quick_start::clamp_game_config_to_limits;
 use crate::amqp::MessageQueue;
+use crate::cluster::ClusterRouter;
 use clokwerk::{Interval, ScheduleHandle, Scheduler};
+use shared::auth::{check_caller_owns_resource, is_internal_caller};
 use shared::grpc_error::{
     empty_request_field_error, missing_request_field_error, negative_request_field_error,
 };
+use shared::page_token::{create_page_token_for_index, parse_page_token_index};
 use shared::proto::crusty_cards_api::{
-    game_service_server::GameService, search_games_request::GameStageFilter,
-    AddArtificialPlayerRequest, BanUserRequest, CreateChatMessageRequest, CreateGameRequest,
-    GameInfo, GameView, GetGameViewRequest, JoinGameRequest, KickUserRequest, LeaveGameRequest,
-    ListWhiteCardTextsRequest, ListWhiteCardTextsResponse, PlayCardsRequest,
+    game_service_server::GameService,
+    search_games_request::{GameStageFilter, OrderBy},
+    AchievementProgress, AddArtificialPlayerRequest, BanUserRequest, CreateChatMessageRequest,
+    CreateGameRequest, GameInfo, GameView, GetGameViewRequest, GetUserDirectoryEntryRequest,
+    JoinGameRequest, KickUserRequest, LeaveGameRequest, ListBlackCardTextsRequest,
+    ListBlackCardTextsResponse, ListWhiteCardTextsRequest, ListWhiteCardTextsResponse,
+    PlayCardsRequest, QuickJoinRequest, QuickStartGameRequest, QuickStartGameResponse,
     RemoveArtificialPlayerRequest, SearchGamesRequest, SearchGamesResponse, StartGameRequest,
-    StopGameRequest, UnbanUserRequest, UnplayCardsRequest, VoteCardRequest,
-    VoteStartNextRoundRequest,
+    StopGameRequest, UnbanUserRequest, UnplayCardsRequest, UpdateGameConfigRequest,
+    UpdateUserDirectoryEntryRequest, UserDirectoryEntry, UserStatsIncrement, VoteCardRequest,
+    VoteStartNextRoundRequest, WhiteCardStats,
 };
 use shared::proto::google::protobuf::Empty;
-use shared::proto_validation::ValidatedGameConfig;
+use shared::proto_validation::{BoundedPageSize, ValidatedGameConfig};
+use shared::time::system_time_to_timestamp_proto;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::sync::Mutex;
-use std::time::Duration;
-use tonic::{Request, Response, Status};
+use std::time::{Duration, SystemTime};
+use tonic::{Code, Request, Response, Status};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
+const SERVER_RESTARTING_MESSAGE: &str =
+    "The server hosting this game is restarting, so this game will end shortly.";
+
+// Limits that apply to every game hosted by this instance.
+pub struct GameLimits {
+    // Games that haven't had any activity for this long are removed.
+    pub unused_game_ttl: Duration,
+    pub max_chat_messages_per_game: usize,
+}
+
+impl Default for GameLimits {
+    fn default() -> Self {
+        Self {
+            unused_game_ttl: Duration::from_secs(60 * 60 * 4),
+            max_chat_messages_per_game: DEFAULT_MAX_CHAT_MESSAGES_PER_GAME,
+        }
+    }
+}
+
 pub struct GameServiceImpl {
     games: Arc<Mutex<GameIndexer>>,
     resource_fetcher: Box<dyn ApiResourceFetcher>,
     message_queue_or: Option<MessageQueue>,
+    cluster_router: ClusterRouter,
+    max_chat_messages_per_game: usize,
+    is_shutting_down: AtomicBool,
     #[allow(dead_code)]
     // We only need the handle here to make sure that the recurring thread is dropped whenever this struct is dropped.
     schedule_handle: ScheduleHandle,
@@ -37,38 +73,286 @@ impl GameServiceImpl {
     pub fn new(
         resource_fetcher: Box<dyn ApiResourceFetcher>,
         message_queue_or: Option<MessageQueue>,
+        cluster_router: ClusterRouter,
+        game_limits: GameLimits,
     ) -> GameServiceImpl {
         let games = Arc::new(Mutex::new(GameIndexer::new()));
         let games_scheduler_clone = games.clone();
+        let unused_game_ttl = game_limits.unused_game_ttl;
         let mut scheduler = Scheduler::new();
         scheduler.every(Interval::Minutes(1)).run(move || {
-            // Remove games that weren't used in the past 4 hours.
             games_scheduler_clone
                 .clone()
                 .lock()
                 .unwrap()
-                .remove_unused_games(Duration::from_secs(60 * 60 * 4));
+                .remove_unused_games(unused_game_ttl);
+        });
+        let games_metrics_clone = games.clone();
+        scheduler.every(Interval::Seconds(15)).run(move || {
+            record_game_metrics(
+                games_metrics_clone
+                    .lock()
+                    .unwrap()
+                    .get_games_by_insert_time(),
+            );
         });
         let schedule_handle = scheduler.watch_thread(Duration::from_millis(100));
         GameServiceImpl {
             games,
             resource_fetcher,
             message_queue_or,
+            cluster_router,
+            max_chat_messages_per_game: game_limits.max_chat_messages_per_game,
+            is_shutting_down: AtomicBool::new(false),
             schedule_handle,
         }
     }
 
-    fn generate_game_id() -> String {
-        Uuid::new_v4().to_simple().to_string()
+    // Stops new games from being created and warns every player on this
+    // instance that their game is about to end. Games only live in memory,
+    // so there's no game state to flush before the process exits.
+    pub async fn begin_shutdown(&self) {
+        self.is_shutting_down.store(true, Ordering::SeqCst);
+
+        let user_names = {
+            let mut games = self.games.lock().unwrap();
+            let mut user_names = Vec::new();
+            for game in games.get_games_by_insert_time_mut() {
+                game.post_system_message(String::from(SERVER_RESTARTING_MESSAGE));
+                user_names.append(&mut game.get_user_names_for_all_real_players());
+            }
+            user_names
+        };
+
+        info!(
+            user_count = user_names.len(),
+            "Notifying users that the server is restarting."
+        );
+        if let Some(message_queue) = &self.message_queue_or {
+            if let Err(err) = message_queue
+                .server_restarting_for_users(user_names.clone())
+                .await
+            {
+                record_amqp_publish_failure();
+                error!(error = %err, "Failed to publish server restarting message.");
+            }
+        }
+        // Clients that only handle game updates will still see the system chat message.
+        self.try_send_amqp_game_update_message_to_users(user_names)
+            .await;
+    }
+
+    // Games are always created on the instance that receives the `create_game`
+    // request, so we only hand out ids that hash to this instance.
+    fn generate_game_id(&self) -> String {
+        loop {
+            let game_id = Uuid::new_v4().to_simple().to_string();
+            if self.cluster_router.owns_game(&game_id) {
+                return game_id;
+            }
+        }
+    }
+
+    fn user_is_in_local_game(&self, user_name: &str) -> bool {
+        self.games
+            .lock()
+            .unwrap()
+            .get_game_by_player_id(&PlayerId::RealUser(String::from(user_name)))
+            .is_some()
+    }
+
+    fn local_game_contains_user(&self, game_id: &str, user_name: &str) -> bool {
+        self.games
+            .lock()
+            .unwrap()
+            .get_game_by_player_id(&PlayerId::RealUser(String::from(user_name)))
+            .map_or(false, |game| game.get_game_id() == game_id)
+    }
+
+    async fn user_is_in_game(&self, user_name: &str, game_id: &str) -> Result<bool, Status> {
+        if self.cluster_router.owns_game(game_id) {
+            return Ok(self.local_game_contains_user(game_id, user_name));
+        }
+        self.cluster_router
+            .peer_game_contains_user(game_id, user_name)
+            .await
+    }
+
+    // Checks the user directory, so games hosted by other instances are covered too.
+    async fn check_user_is_not_in_game(&self, user_name: &str) -> Result<(), Status> {
+        if let Some(game_id) = self.cluster_router.get_user_game_id(user_name).await? {
+            if self.user_is_in_game(user_name, &game_id).await? {
+                return Err(Status::invalid_argument("User is already in a game."));
+            }
+        }
+        Ok(())
+    }
+
+    // Points the user's directory entry at a game hosted by this instance. This
+    // must succeed before the user is added to the game, and fails if the user
+    // is still in a different game anywhere in the cluster.
+    async fn claim_user_directory_entry(
+        &self,
+        user_name: &str,
+        game_id: &str,
+    ) -> Result<(), Status> {
+        let mut expected_game_id = String::from("");
+        loop {
+            let current_game_id = self
+                .cluster_router
+                .update_user_directory_entry(user_name, &expected_game_id, game_id)
+                .await?;
+            if current_game_id == game_id {
+                return Ok(());
+            }
+            if self.user_is_in_game(user_name, &current_game_id).await? {
+                return Err(Status::invalid_argument("User is already in a game."));
+            }
+            // Entries are left behind when their game is removed for inactivity,
+            // so an entry for a game that the user isn't in can be taken over.
+            expected_game_id = current_game_id;
+        }
+    }
+
+    // Called whenever a user leaves a game hosted by this instance. Failures are only logged,
+    // since a leftover entry is taken over the next time the user joins a game.
+    async fn release_user_directory_entry(&self, user_name: &str, game_id: &str) {
+        // A concurrent request may have added the user to the same game again.
+        if self.local_game_contains_user(game_id, user_name) {
+            return;
+        }
+        if let Err(err) = self
+            .cluster_router
+            .update_user_directory_entry(user_name, game_id, "")
+            .await
+        {
+            warn!(user_name, error = %err, "Failed to release user directory entry.");
+        }
+    }
+
+    async fn create_game_with_owner(
+        &self,
+        user_name: String,
+        validated_game_config: ValidatedGameConfig,
+    ) -> Result<GameView, Status> {
+        if self.is_shutting_down.load(Ordering::SeqCst) {
+            return Err(Status::unavailable(
+                "Server is shutting down, so new games can't be created on it.",
+            ));
+        }
+
+        let (black_cards, white_cards) = match self
+            .resource_fetcher
+            .get_custom_cards_from_multiple_custom_cardpacks(
+                validated_game_config.get_custom_cardpack_names(),
+            )
+            .await
+        {
+            Ok(res) => res,
+            Err(err) => return Err(err),
+        };
+
+        let (default_black_cards, default_white_cards) = match self
+            .resource_fetcher
+            .get_default_cards_from_multiple_default_cardpacks(
+                validated_game_config.get_default_cardpack_names(),
+            )
+            .await
+        {
+            Ok(res) => res,
+            Err(err) => return Err(err),
+        };
+
+        let user = match self.resource_fetcher.get_user(user_name.clone()).await {
+            Ok(user) => user,
+            Err(err) => return Err(err),
+        };
+
+        let game_id = self.generate_game_id();
+        self.claim_user_directory_entry(&user_name, &game_id)
+            .await?;
+
+        let game_view_or = {
+            let mut games = self.games.lock().unwrap();
+            if games
+                .get_game_by_player_id(&PlayerId::RealUser(user_name.clone()))
+                .is_some()
+            {
+                Err(Status::invalid_argument(format!(
+                    "User {} is already in a game.",
+                    user_name
+                )))
+            } else {
+                Game::new_with_owner(
+                    game_id.clone(),
+                    validated_game_config,
+                    black_cards,
+                    white_cards,
+                    default_black_cards,
+                    default_white_cards,
+                    user,
+                    self.max_chat_messages_per_game,
+                )
+                .map(|game| {
+                    let game_view = game.get_user_view(&user_name).unwrap();
+                    games.insert_game(game);
+                    game_view
+                })
+            }
+        };
+        if game_view_or.is_err() {
+            self.release_user_directory_entry(&user_name, &game_id)
+                .await;
+        }
+        game_view_or
+    }
+
+    // Stats are best-effort, so failing to report them shouldn't fail the request that earned them.
+    async fn try_report_user_stats(&self, increments: Vec<UserStatsIncrement>) {
+        if increments.is_empty() {
+            return;
+        }
+        if let Err(err) = self.resource_fetcher.report_user_stats(increments).await {
+            warn!(error = %err, "Failed to report user stats.");
+        }
+    }
+
+    async fn try_report_achievement_progress(
+        &self,
+        achievement_progress: Vec<AchievementProgress>,
+    ) {
+        if achievement_progress.is_empty() {
+            return;
+        }
+        if let Err(err) = self
+            .resource_fetcher
+            .report_achievement_progress(achievement_progress)
+            .await
+        {
+            warn!(error = %err, "Failed to report achievement progress.");
+        }
+    }
+
+    async fn try_report_white_card_stats(&self, white_card_stats: Vec<WhiteCardStats>) {
+        if white_card_stats.is_empty() {
+            return;
+        }
+        if let Err(err) = self
+            .resource_fetcher
+            .report_white_card_stats(white_card_stats)
+            .await
+        {
+            warn!(error = %err, "Failed to report white card stats.");
+        }
     }
 
     async fn try_send_amqp_game_update_message_to_users(&self, user_names: Vec<impl ToString>) {
         match &self.message_queue_or {
             Some(message_queue) => {
-                message_queue
-                    .game_updated_for_users(user_names)
-                    .await
-                    .unwrap();
+                if let Err(err) = message_queue.game_updated_for_users(user_names).await {
+                    record_amqp_publish_failure();
+                    error!(error = %err, "Failed to publish game update message.");
+                }
             }
             None => {}
         };
@@ -102,45 +386,99 @@ impl GameService for GameServiceImpl {
             return Err(negative_request_field_error("min_available_player_slots"));
         }
 
+        let order_by = match OrderBy::from_i32(request.get_ref().order_by) {
+            Some(order_by) => order_by,
+            None => {
+                return Err(Status::invalid_argument(
+                    "Request contains invalid value for OrderBy.",
+                ))
+            }
+        };
+
+        // Searches without a page size only return the first `default_page_size` games,
+        // so callers that want every matching game need to follow `next_page_token`.
+        let bounded_page_size = BoundedPageSize::new(request.get_ref().page_size)?;
+
+        // Anyone could use `exclude_banned_user` to find out which games have banned
+        // another user, so it's only honoured for requests from inside the cluster,
+        // such as the searches that quick join sends to peer instances.
+        let exclude_banned_user =
+            if ClusterRouter::is_forwarded_request(&request) || is_internal_caller(&request) {
+                request.get_ref().exclude_banned_user.clone()
+            } else {
+                String::from("")
+            };
+
+        let request_without_page_token = {
+            let mut req = request.get_ref().clone();
+            req.page_token = String::from("");
+            req.page_size = 0;
+            req.exclude_banned_user = exclude_banned_user.clone();
+            req
+        };
+
+        let mut start_index: usize = 0;
+
+        if !request.get_ref().page_token.is_empty() {
+            start_index =
+                parse_page_token_index(&request_without_page_token, &request.get_ref().page_token)?;
+        }
+
         let mut game_info_list: Vec<GameInfo> = self
             .games
             .lock()
             .unwrap()
             .get_games_by_insert_time()
             .iter()
+            // Ban lists aren't part of `GameInfo`, so this filter is applied to the games themselves.
+            .filter(|game| {
+                exclude_banned_user.is_empty() || !game.user_is_banned(&exclude_banned_user)
+            })
             .map(|game| game.get_game_info())
+            .filter(|game_info| {
+                game_matches_search_request(game_info, request.get_ref(), game_stage_filter)
+            })
             .collect();
 
-        game_info_list.retain(|game_info| match &game_info.config {
-            Some(config) => {
-                if !config.display_name.contains(&request.get_ref().query) {
-                    return false;
-                }
+        // Forwarded searches come from another instance which merges results from every
+        // instance, so we return all matching games and leave sorting and paging to the caller.
+        if ClusterRouter::is_forwarded_request(&request) {
+            return Ok(Response::new(SearchGamesResponse {
+                total_size: game_info_list.len() as i64,
+                games: game_info_list,
+                next_page_token: String::from(""),
+            }));
+        }
 
-                let open_player_slots = config.max_players - game_info.player_count;
-                if open_player_slots < request.get_ref().min_available_player_slots {
-                    return false;
-                }
+        game_info_list.append(
+            &mut self
+                .cluster_router
+                .search_peer_games(&request_without_page_token)
+                .await,
+        );
+        sort_games(&mut game_info_list, order_by);
 
-                let passes_game_stage_filter = match game_stage_filter {
-                    GameStageFilter::Unspecified => false,
-                    GameStageFilter::FilterNone => true,
-                    GameStageFilter::FilterStopped => !game_info.is_running,
-                    GameStageFilter::FilterRunning => game_info.is_running,
-                };
-                if !passes_game_stage_filter {
-                    return false;
-                }
+        let total_size = game_info_list.len();
+        let end_index = std::cmp::min(
+            start_index + bounded_page_size.take_i64() as usize,
+            total_size,
+        );
+        let games = if start_index < end_index {
+            game_info_list.drain(start_index..end_index).collect()
+        } else {
+            Vec::new()
+        };
 
-                true
-            }
-            None => false,
-        });
+        let mut next_page_token = String::from("");
+        if !games.is_empty() && total_size > end_index {
+            next_page_token = create_page_token_for_index(&request_without_page_token, end_index);
+        }
 
-        let response = SearchGamesResponse {
-            games: game_info_list,
-        };
-        Ok(Response::new(response))
+        Ok(Response::new(SearchGamesResponse {
+            games,
+            next_page_token,
+            total_size: total_size as i64,
+        }))
     }
 
     async fn create_game(
@@ -150,6 +488,7 @@ impl GameService for GameServiceImpl {
         if request.get_ref().user_name.is_empty() {
             return Err(empty_request_field_error("user_name"));
         }
+        check_caller_owns_resource(&request, &request.get_ref().user_name)?;
 
         let user_name = String::from(&request.get_ref().user_name);
 
@@ -163,58 +502,240 @@ impl GameService for GameServiceImpl {
             Err(err) => return Err(err),
         };
 
-        let (black_cards, white_cards) = match self
+        let game_view = self
+            .create_game_with_owner(user_name, validated_game_config)
+            .await?;
+        Ok(Response::new(game_view))
+    }
+
+    async fn quick_start_game(
+        &self,
+        request: Request<QuickStartGameRequest>,
+    ) -> Result<Response<QuickStartGameResponse>, Status> {
+        if request.get_ref().user_name.is_empty() {
+            return Err(empty_request_field_error("user_name"));
+        }
+        check_caller_owns_resource(&request, &request.get_ref().user_name)?;
+
+        let user_name = String::from(&request.get_ref().user_name);
+
+        let user_settings = match self
             .resource_fetcher
-            .get_custom_cards_from_multiple_custom_cardpacks(
-                validated_game_config.get_custom_cardpack_names(),
-            )
+            .get_user_settings(user_name.clone())
             .await
         {
-            Ok(res) => res,
+            Ok(user_settings) => user_settings,
             Err(err) => return Err(err),
         };
 
-        let (default_black_cards, default_white_cards) = match self
-            .resource_fetcher
-            .get_default_cards_from_multiple_default_cardpacks(
-                validated_game_config.get_default_cardpack_names(),
-            )
-            .await
-        {
-            Ok(res) => res,
-            Err(err) => return Err(err),
+        let mut game_config = match user_settings.quick_start_game_config {
+            Some(game_config) => game_config,
+            None => {
+                return Err(Status::failed_precondition(
+                    "User does not have a saved quick start game config.",
+                ))
+            }
+        };
+
+        let mut adjustments = clamp_game_config_to_limits(&mut game_config);
+
+        let mut custom_cardpack_names = Vec::new();
+        for custom_cardpack_name in game_config.custom_cardpack_names.drain(..) {
+            match self
+                .resource_fetcher
+                .custom_cardpack_exists(custom_cardpack_name.clone())
+                .await
+            {
+                Ok(true) => custom_cardpack_names.push(custom_cardpack_name),
+                Ok(false) => adjustments.push(format!(
+                    "Removed custom cardpack `{}` because it has been deleted.",
+                    custom_cardpack_name
+                )),
+                Err(err) => return Err(err),
+            };
+        }
+        game_config.custom_cardpack_names = custom_cardpack_names;
+
+        let mut default_cardpack_names = Vec::new();
+        for default_cardpack_name in game_config.default_cardpack_names.drain(..) {
+            match self
+                .resource_fetcher
+                .default_cardpack_exists(default_cardpack_name.clone())
+                .await
+            {
+                Ok(true) => default_cardpack_names.push(default_cardpack_name),
+                Ok(false) => adjustments.push(format!(
+                    "Removed default cardpack `{}` because it no longer exists.",
+                    default_cardpack_name
+                )),
+                Err(err) => return Err(err),
+            };
+        }
+        game_config.default_cardpack_names = default_cardpack_names;
+
+        let validated_game_config = match ValidatedGameConfig::new(game_config) {
+            Ok(validated_game_config) => validated_game_config,
+            Err(err) => {
+                return Err(Status::failed_precondition(format!(
+                    "Saved quick start game config is no longer valid. {}",
+                    err.message()
+                )))
+            }
         };
 
+        let game_view = self
+            .create_game_with_owner(user_name, validated_game_config)
+            .await?;
+        Ok(Response::new(QuickStartGameResponse {
+            game_view: Some(game_view),
+            adjustments,
+        }))
+    }
+
+    async fn quick_join(
+        &self,
+        request: Request<QuickJoinRequest>,
+    ) -> Result<Response<GameView>, Status> {
+        if request.get_ref().user_name.is_empty() {
+            return Err(empty_request_field_error("user_name"));
+        }
+        check_caller_owns_resource(&request, &request.get_ref().user_name)?;
+
+        let user_name = String::from(&request.get_ref().user_name);
+
         let user = match self.resource_fetcher.get_user(user_name.clone()).await {
             Ok(user) => user,
             Err(err) => return Err(err),
         };
 
-        let mut games = self.games.lock().unwrap();
-        if games
-            .get_game_by_player_id(&PlayerId::RealUser(user_name.clone()))
-            .is_some()
+        if self.user_is_in_local_game(&user_name) {
+            return Err(Status::invalid_argument("User is already in a game."));
+        }
+        self.check_user_is_not_in_game(&user_name).await?;
+
+        // The cardpacks in the user's quick start config are the best signal we have
+        // for which cardpacks they like to play with.
+        let preferred_cardpack_names = match self
+            .resource_fetcher
+            .get_user_settings(user_name.clone())
+            .await
         {
-            return Err(Status::invalid_argument(format!(
-                "User {} is already in a game.",
-                user_name
-            )));
-        }
-        let game = match Game::new_with_owner(
-            GameServiceImpl::generate_game_id(),
-            validated_game_config,
-            black_cards,
-            white_cards,
-            default_black_cards,
-            default_white_cards,
-            user,
-        ) {
-            Ok(game) => game,
+            Ok(user_settings) => match user_settings.quick_start_game_config {
+                Some(game_config) => game_config
+                    .custom_cardpack_names
+                    .into_iter()
+                    .chain(game_config.default_cardpack_names.into_iter())
+                    .collect(),
+                None => Vec::new(),
+            },
             Err(err) => return Err(err),
         };
-        let game_view = game.get_user_view(&user_name).unwrap();
-        games.insert_game(game);
-        return Ok(Response::new(game_view));
+
+        // Games don't have a visibility setting, since every game can already be found
+        // through `search_games`. So every game is public and can be quick joined.
+        let mut candidate_games: Vec<GameInfo> = self
+            .games
+            .lock()
+            .unwrap()
+            .get_games_by_insert_time()
+            .iter()
+            .filter(|game| !game.user_is_banned(&user_name))
+            .map(|game| game.get_game_info())
+            .collect();
+        candidate_games.append(
+            &mut self
+                .cluster_router
+                .search_peer_games(&SearchGamesRequest {
+                    query: String::from(""),
+                    min_available_player_slots: 0,
+                    game_stage_filter: GameStageFilter::FilterNone.into(),
+                    owner_filter: String::from(""),
+                    cardpack_filter: String::from(""),
+                    has_open_seats: true,
+                    exclude_banned_user: user_name.clone(),
+                    order_by: OrderBy::Unspecified.into(),
+                    page_size: 0,
+                    page_token: String::from(""),
+                })
+                .await,
+        );
+
+        let ranked_games = rank_games_for_quick_join(
+            candidate_games,
+            &preferred_cardpack_names,
+            system_time_to_timestamp_proto(&SystemTime::now()).seconds,
+        );
+
+        // Games can fill up or ban the user between ranking and joining, and their owner
+        // can become unreachable, so we try each game in order and move on whenever one
+        // can't be joined. Joining happens while holding the lock on the game (or on the
+        // instance that owns it), so the seat can't be taken out from under us.
+        for game_info in ranked_games {
+            if !self.cluster_router.owns_game(&game_info.game_id) {
+                match self
+                    .cluster_router
+                    .forward(
+                        self.cluster_router
+                            .get_game_owner_address(&game_info.game_id),
+                        JoinGameRequest {
+                            user_name: user_name.clone(),
+                            game_id: game_info.game_id.clone(),
+                        },
+                        |mut client, request| async move { client.join_game(request).await },
+                    )
+                    .await
+                {
+                    Ok(response) => return Ok(response),
+                    // Peers respond with `InvalidArgument` when the game can no longer be joined.
+                    Err(status) if status.code() == Code::InvalidArgument => {}
+                    Err(err) => warn!(
+                        game_id = game_info.game_id.as_str(),
+                        error = %err,
+                        "Failed to quick join game on another instance."
+                    ),
+                };
+                continue;
+            }
+
+            if let Err(err) = self
+                .claim_user_directory_entry(&user_name, &game_info.game_id)
+                .await
+            {
+                warn!(
+                    game_id = game_info.game_id.as_str(),
+                    error = %err,
+                    "Failed to claim user directory entry for quick join."
+                );
+                continue;
+            }
+            let joined_game_or = {
+                let mut games = self.games.lock().unwrap();
+                match games.get_game_by_game_id(&game_info.game_id) {
+                    Some(game) => match game.join(user.clone()) {
+                        Ok(()) => Some((
+                            game.get_user_names_for_all_real_players(),
+                            game.get_user_view(&user_name),
+                        )),
+                        Err(_) => None,
+                    },
+                    None => None,
+                }
+            };
+            if let Some((users_to_update, game_view_or)) = joined_game_or {
+                self.try_send_amqp_game_update_message_to_users(users_to_update)
+                    .await;
+                return match game_view_or {
+                    Ok(game_view) => Ok(Response::new(game_view)),
+                    Err(err) => Err(err),
+                };
+            }
+            self.release_user_directory_entry(&user_name, &game_info.game_id)
+                .await;
+        }
+
+        Err(Status::not_found(
+            "There are no open games to join right now. Try creating one instead!",
+        ))
     }
 
     async fn start_game(
@@ -224,6 +745,20 @@ impl GameService for GameServiceImpl {
         if request.get_ref().user_name.is_empty() {
             return Err(empty_request_field_error("user_name"));
         }
+        check_caller_owns_resource(&request, &request.get_ref().user_name)?;
+
+        if let Some(response) = self
+            .cluster_router
+            .forward_to_user_game_host(
+                &request,
+                &request.get_ref().user_name,
+                self.user_is_in_local_game(&request.get_ref().user_name),
+                |mut client, request| async move { client.start_game(request).await },
+            )
+            .await
+        {
+            return response;
+        }
 
         let (users_to_update, game_view_or) = {
             let mut games = self.games.lock().unwrap();
@@ -254,8 +789,22 @@ impl GameService for GameServiceImpl {
         if request.get_ref().user_name.is_empty() {
             return Err(empty_request_field_error("user_name"));
         }
+        check_caller_owns_resource(&request, &request.get_ref().user_name)?;
 
-        let (users_to_update, game_view_or) = {
+        if let Some(response) = self
+            .cluster_router
+            .forward_to_user_game_host(
+                &request,
+                &request.get_ref().user_name,
+                self.user_is_in_local_game(&request.get_ref().user_name),
+                |mut client, request| async move { client.stop_game(request).await },
+            )
+            .await
+        {
+            return response;
+        }
+
+        let (users_to_update, user_stats, achievement_progress, game_view_or) = {
             let mut games = self.games.lock().unwrap();
             let game = match games.get_game_by_player_id(&PlayerId::RealUser(String::from(
                 &request.get_ref().user_name,
@@ -266,6 +815,8 @@ impl GameService for GameServiceImpl {
             game.stop(&request.get_ref().user_name)?;
             (
                 game.get_user_names_for_all_real_players(),
+                game.take_unreported_user_stats(),
+                game.take_unreported_achievement_progress(),
                 match game.get_user_view(&request.get_ref().user_name) {
                     Ok(game_view) => Ok(Response::new(game_view)),
                     Err(err) => Err(err),
@@ -274,31 +825,171 @@ impl GameService for GameServiceImpl {
         };
         self.try_send_amqp_game_update_message_to_users(users_to_update)
             .await;
+        self.try_report_user_stats(user_stats).await;
+        self.try_report_achievement_progress(achievement_progress)
+            .await;
         game_view_or
     }
 
-    async fn join_game(
+    async fn update_game_config(
         &self,
-        request: Request<JoinGameRequest>,
+        request: Request<UpdateGameConfigRequest>,
     ) -> Result<Response<GameView>, Status> {
-        // TODO - If user is already in a game, then make them leave that game before joining the other one.
         if request.get_ref().user_name.is_empty() {
             return Err(empty_request_field_error("user_name"));
         }
-        if request.get_ref().game_id.is_empty() {
-            return Err(empty_request_field_error("game_id"));
+        check_caller_owns_resource(&request, &request.get_ref().user_name)?;
+        let update_mask_paths = match &request.get_ref().update_mask {
+            Some(update_mask) => update_mask.paths.clone(),
+            None => return Err(missing_request_field_error("update_mask")),
+        };
+        if request.get_ref().game_config.is_none() {
+            return Err(missing_request_field_error("game_config"));
         }
 
-        let user = match self
-            .resource_fetcher
-            .get_user(String::from(&request.get_ref().user_name))
+        if let Some(response) = self
+            .cluster_router
+            .forward_to_user_game_host(
+                &request,
+                &request.get_ref().user_name,
+                self.user_is_in_local_game(&request.get_ref().user_name),
+                |mut client, request| async move { client.update_game_config(request).await },
+            )
+            .await
+        {
+            return response;
+        }
+
+        let user_name = String::from(&request.get_ref().user_name);
+
+        let (game_id, validated_game_config, requires_new_cards) = {
+            let mut games = self.games.lock().unwrap();
+            let game = match games.get_game_by_player_id(&PlayerId::RealUser(user_name.clone())) {
+                Some(game) => game,
+                None => return Err(Status::invalid_argument("User is not in a game.")),
+            };
+            game.check_can_update_config(&user_name)?;
+            // Unwrap is safe here because we've already checked that the game config is set.
+            let validated_game_config = game.get_config().with_updates(
+                request.get_ref().game_config.as_ref().unwrap(),
+                &update_mask_paths,
+            )?;
+            let requires_new_cards = game.config_change_requires_new_cards(&validated_game_config);
+            (
+                String::from(game.get_game_id()),
+                validated_game_config,
+                requires_new_cards,
+            )
+        };
+
+        // Cards are fetched without holding the lock since it can take a while.
+        let new_cards_or = if requires_new_cards {
+            let (custom_black_cards, custom_white_cards) = match self
+                .resource_fetcher
+                .get_custom_cards_from_multiple_custom_cardpacks(
+                    validated_game_config.get_custom_cardpack_names(),
+                )
+                .await
+            {
+                Ok(res) => res,
+                Err(err) => return Err(err),
+            };
+
+            let (default_black_cards, default_white_cards) = match self
+                .resource_fetcher
+                .get_default_cards_from_multiple_default_cardpacks(
+                    validated_game_config.get_default_cardpack_names(),
+                )
+                .await
+            {
+                Ok(res) => res,
+                Err(err) => return Err(err),
+            };
+
+            Some(GameCards {
+                custom_black_cards,
+                custom_white_cards,
+                default_black_cards,
+                default_white_cards,
+            })
+        } else {
+            None
+        };
+
+        let (users_to_update, game_view_or) = {
+            let mut games = self.games.lock().unwrap();
+            let game = match games.get_game_by_player_id(&PlayerId::RealUser(user_name.clone())) {
+                Some(game) => game,
+                None => return Err(Status::invalid_argument("User is not in a game.")),
+            };
+            // Other updates may have been applied while cards were being fetched, so the
+            // update mask is applied again to the latest config rather than overwriting it.
+            // Unwrap is safe here because we've already checked that the game config is set.
+            let latest_game_config = game.get_config().with_updates(
+                request.get_ref().game_config.as_ref().unwrap(),
+                &update_mask_paths,
+            )?;
+            // Cards fetched for a different game or set of cardpacks can't be used.
+            if game.get_game_id() != game_id
+                || (new_cards_or.is_some()
+                    && !Game::configs_have_same_cards(&latest_game_config, &validated_game_config))
+            {
+                return Err(Status::aborted(
+                    "Game config was changed by another request. Please try again.",
+                ));
+            }
+            game.update_config(&user_name, latest_game_config, new_cards_or)?;
+            (
+                game.get_user_names_for_all_real_players(),
+                match game.get_user_view(&user_name) {
+                    Ok(game_view) => Ok(Response::new(game_view)),
+                    Err(err) => Err(err),
+                },
+            )
+        };
+        self.try_send_amqp_game_update_message_to_users(users_to_update)
+            .await;
+        game_view_or
+    }
+
+    async fn join_game(
+        &self,
+        request: Request<JoinGameRequest>,
+    ) -> Result<Response<GameView>, Status> {
+        // TODO - If user is already in a game, then make them leave that game before joining the other one.
+        if request.get_ref().user_name.is_empty() {
+            return Err(empty_request_field_error("user_name"));
+        }
+        check_caller_owns_resource(&request, &request.get_ref().user_name)?;
+        if request.get_ref().game_id.is_empty() {
+            return Err(empty_request_field_error("game_id"));
+        }
+
+        if let Some(response) = self
+            .cluster_router
+            .forward_to_game_owner(
+                &request,
+                &request.get_ref().game_id,
+                |mut client, request| async move { client.join_game(request).await },
+            )
+            .await
+        {
+            return response;
+        }
+
+        let user = match self
+            .resource_fetcher
+            .get_user(String::from(&request.get_ref().user_name))
             .await
         {
             Ok(user) => user,
             Err(err) => return Err(err),
         };
 
-        let (users_to_update, game_view_or) = {
+        self.claim_user_directory_entry(&request.get_ref().user_name, &request.get_ref().game_id)
+            .await?;
+
+        let joined_game_or = {
             let mut games = self.games.lock().unwrap();
             if games
                 .get_game_by_player_id(&PlayerId::RealUser(String::from(
@@ -306,29 +997,40 @@ impl GameService for GameServiceImpl {
                 )))
                 .is_some()
             {
-                return Err(Status::invalid_argument("User is already in a game."));
-            }
-            let game = match games.get_game_by_game_id(&request.get_ref().game_id) {
-                Some(game) => game,
-                None => {
-                    return Err(Status::invalid_argument(format!(
+                Err(Status::invalid_argument("User is already in a game."))
+            } else {
+                match games.get_game_by_game_id(&request.get_ref().game_id) {
+                    Some(game) => match game.join(user) {
+                        Ok(()) => Ok((
+                            game.get_user_names_for_all_real_players(),
+                            game.get_user_view(&request.get_ref().user_name),
+                        )),
+                        Err(err) => Err(err),
+                    },
+                    None => Err(Status::invalid_argument(format!(
                         "Game does not exist with id: `{}`.",
                         request.get_ref().game_id
-                    )))
+                    ))),
                 }
-            };
-            game.join(user)?;
-            (
-                game.get_user_names_for_all_real_players(),
-                match game.get_user_view(&request.get_ref().user_name) {
-                    Ok(game_view) => Ok(Response::new(game_view)),
-                    Err(err) => Err(err),
-                },
-            )
+            }
+        };
+        let (users_to_update, game_view_or) = match joined_game_or {
+            Ok(joined_game) => joined_game,
+            Err(err) => {
+                self.release_user_directory_entry(
+                    &request.get_ref().user_name,
+                    &request.get_ref().game_id,
+                )
+                .await;
+                return Err(err);
+            }
         };
         self.try_send_amqp_game_update_message_to_users(users_to_update)
             .await;
-        game_view_or
+        match game_view_or {
+            Ok(game_view) => Ok(Response::new(game_view)),
+            Err(err) => Err(err),
+        }
     }
 
     async fn leave_game(
@@ -338,10 +1040,24 @@ impl GameService for GameServiceImpl {
         if request.get_ref().user_name.is_empty() {
             return Err(empty_request_field_error("user_name"));
         }
+        check_caller_owns_resource(&request, &request.get_ref().user_name)?;
+
+        if let Some(response) = self
+            .cluster_router
+            .forward_to_user_game_host(
+                &request,
+                &request.get_ref().user_name,
+                self.user_is_in_local_game(&request.get_ref().user_name),
+                |mut client, request| async move { client.leave_game(request).await },
+            )
+            .await
+        {
+            return response;
+        }
 
-        let users_to_update = {
+        let (game_id, users_to_update, user_stats, achievement_progress) = {
             let mut games = self.games.lock().unwrap();
-            let (game_id, game_is_empty, users_to_update) = {
+            let (game_id, game_is_empty, users_to_update, user_stats, achievement_progress) = {
                 let game = match games.get_game_by_player_id(&PlayerId::RealUser(String::from(
                     &request.get_ref().user_name,
                 ))) {
@@ -353,15 +1069,22 @@ impl GameService for GameServiceImpl {
                     String::from(game.get_game_id()),
                     game.is_empty(),
                     game.get_user_names_for_all_real_players(),
+                    game.take_unreported_user_stats(),
+                    game.take_unreported_achievement_progress(),
                 )
             };
             if game_is_empty {
                 games.remove_game(&game_id);
             }
-            users_to_update
+            (game_id, users_to_update, user_stats, achievement_progress)
         };
+        self.release_user_directory_entry(&request.get_ref().user_name, &game_id)
+            .await;
         self.try_send_amqp_game_update_message_to_users(users_to_update)
             .await;
+        self.try_report_user_stats(user_stats).await;
+        self.try_report_achievement_progress(achievement_progress)
+            .await;
         Ok(Response::new(Empty {}))
     }
 
@@ -372,11 +1095,25 @@ impl GameService for GameServiceImpl {
         if request.get_ref().user_name.is_empty() {
             return Err(empty_request_field_error("user_name"));
         }
+        check_caller_owns_resource(&request, &request.get_ref().user_name)?;
         if request.get_ref().troll_user_name.is_empty() {
             return Err(empty_request_field_error("troll_user_name"));
         }
 
-        let (users_to_update, game_view_or) = {
+        if let Some(response) = self
+            .cluster_router
+            .forward_to_user_game_host(
+                &request,
+                &request.get_ref().user_name,
+                self.user_is_in_local_game(&request.get_ref().user_name),
+                |mut client, request| async move { client.kick_user(request).await },
+            )
+            .await
+        {
+            return response;
+        }
+
+        let (game_id, users_to_update, user_stats, achievement_progress, game_view_or) = {
             let mut games = self.games.lock().unwrap();
             let game = match games.get_game_by_player_id(&PlayerId::RealUser(String::from(
                 &request.get_ref().user_name,
@@ -389,15 +1126,23 @@ impl GameService for GameServiceImpl {
                 &request.get_ref().troll_user_name,
             )?;
             (
+                String::from(game.get_game_id()),
                 game.get_user_names_for_all_real_players(),
+                game.take_unreported_user_stats(),
+                game.take_unreported_achievement_progress(),
                 match game.get_user_view(&request.get_ref().user_name) {
                     Ok(game_view) => Ok(Response::new(game_view)),
                     Err(err) => Err(err),
                 },
             )
         };
+        self.release_user_directory_entry(&request.get_ref().troll_user_name, &game_id)
+            .await;
         self.try_send_amqp_game_update_message_to_users(users_to_update)
             .await;
+        self.try_report_user_stats(user_stats).await;
+        self.try_report_achievement_progress(achievement_progress)
+            .await;
         game_view_or
     }
 
@@ -408,10 +1153,24 @@ impl GameService for GameServiceImpl {
         if request.get_ref().user_name.is_empty() {
             return Err(empty_request_field_error("user_name"));
         }
+        check_caller_owns_resource(&request, &request.get_ref().user_name)?;
         if request.get_ref().troll_user_name.is_empty() {
             return Err(empty_request_field_error("troll_user_name"));
         }
 
+        if let Some(response) = self
+            .cluster_router
+            .forward_to_user_game_host(
+                &request,
+                &request.get_ref().user_name,
+                self.user_is_in_local_game(&request.get_ref().user_name),
+                |mut client, request| async move { client.ban_user(request).await },
+            )
+            .await
+        {
+            return response;
+        }
+
         let troll_user = match self
             .resource_fetcher
             .get_user(String::from(&request.get_ref().troll_user_name))
@@ -421,7 +1180,7 @@ impl GameService for GameServiceImpl {
             Err(err) => return Err(err),
         };
 
-        let (users_to_update, game_view_or) = {
+        let (game_id, users_to_update, user_stats, achievement_progress, game_view_or) = {
             let mut games = self.games.lock().unwrap();
             let game = match games.get_game_by_player_id(&PlayerId::RealUser(String::from(
                 &request.get_ref().user_name,
@@ -431,15 +1190,23 @@ impl GameService for GameServiceImpl {
             };
             game.ban_user(&request.get_ref().user_name, troll_user)?;
             (
+                String::from(game.get_game_id()),
                 game.get_user_names_for_all_real_players(),
+                game.take_unreported_user_stats(),
+                game.take_unreported_achievement_progress(),
                 match game.get_user_view(&request.get_ref().user_name) {
                     Ok(game_view) => Ok(Response::new(game_view)),
                     Err(err) => Err(err),
                 },
             )
         };
+        self.release_user_directory_entry(&request.get_ref().troll_user_name, &game_id)
+            .await;
         self.try_send_amqp_game_update_message_to_users(users_to_update)
             .await;
+        self.try_report_user_stats(user_stats).await;
+        self.try_report_achievement_progress(achievement_progress)
+            .await;
         game_view_or
     }
 
@@ -450,10 +1217,24 @@ impl GameService for GameServiceImpl {
         if request.get_ref().user_name.is_empty() {
             return Err(empty_request_field_error("user_name"));
         }
+        check_caller_owns_resource(&request, &request.get_ref().user_name)?;
         if request.get_ref().troll_user_name.is_empty() {
             return Err(empty_request_field_error("troll_user_name"));
         }
 
+        if let Some(response) = self
+            .cluster_router
+            .forward_to_user_game_host(
+                &request,
+                &request.get_ref().user_name,
+                self.user_is_in_local_game(&request.get_ref().user_name),
+                |mut client, request| async move { client.unban_user(request).await },
+            )
+            .await
+        {
+            return response;
+        }
+
         let (users_to_update, game_view_or) = {
             let mut games = self.games.lock().unwrap();
             let game = match games.get_game_by_player_id(&PlayerId::RealUser(String::from(
@@ -486,6 +1267,20 @@ impl GameService for GameServiceImpl {
         if request.get_ref().user_name.is_empty() {
             return Err(empty_request_field_error("user_name"));
         }
+        check_caller_owns_resource(&request, &request.get_ref().user_name)?;
+
+        if let Some(response) = self
+            .cluster_router
+            .forward_to_user_game_host(
+                &request,
+                &request.get_ref().user_name,
+                self.user_is_in_local_game(&request.get_ref().user_name),
+                |mut client, request| async move { client.play_cards(request).await },
+            )
+            .await
+        {
+            return response;
+        }
 
         let (users_to_update, game_view_or) = {
             let mut games = self.games.lock().unwrap();
@@ -516,6 +1311,20 @@ impl GameService for GameServiceImpl {
         if request.get_ref().user_name.is_empty() {
             return Err(empty_request_field_error("user_name"));
         }
+        check_caller_owns_resource(&request, &request.get_ref().user_name)?;
+
+        if let Some(response) = self
+            .cluster_router
+            .forward_to_user_game_host(
+                &request,
+                &request.get_ref().user_name,
+                self.user_is_in_local_game(&request.get_ref().user_name),
+                |mut client, request| async move { client.unplay_cards(request).await },
+            )
+            .await
+        {
+            return response;
+        }
 
         let (users_to_update, game_view_or) = {
             let mut games = self.games.lock().unwrap();
@@ -546,6 +1355,7 @@ impl GameService for GameServiceImpl {
         if request.get_ref().user_name.is_empty() {
             return Err(empty_request_field_error("user_name"));
         }
+        check_caller_owns_resource(&request, &request.get_ref().user_name)?;
         if request.get_ref().choice == 0 {
             return Err(empty_request_field_error("choice"));
         }
@@ -553,7 +1363,20 @@ impl GameService for GameServiceImpl {
             return Err(negative_request_field_error("choice"));
         }
 
-        let (users_to_update, game_view_or) = {
+        if let Some(response) = self
+            .cluster_router
+            .forward_to_user_game_host(
+                &request,
+                &request.get_ref().user_name,
+                self.user_is_in_local_game(&request.get_ref().user_name),
+                |mut client, request| async move { client.vote_card(request).await },
+            )
+            .await
+        {
+            return response;
+        }
+
+        let (users_to_update, user_stats, achievement_progress, white_card_stats, game_view_or) = {
             let mut games = self.games.lock().unwrap();
             let game = match games.get_game_by_player_id(&PlayerId::RealUser(String::from(
                 &request.get_ref().user_name,
@@ -564,6 +1387,9 @@ impl GameService for GameServiceImpl {
             game.vote_card(&request.get_ref().user_name, request.get_ref().choice)?;
             (
                 game.get_user_names_for_all_real_players(),
+                game.take_unreported_user_stats(),
+                game.take_unreported_achievement_progress(),
+                game.take_unreported_white_card_stats(),
                 match game.get_user_view(&request.get_ref().user_name) {
                     Ok(game_view) => Ok(Response::new(game_view)),
                     Err(err) => Err(err),
@@ -572,6 +1398,10 @@ impl GameService for GameServiceImpl {
         };
         self.try_send_amqp_game_update_message_to_users(users_to_update)
             .await;
+        self.try_report_user_stats(user_stats).await;
+        self.try_report_achievement_progress(achievement_progress)
+            .await;
+        self.try_report_white_card_stats(white_card_stats).await;
         game_view_or
     }
 
@@ -582,6 +1412,20 @@ impl GameService for GameServiceImpl {
         if request.get_ref().user_name.is_empty() {
             return Err(empty_request_field_error("user_name"));
         }
+        check_caller_owns_resource(&request, &request.get_ref().user_name)?;
+
+        if let Some(response) = self
+            .cluster_router
+            .forward_to_user_game_host(
+                &request,
+                &request.get_ref().user_name,
+                self.user_is_in_local_game(&request.get_ref().user_name),
+                |mut client, request| async move { client.vote_start_next_round(request).await },
+            )
+            .await
+        {
+            return response;
+        }
 
         let (users_to_update, game_view_or) = {
             let mut games = self.games.lock().unwrap();
@@ -612,6 +1456,20 @@ impl GameService for GameServiceImpl {
         if request.get_ref().user_name.is_empty() {
             return Err(empty_request_field_error("user_name"));
         }
+        check_caller_owns_resource(&request, &request.get_ref().user_name)?;
+
+        if let Some(response) = self
+            .cluster_router
+            .forward_to_user_game_host(
+                &request,
+                &request.get_ref().user_name,
+                self.user_is_in_local_game(&request.get_ref().user_name),
+                |mut client, request| async move { client.add_artificial_player(request).await },
+            )
+            .await
+        {
+            return response;
+        }
 
         let (users_to_update, game_view_or) = {
             let mut games = self.games.lock().unwrap();
@@ -645,8 +1503,22 @@ impl GameService for GameServiceImpl {
         if request.get_ref().user_name.is_empty() {
             return Err(empty_request_field_error("user_name"));
         }
+        check_caller_owns_resource(&request, &request.get_ref().user_name)?;
 
-        let (users_to_update, game_view_or) = {
+        if let Some(response) = self
+            .cluster_router
+            .forward_to_user_game_host(
+                &request,
+                &request.get_ref().user_name,
+                self.user_is_in_local_game(&request.get_ref().user_name),
+                |mut client, request| async move { client.remove_artificial_player(request).await },
+            )
+            .await
+        {
+            return response;
+        }
+
+        let (users_to_update, user_stats, achievement_progress, game_view_or) = {
             let mut games = self.games.lock().unwrap();
             let game = match games.get_game_by_player_id(&PlayerId::RealUser(String::from(
                 &request.get_ref().user_name,
@@ -660,6 +1532,8 @@ impl GameService for GameServiceImpl {
             )?;
             (
                 game.get_user_names_for_all_real_players(),
+                game.take_unreported_user_stats(),
+                game.take_unreported_achievement_progress(),
                 match game.get_user_view(&request.get_ref().user_name) {
                     Ok(game_view) => Ok(Response::new(game_view)),
                     Err(err) => Err(err),
@@ -668,6 +1542,9 @@ impl GameService for GameServiceImpl {
         };
         self.try_send_amqp_game_update_message_to_users(users_to_update)
             .await;
+        self.try_report_user_stats(user_stats).await;
+        self.try_report_achievement_progress(achievement_progress)
+            .await;
         game_view_or
     }
 
@@ -678,6 +1555,7 @@ impl GameService for GameServiceImpl {
         if request.get_ref().user_name.is_empty() {
             return Err(empty_request_field_error("user_name"));
         }
+        check_caller_owns_resource(&request, &request.get_ref().user_name)?;
         let chat_message = match &request.get_ref().chat_message {
             Some(chat_message) => chat_message,
             None => return Err(missing_request_field_error("chat_message")),
@@ -686,6 +1564,19 @@ impl GameService for GameServiceImpl {
             return Err(empty_request_field_error("chat_message.text"));
         }
 
+        if let Some(response) = self
+            .cluster_router
+            .forward_to_user_game_host(
+                &request,
+                &request.get_ref().user_name,
+                self.user_is_in_local_game(&request.get_ref().user_name),
+                |mut client, request| async move { client.create_chat_message(request).await },
+            )
+            .await
+        {
+            return response;
+        }
+
         let (users_to_update, game_view_or) = {
             let mut games = self.games.lock().unwrap();
             let game = match games.get_game_by_player_id(&PlayerId::RealUser(String::from(
@@ -718,6 +1609,20 @@ impl GameService for GameServiceImpl {
         if request.get_ref().user_name.is_empty() {
             return Err(empty_request_field_error("user_name"));
         }
+        check_caller_owns_resource(&request, &request.get_ref().user_name)?;
+
+        if let Some(response) = self
+            .cluster_router
+            .forward_to_user_game_host(
+                &request,
+                &request.get_ref().user_name,
+                self.user_is_in_local_game(&request.get_ref().user_name),
+                |mut client, request| async move { client.get_game_view(request).await },
+            )
+            .await
+        {
+            return response;
+        }
 
         let mut games = self.games.lock().unwrap();
         let game = match games.get_game_by_player_id(&PlayerId::RealUser(String::from(
@@ -732,27 +1637,52 @@ impl GameService for GameServiceImpl {
         }
     }
 
-    // TODO - Test this.
     async fn list_white_card_texts(
         &self,
         request: Request<ListWhiteCardTextsRequest>,
     ) -> Result<Response<ListWhiteCardTextsResponse>, Status> {
+        if let Some(response) = self
+            .cluster_router
+            .forward_to_game_owner(
+                &request,
+                &request.get_ref().game_id,
+                |mut client, request| async move { client.list_white_card_texts(request).await },
+            )
+            .await
+        {
+            return response;
+        }
+
+        let bounded_page_size = BoundedPageSize::new(request.get_ref().page_size)?;
+
+        let request_without_page_token = {
+            let mut req = request.get_ref().clone();
+            req.page_token = String::from("");
+            req.page_size = 0;
+            req
+        };
+
+        let mut skip: usize = 0;
+
+        if !request.get_ref().page_token.is_empty() {
+            skip =
+                parse_page_token_index(&request_without_page_token, &request.get_ref().page_token)?;
+        }
+
+        let page_size = bounded_page_size.take_i64() as usize;
+
         let mut games = self.games.lock().unwrap();
         let game = match games.get_game_by_game_id(&request.get_ref().game_id) {
             Some(game) => game,
             None => return Err(Status::not_found("Game does not exist.")),
         };
-        // TODO - We should make the page tokens for this RPC opaque. Right now it's just a stringified index.
-        let skip = request.get_ref().page_token.parse::<usize>().unwrap();
-        let (card_texts, has_next_page, total_size) = game.search_white_card_texts(
-            request.get_ref().page_size as usize,
-            skip,
-            &request.get_ref().filter,
-        );
+        // `total_size` counts the texts matching the filter, not every text in the game.
+        let (card_texts, has_next_page, total_size) =
+            game.search_white_card_texts(page_size, skip, &request.get_ref().filter);
         let next_page_token = if has_next_page {
-            format!("{}", skip + request.get_ref().page_size as usize)
+            create_page_token_for_index(&request_without_page_token, skip + page_size)
         } else {
-            "".to_string()
+            String::from("")
         };
         Ok(Response::new(ListWhiteCardTextsResponse {
             card_texts,
@@ -760,15 +1690,103 @@ impl GameService for GameServiceImpl {
             total_size: total_size as i64,
         }))
     }
+
+    async fn list_black_card_texts(
+        &self,
+        request: Request<ListBlackCardTextsRequest>,
+    ) -> Result<Response<ListBlackCardTextsResponse>, Status> {
+        if let Some(response) = self
+            .cluster_router
+            .forward_to_game_owner(
+                &request,
+                &request.get_ref().game_id,
+                |mut client, request| async move { client.list_black_card_texts(request).await },
+            )
+            .await
+        {
+            return response;
+        }
+
+        let bounded_page_size = BoundedPageSize::new(request.get_ref().page_size)?;
+
+        let request_without_page_token = {
+            let mut req = request.get_ref().clone();
+            req.page_token = String::from("");
+            req.page_size = 0;
+            req
+        };
+
+        let mut skip: usize = 0;
+
+        if !request.get_ref().page_token.is_empty() {
+            skip =
+                parse_page_token_index(&request_without_page_token, &request.get_ref().page_token)?;
+        }
+
+        let page_size = bounded_page_size.take_i64() as usize;
+
+        let mut games = self.games.lock().unwrap();
+        let game = match games.get_game_by_game_id(&request.get_ref().game_id) {
+            Some(game) => game,
+            None => return Err(Status::not_found("Game does not exist.")),
+        };
+        // `total_size` counts the texts matching the filter, not every text in the game.
+        let (card_texts, has_next_page, total_size) =
+            game.search_black_card_texts(page_size, skip, &request.get_ref().filter);
+        let next_page_token = if has_next_page {
+            create_page_token_for_index(&request_without_page_token, skip + page_size)
+        } else {
+            String::from("")
+        };
+        Ok(Response::new(ListBlackCardTextsResponse {
+            card_texts,
+            next_page_token,
+            total_size: total_size as i64,
+        }))
+    }
+
+    async fn get_user_directory_entry(
+        &self,
+        request: Request<GetUserDirectoryEntryRequest>,
+    ) -> Result<Response<UserDirectoryEntry>, Status> {
+        ClusterRouter::check_request_is_forwarded(&request)?;
+        if request.get_ref().user_name.is_empty() {
+            return Err(empty_request_field_error("user_name"));
+        }
+        Ok(Response::new(
+            self.cluster_router
+                .get_local_user_directory_entry(&request.get_ref().user_name),
+        ))
+    }
+
+    async fn update_user_directory_entry(
+        &self,
+        request: Request<UpdateUserDirectoryEntryRequest>,
+    ) -> Result<Response<UserDirectoryEntry>, Status> {
+        ClusterRouter::check_request_is_forwarded(&request)?;
+        if request.get_ref().user_name.is_empty() {
+            return Err(empty_request_field_error("user_name"));
+        }
+        Ok(Response::new(
+            self.cluster_router.update_local_user_directory_entry(
+                &request.get_ref().user_name,
+                &request.get_ref().expected_game_id,
+                &request.get_ref().game_id,
+            ),
+        ))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::super::api_resource_fetcher::MockApiResourceFetcher;
     use super::*;
+    use shared::auth::{create_internal_request, create_request_authenticated_as};
+    use shared::constants::{MAX_HAND_SIZE_LIMIT, MIN_HAND_SIZE_LIMIT};
     use shared::proto::crusty_cards_api::{
-        CustomBlackCard, CustomWhiteCard, DefaultBlackCard, DefaultWhiteCard, User,
+        CustomBlackCard, CustomWhiteCard, DefaultBlackCard, DefaultWhiteCard, User, UserSettings,
     };
+    use shared::proto::google::protobuf::FieldMask;
     use shared::test_helper::get_valid_test_game_config;
 
     // The GameView proto contains fields such as player join_time that will be different for every test run.
@@ -863,7 +1881,12 @@ mod tests {
                     vec![create_empty_default_white_card()],
                 ))
             });
-        let game_service_impl = GameServiceImpl::new(Box::from(mock_api_resource_fetcher), None);
+        let game_service_impl = GameServiceImpl::new(
+            Box::from(mock_api_resource_fetcher),
+            None,
+            ClusterRouter::new_single_instance(),
+            GameLimits::default(),
+        );
 
         let mut create_game_request = CreateGameRequest {
             user_name: String::from(""),
@@ -880,26 +1903,215 @@ mod tests {
         game_view_or = game_service_impl
             .create_game(Request::new(create_game_request.clone()))
             .await;
+        assert_eq!(
+            format!("{}", game_view_or.err().unwrap()),
+            "status: Unauthenticated, message: \"Request must include a session token.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+        game_view_or = game_service_impl
+            .create_game(create_request_authenticated_as(
+                create_game_request.clone(),
+                "other_user_name",
+            ))
+            .await;
+        assert_eq!(
+            format!("{}", game_view_or.err().unwrap()),
+            "status: PermissionDenied, message: \"Caller does not have access to `test_user_name`.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+        game_view_or = game_service_impl
+            .create_game(create_request_authenticated_as(
+                create_game_request.clone(),
+                "test_user_name",
+            ))
+            .await;
         assert_eq!(
             format!("{}", game_view_or.err().unwrap()),
             "status: InvalidArgument, message: \"Request is missing required field `game_config`.\", details: [], metadata: MetadataMap { headers: {} }"
         );
         create_game_request.game_config = Some(get_valid_test_game_config());
         game_view_or = game_service_impl
-            .create_game(Request::new(create_game_request))
+            .create_game(create_request_authenticated_as(
+                create_game_request,
+                "test_user_name",
+            ))
             .await;
         assert_eq!(game_view_or.is_ok(), true);
         assert_eq!(format!("{:?}", validate_and_remove_changing_parameters_from_game_view(game_view_or.unwrap().into_inner())), "GameView { game_id: \"\", config: Some(GameConfig { display_name: \"Test Game\", max_players: 3, hand_size: 3, custom_cardpack_names: [\"test_custom_cardpack_name\"], default_cardpack_names: [\"test_default_cardpack_name\"], blank_white_card_config: Some(BlankWhiteCardConfig { behavior: Disabled, blank_white_cards_added: None }), end_condition: Some(EndlessMode(Empty)) }), stage: NotRunning, hand: [], players: [Player { score: 0, join_time: None, identifier: Some(User(User { name: \"\", display_name: \"\", create_time: None, update_time: None })) }], queued_players: [], banned_users: [], judge: None, owner: Some(User { name: \"\", display_name: \"\", create_time: None, update_time: None }), white_played: [], current_black_card: None, winner: None, chat_messages: [], past_rounds: [], create_time: None, last_activity_time: None }");
     }
 
     #[tokio::test]
-    async fn search_games() {
+    async fn create_game_after_shutdown_begins() {
+        let game_service_impl = GameServiceImpl::new(
+            Box::from(MockApiResourceFetcher::new()),
+            None,
+            ClusterRouter::new_single_instance(),
+            GameLimits::default(),
+        );
+        game_service_impl.begin_shutdown().await;
+
+        let game_view_or = game_service_impl
+            .create_game(create_request_authenticated_as(
+                CreateGameRequest {
+                    user_name: String::from("test_user_name"),
+                    game_config: Some(get_valid_test_game_config()),
+                },
+                "test_user_name",
+            ))
+            .await;
+        assert_eq!(
+            format!("{}", game_view_or.err().unwrap()),
+            "status: Unavailable, message: \"Server is shutting down, so new games can't be created on it.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+    }
+
+    #[tokio::test]
+    async fn joining_and_leaving_games_updates_user_directory() {
         let mut mock_api_resource_fetcher = MockApiResourceFetcher::new();
-        let user = User {
-            name: String::from("owner"),
-            display_name: String::from(""),
-            create_time: None,
-            update_time: None,
+        mock_api_resource_fetcher
+            .expect_get_user()
+            .returning(|user_name| {
+                Ok(User {
+                    name: user_name,
+                    display_name: String::from(""),
+                    create_time: None,
+                    update_time: None,
+                })
+            });
+        mock_api_resource_fetcher
+            .expect_get_custom_cards_from_multiple_custom_cardpacks()
+            .return_once(move |_| {
+                Ok((
+                    vec![create_empty_custom_black_card()],
+                    vec![create_empty_custom_white_card()],
+                ))
+            });
+        mock_api_resource_fetcher
+            .expect_get_default_cards_from_multiple_default_cardpacks()
+            .return_once(move |_| {
+                Ok((
+                    vec![create_empty_default_black_card()],
+                    vec![create_empty_default_white_card()],
+                ))
+            });
+        let game_service_impl = GameServiceImpl::new(
+            Box::from(mock_api_resource_fetcher),
+            None,
+            ClusterRouter::new_single_instance(),
+            GameLimits::default(),
+        );
+
+        let game_id = game_service_impl
+            .create_game(create_request_authenticated_as(
+                CreateGameRequest {
+                    user_name: String::from("users/1"),
+                    game_config: Some(get_valid_test_game_config()),
+                },
+                "users/1",
+            ))
+            .await
+            .unwrap()
+            .into_inner()
+            .game_id;
+        assert_eq!(
+            game_service_impl
+                .cluster_router
+                .get_user_game_id("users/1")
+                .await
+                .unwrap(),
+            Some(game_id.clone())
+        );
+
+        game_service_impl
+            .join_game(create_request_authenticated_as(
+                JoinGameRequest {
+                    user_name: String::from("users/2"),
+                    game_id: game_id.clone(),
+                },
+                "users/2",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            game_service_impl
+                .cluster_router
+                .get_user_game_id("users/2")
+                .await
+                .unwrap(),
+            Some(game_id.clone())
+        );
+
+        // Failing to join another game leaves the entry alone.
+        let game_view_or = game_service_impl
+            .join_game(create_request_authenticated_as(
+                JoinGameRequest {
+                    user_name: String::from("users/2"),
+                    game_id: String::from("missing_game_id"),
+                },
+                "users/2",
+            ))
+            .await;
+        assert_eq!(
+            format!("{}", game_view_or.err().unwrap()),
+            "status: InvalidArgument, message: \"User is already in a game.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+        assert_eq!(
+            game_service_impl
+                .cluster_router
+                .get_user_game_id("users/2")
+                .await
+                .unwrap(),
+            Some(game_id.clone())
+        );
+
+        game_service_impl
+            .leave_game(create_request_authenticated_as(
+                LeaveGameRequest {
+                    user_name: String::from("users/2"),
+                },
+                "users/2",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            game_service_impl
+                .cluster_router
+                .get_user_game_id("users/2")
+                .await
+                .unwrap(),
+            None
+        );
+
+        // Entries claimed for a game that can't be joined are released again.
+        let game_view_or = game_service_impl
+            .join_game(create_request_authenticated_as(
+                JoinGameRequest {
+                    user_name: String::from("users/2"),
+                    game_id: String::from("missing_game_id"),
+                },
+                "users/2",
+            ))
+            .await;
+        assert_eq!(
+            format!("{}", game_view_or.err().unwrap()),
+            "status: InvalidArgument, message: \"Game does not exist with id: `missing_game_id`.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+        assert_eq!(
+            game_service_impl
+                .cluster_router
+                .get_user_game_id("users/2")
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn search_games() {
+        let mut mock_api_resource_fetcher = MockApiResourceFetcher::new();
+        let user = User {
+            name: String::from("owner"),
+            display_name: String::from(""),
+            create_time: None,
+            update_time: None,
         };
         mock_api_resource_fetcher
             .expect_get_user()
@@ -920,13 +2132,25 @@ mod tests {
                     vec![create_empty_default_white_card()],
                 ))
             });
-        let game_service_impl = GameServiceImpl::new(Box::from(mock_api_resource_fetcher), None);
+        let game_service_impl = GameServiceImpl::new(
+            Box::from(mock_api_resource_fetcher),
+            None,
+            ClusterRouter::new_single_instance(),
+            GameLimits::default(),
+        );
 
         // Should not contain any games on intialization.
         let mut search_games_request = SearchGamesRequest {
             query: String::from(""),
             min_available_player_slots: 0,
             game_stage_filter: GameStageFilter::Unspecified.into(),
+            owner_filter: String::from(""),
+            cardpack_filter: String::from(""),
+            has_open_seats: false,
+            exclude_banned_user: String::from(""),
+            order_by: OrderBy::Unspecified.into(),
+            page_size: 0,
+            page_token: String::from(""),
         };
         let search_games_response_or: Result<Response<SearchGamesResponse>, Status> =
             game_service_impl
@@ -949,7 +2173,10 @@ mod tests {
             game_config: Some(get_valid_test_game_config()),
         };
         let game_view_or = game_service_impl
-            .create_game(Request::new(create_game_request))
+            .create_game(create_request_authenticated_as(
+                create_game_request,
+                "test_user_name",
+            ))
             .await;
         assert_eq!(game_view_or.is_ok(), true);
 
@@ -958,6 +2185,13 @@ mod tests {
             query: String::from(""),
             min_available_player_slots: 0,
             game_stage_filter: GameStageFilter::Unspecified.into(),
+            owner_filter: String::from(""),
+            cardpack_filter: String::from(""),
+            has_open_seats: false,
+            exclude_banned_user: String::from(""),
+            order_by: OrderBy::Unspecified.into(),
+            page_size: 0,
+            page_token: String::from(""),
         };
         let search_games_response_or: Result<Response<SearchGamesResponse>, Status> =
             game_service_impl
@@ -987,4 +2221,764 @@ mod tests {
         game_info.last_activity_time = None;
         assert_eq!(format!("{:?}", game_info), "GameInfo { game_id: \"\", config: Some(GameConfig { display_name: \"Test Game\", max_players: 3, hand_size: 3, custom_cardpack_names: [\"test_custom_cardpack_name\"], default_cardpack_names: [\"test_default_cardpack_name\"], blank_white_card_config: Some(BlankWhiteCardConfig { behavior: Disabled, blank_white_cards_added: None }), end_condition: Some(EndlessMode(Empty)) }), player_count: 1, owner: Some(User { name: \"owner\", display_name: \"\", create_time: None, update_time: None }), is_running: false, create_time: None, last_activity_time: None }");
     }
+
+    #[tokio::test]
+    async fn search_games_excludes_games_that_banned_user() {
+        let mut mock_api_resource_fetcher = MockApiResourceFetcher::new();
+        mock_api_resource_fetcher
+            .expect_get_user()
+            .returning(|user_name| {
+                Ok(User {
+                    name: user_name,
+                    display_name: String::from(""),
+                    create_time: None,
+                    update_time: None,
+                })
+            });
+        mock_api_resource_fetcher
+            .expect_get_custom_cards_from_multiple_custom_cardpacks()
+            .return_once(move |_| {
+                Ok((
+                    vec![create_empty_custom_black_card()],
+                    vec![create_empty_custom_white_card()],
+                ))
+            });
+        mock_api_resource_fetcher
+            .expect_get_default_cards_from_multiple_default_cardpacks()
+            .return_once(move |_| {
+                Ok((
+                    vec![create_empty_default_black_card()],
+                    vec![create_empty_default_white_card()],
+                ))
+            });
+        let game_service_impl = GameServiceImpl::new(
+            Box::from(mock_api_resource_fetcher),
+            None,
+            ClusterRouter::new_single_instance(),
+            GameLimits::default(),
+        );
+
+        game_service_impl
+            .create_game(create_request_authenticated_as(
+                CreateGameRequest {
+                    user_name: String::from("users/owner"),
+                    game_config: Some(get_valid_test_game_config()),
+                },
+                "users/owner",
+            ))
+            .await
+            .unwrap();
+        game_service_impl
+            .ban_user(create_request_authenticated_as(
+                BanUserRequest {
+                    user_name: String::from("users/owner"),
+                    troll_user_name: String::from("users/troll"),
+                },
+                "users/owner",
+            ))
+            .await
+            .unwrap();
+
+        let mut search_games_request = SearchGamesRequest {
+            query: String::from(""),
+            min_available_player_slots: 0,
+            game_stage_filter: GameStageFilter::FilterNone.into(),
+            owner_filter: String::from(""),
+            cardpack_filter: String::from(""),
+            has_open_seats: false,
+            exclude_banned_user: String::from("users/troll"),
+            order_by: OrderBy::Unspecified.into(),
+            page_size: 0,
+            page_token: String::from(""),
+        };
+        let search_games_response = game_service_impl
+            .search_games(create_internal_request(search_games_request.clone()))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(search_games_response.games.len(), 0);
+        assert_eq!(search_games_response.total_size, 0);
+
+        // Requests from outside the cluster can't use the field to find out who is banned.
+        let search_games_response = game_service_impl
+            .search_games(create_request_authenticated_as(
+                search_games_request.clone(),
+                "users/someone_else",
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(search_games_response.games.len(), 1);
+        assert_eq!(search_games_response.total_size, 1);
+
+        search_games_request.exclude_banned_user = String::from("users/someone_else");
+        let search_games_response = game_service_impl
+            .search_games(create_internal_request(search_games_request))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(search_games_response.games.len(), 1);
+        assert_eq!(search_games_response.total_size, 1);
+    }
+
+    #[tokio::test]
+    async fn search_games_paginates_results() {
+        let mut mock_api_resource_fetcher = MockApiResourceFetcher::new();
+        mock_api_resource_fetcher
+            .expect_get_user()
+            .returning(|user_name| {
+                let mut user = create_empty_user();
+                user.name = user_name;
+                Ok(user)
+            });
+        mock_api_resource_fetcher
+            .expect_get_custom_cards_from_multiple_custom_cardpacks()
+            .returning(|_| {
+                Ok((
+                    vec![create_empty_custom_black_card()],
+                    vec![create_empty_custom_white_card()],
+                ))
+            });
+        mock_api_resource_fetcher
+            .expect_get_default_cards_from_multiple_default_cardpacks()
+            .returning(|_| {
+                Ok((
+                    vec![create_empty_default_black_card()],
+                    vec![create_empty_default_white_card()],
+                ))
+            });
+        let game_service_impl = GameServiceImpl::new(
+            Box::from(mock_api_resource_fetcher),
+            None,
+            ClusterRouter::new_single_instance(),
+            GameLimits::default(),
+        );
+
+        for i in 0..3 {
+            let create_game_request = CreateGameRequest {
+                user_name: format!("users/{}", i),
+                game_config: Some(get_valid_test_game_config()),
+            };
+            assert!(game_service_impl
+                .create_game(create_request_authenticated_as(
+                    create_game_request,
+                    &format!("users/{}", i)
+                ))
+                .await
+                .is_ok());
+        }
+
+        let mut search_games_request = SearchGamesRequest {
+            query: String::from("test"),
+            min_available_player_slots: 0,
+            game_stage_filter: GameStageFilter::FilterNone.into(),
+            owner_filter: String::from(""),
+            cardpack_filter: String::from(""),
+            has_open_seats: true,
+            exclude_banned_user: String::from(""),
+            order_by: OrderBy::Newest.into(),
+            page_size: 2,
+            page_token: String::from(""),
+        };
+        let first_page = game_service_impl
+            .search_games(Request::new(search_games_request.clone()))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(first_page.games.len(), 2);
+        assert_eq!(first_page.total_size, 3);
+        assert_eq!(first_page.next_page_token.is_empty(), false);
+
+        search_games_request.page_token = first_page.next_page_token.clone();
+        let second_page = game_service_impl
+            .search_games(Request::new(search_games_request.clone()))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(second_page.games.len(), 1);
+        assert_eq!(second_page.total_size, 3);
+        assert_eq!(second_page.next_page_token, "");
+        assert_eq!(
+            first_page
+                .games
+                .iter()
+                .any(|game_info| game_info.game_id == second_page.games[0].game_id),
+            false
+        );
+
+        // Page tokens can't be reused with different search parameters.
+        search_games_request.owner_filter = String::from("users/0");
+        assert_eq!(
+            format!(
+                "{}",
+                game_service_impl
+                    .search_games(Request::new(search_games_request.clone()))
+                    .await
+                    .unwrap_err()
+            ),
+            "status: InvalidArgument, message: \"All request fields must match the previous request.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+
+        search_games_request.page_token = String::from("garbage");
+        assert_eq!(
+            format!(
+                "{}",
+                game_service_impl
+                    .search_games(Request::new(search_games_request.clone()))
+                    .await
+                    .unwrap_err()
+            ),
+            "status: InvalidArgument, message: \"Page token is invalid.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+
+        search_games_request.page_token = String::from("");
+        let owner_filtered_page = game_service_impl
+            .search_games(Request::new(search_games_request))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(owner_filtered_page.games.len(), 1);
+        assert_eq!(
+            owner_filtered_page.games[0].owner.as_ref().unwrap().name,
+            "users/0"
+        );
+    }
+
+    #[tokio::test]
+    async fn list_white_card_texts() {
+        let mut mock_api_resource_fetcher = MockApiResourceFetcher::new();
+        mock_api_resource_fetcher
+            .expect_get_user()
+            .return_once(move |_| Ok(create_empty_user()));
+        mock_api_resource_fetcher
+            .expect_get_custom_cards_from_multiple_custom_cardpacks()
+            .return_once(move |_| {
+                let mut first_white_card = create_empty_custom_white_card();
+                first_white_card.text = String::from("Card 1");
+                let mut second_white_card = create_empty_custom_white_card();
+                second_white_card.text = String::from("Card 2");
+                Ok((
+                    vec![create_empty_custom_black_card()],
+                    vec![first_white_card, second_white_card],
+                ))
+            });
+        mock_api_resource_fetcher
+            .expect_get_default_cards_from_multiple_default_cardpacks()
+            .return_once(move |_| {
+                let mut white_card = create_empty_default_white_card();
+                white_card.text = String::from("Card 3");
+                Ok((vec![create_empty_default_black_card()], vec![white_card]))
+            });
+        let game_service_impl = GameServiceImpl::new(
+            Box::from(mock_api_resource_fetcher),
+            None,
+            ClusterRouter::new_single_instance(),
+            GameLimits::default(),
+        );
+
+        let create_game_request = CreateGameRequest {
+            user_name: String::from("test_user_name"),
+            game_config: Some(get_valid_test_game_config()),
+        };
+        let game_id = game_service_impl
+            .create_game(create_request_authenticated_as(
+                create_game_request,
+                "test_user_name",
+            ))
+            .await
+            .unwrap()
+            .into_inner()
+            .game_id;
+
+        let mut list_white_card_texts_request = ListWhiteCardTextsRequest {
+            game_id,
+            page_size: 2,
+            page_token: String::from(""),
+            filter: String::from(""),
+        };
+        let first_page = game_service_impl
+            .list_white_card_texts(Request::new(list_white_card_texts_request.clone()))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(first_page.card_texts, vec!["Card 1", "Card 2"]);
+        assert_eq!(first_page.total_size, 3);
+        assert_eq!(first_page.next_page_token.is_empty(), false);
+
+        // Page size may change between pages.
+        list_white_card_texts_request.page_token = first_page.next_page_token;
+        list_white_card_texts_request.page_size = 10;
+        let second_page = game_service_impl
+            .list_white_card_texts(Request::new(list_white_card_texts_request.clone()))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(second_page.card_texts, vec!["Card 3"]);
+        assert_eq!(second_page.total_size, 3);
+        assert_eq!(second_page.next_page_token, "");
+
+        // Page tokens can't be reused with a different filter.
+        list_white_card_texts_request.filter = String::from("Card");
+        assert_eq!(
+            format!(
+                "{}",
+                game_service_impl
+                    .list_white_card_texts(Request::new(list_white_card_texts_request.clone()))
+                    .await
+                    .unwrap_err()
+            ),
+            "status: InvalidArgument, message: \"All request fields must match the previous request.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+
+        list_white_card_texts_request.page_token = String::from("1");
+        assert_eq!(
+            format!(
+                "{}",
+                game_service_impl
+                    .list_white_card_texts(Request::new(list_white_card_texts_request.clone()))
+                    .await
+                    .unwrap_err()
+            ),
+            "status: InvalidArgument, message: \"Page token is invalid.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+
+        list_white_card_texts_request.page_token = String::from("");
+        list_white_card_texts_request.page_size = -1;
+        assert_eq!(
+            format!(
+                "{}",
+                game_service_impl
+                    .list_white_card_texts(Request::new(list_white_card_texts_request))
+                    .await
+                    .unwrap_err()
+            ),
+            "status: InvalidArgument, message: \"Page size cannot be negative.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+    }
+
+    #[tokio::test]
+    async fn list_black_card_texts() {
+        let mut mock_api_resource_fetcher = MockApiResourceFetcher::new();
+        mock_api_resource_fetcher
+            .expect_get_user()
+            .return_once(move |_| Ok(create_empty_user()));
+        mock_api_resource_fetcher
+            .expect_get_custom_cards_from_multiple_custom_cardpacks()
+            .return_once(move |_| {
+                let mut first_black_card = create_empty_custom_black_card();
+                first_black_card.text = String::from("Why is the sky blue?");
+                let mut second_black_card = create_empty_custom_black_card();
+                second_black_card.text = String::from("What is love?");
+                Ok((
+                    vec![first_black_card, second_black_card],
+                    vec![create_empty_custom_white_card()],
+                ))
+            });
+        mock_api_resource_fetcher
+            .expect_get_default_cards_from_multiple_default_cardpacks()
+            .return_once(move |_| {
+                let mut black_card = create_empty_default_black_card();
+                black_card.text = String::from("Why can't I sleep?");
+                Ok((vec![black_card], vec![create_empty_default_white_card()]))
+            });
+        let game_service_impl = GameServiceImpl::new(
+            Box::from(mock_api_resource_fetcher),
+            None,
+            ClusterRouter::new_single_instance(),
+            GameLimits::default(),
+        );
+
+        let create_game_request = CreateGameRequest {
+            user_name: String::from("test_user_name"),
+            game_config: Some(get_valid_test_game_config()),
+        };
+        let game_id = game_service_impl
+            .create_game(create_request_authenticated_as(
+                create_game_request,
+                "test_user_name",
+            ))
+            .await
+            .unwrap()
+            .into_inner()
+            .game_id;
+
+        let mut list_black_card_texts_request = ListBlackCardTextsRequest {
+            game_id: game_id.clone(),
+            page_size: 0,
+            page_token: String::from(""),
+            filter: String::from(""),
+        };
+        let all_texts = game_service_impl
+            .list_black_card_texts(Request::new(list_black_card_texts_request.clone()))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(
+            all_texts.card_texts,
+            vec![
+                "Why is the sky blue?",
+                "What is love?",
+                "Why can't I sleep?"
+            ]
+        );
+        assert_eq!(all_texts.total_size, 3);
+        assert_eq!(all_texts.next_page_token, "");
+
+        // Total size only counts texts that match the filter.
+        list_black_card_texts_request.filter = String::from("why");
+        list_black_card_texts_request.page_size = 1;
+        let first_page = game_service_impl
+            .list_black_card_texts(Request::new(list_black_card_texts_request.clone()))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(first_page.card_texts, vec!["Why is the sky blue?"]);
+        assert_eq!(first_page.total_size, 2);
+        assert_eq!(first_page.next_page_token.is_empty(), false);
+
+        list_black_card_texts_request.page_token = first_page.next_page_token;
+        let second_page = game_service_impl
+            .list_black_card_texts(Request::new(list_black_card_texts_request.clone()))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(second_page.card_texts, vec!["Why can't I sleep?"]);
+        assert_eq!(second_page.total_size, 2);
+        assert_eq!(second_page.next_page_token, "");
+
+        // Page tokens can't be reused with a different filter.
+        list_black_card_texts_request.filter = String::from("what");
+        assert_eq!(
+            format!(
+                "{}",
+                game_service_impl
+                    .list_black_card_texts(Request::new(list_black_card_texts_request.clone()))
+                    .await
+                    .unwrap_err()
+            ),
+            "status: InvalidArgument, message: \"All request fields must match the previous request.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+
+        list_black_card_texts_request.game_id = String::from("missing_game_id");
+        list_black_card_texts_request.page_token = String::from("");
+        assert_eq!(
+            format!(
+                "{}",
+                game_service_impl
+                    .list_black_card_texts(Request::new(list_black_card_texts_request))
+                    .await
+                    .unwrap_err()
+            ),
+            "status: NotFound, message: \"Game does not exist.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+    }
+
+    #[tokio::test]
+    async fn update_game_config() {
+        let mut mock_api_resource_fetcher = MockApiResourceFetcher::new();
+        mock_api_resource_fetcher
+            .expect_get_user()
+            .return_once(|user_name| {
+                let mut user = create_empty_user();
+                user.name = user_name;
+                Ok(user)
+            });
+        mock_api_resource_fetcher
+            .expect_get_custom_cards_from_multiple_custom_cardpacks()
+            .times(2)
+            .returning(|_| {
+                Ok((
+                    vec![create_empty_custom_black_card()],
+                    vec![create_empty_custom_white_card()],
+                ))
+            });
+        mock_api_resource_fetcher
+            .expect_get_default_cards_from_multiple_default_cardpacks()
+            .times(2)
+            .returning(|_| {
+                Ok((
+                    vec![create_empty_default_black_card()],
+                    vec![create_empty_default_white_card()],
+                ))
+            });
+        let game_service_impl = GameServiceImpl::new(
+            Box::from(mock_api_resource_fetcher),
+            None,
+            ClusterRouter::new_single_instance(),
+            GameLimits::default(),
+        );
+
+        let create_game_request = CreateGameRequest {
+            user_name: String::from("test_user_name"),
+            game_config: Some(get_valid_test_game_config()),
+        };
+        assert!(game_service_impl
+            .create_game(create_request_authenticated_as(
+                create_game_request,
+                "test_user_name"
+            ))
+            .await
+            .is_ok());
+
+        let mut game_config = get_valid_test_game_config();
+        game_config.display_name = String::from("Renamed Game");
+        game_config.default_cardpack_names = vec![String::from("other_default_cardpack_name")];
+        let mut update_game_config_request = UpdateGameConfigRequest {
+            user_name: String::from("test_user_name"),
+            game_config: Some(game_config),
+            update_mask: None,
+        };
+        assert_eq!(
+            format!(
+                "{}",
+                game_service_impl
+                    .update_game_config(create_request_authenticated_as(update_game_config_request.clone(), "test_user_name"))
+                    .await
+                    .unwrap_err()
+            ),
+            "status: InvalidArgument, message: \"Request is missing required field `update_mask`.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+
+        // Doesn't refetch cards when cardpacks are unchanged.
+        update_game_config_request.update_mask = Some(FieldMask {
+            paths: vec![String::from("display_name")],
+        });
+        let game_view = game_service_impl
+            .update_game_config(create_request_authenticated_as(
+                update_game_config_request.clone(),
+                "test_user_name",
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        let config = game_view.config.unwrap();
+        assert_eq!(config.display_name, "Renamed Game");
+        assert_eq!(
+            config.default_cardpack_names,
+            vec![String::from("test_default_cardpack_name")]
+        );
+        assert_eq!(game_view.players.len(), 1);
+
+        // Refetches cards when cardpacks change.
+        update_game_config_request.update_mask = Some(FieldMask {
+            paths: vec![String::from("default_cardpack_names")],
+        });
+        let game_view = game_service_impl
+            .update_game_config(create_request_authenticated_as(
+                update_game_config_request,
+                "test_user_name",
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(
+            game_view.config.unwrap().default_cardpack_names,
+            vec![String::from("other_default_cardpack_name")]
+        );
+        assert_eq!(game_view.players.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn quick_start_game() {
+        let mut mock_api_resource_fetcher = MockApiResourceFetcher::new();
+        mock_api_resource_fetcher
+            .expect_get_user()
+            .return_once(|user_name| {
+                let mut user = create_empty_user();
+                user.name = user_name;
+                Ok(user)
+            });
+        mock_api_resource_fetcher
+            .expect_get_user_settings()
+            .return_once(|user_name| {
+                let mut game_config = get_valid_test_game_config();
+                game_config.hand_size = 1000;
+                Ok(UserSettings {
+                    name: format!("{}/settings", user_name),
+                    color_scheme: 0,
+                    quick_start_game_config: Some(game_config),
+                })
+            });
+        mock_api_resource_fetcher
+            .expect_custom_cardpack_exists()
+            .return_once(|_| Ok(false));
+        mock_api_resource_fetcher
+            .expect_default_cardpack_exists()
+            .return_once(|_| Ok(true));
+        mock_api_resource_fetcher
+            .expect_get_custom_cards_from_multiple_custom_cardpacks()
+            .return_once(|custom_cardpack_names| {
+                assert!(custom_cardpack_names.is_empty());
+                Ok((Vec::new(), Vec::new()))
+            });
+        mock_api_resource_fetcher
+            .expect_get_default_cards_from_multiple_default_cardpacks()
+            .return_once(|_| {
+                Ok((
+                    vec![create_empty_default_black_card()],
+                    vec![create_empty_default_white_card()],
+                ))
+            });
+        let game_service_impl = GameServiceImpl::new(
+            Box::from(mock_api_resource_fetcher),
+            None,
+            ClusterRouter::new_single_instance(),
+            GameLimits::default(),
+        );
+
+        let quick_start_game_response = game_service_impl
+            .quick_start_game(create_request_authenticated_as(
+                QuickStartGameRequest {
+                    user_name: String::from("users/1234"),
+                },
+                "users/1234",
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(
+            quick_start_game_response.adjustments,
+            vec![
+                format!("Changed `hand_size` from 1000 to {} to fit within the allowed range of {} to {}.", MAX_HAND_SIZE_LIMIT, MIN_HAND_SIZE_LIMIT, MAX_HAND_SIZE_LIMIT),
+                String::from("Removed custom cardpack `test_custom_cardpack_name` because it has been deleted."),
+            ]
+        );
+        let config = quick_start_game_response.game_view.unwrap().config.unwrap();
+        assert_eq!(config.hand_size, MAX_HAND_SIZE_LIMIT);
+        assert!(config.custom_cardpack_names.is_empty());
+        assert_eq!(
+            config.default_cardpack_names,
+            vec![String::from("test_default_cardpack_name")]
+        );
+    }
+
+    #[tokio::test]
+    async fn quick_join() {
+        let mut mock_api_resource_fetcher = MockApiResourceFetcher::new();
+        mock_api_resource_fetcher
+            .expect_get_user()
+            .returning(|user_name| {
+                let mut user = create_empty_user();
+                user.name = user_name;
+                Ok(user)
+            });
+        mock_api_resource_fetcher
+            .expect_get_user_settings()
+            .returning(|user_name| {
+                Ok(UserSettings {
+                    name: format!("{}/settings", user_name),
+                    color_scheme: 0,
+                    quick_start_game_config: Some(get_valid_test_game_config()),
+                })
+            });
+        mock_api_resource_fetcher
+            .expect_get_custom_cards_from_multiple_custom_cardpacks()
+            .returning(|_| {
+                Ok((
+                    vec![create_empty_custom_black_card()],
+                    vec![create_empty_custom_white_card()],
+                ))
+            });
+        mock_api_resource_fetcher
+            .expect_get_default_cards_from_multiple_default_cardpacks()
+            .returning(|_| {
+                Ok((
+                    vec![create_empty_default_black_card()],
+                    vec![create_empty_default_white_card()],
+                ))
+            });
+        let game_service_impl = GameServiceImpl::new(
+            Box::from(mock_api_resource_fetcher),
+            None,
+            ClusterRouter::new_single_instance(),
+            GameLimits::default(),
+        );
+
+        assert_eq!(
+            format!(
+                "{}",
+                game_service_impl
+                    .quick_join(create_request_authenticated_as(QuickJoinRequest {
+                        user_name: String::from("users/joiner_1"),
+                    }, "users/joiner_1"))
+                    .await
+                    .unwrap_err()
+            ),
+            "status: NotFound, message: \"There are no open games to join right now. Try creating one instead!\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+
+        assert!(game_service_impl
+            .create_game(create_request_authenticated_as(
+                CreateGameRequest {
+                    user_name: String::from("users/owner_1"),
+                    game_config: Some(get_valid_test_game_config()),
+                },
+                "users/owner_1"
+            ))
+            .await
+            .is_ok());
+        let mut other_game_config = get_valid_test_game_config();
+        other_game_config.display_name = String::from("Other Game");
+        other_game_config.default_cardpack_names =
+            vec![String::from("other_default_cardpack_name")];
+        assert!(game_service_impl
+            .create_game(create_request_authenticated_as(
+                CreateGameRequest {
+                    user_name: String::from("users/owner_2"),
+                    game_config: Some(other_game_config),
+                },
+                "users/owner_2"
+            ))
+            .await
+            .is_ok());
+
+        // Prefers the game that shares cardpacks with the user's quick start config.
+        let game_view = game_service_impl
+            .quick_join(create_request_authenticated_as(
+                QuickJoinRequest {
+                    user_name: String::from("users/joiner_1"),
+                },
+                "users/joiner_1",
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(game_view.config.unwrap().display_name, "Test Game");
+
+        // Skips games that the user is banned from.
+        assert!(game_service_impl
+            .ban_user(create_request_authenticated_as(
+                BanUserRequest {
+                    user_name: String::from("users/owner_1"),
+                    troll_user_name: String::from("users/joiner_2"),
+                },
+                "users/owner_1"
+            ))
+            .await
+            .is_ok());
+        let game_view = game_service_impl
+            .quick_join(create_request_authenticated_as(
+                QuickJoinRequest {
+                    user_name: String::from("users/joiner_2"),
+                },
+                "users/joiner_2",
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(game_view.config.unwrap().display_name, "Other Game");
+
+        assert_eq!(
+            format!(
+                "{}",
+                game_service_impl
+                    .quick_join(create_request_authenticated_as(QuickJoinRequest {
+                        user_name: String::from("users/joiner_2"),
+                    }, "users/joiner_2"))
+                    .await
+                    .unwrap_err()
+            ),
+            "status: InvalidArgument, message: \"User is already in a game.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+    }
 }
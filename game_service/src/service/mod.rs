@@ -1,2 +1,7 @@
+pub mod api_call_policy;
 pub mod api_resource_fetcher;
+pub mod caching_api_resource_fetcher;
+pub mod game_search;
 pub mod game_service_impl;
+pub mod quick_join;
+pub mod quick_start;
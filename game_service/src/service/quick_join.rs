@@ -0,0 +1,212 @@
+use shared::proto::crusty_cards_api::GameInfo;
+use std::cmp::Ordering;
+
+// How much each factor counts towards a game's quick join score.
+const FILLED_SEAT_WEIGHT: f64 = 1.0;
+const LOBBY_WEIGHT: f64 = 2.0;
+const RECENT_ACTIVITY_WEIGHT: f64 = 2.0;
+const SHARED_CARDPACK_WEIGHT: f64 = 3.0;
+
+// The recent activity score halves for every five minutes that a game sits idle.
+const ACTIVITY_HALF_LIFE_SECONDS: f64 = 300.0;
+
+// Scores how good of a fit a game is for a user looking for any game to join,
+// or returns None if the game has no open seats. Games that already have some
+// players, haven't started yet, were recently active and use the user's preferred
+// cardpacks all score higher.
+pub fn score_game_for_quick_join(
+    game_info: &GameInfo,
+    preferred_cardpack_names: &[String],
+    now_unix_seconds: i64,
+) -> Option<f64> {
+    let config = match &game_info.config {
+        Some(config) => config,
+        None => return None,
+    };
+    if config.max_players <= 0 || game_info.player_count >= config.max_players {
+        return None;
+    }
+
+    // Joining a game with a few players in it is more fun than sitting
+    // alone in an empty lobby, so fuller games score higher.
+    let filled_seat_score = game_info.player_count as f64 / config.max_players as f64;
+
+    // New players get to play from the first round if the game hasn't started.
+    let lobby_score = if game_info.is_running { 0.0 } else { 1.0 };
+
+    let recent_activity_score = match &game_info.last_activity_time {
+        Some(last_activity_time) => {
+            let idle_seconds = (now_unix_seconds - last_activity_time.seconds).max(0) as f64;
+            0.5_f64.powf(idle_seconds / ACTIVITY_HALF_LIFE_SECONDS)
+        }
+        None => 0.0,
+    };
+
+    let shared_cardpack_score = if preferred_cardpack_names.is_empty() {
+        0.0
+    } else {
+        let shared_cardpack_count = preferred_cardpack_names
+            .iter()
+            .filter(|&cardpack_name| {
+                config.custom_cardpack_names.contains(cardpack_name)
+                    || config.default_cardpack_names.contains(cardpack_name)
+            })
+            .count();
+        shared_cardpack_count as f64 / preferred_cardpack_names.len() as f64
+    };
+
+    Some(
+        FILLED_SEAT_WEIGHT * filled_seat_score
+            + LOBBY_WEIGHT * lobby_score
+            + RECENT_ACTIVITY_WEIGHT * recent_activity_score
+            + SHARED_CARDPACK_WEIGHT * shared_cardpack_score,
+    )
+}
+
+// Returns the games that have open seats, best match first.
+// Ties are broken by game id so that the order is deterministic.
+pub fn rank_games_for_quick_join(
+    games: Vec<GameInfo>,
+    preferred_cardpack_names: &[String],
+    now_unix_seconds: i64,
+) -> Vec<GameInfo> {
+    let mut scored_games: Vec<(GameInfo, f64)> = games
+        .into_iter()
+        .filter_map(|game_info| {
+            score_game_for_quick_join(&game_info, preferred_cardpack_names, now_unix_seconds)
+                .map(|score| (game_info, score))
+        })
+        .collect();
+    scored_games.sort_by(|(a_game, a_score), (b_game, b_score)| {
+        b_score
+            .partial_cmp(a_score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a_game.game_id.cmp(&b_game.game_id))
+    });
+    scored_games
+        .into_iter()
+        .map(|(game_info, _)| game_info)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::proto::google::protobuf::Timestamp;
+    use shared::test_helper::get_valid_test_game_config;
+
+    const NOW_UNIX_SECONDS: i64 = 1_000_000;
+
+    // Test game config has 3 max players.
+    fn create_game_info(
+        game_id: &str,
+        player_count: i32,
+        is_running: bool,
+        idle_seconds: i64,
+    ) -> GameInfo {
+        GameInfo {
+            game_id: String::from(game_id),
+            config: Some(get_valid_test_game_config()),
+            player_count,
+            owner: None,
+            is_running,
+            create_time: None,
+            last_activity_time: Some(Timestamp {
+                seconds: NOW_UNIX_SECONDS - idle_seconds,
+                nanos: 0,
+            }),
+        }
+    }
+
+    fn get_game_ids(games: &[GameInfo]) -> Vec<&str> {
+        games.iter().map(|game| &game.game_id[..]).collect()
+    }
+
+    #[test]
+    fn skips_full_games() {
+        assert_eq!(
+            score_game_for_quick_join(&create_game_info("1", 3, false, 0), &[], NOW_UNIX_SECONDS),
+            None
+        );
+        assert!(score_game_for_quick_join(
+            &create_game_info("1", 2, false, 0),
+            &[],
+            NOW_UNIX_SECONDS
+        )
+        .is_some());
+    }
+
+    #[test]
+    fn prefers_fuller_games() {
+        let games = vec![
+            create_game_info("empty", 0, false, 0),
+            create_game_info("half", 1, false, 0),
+            create_game_info("almost_full", 2, false, 0),
+        ];
+        assert_eq!(
+            get_game_ids(&rank_games_for_quick_join(games, &[], NOW_UNIX_SECONDS)),
+            vec!["almost_full", "half", "empty"]
+        );
+    }
+
+    #[test]
+    fn prefers_lobbies_over_running_games() {
+        let games = vec![
+            create_game_info("running", 2, true, 0),
+            create_game_info("lobby", 1, false, 0),
+        ];
+        assert_eq!(
+            get_game_ids(&rank_games_for_quick_join(games, &[], NOW_UNIX_SECONDS)),
+            vec!["lobby", "running"]
+        );
+    }
+
+    #[test]
+    fn prefers_recently_active_games() {
+        let games = vec![
+            create_game_info("idle_for_an_hour", 1, false, 60 * 60),
+            create_game_info("just_active", 1, false, 0),
+            create_game_info("idle_for_five_minutes", 1, false, 5 * 60),
+        ];
+        assert_eq!(
+            get_game_ids(&rank_games_for_quick_join(games, &[], NOW_UNIX_SECONDS)),
+            vec!["just_active", "idle_for_five_minutes", "idle_for_an_hour"]
+        );
+    }
+
+    #[test]
+    fn prefers_shared_cardpacks() {
+        let mut other_cardpacks_game = create_game_info("other_cardpacks", 2, false, 0);
+        let other_cardpacks_config = other_cardpacks_game.config.as_mut().unwrap();
+        other_cardpacks_config.custom_cardpack_names = vec![String::from("other_custom")];
+        other_cardpacks_config.default_cardpack_names = vec![String::from("other_default")];
+        let games = vec![
+            other_cardpacks_game,
+            create_game_info("shared_cardpacks", 1, false, 0),
+        ];
+        let preferred_cardpack_names = vec![
+            String::from("test_custom_cardpack_name"),
+            String::from("test_default_cardpack_name"),
+        ];
+        assert_eq!(
+            get_game_ids(&rank_games_for_quick_join(
+                games,
+                &preferred_cardpack_names,
+                NOW_UNIX_SECONDS
+            )),
+            vec!["shared_cardpacks", "other_cardpacks"]
+        );
+    }
+
+    #[test]
+    fn breaks_ties_by_game_id() {
+        let games = vec![
+            create_game_info("b", 1, false, 0),
+            create_game_info("a", 1, false, 0),
+        ];
+        assert_eq!(
+            get_game_ids(&rank_games_for_quick_join(games, &[], NOW_UNIX_SECONDS)),
+            vec!["a", "b"]
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use shared::constants::*;
+use shared::proto::crusty_cards_api::{game_config::EndCondition, GameConfig};
+
+// Limits may have changed since the user saved their quick start config,
+// so numeric properties are clamped into the current limits rather than
+// rejecting the whole config. Returns a description of every change made.
+pub fn clamp_game_config_to_limits(config: &mut GameConfig) -> Vec<String> {
+    let mut adjustments = Vec::new();
+
+    if let Some(adjustment) = clamp_property(
+        "max_players",
+        &mut config.max_players,
+        MIN_PLAYER_LIMIT,
+        MAX_PLAYER_LIMIT,
+    ) {
+        adjustments.push(adjustment);
+    }
+
+    if let Some(adjustment) = clamp_property(
+        "hand_size",
+        &mut config.hand_size,
+        MIN_HAND_SIZE_LIMIT,
+        MAX_HAND_SIZE_LIMIT,
+    ) {
+        adjustments.push(adjustment);
+    }
+
+    if let Some(EndCondition::MaxScore(max_score)) = &mut config.end_condition {
+        if let Some(adjustment) =
+            clamp_property("max_score", max_score, MIN_SCORE_LIMIT, MAX_SCORE_LIMIT)
+        {
+            adjustments.push(adjustment);
+        }
+    }
+
+    adjustments
+}
+
+fn clamp_property(name: &str, value: &mut i32, min: i32, max: i32) -> Option<String> {
+    let clamped_value = (*value).clamp(min, max);
+    if clamped_value == *value {
+        return None;
+    }
+    let adjustment = format!(
+        "Changed `{}` from {} to {} to fit within the allowed range of {} to {}.",
+        name, value, clamped_value, min, max
+    );
+    *value = clamped_value;
+    Some(adjustment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::test_helper::get_valid_test_game_config;
+
+    #[test]
+    fn valid_config_is_unchanged() {
+        let mut config = get_valid_test_game_config();
+        assert!(clamp_game_config_to_limits(&mut config).is_empty());
+        assert_eq!(config, get_valid_test_game_config());
+    }
+
+    #[test]
+    fn clamps_properties_into_limits() {
+        let mut config = get_valid_test_game_config();
+        config.max_players = MAX_PLAYER_LIMIT + 1;
+        config.hand_size = 0;
+        config.end_condition = Some(EndCondition::MaxScore(MAX_SCORE_LIMIT + 50));
+
+        assert_eq!(
+            clamp_game_config_to_limits(&mut config),
+            vec![
+                format!(
+                    "Changed `max_players` from {} to {} to fit within the allowed range of {} to {}.",
+                    MAX_PLAYER_LIMIT + 1,
+                    MAX_PLAYER_LIMIT,
+                    MIN_PLAYER_LIMIT,
+                    MAX_PLAYER_LIMIT
+                ),
+                format!(
+                    "Changed `hand_size` from 0 to {} to fit within the allowed range of {} to {}.",
+                    MIN_HAND_SIZE_LIMIT, MIN_HAND_SIZE_LIMIT, MAX_HAND_SIZE_LIMIT
+                ),
+                format!(
+                    "Changed `max_score` from {} to {} to fit within the allowed range of {} to {}.",
+                    MAX_SCORE_LIMIT + 50,
+                    MAX_SCORE_LIMIT,
+                    MIN_SCORE_LIMIT,
+                    MAX_SCORE_LIMIT
+                ),
+            ]
+        );
+        assert_eq!(config.max_players, MAX_PLAYER_LIMIT);
+        assert_eq!(config.hand_size, MIN_HAND_SIZE_LIMIT);
+        assert_eq!(
+            config.end_condition,
+            Some(EndCondition::MaxScore(MAX_SCORE_LIMIT))
+        );
+    }
+}
@@ -1,10 +1,16 @@
+use std::env;
 use std::fs;
+use std::path::PathBuf;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let _ = fs::create_dir("./src/proto");
     tonic_build::configure()
         .out_dir("./src/proto")
         .compile_well_known_types(true)
+        // Read by `metrics` to find which gRPC methods exist.
+        .file_descriptor_set_path(
+            PathBuf::from(env::var("OUT_DIR")?).join("file_descriptor_set.bin"),
+        )
         .compile(
             &[
                 "proto/crusty_cards_api/admin_service.proto",
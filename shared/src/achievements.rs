@@ -0,0 +1,126 @@
+// Things that can happen to a player during a game. The game service
+// reports these as they happen, and achievements are unlocked by them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GameEvent {
+    // The player won a round.
+    RoundWon,
+    // The player won a round with at least one blank white card.
+    RoundWonWithBlankCard,
+    // The player played cards in a round but someone else won it.
+    RoundLost,
+    // A game in which the player judged at least one round has ended.
+    GameJudged,
+    // A round was judged while the player was in a game with no open seats.
+    RoundPlayedInFullGame,
+}
+
+pub enum AchievementRule {
+    // Unlocks once the event has happened `count` times in total, across any number of games.
+    Count {
+        event: GameEvent,
+        count: i64,
+    },
+    // Unlocks once the event happens `count` times in a row within a single game.
+    // The streak starts over whenever `reset_event` happens or a new game starts.
+    Streak {
+        event: GameEvent,
+        reset_event: GameEvent,
+        count: i64,
+    },
+}
+
+pub struct AchievementDefinition {
+    pub id: &'static str,
+    pub display_name: &'static str,
+    pub description: &'static str,
+    pub rule: AchievementRule,
+}
+
+impl AchievementDefinition {
+    // Streaks are tracked by the game service within a single game, so
+    // a streak only needs to be completed once to unlock its achievement.
+    pub fn get_required_progress(&self) -> i64 {
+        match self.rule {
+            AchievementRule::Count { count, .. } => count,
+            AchievementRule::Streak { .. } => 1,
+        }
+    }
+}
+
+pub const ACHIEVEMENT_DEFINITIONS: &[AchievementDefinition] = &[
+    AchievementDefinition {
+        id: "blank-slate",
+        display_name: "Blank Slate",
+        description: "Win a round with a blank white card.",
+        rule: AchievementRule::Count {
+            event: GameEvent::RoundWonWithBlankCard,
+            count: 1,
+        },
+    },
+    AchievementDefinition {
+        id: "on-a-roll",
+        display_name: "On a Roll",
+        description: "Win five rounds in a row.",
+        rule: AchievementRule::Streak {
+            event: GameEvent::RoundWon,
+            reset_event: GameEvent::RoundLost,
+            count: 5,
+        },
+    },
+    AchievementDefinition {
+        id: "seasoned-judge",
+        display_name: "Seasoned Judge",
+        description: "Judge in ten different games.",
+        rule: AchievementRule::Count {
+            event: GameEvent::GameJudged,
+            count: 10,
+        },
+    },
+    AchievementDefinition {
+        id: "full-house",
+        display_name: "Full House",
+        description: "Play in a game with the maximum number of players.",
+        rule: AchievementRule::Count {
+            event: GameEvent::RoundPlayedInFullGame,
+            count: 1,
+        },
+    },
+];
+
+pub fn get_achievement_definition(id: &str) -> Option<&'static AchievementDefinition> {
+    ACHIEVEMENT_DEFINITIONS
+        .iter()
+        .find(|definition| definition.id == id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn achievement_ids_are_unique() {
+        let ids: HashSet<&str> = ACHIEVEMENT_DEFINITIONS
+            .iter()
+            .map(|definition| definition.id)
+            .collect();
+        assert_eq!(ids.len(), ACHIEVEMENT_DEFINITIONS.len());
+    }
+
+    #[test]
+    fn gets_achievement_definition() {
+        assert_eq!(
+            get_achievement_definition("seasoned-judge")
+                .unwrap()
+                .get_required_progress(),
+            10
+        );
+        assert_eq!(
+            get_achievement_definition("on-a-roll")
+                .unwrap()
+                .get_required_progress(),
+            1
+        );
+        assert!(get_achievement_definition("fake-achievement").is_none());
+    }
+}
@@ -0,0 +1,479 @@
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tonic::codegen::InterceptedService;
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::service::Interceptor;
+use tonic::transport::Channel;
+use tonic::{Request, Status};
+use tower::{Layer, Service};
+
+pub const AUTHORIZATION_METADATA_KEY: &str = "authorization";
+pub const BEARER_PREFIX: &str = "Bearer ";
+
+// Sent by internal services, such as game service, on every request they make to
+// another service. Its value is a secret shared by every service in the cluster.
+pub const INTERNAL_SERVICE_SECRET_METADATA_KEY: &str = "x-crusty-cards-internal-service-secret";
+
+// Signing key used in auth test mode. It's public, so anyone can sign a
+// token for any user with it. Never enable test mode in production.
+pub const TEST_MODE_SESSION_TOKEN_SECRET: &str = "crusty-cards-local-test-mode-signing-key";
+
+// Internal service secret used in auth test mode. Like the
+// session token secret above, it's public and insecure.
+pub const TEST_MODE_INTERNAL_SERVICE_SECRET: &str = "crusty-cards-local-test-mode-internal-secret";
+
+tokio::task_local! {
+    static CALLER_AUTHORIZATION: Option<String>;
+}
+
+#[derive(Serialize, Deserialize)]
+struct SessionTokenClaims {
+    // The name of the user that the token was issued to, such as `users/1234`.
+    sub: String,
+    // Expiration time, in seconds since the Unix epoch.
+    exp: u64,
+}
+
+// Session tokens are JWTs signed with HMAC-SHA256. They're issued
+// to users when they sign in, and identify the user in every request.
+pub struct SessionTokenKey {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+}
+
+impl SessionTokenKey {
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            encoding_key: EncodingKey::from_secret(secret),
+            decoding_key: DecodingKey::from_secret(secret),
+        }
+    }
+
+    pub fn create_session_token(&self, user_name: &str, lifetime: Duration) -> String {
+        let expire_time = SystemTime::now() + lifetime;
+        let claims = SessionTokenClaims {
+            sub: String::from(user_name),
+            exp: expire_time
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        };
+        // Unwrap is safe here because HMAC signing can't fail.
+        encode(&Header::new(Algorithm::HS256), &claims, &self.encoding_key).unwrap()
+    }
+
+    // Returns the name of the user that the token was issued to.
+    pub fn verify_session_token(&self, session_token: &str) -> Result<String, Status> {
+        match decode::<SessionTokenClaims>(
+            session_token,
+            &self.decoding_key,
+            &Validation::new(Algorithm::HS256),
+        ) {
+            Ok(token_data) => Ok(token_data.claims.sub),
+            Err(_) => Err(Status::unauthenticated(
+                "Session token is invalid or has expired.",
+            )),
+        }
+    }
+}
+
+// The user that sent a request, as proven by their session token.
+#[derive(Clone)]
+struct AuthenticatedUser {
+    user_name: String,
+}
+
+// Marks a request as sent by another service in the cluster rather than by a user.
+#[derive(Clone)]
+struct InternalCaller;
+
+// Verifies the session token sent as a bearer token in the `authorization`
+// metadata, and binds the user it was issued to to the request. Requests
+// without a token are let through anonymously, so that public RPCs keep
+// working, and handlers decide whether they need a caller by calling
+// `check_caller_owns_resource`. Requests with an invalid token are rejected.
+//
+// Services that accept internal traffic also verify the internal service
+// secret, and mark requests that include it as coming from an internal caller.
+#[derive(Clone)]
+pub struct SessionAuthInterceptor {
+    session_token_key: Arc<SessionTokenKey>,
+    internal_service_secret_or: Option<Arc<String>>,
+}
+
+impl SessionAuthInterceptor {
+    pub fn new(session_token_key: Arc<SessionTokenKey>) -> Self {
+        Self {
+            session_token_key,
+            internal_service_secret_or: None,
+        }
+    }
+
+    pub fn new_with_internal_service_secret(
+        session_token_key: Arc<SessionTokenKey>,
+        internal_service_secret: &str,
+    ) -> Self {
+        Self {
+            session_token_key,
+            internal_service_secret_or: Some(Arc::new(String::from(internal_service_secret))),
+        }
+    }
+
+    fn check_internal_service_secret(&self, request: &Request<()>) -> Result<bool, Status> {
+        let metadata_value = match request.metadata().get(INTERNAL_SERVICE_SECRET_METADATA_KEY) {
+            Some(metadata_value) => metadata_value,
+            None => return Ok(false),
+        };
+        match &self.internal_service_secret_or {
+            Some(internal_service_secret) => {
+                if constant_time_eq(
+                    metadata_value.as_bytes(),
+                    internal_service_secret.as_bytes(),
+                ) {
+                    Ok(true)
+                } else {
+                    Err(Status::unauthenticated(
+                        "Internal service secret is invalid.",
+                    ))
+                }
+            }
+            None => Err(Status::permission_denied(
+                "This service does not accept internal traffic.",
+            )),
+        }
+    }
+}
+
+// Compares every byte regardless of where the first difference is,
+// so that response times don't reveal how much of a secret was guessed.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter()
+        .zip(b.iter())
+        .fold(0, |difference, (a, b)| difference | (a ^ b))
+        == 0
+}
+
+impl Interceptor for SessionAuthInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if self.check_internal_service_secret(&request)? {
+            request.extensions_mut().insert(InternalCaller);
+        }
+
+        let session_token = match request.metadata().get(AUTHORIZATION_METADATA_KEY) {
+            Some(metadata_value) => match metadata_value
+                .to_str()
+                .ok()
+                .and_then(|value| value.strip_prefix(BEARER_PREFIX))
+            {
+                Some(session_token) => String::from(session_token),
+                None => {
+                    return Err(Status::unauthenticated(
+                        "Authorization metadata must be a bearer token.",
+                    ))
+                }
+            },
+            None => return Ok(request),
+        };
+        let user_name = self
+            .session_token_key
+            .verify_session_token(&session_token)?;
+        request
+            .extensions_mut()
+            .insert(AuthenticatedUser { user_name });
+        Ok(request)
+    }
+}
+
+pub fn is_internal_caller<T>(request: &Request<T>) -> bool {
+    request.extensions().get::<InternalCaller>().is_some()
+}
+
+// For RPCs that only other services in the cluster are allowed to call.
+pub fn check_caller_is_internal<T>(request: &Request<T>) -> Result<(), Status> {
+    if is_internal_caller(request) {
+        Ok(())
+    } else {
+        Err(Status::permission_denied(
+            "Only internal services may call this method.",
+        ))
+    }
+}
+
+pub fn get_caller_user_name<T>(request: &Request<T>) -> Option<&str> {
+    request
+        .extensions()
+        .get::<AuthenticatedUser>()
+        .map(|authenticated_user| authenticated_user.user_name.as_str())
+}
+
+// Returns an error unless the request was sent by the user with the given
+// name, or by the user that the given resource belongs to. For instance,
+// `users/1234/cardpacks/5678` belongs to `users/1234`. Internal callers
+// act on behalf of users, so they have access to every resource.
+pub fn check_caller_owns_resource<T>(
+    request: &Request<T>,
+    resource_name: &str,
+) -> Result<(), Status> {
+    if is_internal_caller(request) {
+        return Ok(());
+    }
+    let caller_user_name = match get_caller_user_name(request) {
+        Some(caller_user_name) => caller_user_name,
+        None => {
+            return Err(Status::unauthenticated(
+                "Request must include a session token.",
+            ))
+        }
+    };
+    let is_owner = match resource_name.strip_prefix(caller_user_name) {
+        Some(remainder) => remainder.is_empty() || remainder.starts_with('/'),
+        None => false,
+    };
+    if is_owner {
+        Ok(())
+    } else {
+        Err(Status::permission_denied(format!(
+            "Caller does not have access to `{}`.",
+            resource_name
+        )))
+    }
+}
+
+// Creates a request that's already been authenticated as the given user,
+// as if it had passed through `SessionAuthInterceptor`. Meant for tests.
+pub fn create_request_authenticated_as<T>(message: T, user_name: &str) -> Request<T> {
+    let mut request = Request::new(message);
+    request.extensions_mut().insert(AuthenticatedUser {
+        user_name: String::from(user_name),
+    });
+    request
+}
+
+// Creates a request that's already been marked as sent by an
+// internal caller, as if it had passed through `SessionAuthInterceptor`.
+// Meant for tests.
+pub fn create_internal_request<T>(message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    request.extensions_mut().insert(InternalCaller);
+    request
+}
+
+// Adds the internal service secret to every request made by a client, so that
+// the service receiving them can tell that they come from inside the cluster.
+// For example, `CardpackServiceClient::with_interceptor(channel, interceptor)`.
+#[derive(Clone)]
+pub struct InternalServiceSecretInterceptor {
+    internal_service_secret: MetadataValue<Ascii>,
+}
+
+impl InternalServiceSecretInterceptor {
+    // The secret must be printable ASCII, which `ConfigLoader::get_auth_config` ensures.
+    pub fn new(internal_service_secret: &str) -> Self {
+        Self {
+            internal_service_secret: internal_service_secret.parse().unwrap(),
+        }
+    }
+}
+
+impl Interceptor for InternalServiceSecretInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        request.metadata_mut().insert(
+            INTERNAL_SERVICE_SECRET_METADATA_KEY,
+            self.internal_service_secret.clone(),
+        );
+        Ok(request)
+    }
+}
+
+// A channel to another service in the cluster that authenticates as an internal caller.
+pub type InternalServiceChannel = InterceptedService<Channel, InternalServiceSecretInterceptor>;
+
+// Adds the session token of the request currently being handled, so that calls
+// made to other services on behalf of the caller are authenticated as the caller.
+pub fn attach_caller_session_token<T>(request: &mut Request<T>) {
+    let authorization_or = CALLER_AUTHORIZATION
+        .try_with(|authorization_or| authorization_or.clone())
+        .ok()
+        .flatten();
+    if let Some(authorization) = authorization_or {
+        if let Ok(metadata_value) = authorization.parse() {
+            request
+                .metadata_mut()
+                .insert(AUTHORIZATION_METADATA_KEY, metadata_value);
+        }
+    }
+}
+
+// Makes the caller's session token available to `attach_caller_session_token`
+// while each request is handled. Only needed by services that call other
+// services on behalf of their callers. Add it with
+// `Server::builder().layer(SessionTokenPropagationLayer)`.
+#[derive(Clone, Copy, Default)]
+pub struct SessionTokenPropagationLayer;
+
+impl<S> Layer<S> for SessionTokenPropagationLayer {
+    type Service = SessionTokenPropagationService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SessionTokenPropagationService { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct SessionTokenPropagationService<S> {
+    inner: S,
+}
+
+impl<S, ReqBody> Service<http::Request<ReqBody>> for SessionTokenPropagationService<S>
+where
+    S: Service<http::Request<ReqBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<ReqBody>) -> Self::Future {
+        let authorization_or = request
+            .headers()
+            .get(AUTHORIZATION_METADATA_KEY)
+            .and_then(|header_value| header_value.to_str().ok())
+            .map(String::from);
+        Box::pin(CALLER_AUTHORIZATION.scope(authorization_or, self.inner.call(request)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_request_with_authorization(authorization: &str) -> Request<()> {
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert(AUTHORIZATION_METADATA_KEY, authorization.parse().unwrap());
+        request
+    }
+
+    #[test]
+    fn interceptor_binds_caller_to_request() {
+        let session_token_key = Arc::new(SessionTokenKey::new(
+            TEST_MODE_SESSION_TOKEN_SECRET.as_bytes(),
+        ));
+        let mut interceptor = SessionAuthInterceptor::new(session_token_key.clone());
+
+        let anonymous_request = interceptor.call(Request::new(())).unwrap();
+        assert_eq!(get_caller_user_name(&anonymous_request), None);
+
+        let session_token =
+            session_token_key.create_session_token("users/1234", Duration::from_secs(60));
+        let request = interceptor
+            .call(create_request_with_authorization(&format!(
+                "Bearer {}",
+                session_token
+            )))
+            .unwrap();
+        assert_eq!(get_caller_user_name(&request), Some("users/1234"));
+
+        assert_eq!(
+            interceptor
+                .call(create_request_with_authorization(&session_token))
+                .err()
+                .unwrap()
+                .message(),
+            "Authorization metadata must be a bearer token."
+        );
+        let other_key = SessionTokenKey::new(b"some-other-signing-key");
+        assert_eq!(
+            interceptor
+                .call(create_request_with_authorization(&format!(
+                    "Bearer {}",
+                    other_key.create_session_token("users/1234", Duration::from_secs(60))
+                )))
+                .err()
+                .unwrap()
+                .message(),
+            "Session token is invalid or has expired."
+        );
+    }
+
+    #[test]
+    fn interceptor_identifies_internal_callers() {
+        let session_token_key = Arc::new(SessionTokenKey::new(
+            TEST_MODE_SESSION_TOKEN_SECRET.as_bytes(),
+        ));
+        let create_request_from_internal_service = || {
+            InternalServiceSecretInterceptor::new(TEST_MODE_INTERNAL_SERVICE_SECRET)
+                .call(Request::new(()))
+                .unwrap()
+        };
+
+        let mut interceptor = SessionAuthInterceptor::new_with_internal_service_secret(
+            session_token_key.clone(),
+            TEST_MODE_INTERNAL_SERVICE_SECRET,
+        );
+        let request = interceptor.call(Request::new(())).unwrap();
+        assert!(!is_internal_caller(&request));
+        assert!(check_caller_is_internal(&request).is_err());
+        let request = interceptor
+            .call(create_request_from_internal_service())
+            .unwrap();
+        assert!(is_internal_caller(&request));
+        assert!(check_caller_is_internal(&request).is_ok());
+        assert!(check_caller_owns_resource(&request, "users/1234/settings").is_ok());
+
+        let mut other_interceptor = SessionAuthInterceptor::new_with_internal_service_secret(
+            session_token_key.clone(),
+            "some-other-internal-service-secret",
+        );
+        assert_eq!(
+            other_interceptor
+                .call(create_request_from_internal_service())
+                .err()
+                .unwrap()
+                .code(),
+            tonic::Code::Unauthenticated
+        );
+        assert_eq!(
+            SessionAuthInterceptor::new(session_token_key)
+                .call(create_request_from_internal_service())
+                .err()
+                .unwrap()
+                .code(),
+            tonic::Code::PermissionDenied
+        );
+    }
+
+    #[test]
+    fn check_caller_owns_resource_matches_resource_parent() {
+        let request = create_request_authenticated_as((), "users/1234");
+        assert!(check_caller_owns_resource(&request, "users/1234").is_ok());
+        assert!(check_caller_owns_resource(&request, "users/1234/cardpacks/5678").is_ok());
+        assert_eq!(
+            check_caller_owns_resource(&request, "users/12345")
+                .err()
+                .unwrap()
+                .code(),
+            tonic::Code::PermissionDenied
+        );
+        assert_eq!(
+            check_caller_owns_resource(&Request::new(()), "users/1234")
+                .err()
+                .unwrap()
+                .code(),
+            tonic::Code::Unauthenticated
+        );
+    }
+}
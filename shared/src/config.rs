@@ -0,0 +1,631 @@
+use super::auth::{TEST_MODE_INTERNAL_SERVICE_SECRET, TEST_MODE_SESSION_TOKEN_SECRET};
+use super::constants::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use super::rate_limit::{parse_rate_limit_rule, RateLimit, DEFAULT_RATE_LIMIT_RULES};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+// Points to an optional TOML config file. When it's unset, config values
+// are only read from the environment and otherwise fall back to defaults.
+pub const CONFIG_FILE_ENV_VAR: &str = "CONFIG_FILE";
+
+const MIN_SECRET_LENGTH: usize = 32;
+
+// Every problem found while loading config, so that a misconfigured
+// service reports everything that needs fixing at once.
+pub struct ConfigError {
+    messages: Vec<String>,
+}
+
+impl ConfigError {
+    pub fn get_messages(&self) -> &[String] {
+        &self.messages
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("Invalid configuration:")?;
+        for message in &self.messages {
+            formatter.write_str(&format!("\n  - {}", message))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ConfigError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, formatter)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+pub struct AuthConfig {
+    session_token_secret: String,
+    internal_service_secret: String,
+    is_test_mode: bool,
+}
+
+impl AuthConfig {
+    // Session tokens are signed and verified with this.
+    pub fn get_session_token_secret(&self) -> &[u8] {
+        self.session_token_secret.as_bytes()
+    }
+
+    // Shared by every service in the cluster, and sent
+    // with every request that one service makes to another.
+    pub fn get_internal_service_secret(&self) -> &str {
+        &self.internal_service_secret
+    }
+
+    pub fn is_test_mode(&self) -> bool {
+        self.is_test_mode
+    }
+}
+
+enum RawValue {
+    Env(String),
+    File(toml::Value),
+}
+
+// Reads config values from a TOML file, with environment variables taking
+// precedence. Each key in the file can be overridden by the environment
+// variable with the same name in uppercase, so `api_uri` in the file is
+// overridden by `API_URI`. Empty environment variables count as unset.
+// Command line flags such as `--api_uri=<value>` override both.
+//
+// Getters never fail. Instead, they record any problem and return the
+// default, and `finish` returns every recorded problem at the end.
+pub struct ConfigLoader {
+    file_values: toml::value::Table,
+    env_values: HashMap<String, String>,
+    flag_values: HashMap<String, String>,
+    used_keys: HashSet<String>,
+    errors: Vec<String>,
+}
+
+impl ConfigLoader {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let env_values: HashMap<String, String> = std::env::vars().collect();
+        let file_contents_or = match env_values.get(CONFIG_FILE_ENV_VAR) {
+            Some(path) if !path.trim().is_empty() => match std::fs::read_to_string(path.trim()) {
+                Ok(file_contents) => Some(file_contents),
+                Err(err) => {
+                    return Err(ConfigError {
+                        messages: vec![format!(
+                            "Unable to read config file `{}`: {}.",
+                            path.trim(),
+                            err
+                        )],
+                    })
+                }
+            },
+            _ => None,
+        };
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        Self::new(file_contents_or.as_deref(), env_values)?.with_flags(&args)
+    }
+
+    // Takes the environment as a map so that services can test their config
+    // without depending on the environment of the process running the tests.
+    pub fn new(
+        file_contents_or: Option<&str>,
+        env_values: HashMap<String, String>,
+    ) -> Result<Self, ConfigError> {
+        let file_values = match file_contents_or {
+            Some(file_contents) => match file_contents.parse::<toml::Value>() {
+                Ok(toml::Value::Table(table)) => table,
+                Ok(_) => toml::value::Table::new(),
+                Err(err) => {
+                    return Err(ConfigError {
+                        messages: vec![format!("Unable to parse config file: {}.", err)],
+                    })
+                }
+            },
+            None => toml::value::Table::new(),
+        };
+        Ok(Self {
+            file_values,
+            env_values,
+            flag_values: HashMap::new(),
+            used_keys: HashSet::new(),
+            errors: Vec::new(),
+        })
+    }
+
+    // Flags look like `--storage=memory`. Dashes in the key are
+    // treated as underscores, so `--mongo-uri` sets `mongo_uri`.
+    pub fn with_flags(mut self, args: &[String]) -> Result<Self, ConfigError> {
+        let mut errors = Vec::new();
+        for arg in args {
+            match arg.strip_prefix("--").and_then(|flag| flag.split_once('=')) {
+                Some((key, value)) if !key.is_empty() => {
+                    self.flag_values
+                        .insert(key.replace('-', "_"), String::from(value.trim()));
+                }
+                _ => errors.push(format!(
+                    "Command line argument `{}` must look like `--<key>=<value>`.",
+                    arg
+                )),
+            };
+        }
+        if errors.is_empty() {
+            Ok(self)
+        } else {
+            Err(ConfigError { messages: errors })
+        }
+    }
+
+    fn get_raw_value(&mut self, key: &str) -> Option<RawValue> {
+        self.used_keys.insert(String::from(key));
+        match self.flag_values.get(key) {
+            Some(value) if !value.is_empty() => return Some(RawValue::Env(value.clone())),
+            _ => {}
+        };
+        match self.env_values.get(&key.to_uppercase()) {
+            Some(value) if !value.trim().is_empty() => {
+                return Some(RawValue::Env(String::from(value.trim())))
+            }
+            _ => {}
+        };
+        self.file_values.get(key).cloned().map(RawValue::File)
+    }
+
+    pub fn get_optional_string(&mut self, key: &str) -> Option<String> {
+        match self.get_raw_value(key)? {
+            RawValue::Env(value) => Some(value),
+            RawValue::File(toml::Value::String(value)) if !value.trim().is_empty() => {
+                Some(String::from(value.trim()))
+            }
+            RawValue::File(toml::Value::String(_)) => None,
+            RawValue::File(_) => {
+                self.add_error(format!("`{}` must be a string.", key));
+                None
+            }
+        }
+    }
+
+    pub fn get_string(&mut self, key: &str, default: &str) -> String {
+        match self.get_optional_string(key) {
+            Some(value) => value,
+            None => String::from(default),
+        }
+    }
+
+    pub fn get_required_string(&mut self, key: &str) -> String {
+        match self.get_optional_string(key) {
+            Some(value) => value,
+            None => {
+                self.add_error(format!(
+                    "`{}` must be set, either in the config file or with the `{}` environment variable.",
+                    key,
+                    key.to_uppercase()
+                ));
+                String::new()
+            }
+        }
+    }
+
+    // Same as `get_required_string`, but the value must also
+    // start with one of the given schemes, such as `http://`.
+    pub fn get_required_uri(&mut self, key: &str, schemes: &[&str]) -> String {
+        let uri = self.get_required_string(key);
+        if !uri.is_empty() {
+            self.check_uri_scheme(key, &uri, schemes);
+        }
+        uri
+    }
+
+    pub fn get_uri(&mut self, key: &str, default: &str, schemes: &[&str]) -> String {
+        let uri = self.get_string(key, default);
+        self.check_uri_scheme(key, &uri, schemes);
+        uri
+    }
+
+    fn check_uri_scheme(&mut self, key: &str, uri: &str, schemes: &[&str]) {
+        if !schemes.iter().any(|scheme| uri.starts_with(scheme)) {
+            self.add_error(format!(
+                "`{}` must start with one of: {}.",
+                key,
+                schemes.join(", ")
+            ));
+        }
+    }
+
+    // Lists are comma-separated in environment variables and arrays of strings in the config file.
+    pub fn get_string_list(&mut self, key: &str) -> Vec<String> {
+        match self.get_raw_value(key) {
+            Some(RawValue::Env(value)) => parse_comma_separated_list(&value),
+            Some(RawValue::File(toml::Value::Array(values))) => {
+                let mut strings = Vec::new();
+                for value in values {
+                    match value {
+                        toml::Value::String(value) if !value.trim().is_empty() => {
+                            strings.push(String::from(value.trim()))
+                        }
+                        toml::Value::String(_) => {}
+                        _ => {
+                            self.add_error(format!("`{}` must only contain strings.", key));
+                            return Vec::new();
+                        }
+                    };
+                }
+                strings
+            }
+            Some(RawValue::File(_)) => {
+                self.add_error(format!("`{}` must be an array of strings.", key));
+                Vec::new()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    // Returns a value in the range `min..=max`.
+    pub fn get_integer(&mut self, key: &str, default: i64, min: i64, max: i64) -> i64 {
+        let value_or = match self.get_raw_value(key) {
+            Some(RawValue::Env(value)) => value.parse::<i64>().ok(),
+            Some(RawValue::File(toml::Value::Integer(value))) => Some(value),
+            Some(RawValue::File(_)) => None,
+            None => return default,
+        };
+        match value_or {
+            Some(value) if (min..=max).contains(&value) => value,
+            _ => {
+                self.add_error(format!(
+                    "`{}` must be an integer from {} to {}.",
+                    key, min, max
+                ));
+                default
+            }
+        }
+    }
+
+    pub fn get_bool(&mut self, key: &str, default: bool) -> bool {
+        let value_or = match self.get_raw_value(key) {
+            Some(RawValue::Env(value)) => match value.to_lowercase().as_str() {
+                "true" => Some(true),
+                "false" => Some(false),
+                _ => None,
+            },
+            Some(RawValue::File(toml::Value::Boolean(value))) => Some(value),
+            Some(RawValue::File(_)) => None,
+            None => return default,
+        };
+        match value_or {
+            Some(value) => value,
+            None => {
+                self.add_error(format!("`{}` must be either true or false.", key));
+                default
+            }
+        }
+    }
+
+    pub fn get_port(&mut self, key: &str, default: u16) -> u16 {
+        self.get_integer(key, default as i64, 1, u16::MAX as i64) as u16
+    }
+
+    // Returns the default and maximum page sizes for paginated list
+    // requests, which are applied with `BoundedPageSize::set_limits`.
+    pub fn get_page_size_limits(&mut self) -> (i64, i64) {
+        let default_page_size =
+            self.get_integer("default_page_size", DEFAULT_PAGE_SIZE, 1, i32::MAX as i64);
+        let max_page_size = self.get_integer("max_page_size", MAX_PAGE_SIZE, 1, i32::MAX as i64);
+        if default_page_size > max_page_size {
+            self.add_error(String::from(
+                "`default_page_size` must not be greater than `max_page_size`.",
+            ));
+        }
+        (default_page_size, max_page_size)
+    }
+
+    // In auth test mode, every secret is set to a publicly known test mode
+    // secret so that services can run locally without any shared setup.
+    pub fn get_auth_config(&mut self) -> AuthConfig {
+        let is_test_mode = self.get_bool("auth_test_mode", false);
+        AuthConfig {
+            session_token_secret: self.get_secret(
+                "session_token_secret",
+                is_test_mode,
+                TEST_MODE_SESSION_TOKEN_SECRET,
+            ),
+            internal_service_secret: self.get_secret(
+                "internal_service_secret",
+                is_test_mode,
+                TEST_MODE_INTERNAL_SERVICE_SECRET,
+            ),
+            is_test_mode,
+        }
+    }
+
+    // Rules look like `UserService/UserSearch=30/60`, and
+    // `DEFAULT_RATE_LIMIT_RULES` is used when none are set.
+    pub fn get_rate_limits(&mut self) -> HashMap<String, RateLimit> {
+        let mut rules = self.get_string_list("rate_limits");
+        if rules.is_empty() {
+            rules = DEFAULT_RATE_LIMIT_RULES
+                .iter()
+                .map(|rule| String::from(*rule))
+                .collect();
+        }
+        let mut rate_limits = HashMap::new();
+        for rule in rules {
+            match parse_rate_limit_rule(&rule) {
+                Ok((method, rate_limit)) => {
+                    rate_limits.insert(method, rate_limit);
+                }
+                Err(message) => self.add_error(message),
+            };
+        }
+        rate_limits
+    }
+
+    fn get_secret(&mut self, key: &str, is_test_mode: bool, test_mode_secret: &str) -> String {
+        match (is_test_mode, self.get_optional_string(key)) {
+            (true, Some(_)) => {
+                self.add_error(format!(
+                    "`{}` must not be set when `auth_test_mode` is enabled.",
+                    key
+                ));
+            }
+            (false, Some(secret)) if secret.len() < MIN_SECRET_LENGTH => {
+                self.add_error(format!(
+                    "`{}` must be at least {} characters long.",
+                    key, MIN_SECRET_LENGTH
+                ));
+            }
+            // Secrets are sent as gRPC metadata, which only allows printable ASCII.
+            (false, Some(secret)) if !secret.bytes().all(|byte| byte.is_ascii_graphic()) => {
+                self.add_error(format!(
+                    "`{}` must only contain printable ASCII characters.",
+                    key
+                ));
+            }
+            (false, Some(secret)) => return secret,
+            (false, None) => {
+                self.add_error(format!(
+                    "`{}` must be set unless `auth_test_mode` is enabled.",
+                    key
+                ));
+            }
+            (true, None) => {}
+        };
+        String::from(test_mode_secret)
+    }
+
+    // Records a problem that spans multiple values, such as two services sharing a port.
+    pub fn add_error(&mut self, message: String) {
+        self.errors.push(message);
+    }
+
+    // Keys in the config file that were never read are most likely typos,
+    // so they're treated as errors rather than being silently ignored.
+    pub fn finish(mut self) -> Result<(), ConfigError> {
+        let mut unknown_keys: Vec<&String> = self
+            .file_values
+            .keys()
+            .filter(|key| !self.used_keys.contains(*key))
+            .collect();
+        unknown_keys.sort();
+        let unknown_key_errors: Vec<String> = unknown_keys
+            .into_iter()
+            .map(|key| format!("Config file contains unknown key `{}`.", key))
+            .collect();
+        self.errors.extend(unknown_key_errors);
+
+        let mut unknown_flags: Vec<&String> = self
+            .flag_values
+            .keys()
+            .filter(|key| !self.used_keys.contains(*key))
+            .collect();
+        unknown_flags.sort();
+        let unknown_flag_errors: Vec<String> = unknown_flags
+            .into_iter()
+            .map(|key| format!("Unknown command line flag `--{}`.", key))
+            .collect();
+        self.errors.extend(unknown_flag_errors);
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError {
+                messages: self.errors,
+            })
+        }
+    }
+}
+
+fn parse_comma_separated_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|entry| entry.trim())
+        .filter(|entry| !entry.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_loader(file_contents_or: Option<&str>, env_values: &[(&str, &str)]) -> ConfigLoader {
+        ConfigLoader::new(
+            file_contents_or,
+            env_values
+                .iter()
+                .map(|(key, value)| (String::from(*key), String::from(*value)))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parse_comma_separated_list() {
+        assert!(super::parse_comma_separated_list("").is_empty());
+        assert_eq!(
+            super::parse_comma_separated_list("http://game-0:50052, http://game-1:50052,,"),
+            vec!["http://game-0:50052", "http://game-1:50052"]
+        );
+    }
+
+    #[test]
+    fn environment_overrides_config_file() {
+        let mut loader = create_loader(
+            Some("port = 50053\napi_uri = \"http://file:50052\"\naddresses = [\"a\", \"b\"]"),
+            &[("API_URI", "http://env:50052"), ("PORT", "")],
+        );
+        assert_eq!(loader.get_port("port", 50052), 50053);
+        assert_eq!(
+            loader.get_required_uri("api_uri", &["http://"]),
+            "http://env:50052"
+        );
+        assert_eq!(loader.get_string_list("addresses"), vec!["a", "b"]);
+        assert_eq!(loader.get_string("missing", "default"), "default");
+        assert!(loader.finish().is_ok());
+    }
+
+    #[test]
+    fn collects_every_error() {
+        let mut loader = create_loader(
+            Some("port = 0\ntypo = true"),
+            &[("METRICS_PORT", "not a port"), ("AMQP_URI", "http://amqp")],
+        );
+        assert_eq!(loader.get_port("port", 50052), 50052);
+        assert_eq!(loader.get_port("metrics_port", 9090), 9090);
+        loader.get_required_string("api_uri");
+        loader.get_required_uri("amqp_uri", &["amqp://", "amqps://"]);
+        assert_eq!(
+            loader.finish().err().unwrap().get_messages(),
+            &[
+                "`port` must be an integer from 1 to 65535.",
+                "`metrics_port` must be an integer from 1 to 65535.",
+                "`api_uri` must be set, either in the config file or with the `API_URI` environment variable.",
+                "`amqp_uri` must start with one of: amqp://, amqps://.",
+                "Config file contains unknown key `typo`."
+            ]
+        );
+    }
+
+    #[test]
+    fn secrets_are_required_outside_of_test_mode() {
+        let mut loader = create_loader(None, &[("AUTH_TEST_MODE", "true")]);
+        let auth_config = loader.get_auth_config();
+        assert!(auth_config.is_test_mode());
+        assert_eq!(
+            auth_config.get_session_token_secret(),
+            TEST_MODE_SESSION_TOKEN_SECRET.as_bytes()
+        );
+        assert_eq!(
+            auth_config.get_internal_service_secret(),
+            TEST_MODE_INTERNAL_SERVICE_SECRET
+        );
+        assert!(loader.finish().is_ok());
+
+        let mut loader = create_loader(
+            Some(
+                "session_token_secret = \"0123456789abcdef0123456789abcdef\"\n\
+                 internal_service_secret = \"fedcba9876543210fedcba9876543210\"",
+            ),
+            &[],
+        );
+        let auth_config = loader.get_auth_config();
+        assert!(!auth_config.is_test_mode());
+        assert_eq!(
+            auth_config.get_session_token_secret(),
+            b"0123456789abcdef0123456789abcdef"
+        );
+        assert_eq!(
+            auth_config.get_internal_service_secret(),
+            "fedcba9876543210fedcba9876543210"
+        );
+        assert!(loader.finish().is_ok());
+
+        let mut loader = create_loader(
+            None,
+            &[
+                ("SESSION_TOKEN_SECRET", "too-short"),
+                (
+                    "INTERNAL_SERVICE_SECRET",
+                    "0123456789abcdef 0123456789abcdef",
+                ),
+            ],
+        );
+        loader.get_auth_config();
+        assert_eq!(
+            loader.finish().err().unwrap().get_messages(),
+            &[
+                "`session_token_secret` must be at least 32 characters long.",
+                "`internal_service_secret` must only contain printable ASCII characters."
+            ]
+        );
+
+        let mut loader = create_loader(None, &[("AUTH_TEST_MODE", "yes")]);
+        loader.get_auth_config();
+        assert_eq!(
+            loader.finish().err().unwrap().get_messages(),
+            &[
+                "`auth_test_mode` must be either true or false.",
+                "`session_token_secret` must be set unless `auth_test_mode` is enabled.",
+                "`internal_service_secret` must be set unless `auth_test_mode` is enabled."
+            ]
+        );
+    }
+
+    #[test]
+    fn get_rate_limits() {
+        let mut loader = create_loader(None, &[]);
+        assert_eq!(
+            loader.get_rate_limits().len(),
+            DEFAULT_RATE_LIMIT_RULES.len()
+        );
+        assert!(loader.finish().is_ok());
+
+        let mut loader =
+            create_loader(Some("rate_limits = [\"UserService/UserSearch=5/10\"]"), &[]);
+        let rate_limits = loader.get_rate_limits();
+        assert_eq!(rate_limits.len(), 1);
+        assert_eq!(
+            rate_limits.get("UserService/UserSearch"),
+            Some(&RateLimit::new(5, std::time::Duration::from_secs(10)))
+        );
+        assert!(loader.finish().is_ok());
+
+        let mut loader = create_loader(None, &[("RATE_LIMITS", "UserSearch=5/10")]);
+        assert!(loader.get_rate_limits().is_empty());
+        assert_eq!(
+            loader.finish().err().unwrap().get_messages(),
+            &["Rate limit `UserSearch=5/10` must look like `<Service>/<Method>=<requests>/<seconds>`, with both numbers greater than zero."]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_config_file() {
+        assert!(ConfigLoader::new(Some("port = "), HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn flags_override_environment() {
+        let args = [
+            String::from("--storage=memory"),
+            String::from("--mongo-database=test"),
+            String::from("--unknown=1"),
+        ];
+        let mut loader = create_loader(None, &[("STORAGE", "mongo")])
+            .with_flags(&args)
+            .unwrap();
+        assert_eq!(loader.get_string("storage", "mongo"), "memory");
+        assert_eq!(loader.get_string("mongo_database", "crustyCards"), "test");
+        assert_eq!(
+            loader.finish().err().unwrap().get_messages(),
+            &["Unknown command line flag `--unknown`."]
+        );
+
+        assert_eq!(
+            create_loader(None, &[])
+                .with_flags(&[String::from("memory")])
+                .err()
+                .unwrap()
+                .get_messages(),
+            &["Command line argument `memory` must look like `--<key>=<value>`."]
+        );
+    }
+}
@@ -6,3 +6,5 @@ pub const MIN_HAND_SIZE_LIMIT: i32 = 3;
 pub const MAX_HAND_SIZE_LIMIT: i32 = 20;
 pub const MINIMUM_PLAYERS_REQUIRED_TO_PLAY: usize = 3;
 pub const MAX_BLACK_CARD_ANSWER_FIELDS: usize = 3;
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+pub const MAX_PAGE_SIZE: i64 = 1000;
@@ -11,6 +11,10 @@ pub fn empty_request_field_error(field_name: &str) -> Status {
     Status::invalid_argument(format!("Request field `{}` must not be blank.", field_name))
 }
 
+pub fn invalid_page_token_error() -> Status {
+    Status::invalid_argument("Page token is invalid.")
+}
+
 pub fn negative_request_field_error(field_name: &str) -> Status {
     Status::invalid_argument(format!(
         "Request field `{}` must not be negative.",
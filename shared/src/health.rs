@@ -0,0 +1,142 @@
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tonic_health::server::HealthReporter;
+use tonic_health::ServingStatus;
+use tracing::{info, warn};
+
+const READINESS_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+const READINESS_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+// A dependency that must be reachable for a service to handle requests.
+#[tonic::async_trait]
+pub trait ReadinessCheck: Send + Sync {
+    // Identifies the dependency in logs.
+    fn get_dependency_name(&self) -> &'static str;
+
+    async fn check(&self) -> Result<(), String>;
+}
+
+// Returns the name of each dependency that isn't
+// reachable, along with the reason why.
+pub async fn run_readiness_checks(
+    checks: &[Box<dyn ReadinessCheck>],
+) -> Vec<(&'static str, String)> {
+    let mut failures = Vec::new();
+    for check in checks {
+        let result = match tokio::time::timeout(READINESS_CHECK_TIMEOUT, check.check()).await {
+            Ok(result) => result,
+            Err(_) => Err(String::from("Timed out.")),
+        };
+        if let Err(err) = result {
+            failures.push((check.get_dependency_name(), err));
+        }
+    }
+    failures
+}
+
+// Periodically runs every readiness check and reports the result through the
+// `grpc.health.v1` service, both for the server as a whole (the empty service
+// name, which is what Kubernetes probes by default) and for each named service.
+// Everything is reported as not serving until the first round of checks passes.
+// Abort the returned handle before reporting anything else through the same
+// reporter, or the monitor may overwrite it the next time readiness changes.
+pub fn spawn_readiness_monitor(
+    mut health_reporter: HealthReporter,
+    service_names: Vec<&'static str>,
+    checks: Vec<Box<dyn ReadinessCheck>>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        // The reporter starts out reporting the server as a whole as serving.
+        health_reporter
+            .set_service_status("", ServingStatus::NotServing)
+            .await;
+
+        let mut was_ready_or: Option<bool> = None;
+        loop {
+            let failures = run_readiness_checks(&checks).await;
+            for (dependency_name, err) in &failures {
+                warn!(
+                    dependency = dependency_name,
+                    error = err.as_str(),
+                    "Readiness check failed."
+                );
+            }
+
+            let is_ready = failures.is_empty();
+            if was_ready_or != Some(is_ready) {
+                let status = if is_ready {
+                    ServingStatus::Serving
+                } else {
+                    ServingStatus::NotServing
+                };
+                health_reporter.set_service_status("", status).await;
+                for service_name in &service_names {
+                    health_reporter
+                        .set_service_status(service_name, status)
+                        .await;
+                }
+                info!(ready = is_ready, "Readiness changed.");
+                was_ready_or = Some(is_ready);
+            }
+
+            tokio::time::sleep(READINESS_CHECK_INTERVAL).await;
+        }
+    })
+}
+
+// Reports the server as a whole and each named service as not serving, so that
+// Kubernetes stops routing new requests to the server while it shuts down.
+pub async fn report_not_serving(
+    health_reporter: &mut HealthReporter,
+    service_names: &[&'static str],
+) {
+    health_reporter
+        .set_service_status("", ServingStatus::NotServing)
+        .await;
+    for service_name in service_names {
+        health_reporter
+            .set_service_status(service_name, ServingStatus::NotServing)
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeReadinessCheck {
+        dependency_name: &'static str,
+        result: Result<(), String>,
+    }
+
+    #[tonic::async_trait]
+    impl ReadinessCheck for FakeReadinessCheck {
+        fn get_dependency_name(&self) -> &'static str {
+            self.dependency_name
+        }
+
+        async fn check(&self) -> Result<(), String> {
+            self.result.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn run_readiness_checks_returns_failures() {
+        assert!(run_readiness_checks(&[]).await.is_empty());
+
+        let checks: Vec<Box<dyn ReadinessCheck>> = vec![
+            Box::from(FakeReadinessCheck {
+                dependency_name: "mongo",
+                result: Ok(()),
+            }),
+            Box::from(FakeReadinessCheck {
+                dependency_name: "sonic",
+                result: Err(String::from("Connection refused.")),
+            }),
+        ];
+        assert_eq!(
+            run_readiness_checks(&checks).await,
+            vec![("sonic", String::from("Connection refused."))]
+        );
+    }
+}
@@ -1,8 +1,16 @@
+pub mod achievements;
+pub mod auth;
 pub mod basic_validation;
+pub mod config;
 pub mod constants;
 pub mod grpc_error;
+pub mod health;
+pub mod metrics;
+pub mod page_token;
 pub mod proto;
 pub mod proto_validation;
+pub mod rate_limit;
+pub mod request_tracing;
 pub mod resource_name;
 pub mod test_helper;
 pub mod time;
@@ -0,0 +1,367 @@
+use hyper::body::{HttpBody, SizeHint};
+use hyper::header::CONTENT_TYPE;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use lazy_static::lazy_static;
+use prometheus::{register_histogram_vec, register_int_counter_vec, Encoder, TextEncoder};
+use prometheus::{HistogramVec, IntCounterVec};
+use prost::Message;
+use prost_types::FileDescriptorSet;
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tonic::Code;
+use tower::{Layer, Service};
+use tracing::error;
+
+const METRICS_PATH: &str = "/metrics";
+
+// Requests to methods that don't exist are grouped under this label.
+const UNKNOWN_LABEL: &str = "unknown";
+
+// Written by `build.rs` when compiling the protos.
+const FILE_DESCRIPTOR_SET: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/file_descriptor_set.bin"));
+
+// Served by `tonic_health` rather than defined in our protos.
+const HEALTH_METHOD_PATHS: &[&str] = &[
+    "/grpc.health.v1.Health/Check",
+    "/grpc.health.v1.Health/Watch",
+];
+
+lazy_static! {
+    static ref GRPC_REQUESTS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "grpc_requests_total",
+        "Number of gRPC requests handled, by method and response code.",
+        &["method", "code"]
+    )
+    .unwrap();
+    static ref GRPC_REQUEST_DURATION_SECONDS: HistogramVec = register_histogram_vec!(
+        "grpc_request_duration_seconds",
+        "Time taken to handle gRPC requests, by method.",
+        &["method"]
+    )
+    .unwrap();
+    static ref KNOWN_METHOD_PATHS: HashSet<String> = get_known_method_paths();
+}
+
+fn get_known_method_paths() -> HashSet<String> {
+    let mut method_paths: HashSet<String> = HEALTH_METHOD_PATHS
+        .iter()
+        .map(|method_path| String::from(*method_path))
+        .collect();
+    let file_descriptor_set = match FileDescriptorSet::decode(FILE_DESCRIPTOR_SET) {
+        Ok(file_descriptor_set) => file_descriptor_set,
+        Err(err) => {
+            error!(error = %err, "Failed to decode gRPC method names.");
+            return method_paths;
+        }
+    };
+    for file in &file_descriptor_set.file {
+        for service in &file.service {
+            for method in &service.method {
+                method_paths.insert(format!(
+                    "/{}.{}/{}",
+                    file.package(),
+                    service.name(),
+                    method.name()
+                ));
+            }
+        }
+    }
+    method_paths
+}
+
+// Request paths are chosen by the caller, so only paths of methods that exist
+// are used as labels. Otherwise, a client could create any number of series.
+fn get_grpc_method_label(path: &str) -> String {
+    if KNOWN_METHOD_PATHS.contains(path) {
+        String::from(path)
+    } else {
+        String::from(UNKNOWN_LABEL)
+    }
+}
+
+// Serves every metric in the default Prometheus registry over HTTP
+// at `/metrics`. This runs until the server fails, so callers should
+// spawn it alongside the gRPC server rather than awaiting it directly.
+pub async fn serve_metrics(address: SocketAddr) -> Result<(), hyper::Error> {
+    let make_service =
+        make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle_metrics_request)) });
+    Server::bind(&address).serve(make_service).await
+}
+
+async fn handle_metrics_request(request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if request.uri().path() != METRICS_PATH {
+        return Ok(create_response(StatusCode::NOT_FOUND, Body::empty()));
+    }
+
+    let encoder = TextEncoder::new();
+    let mut buffer = Vec::new();
+    if let Err(err) = encoder.encode(&prometheus::gather(), &mut buffer) {
+        error!(error = %err, "Failed to encode metrics.");
+        return Ok(create_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Body::empty(),
+        ));
+    }
+
+    let mut response = create_response(StatusCode::OK, Body::from(buffer));
+    if let Ok(content_type) = encoder.format_type().parse() {
+        response.headers_mut().insert(CONTENT_TYPE, content_type);
+    }
+    Ok(response)
+}
+
+fn create_response(status: StatusCode, body: Body) -> Response<Body> {
+    let mut response = Response::new(body);
+    *response.status_mut() = status;
+    response
+}
+
+// Records the count and latency of every request passing through a gRPC
+// server. Add it with `Server::builder().layer(GrpcMetricsLayer)`.
+#[derive(Clone, Copy, Default)]
+pub struct GrpcMetricsLayer;
+
+impl<S> Layer<S> for GrpcMetricsLayer {
+    type Service = GrpcMetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        GrpcMetricsService { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct GrpcMetricsService<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> Service<http::Request<ReqBody>> for GrpcMetricsService<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<GrpcCodeBody<ResBody>>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<ReqBody>) -> Self::Future {
+        // gRPC paths look like `/crusty_cards_api.GameService/CreateGame`.
+        let method = get_grpc_method_label(request.uri().path());
+        let start_time = Instant::now();
+        let response_future = self.inner.call(request);
+
+        Box::pin(async move {
+            match response_future.await {
+                // Requests are recorded once their status is known, which
+                // for streaming methods is after the whole stream is sent.
+                Ok(response) => Ok(GrpcCodeBody::wrap_response(response, move |code_or| {
+                    record_grpc_request(&method, &get_grpc_code_label(code_or), start_time)
+                })),
+                Err(err) => {
+                    record_grpc_request(&method, "TransportError", start_time);
+                    Err(err)
+                }
+            }
+        })
+    }
+}
+
+fn record_grpc_request(method: &str, code: &str, start_time: Instant) {
+    GRPC_REQUESTS_TOTAL.with_label_values(&[method, code]).inc();
+    GRPC_REQUEST_DURATION_SECONDS
+        .with_label_values(&[method])
+        .observe(start_time.elapsed().as_secs_f64());
+}
+
+// Tonic sends failed requests as trailers-only responses, so the status code of most
+// errors is in the headers. Every other response sends its status in the trailers.
+fn get_grpc_code(headers: &http::HeaderMap) -> Option<Code> {
+    headers.get("grpc-status").map(|header_value| {
+        match header_value.to_str().map(|value| value.parse::<i32>()) {
+            Ok(Ok(code)) => Code::from_i32(code),
+            _ => Code::Unknown,
+        }
+    })
+}
+
+pub(crate) fn get_grpc_code_label(code_or: Option<Code>) -> String {
+    match code_or {
+        Some(code) => format!("{:?}", code),
+        None => String::from(UNKNOWN_LABEL),
+    }
+}
+
+type OnGrpcCode = Box<dyn FnOnce(Option<Code>) + Send>;
+
+// Wraps a response body to find the response's gRPC status code. `on_code` is
+// called once, as soon as the code is known. It's given `None` if the response
+// ends or is dropped without a status, such as when the client disconnects.
+pub struct GrpcCodeBody<B> {
+    inner: B,
+    on_code_or: Option<OnGrpcCode>,
+}
+
+impl<B> GrpcCodeBody<B> {
+    pub(crate) fn wrap_response(
+        response: http::Response<B>,
+        on_code: impl FnOnce(Option<Code>) + Send + 'static,
+    ) -> http::Response<Self> {
+        let mut on_code_or: Option<OnGrpcCode> = Some(Box::new(on_code));
+        if let Some(code) = get_grpc_code(response.headers()) {
+            if let Some(on_code) = on_code_or.take() {
+                on_code(Some(code));
+            }
+        }
+        response.map(|inner| Self { inner, on_code_or })
+    }
+
+    fn report_code(&mut self, code_or: Option<Code>) {
+        if let Some(on_code) = self.on_code_or.take() {
+            on_code(code_or);
+        }
+    }
+}
+
+impl<B: HttpBody + Unpin> HttpBody for GrpcCodeBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let poll = Pin::new(&mut self.inner).poll_data(cx);
+        if let Poll::Ready(Some(Err(_))) = &poll {
+            self.report_code(None);
+        }
+        poll
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        let poll = Pin::new(&mut self.inner).poll_trailers(cx);
+        match &poll {
+            Poll::Ready(Ok(trailers_or)) => {
+                let code_or = trailers_or.as_ref().and_then(get_grpc_code);
+                self.report_code(code_or);
+            }
+            Poll::Ready(Err(_)) => self.report_code(None),
+            Poll::Pending => {}
+        };
+        poll
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl<B> Drop for GrpcCodeBody<B> {
+    fn drop(&mut self) {
+        self.report_code(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn get_grpc_method_label_for_known_methods() {
+        assert_eq!(
+            get_grpc_method_label("/crusty_cards_api.GameService/CreateGame"),
+            "/crusty_cards_api.GameService/CreateGame"
+        );
+        assert_eq!(
+            get_grpc_method_label("/grpc.health.v1.Health/Check"),
+            "/grpc.health.v1.Health/Check"
+        );
+        assert_eq!(
+            get_grpc_method_label("/crusty_cards_api.GameService/FakeMethod"),
+            "unknown"
+        );
+        assert_eq!(get_grpc_method_label("/"), "unknown");
+    }
+
+    #[test]
+    fn get_grpc_code_from_headers() {
+        let mut headers = http::HeaderMap::new();
+        assert_eq!(get_grpc_code(&headers), None);
+
+        headers.insert("grpc-status", http::HeaderValue::from_static("5"));
+        assert_eq!(get_grpc_code(&headers), Some(Code::NotFound));
+
+        headers.insert("grpc-status", http::HeaderValue::from_static("not a code"));
+        assert_eq!(get_grpc_code(&headers), Some(Code::Unknown));
+    }
+
+    #[test]
+    fn get_grpc_code_label_without_code() {
+        assert_eq!(get_grpc_code_label(Some(Code::Ok)), "Ok");
+        assert_eq!(get_grpc_code_label(None), "unknown");
+    }
+
+    #[tokio::test]
+    async fn grpc_code_body_reads_code_from_trailers() {
+        let (mut sender, body) = Body::channel();
+        let (code_sender, code_receiver) = mpsc::channel();
+        let mut response = GrpcCodeBody::wrap_response(Response::new(body), move |code_or| {
+            code_sender.send(code_or).unwrap()
+        });
+        assert!(code_receiver.try_recv().is_err());
+
+        let mut trailers = http::HeaderMap::new();
+        trailers.insert("grpc-status", http::HeaderValue::from_static("0"));
+        sender.send_trailers(trailers).await.unwrap();
+        drop(sender);
+        assert!(response.body_mut().data().await.is_none());
+        response.body_mut().trailers().await.unwrap();
+        assert_eq!(code_receiver.try_recv(), Ok(Some(Code::Ok)));
+
+        // The code is only reported once.
+        drop(response);
+        assert!(code_receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn grpc_code_body_reads_code_from_headers() {
+        let (code_sender, code_receiver) = mpsc::channel();
+        let mut response = Response::new(Body::empty());
+        response
+            .headers_mut()
+            .insert("grpc-status", http::HeaderValue::from_static("5"));
+        let response = GrpcCodeBody::wrap_response(response, move |code_or| {
+            code_sender.send(code_or).unwrap()
+        });
+        assert_eq!(code_receiver.try_recv(), Ok(Some(Code::NotFound)));
+        drop(response);
+        assert!(code_receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn grpc_code_body_reports_missing_code_when_dropped() {
+        let (code_sender, code_receiver) = mpsc::channel();
+        let response = GrpcCodeBody::wrap_response(Response::new(Body::empty()), move |code_or| {
+            code_sender.send(code_or).unwrap()
+        });
+        drop(response);
+        assert_eq!(code_receiver.try_recv(), Ok(None));
+    }
+}
@@ -1,4 +1,4 @@
-use super::super::service::helper::*;
+use super::grpc_error::invalid_page_token_error;
 use bson::oid::ObjectId;
 use prost::Message;
 use prost_types::Any;
@@ -84,10 +84,26 @@ pub fn parse_page_token_object_id(
     }
 }
 
+// Used by lists that are paginated by position rather than by id.
+pub fn create_page_token_for_index(request_message: &impl Message, index: usize) -> String {
+    create_page_token(request_message, format!("{}", index))
+}
+
+pub fn parse_page_token_index(
+    request_message: &impl Message,
+    request_page_token: &str,
+) -> Result<usize, Status> {
+    let page_token = parse_page_token_string(request_message, request_page_token)?;
+    match page_token.parse::<usize>() {
+        Ok(index) => Ok(index),
+        Err(_) => Err(invalid_page_token_error()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use shared::proto::crusty_cards_api::User;
+    use crate::proto::crusty_cards_api::User;
 
     #[test]
     fn test_serialize_proto_message_to_hex_string() {
@@ -181,4 +197,19 @@ mod tests {
         // Can parse page token created from valid object id.
         assert_eq!(format!("{}", parse_page_token_object_id(&user, "1234").unwrap_err()), "status: InvalidArgument, message: \"Page token is invalid.\", details: [], metadata: MetadataMap { headers: {} }");
     }
+
+    #[test]
+    fn test_conversion_to_index() {
+        let user = User {
+            name: String::from(""),
+            display_name: String::from("Hello"),
+            create_time: None,
+            update_time: None,
+        };
+        let page_token = create_page_token_for_index(&user, 25);
+        assert_eq!(parse_page_token_index(&user, &page_token).unwrap(), 25);
+        // Page tokens that don't hold an index are rejected.
+        let page_token = create_page_token(&user, String::from("5fd07c5000eb828800a3bfa5"));
+        assert_eq!(format!("{}", parse_page_token_index(&user, &page_token).unwrap_err()), "status: InvalidArgument, message: \"Page token is invalid.\", details: [], metadata: MetadataMap { headers: {} }");
+    }
 }
@@ -9,6 +9,7 @@ use super::proto::crusty_cards_api::{
     GameConfig, OAuthCredentials,
 };
 use super::proto::google::protobuf::Empty;
+use std::sync::atomic::{AtomicI64, Ordering};
 use tonic::Status;
 
 pub struct ValidatedOAuthCredentials {
@@ -38,18 +39,29 @@ impl ValidatedOAuthCredentials {
     }
 }
 
+static DEFAULT_PAGE_SIZE_OVERRIDE: AtomicI64 = AtomicI64::new(DEFAULT_PAGE_SIZE);
+static MAX_PAGE_SIZE_OVERRIDE: AtomicI64 = AtomicI64::new(MAX_PAGE_SIZE);
+
 pub struct BoundedPageSize {
     page_size: i64,
 }
 
 impl BoundedPageSize {
+    // Changes the page size limits for every request handled by this process.
+    // This is meant to be called once at startup, with limits from the service config.
+    pub fn set_limits(default_page_size: i64, max_page_size: i64) {
+        DEFAULT_PAGE_SIZE_OVERRIDE.store(default_page_size, Ordering::Relaxed);
+        MAX_PAGE_SIZE_OVERRIDE.store(max_page_size, Ordering::Relaxed);
+    }
+
     fn get_bounded_page_size(page_size: i32) -> Result<i64, Status> {
+        let max_page_size = MAX_PAGE_SIZE_OVERRIDE.load(Ordering::Relaxed);
         if page_size < 0 {
             Err(Status::invalid_argument("Page size cannot be negative."))
         } else if page_size == 0 {
-            Ok(50)
-        } else if page_size > 1000 {
-            Ok(1000)
+            Ok(DEFAULT_PAGE_SIZE_OVERRIDE.load(Ordering::Relaxed))
+        } else if page_size as i64 > max_page_size {
+            Ok(max_page_size)
         } else {
             Ok(page_size as i64)
         }
@@ -238,6 +250,40 @@ impl ValidatedGameConfig {
         }
     }
 
+    // Returns a copy of this config where only the fields listed in
+    // `update_mask_paths` are taken from `update`, revalidating the result.
+    pub fn with_updates(
+        &self,
+        update: &GameConfig,
+        update_mask_paths: &[String],
+    ) -> Result<Self, Status> {
+        let mut config = self.raw_config();
+        for path in update_mask_paths {
+            match path.as_str() {
+                "display_name" => config.display_name = update.display_name.clone(),
+                "max_players" => config.max_players = update.max_players,
+                "hand_size" => config.hand_size = update.hand_size,
+                "max_score" | "endless_mode" => config.end_condition = update.end_condition.clone(),
+                "custom_cardpack_names" => {
+                    config.custom_cardpack_names = update.custom_cardpack_names.clone()
+                }
+                "default_cardpack_names" => {
+                    config.default_cardpack_names = update.default_cardpack_names.clone()
+                }
+                "blank_white_card_config" => {
+                    config.blank_white_card_config = update.blank_white_card_config.clone()
+                }
+                _ => {
+                    return Err(Status::invalid_argument(format!(
+                        "Update mask contains unknown game config field `{}`.",
+                        path
+                    )))
+                }
+            };
+        }
+        Self::new(config)
+    }
+
     fn validate_blank_white_card_config(
         blank_white_card_config: &BlankWhiteCardConfig,
     ) -> Result<(), Status> {
@@ -522,4 +568,59 @@ mod tests {
         };
         assert_eq!(format!("{}", ValidatedGameConfig::new(game_config).err().unwrap()), "status: InvalidArgument, message: \"Game config property `blank_white_card_config.percentage` must not exceed 0.8.\", details: [], metadata: MetadataMap { headers: {} }");
     }
+
+    #[test]
+    fn update_game_config() {
+        let validated_game_config = ValidatedGameConfig::new(get_valid_test_game_config()).unwrap();
+
+        let mut update = get_valid_test_game_config();
+        update.display_name = String::from("Updated Game");
+        update.hand_size = 10;
+        update.max_players = 5;
+
+        // Only fields in the update mask are changed.
+        let updated_game_config = validated_game_config
+            .with_updates(
+                &update,
+                &[String::from("display_name"), String::from("hand_size")],
+            )
+            .unwrap()
+            .raw_config();
+        assert_eq!(updated_game_config.display_name, "Updated Game");
+        assert_eq!(updated_game_config.hand_size, 10);
+        assert_eq!(updated_game_config.max_players, 3);
+
+        // Empty update mask doesn't change anything.
+        assert_eq!(
+            validated_game_config
+                .with_updates(&update, &[])
+                .unwrap()
+                .raw_config(),
+            get_valid_test_game_config()
+        );
+
+        // Updated config is revalidated.
+        update.hand_size = MAX_HAND_SIZE_LIMIT + 1;
+        assert_eq!(
+            format!(
+                "{}",
+                validated_game_config
+                    .with_updates(&update, &[String::from("hand_size")])
+                    .err()
+                    .unwrap()
+            ),
+            format!("status: InvalidArgument, message: \"Game config property `hand_size` must not exceed {}.\", details: [], metadata: MetadataMap {{ headers: {{}} }}", MAX_HAND_SIZE_LIMIT)
+        );
+
+        assert_eq!(
+            format!(
+                "{}",
+                validated_game_config
+                    .with_updates(&update, &[String::from("foo")])
+                    .err()
+                    .unwrap()
+            ),
+            "status: InvalidArgument, message: \"Update mask contains unknown game config field `foo`.\", details: [], metadata: MetadataMap { headers: {} }"
+        );
+    }
 }
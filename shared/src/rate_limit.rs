@@ -0,0 +1,560 @@
+use super::auth::{
+    constant_time_eq, SessionTokenKey, AUTHORIZATION_METADATA_KEY, BEARER_PREFIX,
+    INTERNAL_SERVICE_SECRET_METADATA_KEY,
+};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tonic::transport::server::TcpConnectInfo;
+use tonic::Status;
+use tower::{Layer, Service};
+
+// Set on rejected requests to the number of whole seconds to wait before retrying.
+pub const RETRY_AFTER_METADATA_KEY: &str = "retry-after";
+
+// Used when `rate_limits` isn't configured. Limits for methods that a
+// service doesn't have are ignored, so both services share this list.
+pub const DEFAULT_RATE_LIMIT_RULES: &[&str] = &[
+    "GameService/CreateChatMessage=20/60",
+    "CardpackService/BatchCreateCustomBlackCards=10/60",
+    "CardpackService/BatchCreateCustomWhiteCards=10/60",
+    "UserService/UserSearch=30/60",
+    "UserService/AutocompleteUserSearch=120/60",
+];
+
+// Full buckets behave exactly the same as buckets that don't exist yet, so they're
+// dropped this often. Pruning walks every bucket, so it isn't done on every request.
+const BUCKET_PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+// Caps memory use when lots of callers have partially used buckets at once. Once
+// this many buckets are tracked, the least recently used ones are evicted in a
+// batch, so that the cost of finding them is spread across many requests.
+const MAX_BUCKETS: usize = 100000;
+const BUCKETS_EVICTED_AT_CAPACITY: usize = MAX_BUCKETS / 10;
+
+// Allows bursts of up to `requests` requests, refilling
+// continuously at a rate of `requests` per `period`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    requests: u32,
+    period: Duration,
+}
+
+impl RateLimit {
+    pub fn new(requests: u32, period: Duration) -> Self {
+        Self { requests, period }
+    }
+
+    fn get_refill_interval(&self) -> Duration {
+        self.period / self.requests
+    }
+}
+
+// Parses a rule such as `UserService/UserSearch=30/60`, which
+// allows 30 calls to `UserService/UserSearch` every 60 seconds.
+pub fn parse_rate_limit_rule(rule: &str) -> Result<(String, RateLimit), String> {
+    let invalid_rule_error = || {
+        format!(
+            "Rate limit `{}` must look like `<Service>/<Method>=<requests>/<seconds>`, with both numbers greater than zero.",
+            rule
+        )
+    };
+    let (method, limit) = match rule.split_once('=') {
+        Some((method, limit)) => (method.trim(), limit.trim()),
+        None => return Err(invalid_rule_error()),
+    };
+    if method.split('/').count() != 2 || method.split('/').any(|part| part.is_empty()) {
+        return Err(invalid_rule_error());
+    }
+    let (requests, seconds) = match limit.split_once('/') {
+        Some((requests, seconds)) => (
+            requests.trim().parse::<u32>(),
+            seconds.trim().parse::<u64>(),
+        ),
+        None => return Err(invalid_rule_error()),
+    };
+    match (requests, seconds) {
+        (Ok(requests), Ok(seconds)) if requests > 0 && seconds > 0 => Ok((
+            String::from(method),
+            RateLimit::new(requests, Duration::from_secs(seconds)),
+        )),
+        _ => Err(invalid_rule_error()),
+    }
+}
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill_time: Instant,
+}
+
+impl TokenBucket {
+    fn refill(&mut self, rate_limit: &RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill_time);
+        self.tokens = (self.tokens
+            + elapsed.as_secs_f64() / rate_limit.get_refill_interval().as_secs_f64())
+        .min(rate_limit.requests as f64);
+        self.last_refill_time = now;
+    }
+}
+
+struct TokenBuckets {
+    // Keyed by method and caller.
+    buckets: HashMap<(String, String), TokenBucket>,
+    last_prune_time: Instant,
+}
+
+impl TokenBuckets {
+    fn remove_full_buckets(&mut self, rate_limits: &HashMap<String, RateLimit>, now: Instant) {
+        self.buckets
+            .retain(|(method, _), bucket| match rate_limits.get(method) {
+                Some(rate_limit) => {
+                    bucket.refill(rate_limit, now);
+                    bucket.tokens < rate_limit.requests as f64
+                }
+                None => false,
+            });
+        self.last_prune_time = now;
+    }
+
+    // Buckets are refilled whenever they're used, so the
+    // ones with the oldest refill time were used least recently.
+    fn evict_least_recently_used_buckets(&mut self) {
+        let mut last_refill_times: Vec<Instant> = self
+            .buckets
+            .values()
+            .map(|bucket| bucket.last_refill_time)
+            .collect();
+        let eviction_count = BUCKETS_EVICTED_AT_CAPACITY.clamp(1, last_refill_times.len());
+        let (_, cutoff_time, _) = last_refill_times.select_nth_unstable(eviction_count - 1);
+        let cutoff_time = *cutoff_time;
+        self.buckets
+            .retain(|_, bucket| bucket.last_refill_time > cutoff_time);
+    }
+}
+
+// Tracks a token bucket for each combination of rate limited method and caller.
+pub struct RateLimiter {
+    rate_limits: HashMap<String, RateLimit>,
+    buckets: Mutex<TokenBuckets>,
+}
+
+impl RateLimiter {
+    // Methods are keyed like `UserService/UserSearch`. Methods without a limit are never limited.
+    pub fn new(rate_limits: HashMap<String, RateLimit>) -> Self {
+        Self {
+            rate_limits,
+            buckets: Mutex::new(TokenBuckets {
+                buckets: HashMap::new(),
+                last_prune_time: Instant::now(),
+            }),
+        }
+    }
+
+    // Takes a token for the caller, or returns how long
+    // the caller must wait until a token is available.
+    fn try_acquire(&self, method: &str, caller_key: &str, now: Instant) -> Result<(), Duration> {
+        let rate_limit = match self.rate_limits.get(method) {
+            Some(rate_limit) => rate_limit,
+            None => return Ok(()),
+        };
+        let mut buckets = self.buckets.lock().unwrap();
+        if now.saturating_duration_since(buckets.last_prune_time) >= BUCKET_PRUNE_INTERVAL {
+            buckets.remove_full_buckets(&self.rate_limits, now);
+        }
+        let bucket_key = (String::from(method), String::from(caller_key));
+        if buckets.buckets.len() >= MAX_BUCKETS && !buckets.buckets.contains_key(&bucket_key) {
+            buckets.evict_least_recently_used_buckets();
+        }
+        let bucket = buckets
+            .buckets
+            .entry(bucket_key)
+            .or_insert_with(|| TokenBucket {
+                tokens: rate_limit.requests as f64,
+                last_refill_time: now,
+            });
+        bucket.refill(rate_limit, now);
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(rate_limit
+                .get_refill_interval()
+                .mul_f64(1.0 - bucket.tokens))
+        }
+    }
+}
+
+// gRPC paths look like `/crusty_cards_api.UserService/UserSearch`.
+fn get_method_from_path(path: &str) -> &str {
+    match path.rsplit_once('.') {
+        Some((_, method)) => method,
+        None => path.trim_start_matches('/'),
+    }
+}
+
+// Rejects requests once a caller has used up their token bucket for a method,
+// with `resource_exhausted` and `retry-after` metadata. Callers are identified
+// by the user in their session token, or by their IP address if they don't
+// send a valid one. Add it with `Server::builder().layer(RateLimitLayer::new(..))`.
+//
+// Requests with a valid internal service secret are never limited. They come from
+// other services in the cluster, such as a game service instance forwarding a
+// user's request to another, so the user was already limited where they came in.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    rate_limiter: Arc<RateLimiter>,
+    session_token_key: Arc<SessionTokenKey>,
+    internal_service_secret: Arc<String>,
+}
+
+impl RateLimitLayer {
+    pub fn new(
+        rate_limiter: Arc<RateLimiter>,
+        session_token_key: Arc<SessionTokenKey>,
+        internal_service_secret: &str,
+    ) -> Self {
+        Self {
+            rate_limiter,
+            session_token_key,
+            internal_service_secret: Arc::new(String::from(internal_service_secret)),
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            rate_limiter: self.rate_limiter.clone(),
+            session_token_key: self.session_token_key.clone(),
+            internal_service_secret: self.internal_service_secret.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    rate_limiter: Arc<RateLimiter>,
+    session_token_key: Arc<SessionTokenKey>,
+    internal_service_secret: Arc<String>,
+}
+
+impl<S> RateLimitService<S> {
+    // This runs before `SessionAuthInterceptor`, so the secret is checked here too.
+    fn is_internal_request<ReqBody>(&self, request: &http::Request<ReqBody>) -> bool {
+        match request.headers().get(INTERNAL_SERVICE_SECRET_METADATA_KEY) {
+            Some(header_value) => constant_time_eq(
+                header_value.as_bytes(),
+                self.internal_service_secret.as_bytes(),
+            ),
+            None => false,
+        }
+    }
+
+    fn get_caller_key<ReqBody>(&self, request: &http::Request<ReqBody>) -> String {
+        let user_name_or = request
+            .headers()
+            .get(AUTHORIZATION_METADATA_KEY)
+            .and_then(|header_value| header_value.to_str().ok())
+            .and_then(|authorization| authorization.strip_prefix(BEARER_PREFIX))
+            .and_then(|session_token| {
+                self.session_token_key
+                    .verify_session_token(session_token)
+                    .ok()
+            });
+        if let Some(user_name) = user_name_or {
+            return user_name;
+        }
+        match request
+            .extensions()
+            .get::<TcpConnectInfo>()
+            .and_then(|connect_info| connect_info.remote_addr())
+        {
+            Some(remote_addr) => format!("ip:{}", remote_addr.ip()),
+            None => String::from("unknown"),
+        }
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<http::Request<ReqBody>> for RateLimitService<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>>,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    ResBody: Default + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<ReqBody>) -> Self::Future {
+        if self.is_internal_request(&request) {
+            return Box::pin(self.inner.call(request));
+        }
+        let method = get_method_from_path(request.uri().path());
+        let caller_key = self.get_caller_key(&request);
+        if let Err(retry_after) = self
+            .rate_limiter
+            .try_acquire(method, &caller_key, Instant::now())
+        {
+            return Box::pin(std::future::ready(Ok(create_rate_limited_response(
+                retry_after,
+            ))));
+        }
+        Box::pin(self.inner.call(request))
+    }
+}
+
+fn create_rate_limited_response<ResBody: Default>(
+    retry_after: Duration,
+) -> http::Response<ResBody> {
+    // Rounded up so that retrying after the given number of seconds always succeeds.
+    let retry_after_seconds = retry_after.as_secs() + (retry_after.subsec_nanos() > 0) as u64;
+    let mut status = Status::resource_exhausted(format!(
+        "Too many requests. Retry after {} seconds.",
+        retry_after_seconds
+    ));
+    status
+        .metadata_mut()
+        .insert(RETRY_AFTER_METADATA_KEY, retry_after_seconds.into());
+    let (parts, _) = status.to_http().into_parts();
+    http::Response::from_parts(parts, ResBody::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rate_limit_rules() {
+        assert_eq!(
+            parse_rate_limit_rule("UserService/UserSearch=30/60"),
+            Ok((
+                String::from("UserService/UserSearch"),
+                RateLimit::new(30, Duration::from_secs(60))
+            ))
+        );
+        for rule in DEFAULT_RATE_LIMIT_RULES {
+            assert!(parse_rate_limit_rule(rule).is_ok());
+        }
+        assert!(parse_rate_limit_rule("UserSearch=30/60").is_err());
+        assert!(parse_rate_limit_rule("UserService/UserSearch=0/60").is_err());
+        assert!(parse_rate_limit_rule("UserService/UserSearch=30").is_err());
+    }
+
+    #[test]
+    fn get_method_from_path() {
+        assert_eq!(
+            super::get_method_from_path("/crusty_cards_api.UserService/UserSearch"),
+            "UserService/UserSearch"
+        );
+    }
+
+    #[test]
+    fn rate_limiter_refills_buckets_over_time() {
+        let mut rate_limits = HashMap::new();
+        rate_limits.insert(
+            String::from("UserService/UserSearch"),
+            RateLimit::new(2, Duration::from_secs(10)),
+        );
+        let rate_limiter = RateLimiter::new(rate_limits);
+        let start_time = Instant::now();
+
+        assert!(rate_limiter
+            .try_acquire("UserService/UserSearch", "users/1234", start_time)
+            .is_ok());
+        assert!(rate_limiter
+            .try_acquire("UserService/UserSearch", "users/1234", start_time)
+            .is_ok());
+        assert_eq!(
+            rate_limiter.try_acquire("UserService/UserSearch", "users/1234", start_time),
+            Err(Duration::from_secs(5))
+        );
+
+        // Buckets are separate for each caller, and methods without a limit are never limited.
+        assert!(rate_limiter
+            .try_acquire("UserService/UserSearch", "ip:127.0.0.1", start_time)
+            .is_ok());
+        for _ in 0..10 {
+            assert!(rate_limiter
+                .try_acquire("UserService/GetUser", "users/1234", start_time)
+                .is_ok());
+        }
+
+        assert_eq!(
+            rate_limiter.try_acquire(
+                "UserService/UserSearch",
+                "users/1234",
+                start_time + Duration::from_secs(3)
+            ),
+            Err(Duration::from_secs(2))
+        );
+        assert!(rate_limiter
+            .try_acquire(
+                "UserService/UserSearch",
+                "users/1234",
+                start_time + Duration::from_secs(5)
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn rate_limiter_prunes_full_buckets_once_per_interval() {
+        let mut rate_limits = HashMap::new();
+        rate_limits.insert(
+            String::from("UserService/UserSearch"),
+            RateLimit::new(2, Duration::from_secs(10)),
+        );
+        let rate_limiter = RateLimiter::new(rate_limits);
+        let start_time = Instant::now();
+        let get_bucket_count = || rate_limiter.buckets.lock().unwrap().buckets.len();
+
+        assert!(rate_limiter
+            .try_acquire("UserService/UserSearch", "users/1", start_time)
+            .is_ok());
+        assert!(rate_limiter
+            .try_acquire(
+                "UserService/UserSearch",
+                "users/2",
+                start_time + Duration::from_secs(5)
+            )
+            .is_ok());
+        // Both buckets are full again, but they aren't pruned until the interval has passed.
+        assert!(rate_limiter
+            .try_acquire(
+                "UserService/UserSearch",
+                "users/3",
+                start_time + Duration::from_secs(30)
+            )
+            .is_ok());
+        assert_eq!(get_bucket_count(), 3);
+
+        assert!(rate_limiter
+            .try_acquire(
+                "UserService/UserSearch",
+                "users/4",
+                start_time + Duration::from_secs(61)
+            )
+            .is_ok());
+        assert_eq!(get_bucket_count(), 1);
+
+        // Buckets that are full by now are kept until the next interval.
+        assert!(rate_limiter
+            .try_acquire(
+                "UserService/UserSearch",
+                "users/5",
+                start_time + Duration::from_secs(90)
+            )
+            .is_ok());
+        assert_eq!(get_bucket_count(), 2);
+    }
+
+    #[test]
+    fn rate_limiter_evicts_least_recently_used_buckets_at_capacity() {
+        let mut rate_limits = HashMap::new();
+        rate_limits.insert(
+            String::from("UserService/UserSearch"),
+            RateLimit::new(2, Duration::from_secs(10)),
+        );
+        let rate_limiter = RateLimiter::new(rate_limits);
+        let start_time = Instant::now();
+
+        for i in 0..MAX_BUCKETS {
+            assert!(rate_limiter
+                .try_acquire(
+                    "UserService/UserSearch",
+                    &format!("users/{}", i),
+                    start_time + Duration::from_nanos(i as u64)
+                )
+                .is_ok());
+        }
+        // Callers that already have a bucket don't cause evictions.
+        assert!(rate_limiter
+            .try_acquire(
+                "UserService/UserSearch",
+                "users/0",
+                start_time + Duration::from_nanos(MAX_BUCKETS as u64)
+            )
+            .is_ok());
+        assert_eq!(
+            rate_limiter.buckets.lock().unwrap().buckets.len(),
+            MAX_BUCKETS
+        );
+
+        assert!(rate_limiter
+            .try_acquire(
+                "UserService/UserSearch",
+                "users/new",
+                start_time + Duration::from_nanos(MAX_BUCKETS as u64 + 1)
+            )
+            .is_ok());
+        let buckets = rate_limiter.buckets.lock().unwrap();
+        assert_eq!(
+            buckets.buckets.len(),
+            MAX_BUCKETS - BUCKETS_EVICTED_AT_CAPACITY + 1
+        );
+        // `users/0` was used again, so `users/1` is now the least recently used bucket.
+        let has_bucket = |caller_key: &str| {
+            buckets.buckets.contains_key(&(
+                String::from("UserService/UserSearch"),
+                String::from(caller_key),
+            ))
+        };
+        assert!(has_bucket("users/0"));
+        assert!(!has_bucket("users/1"));
+        assert!(!has_bucket(&format!(
+            "users/{}",
+            BUCKETS_EVICTED_AT_CAPACITY
+        )));
+        assert!(has_bucket(&format!(
+            "users/{}",
+            BUCKETS_EVICTED_AT_CAPACITY + 1
+        )));
+        assert!(has_bucket("users/new"));
+    }
+
+    #[test]
+    fn skips_requests_with_valid_internal_service_secret() {
+        let rate_limit_service = RateLimitLayer::new(
+            Arc::new(RateLimiter::new(HashMap::new())),
+            Arc::new(SessionTokenKey::new(b"session-token-secret")),
+            "internal-service-secret",
+        )
+        .layer(());
+        let mut request = http::Request::new(());
+        assert!(!rate_limit_service.is_internal_request(&request));
+
+        request.headers_mut().insert(
+            INTERNAL_SERVICE_SECRET_METADATA_KEY,
+            http::HeaderValue::from_static("wrong-secret"),
+        );
+        assert!(!rate_limit_service.is_internal_request(&request));
+
+        request.headers_mut().insert(
+            INTERNAL_SERVICE_SECRET_METADATA_KEY,
+            http::HeaderValue::from_static("internal-service-secret"),
+        );
+        assert!(rate_limit_service.is_internal_request(&request));
+    }
+
+    #[test]
+    fn rate_limited_response_includes_retry_after() {
+        let response: http::Response<()> =
+            create_rate_limited_response(Duration::from_millis(1500));
+        assert_eq!(response.headers().get("grpc-status").unwrap(), "8");
+        assert_eq!(
+            response.headers().get(RETRY_AFTER_METADATA_KEY).unwrap(),
+            "2"
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use super::metrics::{get_grpc_code_label, GrpcCodeBody};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tonic::Request;
+use tower::{Layer, Service};
+use tracing::{info, info_span, Instrument};
+use tracing_subscriber::EnvFilter;
+use uuid::Uuid;
+
+// Carries the id of the request that caused a call from one service to another, so
+// that the logs from every service involved in handling a request can be correlated.
+pub const REQUEST_ID_METADATA_KEY: &str = "x-request-id";
+
+// Incoming request ids that are longer than this are replaced rather than logged.
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+// Logs are written to stdout as JSON. The log level can be changed with
+// the `RUST_LOG` environment variable, and defaults to `info`.
+pub fn init_tracing() {
+    let env_filter = match EnvFilter::try_from_default_env() {
+        Ok(env_filter) => env_filter,
+        Err(_) => EnvFilter::new("info"),
+    };
+    tracing_subscriber::fmt()
+        .json()
+        .with_current_span(true)
+        .with_env_filter(env_filter)
+        .init();
+}
+
+// Returns the id of the request currently being handled, or None if this
+// isn't being called while handling a request, such as from a scheduled job.
+pub fn get_current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|request_id| request_id.clone()).ok()
+}
+
+// Creates a request to another service that
+// carries the id of the request being handled.
+pub fn create_request_with_request_id<T>(message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    if let Some(request_id) = get_current_request_id() {
+        if let Ok(metadata_value) = request_id.parse() {
+            request
+                .metadata_mut()
+                .insert(REQUEST_ID_METADATA_KEY, metadata_value);
+        }
+    }
+    request
+}
+
+fn is_valid_request_id(request_id: &str) -> bool {
+    !request_id.is_empty()
+        && request_id.len() <= MAX_REQUEST_ID_LENGTH
+        && request_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn generate_request_id() -> String {
+    Uuid::new_v4().to_simple().to_string()
+}
+
+// Handles every request inside a span that's tagged with the gRPC method and a
+// request id. The id is taken from the request's metadata if the caller set one,
+// and otherwise a new one is generated. Either way, it's sent back to the caller
+// in the response metadata. Add it with `Server::builder().layer(RequestTracingLayer)`.
+#[derive(Clone, Copy, Default)]
+pub struct RequestTracingLayer;
+
+impl<S> Layer<S> for RequestTracingLayer {
+    type Service = RequestTracingService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestTracingService { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct RequestTracingService<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> Service<http::Request<ReqBody>> for RequestTracingService<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<GrpcCodeBody<ResBody>>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<ReqBody>) -> Self::Future {
+        let request_id = match request
+            .headers()
+            .get(REQUEST_ID_METADATA_KEY)
+            .and_then(|header_value| header_value.to_str().ok())
+        {
+            Some(request_id) if is_valid_request_id(request_id) => String::from(request_id),
+            _ => generate_request_id(),
+        };
+        // Unwrap is safe here because valid request ids only contain header-safe characters.
+        let header_value = http::HeaderValue::from_str(&request_id).unwrap();
+        request
+            .headers_mut()
+            .insert(REQUEST_ID_METADATA_KEY, header_value.clone());
+
+        let span = info_span!(
+            "grpc_request",
+            method = request.uri().path(),
+            request_id = request_id.as_str()
+        );
+        let start_time = Instant::now();
+        let response_future = {
+            let _entered = span.enter();
+            self.inner.call(request)
+        };
+
+        let log_span = span.clone();
+        let request_future = async move {
+            let mut response = response_future.await?;
+            response
+                .headers_mut()
+                .insert(REQUEST_ID_METADATA_KEY, header_value);
+            // The status of most responses is only known after the body is sent.
+            Ok(GrpcCodeBody::wrap_response(response, move |code_or| {
+                let _entered = log_span.enter();
+                info!(
+                    code = get_grpc_code_label(code_or).as_str(),
+                    latency_ms = start_time.elapsed().as_millis() as u64,
+                    "Finished handling request."
+                );
+            }))
+        };
+        Box::pin(REQUEST_ID.scope(request_id, request_future.instrument(span)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_request_ids() {
+        assert!(is_valid_request_id(&generate_request_id()));
+        assert!(is_valid_request_id("abc-123_DEF"));
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id("has spaces"));
+        assert!(!is_valid_request_id("line\nbreak"));
+        assert!(!is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LENGTH + 1)));
+    }
+
+    #[tokio::test]
+    async fn propagates_current_request_id() {
+        assert_eq!(get_current_request_id(), None);
+        assert!(create_request_with_request_id(())
+            .metadata()
+            .get(REQUEST_ID_METADATA_KEY)
+            .is_none());
+
+        REQUEST_ID
+            .scope(String::from("1234"), async {
+                assert_eq!(get_current_request_id(), Some(String::from("1234")));
+                assert_eq!(
+                    create_request_with_request_id(())
+                        .metadata()
+                        .get(REQUEST_ID_METADATA_KEY)
+                        .unwrap(),
+                    "1234"
+                );
+            })
+            .await;
+    }
+}
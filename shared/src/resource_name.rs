@@ -200,6 +200,7 @@ macro_rules! top_level_mongo_based_resource_name {
 top_level_mongo_based_resource_name!(UserName, "users/{}");
 top_level_mongo_based_resource_name!(UserSettingsName, "users/{}/settings");
 top_level_mongo_based_resource_name!(UserProfileImageName, "users/{}/profileImage");
+top_level_mongo_based_resource_name!(UserStatsName, "users/{}/stats");
 
 impl UserProfileImageName {
     pub fn to_user_name(&self) -> UserName {
@@ -209,6 +210,14 @@ impl UserProfileImageName {
     }
 }
 
+impl UserStatsName {
+    pub fn to_user_name(&self) -> UserName {
+        UserName {
+            object_id: self.object_id,
+        }
+    }
+}
+
 #[derive(Clone, Hash, PartialEq, Eq)]
 pub struct CustomCardpackName {
     parent_user_object_id: ObjectId,